@@ -7,7 +7,9 @@
 
 //! Interceptor for handling Smithy `@httpChecksum` request checksumming with AWS SigV4
 
-use aws_runtime::content_encoding::{AwsChunkedBody, AwsChunkedBodyOptions};
+use aws_runtime::content_encoding::{
+    AllowUnsizedAwsChunkedBody, AwsChunkedBody, AwsChunkedBodyOptions,
+};
 use aws_runtime::{auth::SigV4OperationSigningConfig, content_encoding::header_value::AWS_CHUNKED};
 use aws_sigv4::http_request::SignableBody;
 use aws_smithy_checksums::ChecksumAlgorithm;
@@ -29,7 +31,8 @@ use std::{fmt, mem};
 /// Errors related to constructing checksum-validated HTTP requests
 #[derive(Debug)]
 pub(crate) enum Error {
-    /// Only request bodies with a known size can be checksum validated
+    /// Only request bodies with a known size can be checksum validated, unless unsized
+    /// `aws-chunked` bodies were opted in to
     UnsizedRequestBody,
     ChecksumHeadersAreUnsupportedForStreamingBody,
 }
@@ -39,7 +42,9 @@ impl fmt::Display for Error {
         match self {
             Self::UnsizedRequestBody => write!(
                 f,
-                "Only request bodies with a known size can be checksum validated."
+                "Only request bodies with a known size can be checksum validated. \
+                   The service needs the decoded content length of `aws-chunked` request bodies, \
+                   which isn't known for this body (for example, because it's compressed as it's sent)."
             ),
             Self::ChecksumHeadersAreUnsupportedForStreamingBody => write!(
                 f,
@@ -140,7 +145,8 @@ where
 
     /// Calculate a checksum and modify the request to include the checksum as a header
     /// (for in-memory request bodies) or a trailer (for streaming request bodies).
-    /// Streaming bodies must be sized or this will return an error.
+    /// Streaming bodies must be sized or this will return an error, unless
+    /// [`AllowUnsizedAwsChunkedBody`] is set in the config bag.
     fn modify_before_signing(
         &self,
         context: &mut BeforeTransmitInterceptorContextMut<'_>,
@@ -195,7 +201,12 @@ fn add_checksum_for_request_body(
                     Some(SignableBody::StreamingUnsignedPayloadTrailer);
                 cfg.interceptor_state().store_put(signing_config);
             }
-            wrap_streaming_request_body_in_checksum_calculating_body(request, checksum_algorithm)?;
+            let allow_unsized = cfg.load::<AllowUnsizedAwsChunkedBody>().is_some();
+            wrap_streaming_request_body_in_checksum_calculating_body(
+                request,
+                checksum_algorithm,
+                allow_unsized,
+            )?;
         }
     }
     Ok(())
@@ -204,12 +215,14 @@ fn add_checksum_for_request_body(
 fn wrap_streaming_request_body_in_checksum_calculating_body(
     request: &mut HttpRequest,
     checksum_algorithm: ChecksumAlgorithm,
+    allow_unsized: bool,
 ) -> Result<(), BuildError> {
-    let original_body_size = request
-        .body()
-        .size_hint()
-        .exact()
-        .ok_or_else(|| BuildError::other(Error::UnsizedRequestBody))?;
+    // Bodies of unknown size (for example, bodies that are compressed as they're sent) are
+    // written out one chunk at a time, but only if the service doesn't need their decoded length.
+    let original_body_size = request.body().size_hint().exact();
+    if original_body_size.is_none() && !allow_unsized {
+        return Err(BuildError::other(Error::UnsizedRequestBody));
+    }
 
     let mut body = {
         let body = mem::replace(request.body_mut(), SdkBody::taken());
@@ -218,8 +231,12 @@ fn wrap_streaming_request_body_in_checksum_calculating_body(
             let checksum = checksum_algorithm.into_impl();
             let trailer_len = HttpChecksum::size(checksum.as_ref());
             let body = calculate::ChecksumBody::new(body, checksum);
-            let aws_chunked_body_options =
-                AwsChunkedBodyOptions::new(original_body_size, vec![trailer_len]);
+            let aws_chunked_body_options = match original_body_size {
+                Some(original_body_size) => {
+                    AwsChunkedBodyOptions::new(original_body_size, vec![trailer_len])
+                }
+                None => AwsChunkedBodyOptions::new_unsized(vec![trailer_len]),
+            };
 
             let body = AwsChunkedBody::new(body, aws_chunked_body_options);
 
@@ -227,10 +244,7 @@ fn wrap_streaming_request_body_in_checksum_calculating_body(
         })
     };
 
-    let encoded_content_length = body
-        .size_hint()
-        .exact()
-        .ok_or_else(|| BuildError::other(Error::UnsizedRequestBody))?;
+    let encoded_content_length = body.size_hint().exact();
 
     let headers = request.headers_mut();
 
@@ -239,19 +253,31 @@ fn wrap_streaming_request_body_in_checksum_calculating_body(
         checksum_algorithm.into_impl().header_name(),
     );
 
-    headers.insert(
-        http::header::CONTENT_LENGTH,
-        HeaderValue::from(encoded_content_length),
-    );
-    headers.insert(
-        http::header::HeaderName::from_static("x-amz-decoded-content-length"),
-        HeaderValue::from(original_body_size),
-    );
+    match (encoded_content_length, original_body_size) {
+        (Some(encoded_content_length), Some(original_body_size)) => {
+            headers.insert(
+                http::header::CONTENT_LENGTH,
+                HeaderValue::from(encoded_content_length),
+            );
+            headers.insert(
+                http::header::HeaderName::from_static("x-amz-decoded-content-length"),
+                HeaderValue::from(original_body_size),
+            );
+        }
+        _ => {
+            headers.remove(http::header::CONTENT_LENGTH);
+        }
+    }
+
+    // Keep any existing content-encoding (such as one set by request compression), since the
+    // service will still need to decode it after removing the aws-chunked encoding.
+    let content_encoding = match headers.get(http::header::CONTENT_ENCODING) {
+        Some(existing) => format!("{AWS_CHUNKED},{existing}"),
+        None => AWS_CHUNKED.to_owned(),
+    };
     headers.insert(
         http::header::CONTENT_ENCODING,
-        HeaderValue::from_str(AWS_CHUNKED)
-            .map_err(BuildError::other)
-            .expect("\"aws-chunked\" will always be a valid HeaderValue"),
+        HeaderValue::from_str(&content_encoding).map_err(BuildError::other)?,
     );
 
     mem::swap(request.body_mut(), &mut body);
@@ -285,8 +311,12 @@ mod tests {
         assert!(request.body().try_clone().is_some());
 
         let checksum_algorithm: ChecksumAlgorithm = "crc32".parse().unwrap();
-        wrap_streaming_request_body_in_checksum_calculating_body(&mut request, checksum_algorithm)
-            .unwrap();
+        wrap_streaming_request_body_in_checksum_calculating_body(
+            &mut request,
+            checksum_algorithm,
+            false,
+        )
+        .unwrap();
 
         // ensure wrapped SdkBody is retryable
         let mut body = request.body().try_clone().expect("body is retryable");
@@ -304,6 +334,90 @@ mod tests {
         );
     }
 
+    /// A body that doesn't know its own size, like a body that's compressed as it's read
+    struct UnsizedBody(Option<bytes::Bytes>);
+
+    impl Body for UnsizedBody {
+        type Data = bytes::Bytes;
+        type Error = aws_smithy_types::body::Error;
+
+        fn poll_data(
+            mut self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<Option<Result<Self::Data, Self::Error>>> {
+            std::task::Poll::Ready(self.0.take().map(Ok))
+        }
+
+        fn poll_trailers(
+            self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<Result<Option<http::HeaderMap>, Self::Error>> {
+            std::task::Poll::Ready(Ok(None))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_checksum_body_of_unknown_size_requires_opt_in() {
+        let mut request: HttpRequest = http::Request::builder()
+            .body(SdkBody::from_body_0_4(UnsizedBody(Some(
+                "Hello world".into(),
+            ))))
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        let checksum_algorithm: ChecksumAlgorithm = "crc32".parse().unwrap();
+        let err = wrap_streaming_request_body_in_checksum_calculating_body(
+            &mut request,
+            checksum_algorithm,
+            false,
+        )
+        .expect_err("the decoded content length is unknown");
+        assert!(
+            format!(
+                "{}",
+                aws_smithy_types::error::display::DisplayErrorContext(&err)
+            )
+            .contains("Only request bodies with a known size can be checksum validated"),
+            "{err:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_checksum_body_of_unknown_size() {
+        let input_text = "Hello world";
+        let mut request: HttpRequest = http::Request::builder()
+            .header("content-encoding", "gzip")
+            .body(SdkBody::from_body_0_4(UnsizedBody(Some(input_text.into()))))
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        let checksum_algorithm: ChecksumAlgorithm = "crc32".parse().unwrap();
+        wrap_streaming_request_body_in_checksum_calculating_body(
+            &mut request,
+            checksum_algorithm,
+            true,
+        )
+        .unwrap();
+
+        let headers = request.headers();
+        assert_eq!(Some("aws-chunked,gzip"), headers.get("content-encoding"));
+        assert_eq!(None, headers.get("content-length"));
+        assert_eq!(None, headers.get("x-amz-decoded-content-length"));
+
+        let body = request.body_mut();
+        let mut body_data = BytesMut::new();
+        while let Some(data) = body.data().await {
+            body_data.extend_from_slice(&data.unwrap())
+        }
+        let body = std::str::from_utf8(&body_data).unwrap();
+        assert_eq!(
+            "B\r\nHello world\r\n0\r\nx-amz-checksum-crc32:i9aeUg==\r\n\r\n",
+            body
+        );
+    }
+
     #[tokio::test]
     async fn test_checksum_body_from_file_is_retryable() {
         use std::io::Write;
@@ -331,8 +445,12 @@ mod tests {
         // ensure original SdkBody is retryable
         assert!(request.body().try_clone().is_some());
 
-        wrap_streaming_request_body_in_checksum_calculating_body(&mut request, checksum_algorithm)
-            .unwrap();
+        wrap_streaming_request_body_in_checksum_calculating_body(
+            &mut request,
+            checksum_algorithm,
+            false,
+        )
+        .unwrap();
 
         // ensure wrapped SdkBody is retryable
         let mut body = request.body().try_clone().expect("body is retryable");
@@ -136,7 +136,8 @@ pub(crate) fn check_headers_for_precalculated_checksum(
 ) -> Option<(ChecksumAlgorithm, bytes::Bytes)> {
     let checksum_algorithms_to_check =
         aws_smithy_checksums::http::CHECKSUM_ALGORITHMS_IN_PRIORITY_ORDER
-            .into_iter()
+            .iter()
+            .copied()
             // Process list of algorithms, from fastest to slowest, that may have been used to checksum
             // the response body, ignoring any that aren't marked as supported algorithms by the model.
             .flat_map(|algo| {
@@ -3,6 +3,7 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
+use aws_smithy_types::config_bag::{Storable, StoreReplace};
 use bytes::{Bytes, BytesMut};
 use http_02x::{HeaderMap, HeaderValue};
 use http_body_04x::{Body, SizeHint};
@@ -21,24 +22,69 @@ pub mod header_value {
     pub const AWS_CHUNKED: &str = "aws-chunked";
 }
 
+/// Opts in to sending streaming request bodies of unknown length with `aws-chunked` encoding.
+///
+/// An `aws-chunked` request normally declares the length of its decoded payload with the
+/// `x-amz-decoded-content-length` header, and services such as S3 reject requests without it.
+/// The length of some streaming bodies, such as bodies that are compressed as they're sent,
+/// isn't known ahead of time. Sending a trailing checksum with one of these bodies fails with a
+/// client-side error unless this opt-in is stored in the config bag, in which case the body is
+/// sent with neither `content-length` nor `x-amz-decoded-content-length`.
+///
+/// Only opt in for services that accept `aws-chunked` requests without a decoded length.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct AllowUnsizedAwsChunkedBody;
+
+impl AllowUnsizedAwsChunkedBody {
+    /// Creates a new [`AllowUnsizedAwsChunkedBody`].
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Storable for AllowUnsizedAwsChunkedBody {
+    type Storer = StoreReplace<Self>;
+}
+
 /// Options used when constructing an [`AwsChunkedBody`].
-#[derive(Debug, Default)]
+#[derive(Debug)]
 #[non_exhaustive]
 pub struct AwsChunkedBodyOptions {
     /// The total size of the stream. Because we only support unsigned encoding
     /// this implies that there will only be a single chunk containing the
     /// underlying payload.
-    stream_length: u64,
+    ///
+    /// When the size of the stream is unknown, each chunk of data from the underlying payload
+    /// is written out as its own chunk instead.
+    stream_length: Option<u64>,
     /// The length of each trailer sent within an `AwsChunkedBody`. Necessary in
     /// order to correctly calculate the total size of the body accurately.
     trailer_lengths: Vec<u64>,
 }
 
+impl Default for AwsChunkedBodyOptions {
+    fn default() -> Self {
+        Self::new(0, Vec::new())
+    }
+}
+
 impl AwsChunkedBodyOptions {
     /// Create a new [`AwsChunkedBodyOptions`].
     pub fn new(stream_length: u64, trailer_lengths: Vec<u64>) -> Self {
         Self {
-            stream_length,
+            stream_length: Some(stream_length),
+            trailer_lengths,
+        }
+    }
+
+    /// Create a new [`AwsChunkedBodyOptions`] for a stream of unknown size.
+    ///
+    /// An `AwsChunkedBody` created with these options writes each chunk of data from the
+    /// underlying payload as it arrives, and can't report its encoded length ahead of time.
+    pub fn new_unsized(trailer_lengths: Vec<u64>) -> Self {
+        Self {
+            stream_length: None,
             trailer_lengths,
         }
     }
@@ -65,6 +111,11 @@ enum AwsChunkedBodyState {
     /// all data is written out. Once there is no more data to write, transition into the
     /// `WritingTrailers` state.
     WritingChunk,
+    /// Write out each chunk of data from the inner body, prefixed by its size, as it arrives. This
+    /// is used instead of `WritingChunkSize` and `WritingChunk` when the size of the stream is
+    /// unknown. Once there is no more data to write, write out the chunk terminator and
+    /// transition into the `WritingTrailers` state.
+    WritingUnsizedChunks,
     /// Write out all trailers associated with this `AwsChunkedBody` and then transition into the
     /// `Closed` state.
     WritingTrailers,
@@ -73,8 +124,8 @@ enum AwsChunkedBodyState {
 }
 
 pin_project! {
-    /// A request body compatible with `Content-Encoding: aws-chunked`. This implementation is only
-    /// capable of writing a single chunk and does not support signed chunks.
+    /// A request body compatible with `Content-Encoding: aws-chunked`. This implementation writes
+    /// a single chunk when the size of the stream is known, and does not support signed chunks.
     ///
     /// Chunked-Body grammar is defined in [ABNF] as:
     ///
@@ -107,18 +158,25 @@ pin_project! {
 impl<Inner> AwsChunkedBody<Inner> {
     /// Wrap the given body in an outer body compatible with `Content-Encoding: aws-chunked`
     pub fn new(body: Inner, options: AwsChunkedBodyOptions) -> Self {
+        let state = if options.stream_length.is_some() {
+            AwsChunkedBodyState::WritingChunkSize
+        } else {
+            AwsChunkedBodyState::WritingUnsizedChunks
+        };
         Self {
             inner: body,
-            state: AwsChunkedBodyState::WritingChunkSize,
+            state,
             options,
             inner_body_bytes_read_so_far: 0,
         }
     }
 
-    fn encoded_length(&self) -> u64 {
+    /// Returns the encoded length of this body, or `None` if the size of the stream is unknown.
+    fn encoded_length(&self) -> Option<u64> {
+        let stream_length = self.options.stream_length?;
         let mut length = 0;
-        if self.options.stream_length != 0 {
-            length += get_unsigned_chunk_bytes_length(self.options.stream_length);
+        if stream_length != 0 {
+            length += get_unsigned_chunk_bytes_length(stream_length);
         }
 
         // End chunk
@@ -132,7 +190,7 @@ impl<Inner> AwsChunkedBody<Inner> {
         // Encoding terminator
         length += CRLF.len() as u64;
 
-        length
+        Some(length)
     }
 }
 
@@ -213,7 +271,11 @@ where
 
         match *this.state {
             AwsChunkedBodyState::WritingChunkSize => {
-                if this.options.stream_length == 0 {
+                let stream_length = this
+                    .options
+                    .stream_length
+                    .expect("only sized streams write a single chunk");
+                if stream_length == 0 {
                     // If the stream is empty, we skip to writing trailers after writing the CHUNK_TERMINATOR.
                     *this.state = AwsChunkedBodyState::WritingTrailers;
                     tracing::trace!("stream is empty, writing chunk terminator");
@@ -221,7 +283,7 @@ where
                 } else {
                     *this.state = AwsChunkedBodyState::WritingChunk;
                     // A chunk must be prefixed by chunk size in hexadecimal
-                    let chunk_size = format!("{:X?}{CRLF}", this.options.stream_length);
+                    let chunk_size = format!("{:X?}{CRLF}", stream_length);
                     tracing::trace!(%chunk_size, "writing chunk size");
                     let chunk_size = Bytes::from(chunk_size);
                     Poll::Ready(Some(Ok(chunk_size)))
@@ -235,7 +297,10 @@ where
                 }
                 Poll::Ready(None) => {
                     let actual_stream_length = *this.inner_body_bytes_read_so_far as u64;
-                    let expected_stream_length = this.options.stream_length;
+                    let expected_stream_length = this
+                        .options
+                        .stream_length
+                        .expect("only sized streams write a single chunk");
                     if actual_stream_length != expected_stream_length {
                         let err = Box::new(AwsChunkedBodyError::StreamLengthMismatch {
                             actual: actual_stream_length,
@@ -253,6 +318,31 @@ where
                 Poll::Ready(Some(Err(e))) => Poll::Ready(Some(Err(e))),
                 Poll::Pending => Poll::Pending,
             },
+            AwsChunkedBodyState::WritingUnsizedChunks => loop {
+                match this.inner.as_mut().poll_data(cx) {
+                    // An empty chunk would be mistaken for the chunk terminator, so skip it
+                    Poll::Ready(Some(Ok(data))) if data.is_empty() => continue,
+                    Poll::Ready(Some(Ok(data))) => {
+                        tracing::trace!(len = data.len(), "writing unsized chunk");
+                        *this.inner_body_bytes_read_so_far += data.len();
+                        // A chunk must be prefixed by chunk size in hexadecimal
+                        let chunk_size = format!("{:X?}{CRLF}", data.len());
+                        let mut chunk =
+                            BytesMut::with_capacity(chunk_size.len() + data.len() + CRLF.len());
+                        chunk.extend_from_slice(chunk_size.as_bytes());
+                        chunk.extend_from_slice(&data);
+                        chunk.extend_from_slice(CRLF.as_bytes());
+                        return Poll::Ready(Some(Ok(chunk.freeze())));
+                    }
+                    Poll::Ready(None) => {
+                        tracing::trace!("no more chunk data, writing chunk terminator");
+                        *this.state = AwsChunkedBodyState::WritingTrailers;
+                        return Poll::Ready(Some(Ok(Bytes::from(CHUNK_TERMINATOR))));
+                    }
+                    Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+                    Poll::Pending => return Poll::Pending,
+                }
+            },
             AwsChunkedBodyState::WritingTrailers => {
                 return match this.inner.poll_trailers(cx) {
                     Poll::Ready(Ok(trailers)) => {
@@ -297,7 +387,10 @@ where
     }
 
     fn size_hint(&self) -> SizeHint {
-        SizeHint::with_exact(self.encoded_length())
+        match self.encoded_length() {
+            Some(encoded_length) => SizeHint::with_exact(encoded_length),
+            None => SizeHint::default(),
+        }
     }
 }
 
@@ -429,6 +522,38 @@ mod tests {
         }
     }
 
+    struct TrailerBody {
+        data: Option<Bytes>,
+        trailers: Option<HeaderMap>,
+    }
+
+    impl Body for TrailerBody {
+        type Data = Bytes;
+        type Error = aws_smithy_types::body::Error;
+
+        fn poll_data(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+            Poll::Ready(self.data.take().map(Ok))
+        }
+
+        fn poll_trailers(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Result<Option<HeaderMap<HeaderValue>>, Self::Error>> {
+            Poll::Ready(Ok(self.trailers.take()))
+        }
+
+        fn is_end_stream(&self) -> bool {
+            self.data.is_none() && self.trailers.is_none()
+        }
+
+        fn size_hint(&self) -> SizeHint {
+            SizeHint::new()
+        }
+    }
+
     #[tokio::test]
     async fn test_aws_chunked_encoding() {
         let test_fut = async {
@@ -526,6 +651,66 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_aws_chunked_encoding_unsized_sputtering_body() {
+        let input = SputteringBody {
+            parts: vec![
+                Some(Bytes::from_static(b"chunk 1, ")),
+                None,
+                Some(Bytes::from_static(b"")),
+                Some(Bytes::from_static(b"chunk 2, the longest chunk")),
+            ],
+            cursor: 0,
+            delay_in_millis: 10,
+        };
+        let opts = AwsChunkedBodyOptions::new_unsized(Vec::new());
+        let mut body = AwsChunkedBody::new(input, opts);
+        assert_eq!(None, body.size_hint().exact());
+
+        let mut output = SegmentedBuf::new();
+        while let Some(buf) = body.data().await {
+            output.push(buf.unwrap());
+        }
+
+        let mut actual_output = String::new();
+        output
+            .reader()
+            .read_to_string(&mut actual_output)
+            .expect("Doesn't cause IO errors");
+
+        let expected_output = "9\r\nchunk 1, \r\n1A\r\nchunk 2, the longest chunk\r\n0\r\n\r\n";
+
+        assert_eq!(expected_output, actual_output);
+    }
+
+    #[tokio::test]
+    async fn test_aws_chunked_encoding_unsized_body_with_trailers() {
+        let mut trailers = HeaderMap::new();
+        trailers.insert("x-amz-checksum-crc32", HeaderValue::from_static("i9aeUg=="));
+        let trailer_len = total_rendered_length_of_trailers(Some(&trailers)) - CRLF.len() as u64;
+        let input = TrailerBody {
+            data: Some(Bytes::from_static(b"Hello world")),
+            trailers: Some(trailers),
+        };
+        let opts = AwsChunkedBodyOptions::new_unsized(vec![trailer_len]);
+        let mut body = AwsChunkedBody::new(input, opts);
+
+        let mut output = SegmentedBuf::new();
+        while let Some(buf) = body.data().await {
+            output.push(buf.unwrap());
+        }
+
+        let mut actual_output = String::new();
+        output
+            .reader()
+            .read_to_string(&mut actual_output)
+            .expect("Doesn't cause IO errors");
+
+        let expected_output = "B\r\nHello world\r\n0\r\nx-amz-checksum-crc32:i9aeUg==\r\n\r\n";
+
+        assert_eq!(expected_output, actual_output);
+    }
+
     #[tokio::test]
     #[should_panic = "called `Result::unwrap()` on an `Err` value: ReportedTrailerLengthMismatch { actual: 44, expected: 0 }"]
     async fn test_aws_chunked_encoding_incorrect_trailer_length_panic() {
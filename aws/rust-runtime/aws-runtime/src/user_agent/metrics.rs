@@ -141,6 +141,8 @@ impl ProvideBusinessMetric for SmithySdkFeature {
             Paginator => Some(BusinessMetric::Paginator),
             GzipRequestCompression => Some(BusinessMetric::GzipRequestCompression),
             ProtocolRpcV2Cbor => Some(BusinessMetric::ProtocolRpcV2Cbor),
+            // There are no business metrics for these algorithms yet
+            ZstdRequestCompression | BrotliRequestCompression => None,
             otherwise => {
                 // This may occur if a customer upgrades only the `aws-smithy-runtime-api` crate
                 // while continuing to use an outdated version of an SDK crate or the `aws-runtime`
@@ -9,10 +9,13 @@ use aws_smithy_async::test_util::tick_advance_sleep::{
     tick_advance_time_and_sleep, TickAdvanceTime,
 };
 use aws_smithy_runtime::{
-    client::http::test_util::dvr::ReplayingClient, test_util::capture_test_logs::show_test_logs,
+    client::http::test_util::dvr::ReplayingClient,
+    client::waiters::{CancellationToken, Jitter},
+    test_util::capture_test_logs::show_test_logs,
 };
 use aws_smithy_runtime_api::client::waiters::error::WaiterError;
 use aws_smithy_types::retry::RetryConfig;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 async fn prerequisites() -> (Client, ReplayingClient, TickAdvanceTime) {
@@ -87,6 +90,67 @@ async fn waiters_exceed_max_wait_time() {
     }
 }
 
+#[tokio::test]
+async fn waiters_report_progress() {
+    let _logs = show_test_logs();
+
+    let (ec2, _, time_source) = prerequisites().await;
+
+    ec2.start_instances()
+        .instance_ids("i-09fb4224219ac6902")
+        .send()
+        .await
+        .unwrap();
+
+    let poll_counts = Arc::new(Mutex::new(Vec::new()));
+    let waiter_task = tokio::spawn({
+        let poll_counts = poll_counts.clone();
+        ec2.wait_until_instance_status_ok()
+            .instance_ids("i-09fb4224219ac6902")
+            .jitter(Jitter::Disabled)
+            .wait_with_progress(Duration::from_secs(300), move |progress| {
+                poll_counts.lock().unwrap().push(progress.poll_count());
+            })
+    });
+
+    time_source.tick(Duration::from_secs(305)).await;
+    waiter_task.await.unwrap().unwrap();
+
+    // Every poll is reported, including the one that succeeded
+    let poll_counts = poll_counts.lock().unwrap();
+    assert!(poll_counts.len() > 1);
+    assert_eq!((1..=poll_counts.len() as u32).collect::<Vec<_>>(), *poll_counts);
+}
+
+#[tokio::test]
+async fn waiters_cancelled() {
+    let _logs = show_test_logs();
+
+    let (ec2, _, time_source) = prerequisites().await;
+
+    ec2.start_instances()
+        .instance_ids("i-09fb4224219ac6902")
+        .send()
+        .await
+        .unwrap();
+
+    let token = CancellationToken::new();
+    let waiter_task = tokio::spawn(
+        ec2.wait_until_instance_status_ok()
+            .instance_ids("i-09fb4224219ac6902")
+            .cancellation_token(token.clone())
+            .wait(Duration::from_secs(300)),
+    );
+
+    time_source.tick(Duration::from_secs(1)).await;
+    token.cancel();
+    let err = waiter_task.await.unwrap().err().expect("should be cancelled");
+    match err {
+        WaiterError::Cancelled(context) => assert_eq!(1, context.poll_count()),
+        err => panic!("unexpected error: {}", DisplayErrorContext(&err)),
+    }
+}
+
 #[tokio::test]
 async fn should_emit_business_metric_for_waiter_in_user_agent() {
     // This function has the same setup and execution as `waiters_success`, but differs in the verification step.
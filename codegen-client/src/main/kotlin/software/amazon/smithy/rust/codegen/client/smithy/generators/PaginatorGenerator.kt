@@ -93,6 +93,7 @@ class PaginatorGenerator private constructor(
         arrayOf(
             *preludeScope,
             "page_size_setter" to pageSizeSetter(),
+            "starting_token_setter" to startingTokenSetter(),
             // Operation Types
             "operation" to symbolProvider.toSymbol(operation),
             "Input" to inputType,
@@ -139,6 +140,8 @@ class PaginatorGenerator private constructor(
 
                     #{page_size_setter:W}
 
+                    #{starting_token_setter:W}
+
                     #{items_fn:W}
 
                     /// Stop paginating when the service returns the same pagination token twice in a row.
@@ -355,4 +358,26 @@ class PaginatorGenerator private constructor(
                 )
             }
         }
+
+    private fun startingTokenSetter() =
+        writable {
+            val memberName = symbolProvider.toMemberName(paginationInfo.inputTokenMember)
+            val tokenT =
+                symbolProvider.toSymbol(paginationInfo.inputTokenMember).rustType().stripOuter<RustType.Option>()
+                    .render(true)
+            rustTemplate(
+                """
+                /// Resume pagination from a token saved from a previous page
+                ///
+                /// The token is usually the pagination token of the last page that was processed.
+                ///
+                /// _Note: this method will override any previously set value for `$memberName`_
+                pub fn starting_token(mut self, token: $tokenT) -> Self {
+                    self.builder.$memberName = #{Some}(token);
+                    self
+                }
+                """,
+                *preludeScope,
+            )
+        }
 }
@@ -54,6 +54,9 @@ interface FluentBuilderConfig {
 
     /** Whether to include config override or not */
     fun includeConfigOverride(): Boolean = true
+
+    /** Additional optional fields on the builder struct, by name. They start out as `None`. */
+    fun additionalFields(): Map<String, RuntimeType> = emptyMap()
 }
 
 private fun FluentBuilderConfig.sendOverridden(): Boolean = sendMethods() != null
@@ -173,9 +176,16 @@ class FluentBuilderGenerator(
             pub struct $builderName {
                 handle: #{Arc}<crate::client::Handle>,
                 inner: #{InputBuilder},$configOverride
+                #{additional_fields}
             }
             """,
             *scope,
+            "additional_fields" to
+                writable {
+                    config.additionalFields().forEach { (name, type) ->
+                        rustTemplate("$name: #{Option}<#{Type}>,", *preludeScope, "Type" to type)
+                    }
+                },
         )
     }
 
@@ -193,10 +203,17 @@ class FluentBuilderGenerator(
                     Self {
                         handle,
                         inner: #{Default}::default(),$configOverride
+                        #{additional_fields}
                     }
                 }
                 """,
                 *scope,
+                "additional_fields" to
+                    writable {
+                        config.additionalFields().keys.forEach { name ->
+                            rustTemplate("$name: #{None},", *preludeScope)
+                        }
+                    },
             )
 
             rustTemplate(
@@ -151,10 +151,14 @@ private class WaiterFluentBuilderConfig(
     private val model = codegenContext.model
     private val runtimeConfig = codegenContext.runtimeConfig
     private val symbolProvider = codegenContext.symbolProvider
+    private val jitter = RuntimeType.smithyRuntime(runtimeConfig).resolve("client::waiters::Jitter")
+    private val cancellationToken =
+        RuntimeType.smithyRuntime(runtimeConfig).resolve("client::waiters::CancellationToken")
 
     private val scope =
         arrayOf(
             *preludeScope,
+            "CancellationToken" to cancellationToken,
             "ConfigBag" to RuntimeType.configBag(runtimeConfig),
             "Duration" to RuntimeType.Duration,
             "Error" to RuntimeType.smithyRuntimeApi(runtimeConfig).resolve("client::interceptors::context::Error"),
@@ -162,6 +166,7 @@ private class WaiterFluentBuilderConfig(
             "HttpResponse" to
                 RuntimeType.smithyRuntimeApiClient(runtimeConfig)
                     .resolve("client::orchestrator::HttpResponse"),
+            "Jitter" to jitter,
             "Operation" to symbolProvider.toSymbol(operation),
             "OperationError" to symbolProvider.symbolForOperationError(operation),
             "OperationOutput" to symbolProvider.toSymbol(operation.outputShape(model)),
@@ -170,6 +175,7 @@ private class WaiterFluentBuilderConfig(
                 RuntimeType.smithyRuntimeApiClient(runtimeConfig)
                     .resolve("client::waiters::error::WaiterError"),
             "WaiterOrchestrator" to RuntimeType.smithyRuntime(runtimeConfig).resolve("client::waiters::WaiterOrchestrator"),
+            "WaiterProgress" to RuntimeType.smithyRuntime(runtimeConfig).resolve("client::waiters::WaiterProgress"),
             "attach_waiter_tracing_span" to RuntimeType.smithyRuntime(runtimeConfig).resolve("client::waiters::attach_waiter_tracing_span"),
         )
 
@@ -177,6 +183,9 @@ private class WaiterFluentBuilderConfig(
 
     override fun includePaginators(): Boolean = false
 
+    override fun additionalFields(): Map<String, RuntimeType> =
+        mapOf("jitter" to jitter, "cancellation_token" to cancellationToken)
+
     override fun documentBuilder(): Writable =
         writable {
             docs(
@@ -197,10 +206,38 @@ private class WaiterFluentBuilderConfig(
     override fun sendMethods(): Writable =
         writable {
             val waiterDocs = waiter.documentation.orNull() ?: "Wait for `${waiterName.toSnakeCase()}`"
+            rustTemplate(
+                """
+                /// Sets the jitter applied to the delay between polls. Defaults to [`Jitter::Full`](#{Jitter}::Full).
+                pub fn jitter(mut self, jitter: #{Jitter}) -> Self {
+                    self.jitter = #{Some}(jitter);
+                    self
+                }
+
+                /// Sets a token that cancels the waiter.
+                ///
+                /// A cancelled waiter returns [`WaiterError::Cancelled`](#{WaiterError}::Cancelled).
+                pub fn cancellation_token(mut self, cancellation_token: #{CancellationToken}) -> Self {
+                    self.cancellation_token = #{Some}(cancellation_token);
+                    self
+                }
+
+                """,
+                *scope,
+            )
             docs(waiterDocs)
             rustTemplate(
                 """
                 pub async fn wait(self, max_wait: #{Duration}) -> #{Result}<#{FinalPollAlias}, #{WaiterErrorAlias}> {
+                    self.wait_with_progress(max_wait, |_| {}).await
+                }
+
+                /// Waits like [`wait`](Self::wait), calling `progress` after each poll.
+                pub async fn wait_with_progress(
+                    self,
+                    max_wait: #{Duration},
+                    progress: impl #{FnMut}(#{WaiterProgress}<'_, #{OperationOutput}, #{OperationError}>),
+                ) -> #{Result}<#{FinalPollAlias}, #{WaiterErrorAlias}> {
                     let input = self.inner.build()
                         .map_err(#{WaiterError}::construction_failure)?;
                     let runtime_plugins = #{Operation}::operation_runtime_plugins(
@@ -223,16 +260,23 @@ private class WaiterFluentBuilderConfig(
                             #{Operation}::orchestrate(&runtime_plugins, input).await
                         }
                     };
-                    let orchestrator = #{WaiterOrchestrator}::builder()
+                    let mut orchestrator = #{WaiterOrchestrator}::builder()
                         .min_delay(#{Duration}::from_secs(${waiter.minDelay}))
                         .max_delay(#{Duration}::from_secs(${waiter.maxDelay}))
                         .max_wait(max_wait)
                         .time_source(time_source)
-                        .sleep_impl(sleep_impl)
+                        .sleep_impl(sleep_impl);
+                    if let #{Some}(jitter) = self.jitter {
+                        orchestrator = orchestrator.jitter(jitter);
+                    }
+                    if let #{Some}(cancellation_token) = self.cancellation_token {
+                        orchestrator = orchestrator.cancellation_token(cancellation_token);
+                    }
+                    let orchestrator = orchestrator
                         .acceptor(acceptor)
                         .operation(operation)
                         .build();
-                    #{attach_waiter_tracing_span}(orchestrator.orchestrate()).await
+                    #{attach_waiter_tracing_span}(orchestrator.orchestrate_with_progress(progress)).await
                 }
                 """,
                 *scope,
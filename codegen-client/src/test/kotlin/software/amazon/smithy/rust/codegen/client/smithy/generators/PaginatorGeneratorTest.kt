@@ -82,6 +82,14 @@ internal class PaginatorGeneratorTest {
             rustCrate.integrationTest("paginators_generated") {
                 Attribute.AllowUnusedImports.render(this)
                 rust("use ${clientCodegenContext.moduleUseName()}::operation::paginated_list::paginator::PaginatedListPaginator;")
+                rust(
+                    """
+                    ##[allow(dead_code)]
+                    fn resume(paginator: PaginatedListPaginator) -> PaginatedListPaginator {
+                        paginator.starting_token("saved-token".to_string())
+                    }
+                    """,
+                )
             }
         }
     }
@@ -6,8 +6,9 @@
 //! Provides types to support stream-like operations for paginators.
 
 use crate::future::pagination_stream::collect::sealed::Collectable;
+use futures_util::future::{select, Either};
 use std::future::Future;
-use std::pin::Pin;
+use std::pin::{pin, Pin};
 use std::task::{Context, Poll};
 
 pub mod collect;
@@ -71,8 +72,98 @@ impl<Item> PaginationStream<Item> {
     pub async fn collect<T: Collectable<Item>>(self) -> T {
         self.0.collect().await
     }
+
+    /// Produces a new [`PaginationStream`] that yields at most `n` items from this stream.
+    ///
+    /// For paginators, the items are pages. Once `n` pages have been yielded, no more pages are
+    /// requested.
+    pub fn take_pages(mut self, n: usize) -> PaginationStream<Item>
+    where
+        Item: Send + 'static,
+    {
+        PaginationStream::new(FnStream::new(move |tx| {
+            Box::pin(async move {
+                for _ in 0..n {
+                    match self.next().await {
+                        Some(item) => {
+                            if tx.send(item).await.is_err() {
+                                return;
+                            }
+                        }
+                        None => return,
+                    }
+                }
+            })
+        }))
+    }
+
+    /// Produces a new [`PaginationStream`] that fetches up to `buffer` items ahead of the reader.
+    ///
+    /// Without prefetching, a paginator doesn't request the next page until the current page has
+    /// been read. With prefetching, the next pages are requested by a background task while the
+    /// reader processes the current page. Items are still yielded in order, and at most `buffer`
+    /// items are fetched, or being fetched, but not yet read.
+    ///
+    /// The background task is handed to `spawn` the first time the stream is polled, so that it can
+    /// run on any async runtime. With Tokio, for example:
+    ///
+    /// ```no_run
+    /// # async fn docs() {
+    /// # use aws_smithy_async::future::pagination_stream::PaginationStream;
+    /// # fn operation_to_yield_paginator<T>() -> PaginationStream<T> {
+    /// #     todo!()
+    /// # }
+    /// # struct Page;
+    /// let stream: PaginationStream<Page> = operation_to_yield_paginator();
+    /// let mut stream = stream.prefetch(2, |task| {
+    ///     tokio::spawn(task);
+    /// });
+    /// # }
+    /// ```
+    ///
+    /// The task stops once the returned stream is dropped, even if a fetch is in progress.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buffer` is zero.
+    pub fn prefetch(
+        mut self,
+        buffer: usize,
+        spawn: impl FnOnce(PrefetchTask) + Send + 'static,
+    ) -> PaginationStream<Item>
+    where
+        Item: Send + 'static,
+    {
+        assert!(buffer > 0, "the prefetch buffer must not be empty");
+        PaginationStream::new(FnStream::new(move |tx| {
+            Box::pin(async move {
+                let (prefetched_tx, mut prefetched) = tokio::sync::mpsc::channel(buffer);
+                spawn(Box::pin(async move {
+                    // Reserve room for the item before fetching it, so that items that are being
+                    // fetched count towards the buffer
+                    while let Ok(permit) = prefetched_tx.reserve().await {
+                        // Stop fetching as soon as the reader is dropped
+                        let next = pin!(self.next());
+                        let closed = pin!(prefetched_tx.closed());
+                        match select(next, closed).await {
+                            Either::Left((Some(item), _)) => permit.send(item),
+                            _ => return,
+                        }
+                    }
+                }));
+                while let Some(item) = prefetched.recv().await {
+                    if tx.send(item).await.is_err() {
+                        return;
+                    }
+                }
+            })
+        }))
+    }
 }
 
+/// Background task that [`PaginationStream::prefetch`] hands to its spawner
+pub type PrefetchTask = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
 impl<T, E> PaginationStream<Result<T, E>> {
     /// Yields the next item in the stream or returns an error if an error is encountered.
     pub async fn try_next(&mut self) -> Result<Option<T>, E> {
@@ -83,6 +174,53 @@ impl<T, E> PaginationStream<Result<T, E>> {
     pub async fn try_collect(self) -> Result<Vec<T>, E> {
         self.collect::<Result<Vec<T>, E>>().await
     }
+
+    /// Produces a new [`PaginationStream`] that yields at most `n` successful items.
+    ///
+    /// Errors are yielded without counting towards the limit, and end the stream.
+    pub fn limit_items(mut self, n: usize) -> PaginationStream<Result<T, E>>
+    where
+        T: Send + 'static,
+        E: Send + 'static,
+    {
+        PaginationStream::new(FnStream::new(move |tx| {
+            Box::pin(async move {
+                let mut remaining = n;
+                while remaining > 0 {
+                    let item = match self.next().await {
+                        Some(Ok(item)) => {
+                            remaining -= 1;
+                            Ok(item)
+                        }
+                        Some(Err(err)) => {
+                            remaining = 0;
+                            Err(err)
+                        }
+                        None => return,
+                    };
+                    if tx.send(item).await.is_err() {
+                        return;
+                    }
+                }
+            })
+        }))
+    }
+
+    /// Produces a new [`PaginationStream`] by mapping each page with `map` then flattening the
+    /// result.
+    ///
+    /// This is equivalent to [`TryFlatMap::flat_map`].
+    pub fn try_flat_map<M, Item, Iter>(self, map: M) -> PaginationStream<Result<Item, E>>
+    where
+        T: Send + 'static,
+        E: Send + 'static,
+        M: Fn(T) -> Iter + Send + 'static,
+        Item: Send + 'static,
+        Iter: IntoIterator<Item = Item> + Send,
+        <Iter as IntoIterator>::IntoIter: Send,
+    {
+        TryFlatMap::new(self).flat_map(map)
+    }
 }
 
 /// Utility wrapper to flatten paginated results
@@ -100,6 +238,9 @@ impl<Page, Err> TryFlatMap<Page, Err> {
     }
 
     /// Produces a new [`PaginationStream`] by mapping this stream with `map` then flattening the result.
+    ///
+    /// Items are yielded one at a time, so the next page isn't requested until every item of the
+    /// current page has been read.
     pub fn flat_map<M, Item, Iter>(mut self, map: M) -> PaginationStream<Result<Item, Err>>
     where
         Page: Send + 'static,
@@ -116,7 +257,9 @@ impl<Page, Err> TryFlatMap<Page, Err> {
                         Ok(page) => {
                             let mapped = map(page);
                             for item in mapped.into_iter() {
-                                let _ = tx.send(Ok(item)).await;
+                                if tx.send(Ok(item)).await.is_err() {
+                                    return;
+                                }
                             }
                         }
                         Err(e) => {
@@ -133,6 +276,7 @@ impl<Page, Err> TryFlatMap<Page, Err> {
 #[cfg(test)]
 mod test {
     use crate::future::pagination_stream::{FnStream, PaginationStream, TryFlatMap};
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use std::sync::{Arc, Mutex};
     use std::time::Duration;
 
@@ -306,4 +450,120 @@ mod test {
                 .await
         )
     }
+
+    /// A stream of `pages` pages that records how many pages have been fetched
+    fn counted_pages(
+        pages: usize,
+        fetched: Arc<AtomicUsize>,
+    ) -> PaginationStream<Result<usize, &'static str>> {
+        PaginationStream::new(FnStream::new(move |tx| {
+            Box::pin(async move {
+                for page in 0..pages {
+                    fetched.fetch_add(1, Ordering::SeqCst);
+                    if tx.send(Ok(page)).await.is_err() {
+                        return;
+                    }
+                }
+            })
+        }))
+    }
+
+    #[tokio::test]
+    async fn take_pages_stops_fetching() {
+        let fetched = Arc::new(AtomicUsize::new(0));
+        let pages = counted_pages(10, fetched.clone())
+            .take_pages(3)
+            .try_collect()
+            .await;
+        assert_eq!(Ok(vec![0, 1, 2]), pages);
+        assert_eq!(3, fetched.load(Ordering::SeqCst));
+
+        let pages = counted_pages(2, fetched.clone())
+            .take_pages(3)
+            .try_collect()
+            .await;
+        assert_eq!(Ok(vec![0, 1]), pages);
+    }
+
+    #[tokio::test]
+    async fn limit_items_counts_successful_items() {
+        let stream = PaginationStream::new(FnStream::new(|tx| {
+            Box::pin(async move {
+                tx.send(Ok(vec![1, 2])).await.unwrap();
+                tx.send(Ok(vec![3, 4])).await.unwrap();
+                tx.send(Err("unreachable")).await.unwrap();
+            })
+        }));
+        assert_eq!(
+            Ok(vec![1, 2, 3]),
+            stream
+                .try_flat_map(|page| page)
+                .limit_items(3)
+                .try_collect()
+                .await
+        );
+
+        let stream = PaginationStream::new(FnStream::new(|tx| {
+            Box::pin(async move {
+                tx.send(Ok(vec![1])).await.unwrap();
+                tx.send(Err("bummer")).await.unwrap();
+            })
+        }));
+        let mut stream = stream.try_flat_map(|page| page).limit_items(3);
+        assert_eq!(Some(Ok(1)), stream.next().await);
+        assert_eq!(Some(Err("bummer")), stream.next().await);
+        assert_eq!(None, stream.next().await);
+    }
+
+    #[tokio::test]
+    async fn prefetch_fetches_ahead_of_the_reader() {
+        tokio::time::pause();
+        let fetched = Arc::new(AtomicUsize::new(0));
+        let mut stream = counted_pages(10, fetched.clone()).prefetch(2, |task| {
+            tokio::spawn(task);
+        });
+        assert_eq!(0, fetched.load(Ordering::SeqCst));
+
+        assert_eq!(Ok(Some(0)), stream.try_next().await);
+        // let the background task run while the first page is "processed"
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(3, fetched.load(Ordering::SeqCst));
+
+        assert_eq!(Ok(Some(1)), stream.try_next().await);
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(4, fetched.load(Ordering::SeqCst));
+
+        let rest = stream.try_collect().await;
+        assert_eq!(Ok((2..10).collect::<Vec<_>>()), rest);
+        assert_eq!(10, fetched.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn prefetch_runs_on_the_given_spawner() {
+        let fetched = Arc::new(AtomicUsize::new(0));
+        let stream = counted_pages(10, fetched.clone()).prefetch(2, |task| {
+            // run the task on a thread of its own instead of the test's runtime
+            std::thread::spawn(move || {
+                tokio::runtime::Builder::new_current_thread()
+                    .build()
+                    .unwrap()
+                    .block_on(task)
+            });
+        });
+        assert_eq!(Ok((0..10).collect::<Vec<_>>()), stream.try_collect().await);
+        assert_eq!(10, fetched.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn prefetch_stops_when_dropped() {
+        tokio::time::pause();
+        let fetched = Arc::new(AtomicUsize::new(0));
+        let mut stream = counted_pages(10, fetched.clone()).prefetch(1, |task| {
+            tokio::spawn(task);
+        });
+        assert_eq!(Ok(Some(0)), stream.try_next().await);
+        drop(stream);
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(2, fetched.load(Ordering::SeqCst));
+    }
 }
@@ -0,0 +1,74 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use aws_smithy_checksums::parallel::ParallelChecksum;
+use aws_smithy_checksums::ChecksumAlgorithm;
+use bytes::Bytes;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const BODY_SIZE: usize = 64 * 1024 * 1024;
+// The size of the pieces of data a body yields
+const FRAME_SIZE: usize = 64 * 1024;
+
+fn frames() -> Vec<Bytes> {
+    let body: Bytes = (0..BODY_SIZE).map(|i| (i % 251) as u8).collect();
+    (0..BODY_SIZE)
+        .step_by(FRAME_SIZE)
+        .map(|start| body.slice(start..start + FRAME_SIZE))
+        .collect()
+}
+
+fn serial(algorithms: &[ChecksumAlgorithm], frames: &[Bytes]) {
+    let mut checksums: Vec<_> = algorithms.iter().map(|a| a.into_impl()).collect();
+    for frame in frames {
+        for checksum in &mut checksums {
+            checksum.update(frame);
+        }
+    }
+    for checksum in checksums {
+        black_box(checksum.finalize());
+    }
+}
+
+fn parallel(algorithms: &[ChecksumAlgorithm], frames: &[Bytes]) {
+    let mut builder = ParallelChecksum::builder();
+    for algorithm in algorithms {
+        builder = builder.algorithm(*algorithm);
+    }
+    let mut checksum = builder.build().unwrap();
+    for frame in frames {
+        checksum.update(frame.clone());
+    }
+    black_box(checksum.finalize().unwrap());
+}
+
+pub fn parallel_benchmark(c: &mut Criterion) {
+    let frames = frames();
+    let cases: [(&str, &[ChecksumAlgorithm]); 4] = [
+        ("crc32", &[ChecksumAlgorithm::Crc32]),
+        ("crc32c", &[ChecksumAlgorithm::Crc32c]),
+        ("crc64nvme", &[ChecksumAlgorithm::Crc64Nvme]),
+        (
+            "crc32c+sha256",
+            &[ChecksumAlgorithm::Crc32c, ChecksumAlgorithm::Sha256],
+        ),
+    ];
+
+    let mut group = c.benchmark_group("checksum");
+    group.throughput(Throughput::Bytes(BODY_SIZE as u64));
+    group.sample_size(10);
+    for (name, algorithms) in cases {
+        group.bench_with_input(BenchmarkId::new("serial", name), algorithms, |b, a| {
+            b.iter(|| serial(a, &frames))
+        });
+        group.bench_with_input(BenchmarkId::new("parallel", name), algorithms, |b, a| {
+            b.iter(|| parallel(a, &frames))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parallel_benchmark);
+criterion_main!(benches);
@@ -6,10 +6,12 @@
 //! Functionality for calculating the checksum of an HTTP body and emitting it as trailers.
 
 use crate::http::HttpChecksum;
+use crate::parallel::{self, ParallelChecksum};
 
 use aws_smithy_http::header::append_merge_header_maps;
 use aws_smithy_types::body::SdkBody;
 
+use bytes::Bytes;
 use http::HeaderMap;
 use http_body::SizeHint;
 use pin_project_lite::pin_project;
@@ -22,7 +24,45 @@ pin_project! {
     pub struct ChecksumBody<InnerBody> {
             #[pin]
             body: InnerBody,
-            checksum: Option<Box<dyn HttpChecksum>>,
+            checksum: Option<Checksums>,
+    }
+}
+
+enum Checksums {
+    Serial(Box<dyn HttpChecksum>),
+    Parallel(ParallelChecksum),
+}
+
+impl Checksums {
+    fn update(&mut self, data: &Bytes) {
+        match self {
+            Checksums::Serial(checksum) => checksum.update(data),
+            // Parallel checksums are calculated on worker threads, without blocking the executor
+            Checksums::Parallel(checksum) => checksum.push(data.clone()),
+        }
+    }
+
+    /// Wait until the checksum is ready to accept more data.
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        match self {
+            Checksums::Serial(_) => Poll::Ready(()),
+            Checksums::Parallel(checksum) => checksum.poll_dispatch(cx),
+        }
+    }
+
+    /// Wait until all data has been checksummed.
+    fn poll_finish(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), parallel::Error>> {
+        match self {
+            Checksums::Serial(_) => Poll::Ready(Ok(())),
+            Checksums::Parallel(checksum) => checksum.poll_finish(cx),
+        }
+    }
+
+    fn headers(self) -> Result<HeaderMap, parallel::Error> {
+        match self {
+            Checksums::Serial(checksum) => Ok(checksum.headers()),
+            Checksums::Parallel(checksum) => checksum.headers(),
+        }
     }
 }
 
@@ -31,7 +71,19 @@ impl ChecksumBody<SdkBody> {
     pub fn new(body: SdkBody, checksum: Box<dyn HttpChecksum>) -> Self {
         Self {
             body,
-            checksum: Some(checksum),
+            checksum: Some(Checksums::Serial(checksum)),
+        }
+    }
+
+    /// Given an `SdkBody` and a [`ParallelChecksum`], create a new `ChecksumBody<SdkBody>` that
+    /// calculates its checksums on a pool of worker threads and emits one trailer per algorithm.
+    ///
+    /// Polling the body never checksums data on the polling thread. Instead, the body waits
+    /// for the worker threads when it has buffered a full batch of data.
+    pub fn new_parallel(body: SdkBody, checksum: ParallelChecksum) -> Self {
+        Self {
+            body,
+            checksum: Some(Checksums::Parallel(checksum)),
         }
     }
 }
@@ -47,6 +99,9 @@ impl http_body::Body for ChecksumBody<SdkBody> {
         let this = self.project();
         match this.checksum {
             Some(checksum) => {
+                if checksum.poll_ready(cx).is_pending() {
+                    return Poll::Pending;
+                }
                 let poll_res = this.body.poll_data(cx);
                 if let Poll::Ready(Some(Ok(data))) = &poll_res {
                     checksum.update(data);
@@ -63,11 +118,21 @@ impl http_body::Body for ChecksumBody<SdkBody> {
         cx: &mut Context<'_>,
     ) -> Poll<Result<Option<HeaderMap>, Self::Error>> {
         let this = self.project();
+        if let Some(checksum) = this.checksum.as_mut() {
+            match checksum.poll_finish(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err.into())),
+                Poll::Ready(Ok(())) => {}
+            }
+        }
         let poll_res = this.body.poll_trailers(cx);
 
         if let Poll::Ready(Ok(maybe_inner_trailers)) = poll_res {
             let checksum_headers = if let Some(checksum) = this.checksum.take() {
-                checksum.headers()
+                match checksum.headers() {
+                    Ok(headers) => headers,
+                    Err(err) => return Poll::Ready(Err(err.into())),
+                }
             } else {
                 return Poll::Ready(Ok(None));
             };
@@ -98,6 +163,7 @@ impl http_body::Body for ChecksumBody<SdkBody> {
 #[cfg(test)]
 mod tests {
     use super::ChecksumBody;
+    use crate::parallel::ParallelChecksum;
     use crate::{http::CRC_32_HEADER_NAME, ChecksumAlgorithm, CRC_32_NAME};
     use aws_smithy_types::base64;
     use aws_smithy_types::body::SdkBody;
@@ -155,4 +221,101 @@ mod tests {
         // Known correct checksum for the input "This is some test text for an SdkBody"
         assert_eq!("0x99B01F72", checksum_trailer);
     }
+
+    #[tokio::test]
+    async fn test_parallel_checksum_body() {
+        let input_text = "This is some test text for an SdkBody";
+        let body = SdkBody::from(input_text);
+        let checksum = ParallelChecksum::builder()
+            .algorithm(ChecksumAlgorithm::Crc32)
+            .algorithm(ChecksumAlgorithm::Sha1)
+            .chunk_size(8)
+            .build()
+            .unwrap();
+        let mut body = ChecksumBody::new_parallel(body, checksum);
+
+        let mut output = SegmentedBuf::new();
+        while let Some(buf) = body.data().await {
+            output.push(buf.unwrap());
+        }
+
+        let mut output_text = String::new();
+        output
+            .reader()
+            .read_to_string(&mut output_text)
+            .expect("Doesn't cause IO errors");
+        assert_eq!(input_text, output_text);
+
+        let trailers = body
+            .trailers()
+            .await
+            .expect("checksum generation was without error")
+            .expect("trailers were set");
+        let checksum_trailer = header_value_as_checksum_string(
+            trailers
+                .get(CRC_32_HEADER_NAME)
+                .expect("trailers contain crc32 checksum"),
+        );
+        assert_eq!("0x99B01F72", checksum_trailer);
+        assert!(trailers.contains_key("x-amz-checksum-sha1"));
+    }
+
+    /// A streaming body that yields its data in pieces
+    struct PiecesBody(std::collections::VecDeque<bytes::Bytes>);
+
+    impl Body for PiecesBody {
+        type Data = bytes::Bytes;
+        type Error = aws_smithy_types::body::Error;
+
+        fn poll_data(
+            mut self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<Option<Result<Self::Data, Self::Error>>> {
+            std::task::Poll::Ready(self.0.pop_front().map(Ok))
+        }
+
+        fn poll_trailers(
+            self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<Result<Option<http::HeaderMap>, Self::Error>> {
+            std::task::Poll::Ready(Ok(None))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_parallel_checksum_body_over_many_batches() {
+        let input: Vec<u8> = (0..50_000u32).map(|i| (i % 251) as u8).collect();
+        // Uneven pieces so that chunks span several pieces of data
+        let pieces = input
+            .chunks(777)
+            .map(bytes::Bytes::copy_from_slice)
+            .collect();
+        let body = SdkBody::from_body_0_4(PiecesBody(pieces));
+        let checksum = ParallelChecksum::builder()
+            .algorithm(ChecksumAlgorithm::Crc32)
+            .algorithm(ChecksumAlgorithm::Sha256)
+            .chunk_size(1000)
+            .parallelism(std::num::NonZeroUsize::new(2).unwrap())
+            .build()
+            .unwrap();
+        let mut body = ChecksumBody::new_parallel(body, checksum);
+
+        let mut output = Vec::new();
+        while let Some(buf) = body.data().await {
+            output.extend_from_slice(&buf.unwrap());
+        }
+        assert_eq!(input, output);
+
+        let trailers = body.trailers().await.unwrap().expect("trailers were set");
+        for algorithm in [ChecksumAlgorithm::Crc32, ChecksumAlgorithm::Sha256] {
+            let mut expected = algorithm.into_impl();
+            let header_name = expected.header_name();
+            expected.update(&input);
+            assert_eq!(
+                expected.header_value(),
+                trailers.get(header_name).unwrap(),
+                "{algorithm:?}"
+            );
+        }
+    }
 }
@@ -218,4 +218,22 @@ mod tests {
         // Verify data is complete and unaltered
         assert_eq!(input_text, output_text);
     }
+
+    #[tokio::test]
+    async fn test_crc64nvme_checksum_validated_body_succeeds_on_match() {
+        let input_text = "This is some test text for an SdkBody";
+        let mut digest = crc64fast_nvme::Digest::new();
+        digest.write(input_text.as_bytes());
+        let actual_checksum = Bytes::copy_from_slice(&digest.sum64().to_be_bytes());
+        let body = SdkBody::from(input_text);
+        let http_checksum = "crc64nvme"
+            .parse::<ChecksumAlgorithm>()
+            .unwrap()
+            .into_impl();
+        let mut body = ChecksumBody::new(body, http_checksum, actual_checksum);
+
+        while let Some(data) = body.data().await {
+            data.expect("checksum matches");
+        }
+    }
 }
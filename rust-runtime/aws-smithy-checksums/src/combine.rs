@@ -0,0 +1,415 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Combining part-level checksums into a checksum for a whole object.
+//!
+//! When an object is uploaded in parts, each part is checksummed on its own. There are two ways of
+//! turning those part-level checksums into an object-level checksum:
+//!
+//! - A [`FullObjectChecksum`] mathematically combines CRC checksums of consecutive parts into the
+//!   checksum that would have been calculated if the whole object had been read in one go. This is
+//!   only possible for the CRC-based algorithms.
+//! - A [`CompositeChecksum`] is a "checksum of checksums": the part checksums are concatenated and
+//!   checksummed again. This works for every algorithm, but the result doesn't match a checksum of
+//!   the object's data.
+
+use crate::http::HttpChecksum;
+use crate::{Checksum, ChecksumAlgorithm};
+use aws_smithy_types::base64;
+use bytes::Bytes;
+use http::HeaderValue;
+use std::fmt::Display;
+
+// Reversed (bit-reflected) generator polynomials for the combinable CRC algorithms
+const CRC_32_POLYNOMIAL: u64 = 0xEDB8_8320;
+const CRC_32_C_POLYNOMIAL: u64 = 0x82F6_3B78;
+const CRC_64_NVME_POLYNOMIAL: u64 = 0x9A6C_9329_AC4B_C9B5;
+
+/// Errors related to combining part-level checksums
+#[derive(Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The checksum algorithm can't be used to build a full-object checksum. Only CRC-based
+    /// algorithms can be combined; use a [`CompositeChecksum`] for other algorithms.
+    UnsupportedAlgorithm(ChecksumAlgorithm),
+    /// A part checksum didn't have the length produced by the checksum algorithm.
+    InvalidPartChecksumLength { expected: usize, actual: usize },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Error::UnsupportedAlgorithm(algorithm) => write!(
+                f,
+                "checksums calculated with {} can't be combined into a full-object checksum",
+                algorithm.as_str()
+            ),
+            Error::InvalidPartChecksumLength { expected, actual } => write!(
+                f,
+                "part checksum was {actual} bytes long, but checksums of this algorithm are {expected} bytes long"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Parameters of a bit-reflected CRC that are needed to combine two checksums.
+#[derive(Debug, Clone, Copy)]
+struct Crc {
+    polynomial: u64,
+    width: u32,
+}
+
+impl Crc {
+    fn for_algorithm(algorithm: ChecksumAlgorithm) -> Option<Self> {
+        let (polynomial, width) = match algorithm {
+            ChecksumAlgorithm::Crc32 => (CRC_32_POLYNOMIAL, 32),
+            ChecksumAlgorithm::Crc32c => (CRC_32_C_POLYNOMIAL, 32),
+            ChecksumAlgorithm::Crc64Nvme => (CRC_64_NVME_POLYNOMIAL, 64),
+            _ => return None,
+        };
+        Some(Self { polynomial, width })
+    }
+
+    /// The polynomial `x^0`, which is the highest bit in the reflected representation.
+    fn one(&self) -> u64 {
+        1 << (self.width - 1)
+    }
+
+    /// Multiply `a` and `b` modulo the generator polynomial.
+    fn multiply(&self, a: u64, mut b: u64) -> u64 {
+        let mut mask = self.one();
+        let mut product = 0;
+        while mask != 0 {
+            if a & mask != 0 {
+                product ^= b;
+            }
+            mask >>= 1;
+            b = if b & 1 != 0 {
+                (b >> 1) ^ self.polynomial
+            } else {
+                b >> 1
+            };
+        }
+        product
+    }
+
+    /// Calculate `x^(8 * length)` modulo the generator polynomial, which is the operator that
+    /// shifts a CRC past `length` zero bytes.
+    fn shift_operator(&self, mut length: u64) -> u64 {
+        let mut result = self.one();
+        // x^1, squared three times to get x^8
+        let mut square = self.one() >> 1;
+        for _ in 0..3 {
+            square = self.multiply(square, square);
+        }
+        while length != 0 {
+            if length & 1 != 0 {
+                result = self.multiply(square, result);
+            }
+            square = self.multiply(square, square);
+            length >>= 1;
+        }
+        result
+    }
+
+    /// Given the CRCs of two consecutive blocks of data, return the CRC of both blocks.
+    fn combine(&self, first: u64, second: u64, second_length: u64) -> u64 {
+        self.multiply(self.shift_operator(second_length), first) ^ second
+    }
+}
+
+/// Given the CRC32 checksums of two consecutive blocks of data and the length of the second block,
+/// return the CRC32 checksum of both blocks as if they'd been checksummed together.
+pub fn combine_crc32(first: u32, second: u32, second_length: u64) -> u32 {
+    Crc::for_algorithm(ChecksumAlgorithm::Crc32)
+        .expect("CRC32 is combinable")
+        .combine(first.into(), second.into(), second_length) as u32
+}
+
+/// Given the CRC32C checksums of two consecutive blocks of data and the length of the second block,
+/// return the CRC32C checksum of both blocks as if they'd been checksummed together.
+pub fn combine_crc32c(first: u32, second: u32, second_length: u64) -> u32 {
+    Crc::for_algorithm(ChecksumAlgorithm::Crc32c)
+        .expect("CRC32C is combinable")
+        .combine(first.into(), second.into(), second_length) as u32
+}
+
+/// Given the CRC64-NVME checksums of two consecutive blocks of data and the length of the second
+/// block, return the CRC64-NVME checksum of both blocks as if they'd been checksummed together.
+pub fn combine_crc64_nvme(first: u64, second: u64, second_length: u64) -> u64 {
+    Crc::for_algorithm(ChecksumAlgorithm::Crc64Nvme)
+        .expect("CRC64-NVME is combinable")
+        .combine(first, second, second_length)
+}
+
+/// Combines the CRC checksums of an object's parts into the checksum of the whole object.
+///
+/// Parts must be added in the order they appear in the object.
+#[derive(Debug)]
+pub struct FullObjectChecksum {
+    algorithm: ChecksumAlgorithm,
+    crc: Crc,
+    state: Option<u64>,
+    length: u64,
+}
+
+impl FullObjectChecksum {
+    /// Create a new `FullObjectChecksum` for the given algorithm, or return an error if checksums
+    /// of that algorithm can't be combined.
+    pub fn new(algorithm: ChecksumAlgorithm) -> Result<Self, Error> {
+        let crc = Crc::for_algorithm(algorithm).ok_or(Error::UnsupportedAlgorithm(algorithm))?;
+        Ok(Self {
+            algorithm,
+            crc,
+            state: None,
+            length: 0,
+        })
+    }
+
+    /// Add the checksum of the next part, as returned by [`Checksum::finalize`], along with the
+    /// length of that part in bytes.
+    pub fn add_part(&mut self, part_checksum: &[u8], part_length: u64) -> Result<(), Error> {
+        let expected = (self.crc.width / 8) as usize;
+        if part_checksum.len() != expected {
+            return Err(Error::InvalidPartChecksumLength {
+                expected,
+                actual: part_checksum.len(),
+            });
+        }
+        let part_checksum = part_checksum
+            .iter()
+            .fold(0u64, |acc, byte| (acc << 8) | u64::from(*byte));
+
+        self.state = Some(match self.state {
+            Some(state) => self.crc.combine(state, part_checksum, part_length),
+            None => part_checksum,
+        });
+        self.length += part_length;
+        Ok(())
+    }
+
+    /// Return the algorithm of the checksums being combined.
+    pub fn algorithm(&self) -> ChecksumAlgorithm {
+        self.algorithm
+    }
+
+    /// Return the combined length of all parts added so far.
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+
+    /// Return the checksum of the whole object, formatted like [`Checksum::finalize`].
+    pub fn finalize(self) -> Bytes {
+        let state = self.state.unwrap_or_default();
+        let bytes = state.to_be_bytes();
+        Bytes::copy_from_slice(&bytes[bytes.len() - (self.crc.width / 8) as usize..])
+    }
+}
+
+/// Builds a "checksum of checksums" from the checksums of an object's parts.
+///
+/// Parts must be added in the order they appear in the object.
+pub struct CompositeChecksum {
+    algorithm: ChecksumAlgorithm,
+    checksum: Box<dyn HttpChecksum>,
+    part_count: usize,
+}
+
+impl std::fmt::Debug for CompositeChecksum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CompositeChecksum")
+            .field("algorithm", &self.algorithm)
+            .field("part_count", &self.part_count)
+            .finish()
+    }
+}
+
+impl CompositeChecksum {
+    /// Create a new `CompositeChecksum` for the given algorithm.
+    pub fn new(algorithm: ChecksumAlgorithm) -> Self {
+        Self {
+            algorithm,
+            checksum: algorithm.into_impl(),
+            part_count: 0,
+        }
+    }
+
+    /// Add the checksum of the next part, as returned by [`Checksum::finalize`].
+    pub fn add_part(&mut self, part_checksum: &[u8]) -> Result<(), Error> {
+        let expected = Checksum::size(&*self.checksum) as usize;
+        if part_checksum.len() != expected {
+            return Err(Error::InvalidPartChecksumLength {
+                expected,
+                actual: part_checksum.len(),
+            });
+        }
+        self.checksum.update(part_checksum);
+        self.part_count += 1;
+        Ok(())
+    }
+
+    /// Return the algorithm of the checksums being combined.
+    pub fn algorithm(&self) -> ChecksumAlgorithm {
+        self.algorithm
+    }
+
+    /// Return the number of parts added so far.
+    pub fn part_count(&self) -> usize {
+        self.part_count
+    }
+
+    /// Return the checksum of the concatenated part checksums.
+    pub fn finalize(self) -> Bytes {
+        self.checksum.finalize()
+    }
+
+    /// Return the composite checksum as a `HeaderValue`, formatted the way S3 reports them: the
+    /// base64-encoded checksum followed by a dash and the number of parts.
+    pub fn header_value(self) -> HeaderValue {
+        let part_count = self.part_count;
+        let checksum = base64::encode(&self.finalize()[..]);
+        HeaderValue::from_str(&format!("{checksum}-{part_count}"))
+            .expect("base64 encoded bytes and digits are always valid header values")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        combine_crc32, combine_crc32c, combine_crc64_nvme, CompositeChecksum, Error,
+        FullObjectChecksum,
+    };
+    use crate::ChecksumAlgorithm;
+    use bytes::Bytes;
+
+    const TEST_DATA: &[u8] = b"Four score and seven years ago our fathers brought forth on this \
+        continent, a new nation, conceived in Liberty, and dedicated to the proposition that all \
+        men are created equal.";
+
+    fn checksum(algorithm: ChecksumAlgorithm, data: &[u8]) -> Bytes {
+        let mut checksum = algorithm.into_impl();
+        checksum.update(data);
+        checksum.finalize()
+    }
+
+    #[test]
+    fn test_combine_crc32() {
+        let (first, second) = TEST_DATA.split_at(40);
+        let combined = combine_crc32(
+            crc32fast::hash(first),
+            crc32fast::hash(second),
+            second.len() as u64,
+        );
+        assert_eq!(crc32fast::hash(TEST_DATA), combined);
+    }
+
+    // TODO(https://github.com/zowens/crc32c/issues/34)
+    // TODO(https://github.com/smithy-lang/smithy-rs/issues/1857)
+    #[cfg(not(any(target_arch = "powerpc", target_arch = "powerpc64")))]
+    #[test]
+    fn test_combine_crc32c() {
+        let (first, second) = TEST_DATA.split_at(17);
+        let combined = combine_crc32c(
+            crc32c::crc32c(first),
+            crc32c::crc32c(second),
+            second.len() as u64,
+        );
+        assert_eq!(crc32c::crc32c(TEST_DATA), combined);
+    }
+
+    #[test]
+    fn test_combine_crc64_nvme() {
+        let crc64 = |data: &[u8]| {
+            let mut digest = crc64fast_nvme::Digest::new();
+            digest.write(data);
+            digest.sum64()
+        };
+        let (first, second) = TEST_DATA.split_at(101);
+        let combined = combine_crc64_nvme(crc64(first), crc64(second), second.len() as u64);
+        assert_eq!(crc64(TEST_DATA), combined);
+    }
+
+    #[test]
+    fn test_combining_with_an_empty_part_is_a_no_op() {
+        let crc = crc32fast::hash(TEST_DATA);
+        assert_eq!(crc, combine_crc32(crc, crc32fast::hash(b""), 0));
+    }
+
+    #[test]
+    fn test_full_object_checksum_matches_checksum_of_whole_object() {
+        for algorithm in [
+            ChecksumAlgorithm::Crc32,
+            ChecksumAlgorithm::Crc32c,
+            ChecksumAlgorithm::Crc64Nvme,
+        ] {
+            let mut full_object_checksum = FullObjectChecksum::new(algorithm).unwrap();
+            for part in TEST_DATA.chunks(32) {
+                full_object_checksum
+                    .add_part(&checksum(algorithm, part), part.len() as u64)
+                    .unwrap();
+            }
+            assert_eq!(TEST_DATA.len() as u64, full_object_checksum.length());
+            assert_eq!(
+                checksum(algorithm, TEST_DATA),
+                full_object_checksum.finalize(),
+                "{algorithm:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_full_object_checksum_rejects_non_crc_algorithms() {
+        assert_eq!(
+            Error::UnsupportedAlgorithm(ChecksumAlgorithm::Sha256),
+            FullObjectChecksum::new(ChecksumAlgorithm::Sha256).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn test_full_object_checksum_rejects_checksums_of_the_wrong_length() {
+        let mut full_object_checksum = FullObjectChecksum::new(ChecksumAlgorithm::Crc32).unwrap();
+        assert_eq!(
+            Error::InvalidPartChecksumLength {
+                expected: 4,
+                actual: 8
+            },
+            full_object_checksum.add_part(&[0; 8], 10).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn test_composite_checksum_is_checksum_of_part_checksums() {
+        let algorithm = ChecksumAlgorithm::Sha256;
+        let (first, second) = TEST_DATA.split_at(64);
+        let first = checksum(algorithm, first);
+        let second = checksum(algorithm, second);
+
+        let mut composite_checksum = CompositeChecksum::new(algorithm);
+        composite_checksum.add_part(&first).unwrap();
+        composite_checksum.add_part(&second).unwrap();
+        assert_eq!(2, composite_checksum.part_count());
+
+        let expected = checksum(algorithm, &[first, second].concat());
+        assert_eq!(expected, composite_checksum.finalize());
+    }
+
+    #[test]
+    fn test_composite_checksum_header_value_includes_part_count() {
+        let algorithm = ChecksumAlgorithm::Sha1;
+        let part = checksum(algorithm, TEST_DATA);
+        let mut composite_checksum = CompositeChecksum::new(algorithm);
+        for _ in 0..3 {
+            composite_checksum.add_part(&part).unwrap();
+        }
+
+        let expected = format!(
+            "{}-3",
+            aws_smithy_types::base64::encode(&checksum(algorithm, &part.repeat(3))[..])
+        );
+        assert_eq!(expected, composite_checksum.header_value());
+    }
+}
@@ -9,14 +9,17 @@ use aws_smithy_types::base64;
 use http::header::{HeaderMap, HeaderValue};
 
 use crate::{
-    Checksum, Crc32, Crc32c, Md5, Sha1, Sha256, CRC_32_C_NAME, CRC_32_NAME, SHA_1_NAME,
-    SHA_256_NAME,
+    Checksum, Crc32, Crc32c, Crc64Nvme, Md5, Sha1, Sha256, Xxhash3, Xxhash64, CRC_32_C_NAME,
+    CRC_32_NAME, CRC_64_NVME_NAME, SHA_1_NAME, SHA_256_NAME, XXHASH_3_NAME, XXHASH_64_NAME,
 };
 
 pub static CRC_32_HEADER_NAME: &str = "x-amz-checksum-crc32";
 pub static CRC_32_C_HEADER_NAME: &str = "x-amz-checksum-crc32c";
+pub static CRC_64_NVME_HEADER_NAME: &str = "x-amz-checksum-crc64nvme";
 pub static SHA_1_HEADER_NAME: &str = "x-amz-checksum-sha1";
 pub static SHA_256_HEADER_NAME: &str = "x-amz-checksum-sha256";
+pub static XXHASH_64_HEADER_NAME: &str = "x-amz-checksum-xxhash64";
+pub static XXHASH_3_HEADER_NAME: &str = "x-amz-checksum-xxhash3";
 
 // Preserved for compatibility purposes. This should never be used by users, only within smithy-rs
 pub(crate) static MD5_HEADER_NAME: &str = "content-md5";
@@ -24,8 +27,15 @@ pub(crate) static MD5_HEADER_NAME: &str = "content-md5";
 /// When a response has to be checksum-verified, we have to check possible headers until we find the
 /// header with the precalculated checksum. Because a service may send back multiple headers, we have
 /// to check them in order based on how fast each checksum is to calculate.
-pub const CHECKSUM_ALGORITHMS_IN_PRIORITY_ORDER: [&str; 4] =
-    [CRC_32_C_NAME, CRC_32_NAME, SHA_1_NAME, SHA_256_NAME];
+pub const CHECKSUM_ALGORITHMS_IN_PRIORITY_ORDER: &[&str] = &[
+    CRC_64_NVME_NAME,
+    CRC_32_C_NAME,
+    CRC_32_NAME,
+    XXHASH_3_NAME,
+    XXHASH_64_NAME,
+    SHA_1_NAME,
+    SHA_256_NAME,
+];
 
 /// Checksum algorithms are use to validate the integrity of data. Structs that implement this trait
 /// can be used as checksum calculators. This trait requires Send + Sync because these checksums are
@@ -81,6 +91,12 @@ impl HttpChecksum for Crc32c {
     }
 }
 
+impl HttpChecksum for Crc64Nvme {
+    fn header_name(&self) -> &'static str {
+        CRC_64_NVME_HEADER_NAME
+    }
+}
+
 impl HttpChecksum for Sha1 {
     fn header_name(&self) -> &'static str {
         SHA_1_HEADER_NAME
@@ -99,12 +115,27 @@ impl HttpChecksum for Md5 {
     }
 }
 
+impl HttpChecksum for Xxhash64 {
+    fn header_name(&self) -> &'static str {
+        XXHASH_64_HEADER_NAME
+    }
+}
+
+impl HttpChecksum for Xxhash3 {
+    fn header_name(&self) -> &'static str {
+        XXHASH_3_HEADER_NAME
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use aws_smithy_types::base64;
     use bytes::Bytes;
 
-    use crate::{ChecksumAlgorithm, CRC_32_C_NAME, CRC_32_NAME, SHA_1_NAME, SHA_256_NAME};
+    use crate::{
+        ChecksumAlgorithm, CRC_32_C_NAME, CRC_32_NAME, CRC_64_NVME_NAME, SHA_1_NAME, SHA_256_NAME,
+        XXHASH_3_NAME, XXHASH_64_NAME,
+    };
 
     use super::HttpChecksum;
 
@@ -204,4 +235,76 @@ mod tests {
         let actual_value = checksum.header_value();
         assert_eq!(expected_value, actual_value)
     }
+
+    #[test]
+    fn test_trailer_length_of_crc64nvme_checksum_body() {
+        let checksum = CRC_64_NVME_NAME
+            .parse::<ChecksumAlgorithm>()
+            .unwrap()
+            .into_impl();
+        let expected_size = 37;
+        let actual_size = HttpChecksum::size(&*checksum);
+        assert_eq!(expected_size, actual_size)
+    }
+
+    #[test]
+    fn test_trailer_value_of_crc64nvme_checksum_body() {
+        let checksum = CRC_64_NVME_NAME
+            .parse::<ChecksumAlgorithm>()
+            .unwrap()
+            .into_impl();
+        // The CRC64-NVME of an empty string is all zeroes
+        let expected_value = Bytes::from_static(b"\0\0\0\0\0\0\0\0");
+        let expected_value = base64::encode(&expected_value);
+        let actual_value = checksum.header_value();
+        assert_eq!(expected_value, actual_value)
+    }
+
+    #[test]
+    fn test_trailer_length_of_xxhash64_checksum_body() {
+        let checksum = XXHASH_64_NAME
+            .parse::<ChecksumAlgorithm>()
+            .unwrap()
+            .into_impl();
+        let expected_size = 36;
+        let actual_size = HttpChecksum::size(&*checksum);
+        assert_eq!(expected_size, actual_size)
+    }
+
+    #[test]
+    fn test_trailer_value_of_xxhash64_checksum_body() {
+        let checksum = XXHASH_64_NAME
+            .parse::<ChecksumAlgorithm>()
+            .unwrap()
+            .into_impl();
+        // The XXH64 (seed 0) of an empty string is ef46db3751d8e999
+        let expected_value = Bytes::from_static(&[0xef, 0x46, 0xdb, 0x37, 0x51, 0xd8, 0xe9, 0x99]);
+        let expected_value = base64::encode(&expected_value);
+        let actual_value = checksum.header_value();
+        assert_eq!(expected_value, actual_value)
+    }
+
+    #[test]
+    fn test_trailer_length_of_xxhash3_checksum_body() {
+        let checksum = XXHASH_3_NAME
+            .parse::<ChecksumAlgorithm>()
+            .unwrap()
+            .into_impl();
+        let expected_size = 35;
+        let actual_size = HttpChecksum::size(&*checksum);
+        assert_eq!(expected_size, actual_size)
+    }
+
+    #[test]
+    fn test_trailer_value_of_xxhash3_checksum_body() {
+        let checksum = XXHASH_3_NAME
+            .parse::<ChecksumAlgorithm>()
+            .unwrap()
+            .into_impl();
+        // The XXH3 (64-bit, seed 0) of an empty string is 2d06800538d394c2
+        let expected_value = Bytes::from_static(&[0x2d, 0x06, 0x80, 0x05, 0x38, 0xd3, 0x94, 0xc2]);
+        let expected_value = base64::encode(&expected_value);
+        let actual_value = checksum.header_value();
+        assert_eq!(expected_value, actual_value)
+    }
 }
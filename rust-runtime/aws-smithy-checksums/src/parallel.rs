@@ -0,0 +1,815 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Calculating checksums of large amounts of data on several threads.
+//!
+//! A [`ParallelChecksum`] buffers incoming data until it has a batch of chunks to work on and then
+//! checksums the batch on a pool of worker threads that's shared by the whole process:
+//!
+//! - Chunks are checksummed independently for CRC-based algorithms. The chunk checksums are then
+//!   combined into the checksum of all the data with a [`FullObjectChecksum`].
+//! - Other algorithms have to see the data in order, so each of them is a single job. This still
+//!   lets several algorithms be calculated in a single pass over the data.
+//!
+//! If the worker threads can't be started, for example on platforms without threads, jobs are run
+//! on the thread that dispatches them instead.
+//!
+//! One batch is checksummed while the next one is buffered. [`ParallelChecksum::update`] only
+//! blocks when the next batch is full before the previous one is done, and
+//! [`ParallelChecksum::finalize`] blocks until all data has been checksummed. The
+//! [`ChecksumBody`](crate::body::calculate::ChecksumBody) created by `new_parallel` waits for
+//! batches without blocking the async executor it's polled on.
+
+use crate::combine::FullObjectChecksum;
+use crate::http::HttpChecksum;
+use crate::ChecksumAlgorithm;
+use bytes::Bytes;
+use http::header::{HeaderMap, HeaderValue};
+use std::fmt::Display;
+use std::num::NonZeroUsize;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, SendError, Sender};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+/// The default size of the chunks that are checksummed independently.
+const DEFAULT_CHUNK_SIZE: usize = 1024 * 1024;
+/// The number of chunks that are checksummed at once, and the number of worker threads, if the
+/// available parallelism can't be determined.
+const FALLBACK_PARALLELISM: usize = 4;
+
+fn available_parallelism() -> usize {
+    thread::available_parallelism()
+        .map(NonZeroUsize::get)
+        .unwrap_or(FALLBACK_PARALLELISM)
+}
+
+/// Errors related to configuring a [`ParallelChecksum`] or calculating its checksums
+#[derive(Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// No checksum algorithms were set.
+    NoAlgorithms,
+    /// The same checksum algorithm was set more than once.
+    DuplicateAlgorithm(ChecksumAlgorithm),
+    /// The chunk size was zero.
+    ZeroChunkSize,
+    /// A checksum job panicked, so the checksums couldn't be calculated.
+    JobFailed,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Error::NoAlgorithms => write!(f, "at least one checksum algorithm must be set"),
+            Error::DuplicateAlgorithm(algorithm) => write!(
+                f,
+                "checksum algorithm {} was set more than once",
+                algorithm.as_str()
+            ),
+            Error::ZeroChunkSize => write!(f, "chunk size must be greater than zero"),
+            Error::JobFailed => write!(f, "a checksum job panicked"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Builder for [`ParallelChecksum`]
+#[derive(Debug, Default)]
+pub struct Builder {
+    algorithms: Vec<ChecksumAlgorithm>,
+    chunk_size: Option<usize>,
+    parallelism: Option<NonZeroUsize>,
+}
+
+impl Builder {
+    /// Add a checksum algorithm to calculate.
+    pub fn algorithm(mut self, algorithm: ChecksumAlgorithm) -> Self {
+        self.algorithms.push(algorithm);
+        self
+    }
+
+    /// Set the size of the chunks that are checksummed independently.
+    ///
+    /// Defaults to 1 MiB.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = Some(chunk_size);
+        self
+    }
+
+    /// Set the number of chunks that are checksummed at once.
+    ///
+    /// Defaults to the available parallelism of the machine, which is also the number of worker
+    /// threads that checksums are calculated on.
+    pub fn parallelism(mut self, parallelism: NonZeroUsize) -> Self {
+        self.parallelism = Some(parallelism);
+        self
+    }
+
+    /// Build a [`ParallelChecksum`], or return an error if the configuration is invalid.
+    pub fn build(self) -> Result<ParallelChecksum, Error> {
+        if self.algorithms.is_empty() {
+            return Err(Error::NoAlgorithms);
+        }
+        let mut calculators: Vec<Calculator> = Vec::with_capacity(self.algorithms.len());
+        for algorithm in self.algorithms {
+            if calculators.iter().any(|c| c.algorithm == algorithm) {
+                return Err(Error::DuplicateAlgorithm(algorithm));
+            }
+            calculators.push(Calculator::new(algorithm));
+        }
+        let chunk_size = self.chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE);
+        if chunk_size == 0 {
+            return Err(Error::ZeroChunkSize);
+        }
+        let parallelism = self
+            .parallelism
+            .map(NonZeroUsize::get)
+            .unwrap_or_else(available_parallelism);
+
+        Ok(ParallelChecksum {
+            calculators,
+            chunk_size,
+            parallelism,
+            pending: Vec::new(),
+            pending_len: 0,
+            in_flight: None,
+            failed: false,
+        })
+    }
+}
+
+struct Calculator {
+    algorithm: ChecksumAlgorithm,
+    header_name: &'static str,
+    state: CalculatorState,
+}
+
+enum CalculatorState {
+    // Chunks are checksummed independently and combined in order
+    Combined(FullObjectChecksum),
+    // Data is checksummed in order by a single job
+    Serial(Box<dyn HttpChecksum>),
+    // The serial checksum is with a job that hasn't finished yet
+    Busy,
+}
+
+impl Calculator {
+    fn new(algorithm: ChecksumAlgorithm) -> Self {
+        let checksum = algorithm.into_impl();
+        let header_name = checksum.header_name();
+        let state = match FullObjectChecksum::new(algorithm) {
+            Ok(full_object_checksum) => CalculatorState::Combined(full_object_checksum),
+            Err(_) => CalculatorState::Serial(checksum),
+        };
+        Self {
+            algorithm,
+            header_name,
+            state,
+        }
+    }
+
+    fn finalize(self) -> Bytes {
+        match self.state {
+            CalculatorState::Combined(full_object_checksum) => full_object_checksum.finalize(),
+            CalculatorState::Serial(checksum) => checksum.finalize(),
+            CalculatorState::Busy => unreachable!("all batches finish before finalizing"),
+        }
+    }
+}
+
+fn checksum_chunk(algorithm: ChecksumAlgorithm, chunk: &[Bytes]) -> Bytes {
+    let mut checksum = algorithm.into_impl();
+    for bytes in chunk {
+        checksum.update(bytes);
+    }
+    checksum.finalize()
+}
+
+/// Calculates one or more checksums over data, using several threads for large amounts of data.
+pub struct ParallelChecksum {
+    calculators: Vec<Calculator>,
+    chunk_size: usize,
+    parallelism: usize,
+    pending: Vec<Bytes>,
+    pending_len: usize,
+    in_flight: Option<Batch>,
+    /// Set once a job has failed, after which data is no longer checksummed
+    failed: bool,
+}
+
+impl std::fmt::Debug for ParallelChecksum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParallelChecksum")
+            .field("algorithms", &self.algorithms().collect::<Vec<_>>())
+            .field("chunk_size", &self.chunk_size)
+            .field("parallelism", &self.parallelism)
+            .field("pending_len", &self.pending_len)
+            .field("in_flight", &self.in_flight.is_some())
+            .finish()
+    }
+}
+
+impl ParallelChecksum {
+    /// Return a new builder for `ParallelChecksum`.
+    pub fn builder() -> Builder {
+        Builder::default()
+    }
+
+    /// Return the algorithms being calculated.
+    pub fn algorithms(&self) -> impl Iterator<Item = ChecksumAlgorithm> + '_ {
+        self.calculators.iter().map(|c| c.algorithm)
+    }
+
+    /// Add data to the checksums. Once enough data has been added to fill a batch of chunks, the
+    /// batch is sent to the worker threads. This blocks until the previous batch is done.
+    pub fn update(&mut self, bytes: Bytes) {
+        self.push(bytes);
+        if self.batch_is_full() {
+            self.wait_for_batch();
+            self.dispatch();
+        }
+    }
+
+    /// Buffer data without sending it to the worker threads.
+    pub(crate) fn push(&mut self, bytes: Bytes) {
+        if bytes.is_empty() {
+            return;
+        }
+        self.pending_len += bytes.len();
+        self.pending.push(bytes);
+    }
+
+    fn batch_is_full(&self) -> bool {
+        self.pending_len >= self.chunk_size * self.parallelism
+    }
+
+    /// If a full batch has been buffered, wait for the previous batch and then send it to the
+    /// worker threads.
+    ///
+    /// This must be ready before buffering more data with [`ParallelChecksum::push`], so that at
+    /// most two batches are held in memory.
+    pub(crate) fn poll_dispatch(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        if self.batch_is_full() {
+            if self.poll_batch(cx).is_pending() {
+                return Poll::Pending;
+            }
+            self.dispatch();
+        }
+        Poll::Ready(())
+    }
+
+    /// Send any remaining data to the worker threads and wait for all of it to be checksummed.
+    ///
+    /// Returns an error if a job failed.
+    pub(crate) fn poll_finish(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        if self.poll_batch(cx).is_pending() {
+            return Poll::Pending;
+        }
+        self.dispatch();
+        if self.poll_batch(cx).is_pending() {
+            return Poll::Pending;
+        }
+        Poll::Ready(self.check_failed())
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        self.wait_for_batch();
+        self.dispatch();
+        self.wait_for_batch();
+        self.check_failed()
+    }
+
+    fn check_failed(&self) -> Result<(), Error> {
+        if self.failed {
+            Err(Error::JobFailed)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Checksum any remaining data and return the calculated checksums, in the order their
+    /// algorithms were added to the builder.
+    ///
+    /// Returns an error if a job failed.
+    pub fn finalize(mut self) -> Result<Vec<(ChecksumAlgorithm, Bytes)>, Error> {
+        self.finish()?;
+        Ok(self
+            .calculators
+            .into_iter()
+            .map(|calculator| (calculator.algorithm, calculator.finalize()))
+            .collect())
+    }
+
+    /// Checksum any remaining data and return the calculated checksums as base64-encoded headers.
+    ///
+    /// Returns an error if a job failed.
+    pub fn headers(mut self) -> Result<HeaderMap<HeaderValue>, Error> {
+        self.finish()?;
+        let mut header_map = HeaderMap::new();
+        for calculator in self.calculators {
+            let header_name = calculator.header_name;
+            let checksum = calculator.finalize();
+            header_map.insert(
+                header_name,
+                HeaderValue::from_str(&aws_smithy_types::base64::encode(&checksum[..]))
+                    .expect("base64 encoded bytes are always valid header values"),
+            );
+        }
+        Ok(header_map)
+    }
+
+    /// Split the pending data into chunks of `chunk_size` bytes, without copying it.
+    fn take_chunks(&mut self) -> Vec<Vec<Bytes>> {
+        let mut chunks = Vec::new();
+        let mut chunk = Vec::new();
+        let mut chunk_len = 0;
+        for mut bytes in self.pending.drain(..) {
+            while !bytes.is_empty() {
+                let taken = bytes.split_to((self.chunk_size - chunk_len).min(bytes.len()));
+                chunk_len += taken.len();
+                chunk.push(taken);
+                if chunk_len == self.chunk_size {
+                    chunks.push(std::mem::take(&mut chunk));
+                    chunk_len = 0;
+                }
+            }
+        }
+        if !chunk.is_empty() {
+            chunks.push(chunk);
+        }
+        self.pending_len = 0;
+        chunks
+    }
+
+    /// Send the pending data to the worker threads as a batch of jobs.
+    fn dispatch(&mut self) {
+        debug_assert!(self.in_flight.is_none(), "only one batch is in flight");
+        if self.failed {
+            // The checksums can't be calculated anymore, so the data is dropped
+            self.pending.clear();
+            self.pending_len = 0;
+            return;
+        }
+        if self.pending_len == 0 {
+            return;
+        }
+        let chunks = Arc::new(self.take_chunks());
+        let job_count = self
+            .calculators
+            .iter()
+            .map(|calculator| match calculator.state {
+                CalculatorState::Combined(_) => chunks.len(),
+                _ => 1,
+            })
+            .sum();
+        let batch = Batch::new(job_count);
+        for (index, calculator) in self.calculators.iter_mut().enumerate() {
+            let algorithm = calculator.algorithm;
+            match std::mem::replace(&mut calculator.state, CalculatorState::Busy) {
+                CalculatorState::Combined(full_object_checksum) => {
+                    calculator.state = CalculatorState::Combined(full_object_checksum);
+                    for chunk_index in 0..chunks.len() {
+                        let chunks = chunks.clone();
+                        batch.spawn(move || {
+                            let chunk = &chunks[chunk_index];
+                            JobOutput::Chunk {
+                                calculator: index,
+                                chunk: chunk_index,
+                                checksum: checksum_chunk(algorithm, chunk),
+                                length: chunk.iter().map(Bytes::len).sum::<usize>() as u64,
+                            }
+                        });
+                    }
+                }
+                CalculatorState::Serial(mut checksum) => {
+                    let chunks = chunks.clone();
+                    batch.spawn(move || {
+                        for bytes in chunks.iter().flatten() {
+                            checksum.update(bytes);
+                        }
+                        JobOutput::Serial {
+                            calculator: index,
+                            checksum,
+                        }
+                    });
+                }
+                CalculatorState::Busy => unreachable!("only one batch is in flight"),
+            }
+        }
+        self.in_flight = Some(batch);
+    }
+
+    fn poll_batch(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        if let Some(batch) = &self.in_flight {
+            match batch.poll(cx) {
+                Poll::Ready(outputs) => {
+                    self.in_flight = None;
+                    self.complete(outputs);
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        Poll::Ready(())
+    }
+
+    fn wait_for_batch(&mut self) {
+        if let Some(batch) = self.in_flight.take() {
+            let outputs = batch.wait();
+            self.complete(outputs);
+        }
+    }
+
+    /// Apply the outputs of a finished batch to the calculators.
+    ///
+    /// If a job panicked, its output is missing and the checksums can't be calculated.
+    fn complete(&mut self, outputs: Vec<Option<JobOutput>>) {
+        let Some(mut outputs) = outputs.into_iter().collect::<Option<Vec<_>>>() else {
+            tracing::warn!("a checksum job panicked; checksums can't be calculated");
+            self.failed = true;
+            return;
+        };
+        // Chunk checksums have to be combined in the order of the chunks
+        outputs.sort_by_key(|output| match output {
+            JobOutput::Chunk {
+                calculator, chunk, ..
+            } => (*calculator, *chunk),
+            JobOutput::Serial { calculator, .. } => (*calculator, 0),
+        });
+        for output in outputs {
+            match output {
+                JobOutput::Chunk {
+                    calculator,
+                    checksum,
+                    length,
+                    ..
+                } => match &mut self.calculators[calculator].state {
+                    CalculatorState::Combined(full_object_checksum) => full_object_checksum
+                        .add_part(&checksum, length)
+                        .expect("chunk checksums have the correct length"),
+                    _ => unreachable!("chunk checksums are only calculated for combined checksums"),
+                },
+                JobOutput::Serial {
+                    calculator,
+                    checksum,
+                } => self.calculators[calculator].state = CalculatorState::Serial(checksum),
+            }
+        }
+    }
+}
+
+/// The result of a single job in a batch.
+enum JobOutput {
+    /// The checksum of a single chunk, to be combined with the checksums of the other chunks
+    Chunk {
+        calculator: usize,
+        chunk: usize,
+        checksum: Bytes,
+        length: u64,
+    },
+    /// A serial checksum, updated with all the data in the batch
+    Serial {
+        calculator: usize,
+        checksum: Box<dyn HttpChecksum>,
+    },
+}
+
+#[derive(Default)]
+struct BatchState {
+    remaining: usize,
+    // A job that panicked has no output
+    outputs: Vec<Option<JobOutput>>,
+    waker: Option<Waker>,
+}
+
+/// A batch of jobs running on the worker pool, which can be waited on by blocking or by polling.
+struct Batch {
+    shared: Arc<(Mutex<BatchState>, Condvar)>,
+}
+
+impl Batch {
+    fn new(job_count: usize) -> Self {
+        let state = BatchState {
+            remaining: job_count,
+            ..Default::default()
+        };
+        Self {
+            shared: Arc::new((Mutex::new(state), Condvar::new())),
+        }
+    }
+
+    fn spawn(&self, job: impl FnOnce() -> JobOutput + Send + 'static) {
+        self.spawn_on(worker_pool(), job)
+    }
+
+    /// Run a job on the given worker pool, or on this thread if there isn't one.
+    fn spawn_on(
+        &self,
+        pool: Option<&WorkerPool>,
+        job: impl FnOnce() -> JobOutput + Send + 'static,
+    ) {
+        let mut reporter = JobReporter {
+            shared: self.shared.clone(),
+            output: None,
+        };
+        let job: Job = Box::new(move || {
+            reporter.output = Some(job());
+        });
+        match pool {
+            Some(pool) => pool.spawn(job),
+            None => run_job(job),
+        }
+    }
+
+    fn poll(&self, cx: &mut Context<'_>) -> Poll<Vec<Option<JobOutput>>> {
+        let mut state = self.shared.0.lock().unwrap();
+        if state.remaining == 0 {
+            Poll::Ready(std::mem::take(&mut state.outputs))
+        } else {
+            state.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+
+    fn wait(self) -> Vec<Option<JobOutput>> {
+        let (state, done) = &*self.shared;
+        let mut state = done
+            .wait_while(state.lock().unwrap(), |state| state.remaining > 0)
+            .unwrap();
+        std::mem::take(&mut state.outputs)
+    }
+}
+
+/// Reports a job's output to its batch when dropped, so that a job that panics still finishes.
+struct JobReporter {
+    shared: Arc<(Mutex<BatchState>, Condvar)>,
+    output: Option<JobOutput>,
+}
+
+impl Drop for JobReporter {
+    fn drop(&mut self) {
+        let (state, done) = &*self.shared;
+        let mut state = state.lock().unwrap();
+        state.outputs.push(self.output.take());
+        state.remaining -= 1;
+        if state.remaining == 0 {
+            done.notify_all();
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Run a job, catching panics. A panicking job is reported by its `JobReporter`.
+fn run_job(job: Job) {
+    let _ = panic::catch_unwind(AssertUnwindSafe(job));
+}
+
+/// Long-lived threads that checksum jobs are run on.
+struct WorkerPool {
+    sender: Sender<Job>,
+}
+
+impl WorkerPool {
+    fn spawn(&self, job: Job) {
+        // Worker threads don't exit while the pool exists, but run the job here if they did
+        if let Err(SendError(job)) = self.sender.send(job) {
+            run_job(job);
+        }
+    }
+}
+
+/// The process-wide worker pool, or `None` if no worker threads could be started.
+fn worker_pool() -> Option<&'static WorkerPool> {
+    static WORKER_POOL: OnceLock<Option<WorkerPool>> = OnceLock::new();
+    WORKER_POOL
+        .get_or_init(|| {
+            let (sender, receiver) = mpsc::channel::<Job>();
+            let receiver = Arc::new(Mutex::new(receiver));
+            let mut started = 0;
+            for index in 0..available_parallelism() {
+                let receiver = receiver.clone();
+                let spawned = thread::Builder::new()
+                    .name(format!("smithy-checksum-{index}"))
+                    .spawn(move || loop {
+                        let job = receiver.lock().unwrap().recv();
+                        match job {
+                            Ok(job) => run_job(job),
+                            Err(_) => return,
+                        }
+                    });
+                match spawned {
+                    Ok(_) => started += 1,
+                    Err(err) => {
+                        tracing::warn!(err = %err, started, "failed to spawn a checksum worker thread");
+                        break;
+                    }
+                }
+            }
+            if started == 0 {
+                tracing::warn!("no checksum worker threads could be started; checksums will be calculated serially");
+                return None;
+            }
+            Some(WorkerPool { sender })
+        })
+        .as_ref()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Batch, Error, JobOutput, ParallelChecksum};
+    use crate::ChecksumAlgorithm;
+    use bytes::Bytes;
+    use std::num::NonZeroUsize;
+
+    const ALL_ALGORITHMS: [ChecksumAlgorithm; 8] = [
+        ChecksumAlgorithm::Crc32,
+        ChecksumAlgorithm::Crc32c,
+        ChecksumAlgorithm::Crc64Nvme,
+        ChecksumAlgorithm::Md5,
+        ChecksumAlgorithm::Sha1,
+        ChecksumAlgorithm::Sha256,
+        ChecksumAlgorithm::Xxhash64,
+        ChecksumAlgorithm::Xxhash3,
+    ];
+
+    fn test_data() -> Vec<u8> {
+        (0..100_000u32).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn serial_checksum(algorithm: ChecksumAlgorithm, data: &[u8]) -> Bytes {
+        let mut checksum = algorithm.into_impl();
+        checksum.update(data);
+        checksum.finalize()
+    }
+
+    #[test]
+    fn test_parallel_checksums_match_serial_checksums() {
+        let data = test_data();
+        let mut builder = ParallelChecksum::builder()
+            .chunk_size(4096)
+            .parallelism(NonZeroUsize::new(3).unwrap());
+        for algorithm in ALL_ALGORITHMS {
+            builder = builder.algorithm(algorithm);
+        }
+        let mut checksum = builder.build().unwrap();
+        // Uneven writes so that chunks span several pieces of data
+        for piece in data.chunks(1000) {
+            checksum.update(Bytes::copy_from_slice(piece));
+        }
+
+        let checksums = checksum.finalize().unwrap();
+        assert_eq!(ALL_ALGORITHMS.len(), checksums.len());
+        for (algorithm, actual) in checksums {
+            assert_eq!(serial_checksum(algorithm, &data), actual, "{algorithm:?}");
+        }
+    }
+
+    #[test]
+    fn test_parallelism_of_one_matches_serial_checksums() {
+        let data = test_data();
+        let mut checksum = ParallelChecksum::builder()
+            .algorithm(ChecksumAlgorithm::Crc64Nvme)
+            .algorithm(ChecksumAlgorithm::Sha256)
+            .chunk_size(4096)
+            .parallelism(NonZeroUsize::new(1).unwrap())
+            .build()
+            .unwrap();
+        for piece in data.chunks(1000) {
+            checksum.update(Bytes::copy_from_slice(piece));
+        }
+
+        for (algorithm, actual) in checksum.finalize().unwrap() {
+            assert_eq!(serial_checksum(algorithm, &data), actual, "{algorithm:?}");
+        }
+    }
+
+    #[test]
+    fn test_data_smaller_than_a_chunk() {
+        let mut checksum = ParallelChecksum::builder()
+            .algorithm(ChecksumAlgorithm::Crc32c)
+            .build()
+            .unwrap();
+        checksum.update(Bytes::from_static(b"test data"));
+
+        let checksums = checksum.finalize().unwrap();
+        assert_eq!(
+            vec![(
+                ChecksumAlgorithm::Crc32c,
+                serial_checksum(ChecksumAlgorithm::Crc32c, b"test data")
+            )],
+            checksums
+        );
+    }
+
+    #[test]
+    fn test_headers_contain_every_algorithm() {
+        let mut checksum = ParallelChecksum::builder()
+            .algorithm(ChecksumAlgorithm::Crc32)
+            .algorithm(ChecksumAlgorithm::Sha256)
+            .build()
+            .unwrap();
+        checksum.update(Bytes::from_static(b"test data"));
+
+        let headers = checksum.headers().unwrap();
+        assert_eq!("0wiusg==", headers.get("x-amz-checksum-crc32").unwrap());
+        assert_eq!(
+            "kW8AJ6V1B0znKjMXd8NHjWUT94alkb2JLaGld78jNfk=",
+            headers.get("x-amz-checksum-sha256").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_invalid_configurations_are_rejected() {
+        assert_eq!(
+            Error::NoAlgorithms,
+            ParallelChecksum::builder().build().unwrap_err()
+        );
+        assert_eq!(
+            Error::DuplicateAlgorithm(ChecksumAlgorithm::Sha1),
+            ParallelChecksum::builder()
+                .algorithm(ChecksumAlgorithm::Sha1)
+                .algorithm(ChecksumAlgorithm::Sha1)
+                .build()
+                .unwrap_err()
+        );
+        assert_eq!(
+            Error::ZeroChunkSize,
+            ParallelChecksum::builder()
+                .algorithm(ChecksumAlgorithm::Sha1)
+                .chunk_size(0)
+                .build()
+                .unwrap_err()
+        );
+    }
+
+    #[test]
+    fn test_batches_run_on_long_lived_worker_threads() {
+        let thread_names = || {
+            let batch = Batch::new(8);
+            for _ in 0..8 {
+                batch.spawn(|| {
+                    let name = std::thread::current().name().unwrap().to_owned();
+                    JobOutput::Chunk {
+                        calculator: 0,
+                        chunk: 0,
+                        checksum: Bytes::from(name),
+                        length: 0,
+                    }
+                });
+            }
+            batch
+                .wait()
+                .into_iter()
+                .map(|output| match output.unwrap() {
+                    JobOutput::Chunk { checksum, .. } => checksum,
+                    JobOutput::Serial { .. } => unreachable!(),
+                })
+                .collect::<std::collections::HashSet<_>>()
+        };
+        let (first, second) = (thread_names(), thread_names());
+        for name in first.iter().chain(&second) {
+            assert!(name.starts_with(b"smithy-checksum-"), "{name:?}");
+        }
+        // No new threads are started for later batches
+        assert!(first.union(&second).count() <= super::available_parallelism());
+    }
+
+    #[test]
+    fn test_jobs_run_on_the_calling_thread_without_a_worker_pool() {
+        let batch = Batch::new(1);
+        let caller = std::thread::current().id();
+        batch.spawn_on(None, move || {
+            assert_eq!(caller, std::thread::current().id());
+            JobOutput::Chunk {
+                calculator: 0,
+                chunk: 0,
+                checksum: Bytes::new(),
+                length: 0,
+            }
+        });
+        assert!(batch.wait()[0].is_some());
+    }
+
+    #[test]
+    fn test_failed_jobs_are_reported_as_errors() {
+        let mut checksum = ParallelChecksum::builder()
+            .algorithm(ChecksumAlgorithm::Crc32)
+            .build()
+            .unwrap();
+        let batch = Batch::new(1);
+        batch.spawn(|| panic!("hasher panicked"));
+        checksum.in_flight = Some(batch);
+        // Data added after the failure is ignored
+        checksum.update(Bytes::from_static(b"test data"));
+
+        assert_eq!(Err(Error::JobFailed), checksum.finalize());
+    }
+}
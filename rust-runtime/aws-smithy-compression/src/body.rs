@@ -3,10 +3,8 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
-//! HTTP body-wrappers that perform request compression
+//! HTTP body-wrappers that perform request compression and response decompression
 
-// Putting this in a `mod` since I expect we'll have to handle response
-// decompression some day.
 /// Functionality for compressing an HTTP request body.
 pub mod compress {
     use aws_smithy_types::body::SdkBody;
@@ -22,16 +20,26 @@ pub mod compress {
             body: InnerBody,
             compress_request: CompressionImpl,
             is_end_stream: bool,
+            // When streaming compression is finished because trailers were received, the trailers
+            // must wait until the remaining compressed data has been sent.
+            pending_trailers: Option<PendingTrailers>,
         }
     }
 
+    #[cfg(feature = "http-body-1-x")]
+    type PendingTrailers = http_body_1_0::Frame<bytes::Bytes>;
+    #[cfg(not(feature = "http-body-1-x"))]
+    type PendingTrailers = std::convert::Infallible;
+
     impl<CR> CompressedBody<SdkBody, CR> {
-        /// Given an [`SdkBody`] and a `Box<dyn CompressRequest>`, create a new `CompressedBody<SdkBody, CR>`.
+        /// Given an [`SdkBody`] and a `Box<dyn CompressRequest>` or `Box<dyn StreamingCompress>`,
+        /// create a new `CompressedBody<SdkBody, CR>`.
         pub fn new(body: SdkBody, compress_request: CR) -> Self {
             Self {
                 body,
                 compress_request,
                 is_end_stream: false,
+                pending_trailers: None,
             }
         }
     }
@@ -41,6 +49,7 @@ pub mod compress {
     pub mod http_body_0_4_x {
         use super::CompressedBody;
         use crate::http::http_body_0_4_x::CompressRequest;
+        use crate::StreamingCompress;
         use aws_smithy_runtime_api::box_error::BoxError;
         use aws_smithy_types::body::SdkBody;
         use http_0_2::HeaderMap;
@@ -90,6 +99,58 @@ pub mod compress {
             }
         }
 
+        /// Compresses the inner body into a single compressed stream, regardless of how many
+        /// chunks of data it's made up of. Trailers from the inner body are passed through.
+        impl Body for CompressedBody<SdkBody, Box<dyn StreamingCompress>> {
+            type Data = bytes::Bytes;
+            type Error = aws_smithy_types::body::Error;
+
+            fn poll_data(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+            ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+                let mut this = self.project();
+                if *this.is_end_stream {
+                    return Poll::Ready(None);
+                }
+                loop {
+                    match this.body.as_mut().poll_data(cx)? {
+                        Poll::Ready(Some(data)) => {
+                            let out = this.compress_request.compress_bytes(&data[..])?;
+                            // Compressors buffer data internally and don't always produce output
+                            if !out.is_empty() {
+                                return Poll::Ready(Some(Ok(out)));
+                            }
+                        }
+                        Poll::Ready(None) => {
+                            *this.is_end_stream = true;
+                            let out = this.compress_request.finish()?;
+                            return Poll::Ready((!out.is_empty()).then_some(Ok(out)));
+                        }
+                        Poll::Pending => return Poll::Pending,
+                    }
+                }
+            }
+
+            fn poll_trailers(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+            ) -> Poll<Result<Option<HeaderMap>, Self::Error>> {
+                let this = self.project();
+                this.body.poll_trailers(cx)
+            }
+
+            fn is_end_stream(&self) -> bool {
+                self.is_end_stream
+            }
+
+            fn size_hint(&self) -> SizeHint {
+                // We can't return a hint because we don't know exactly how
+                // compression will affect the content length
+                SizeHint::default()
+            }
+        }
+
         impl CompressedBody<SdkBody, Box<dyn CompressRequest>> {
             /// Consumes this `CompressedBody` and returns an [`SdkBody`] containing the compressed data.
             ///
@@ -111,6 +172,7 @@ pub mod compress {
     pub mod http_body_1_x {
         use crate::body::compress::CompressedBody;
         use crate::http::http_body_1_x::CompressRequest;
+        use crate::StreamingCompress;
         use aws_smithy_types::body::SdkBody;
         use http_body_1_0::{Body, Frame, SizeHint};
         use std::pin::Pin;
@@ -157,6 +219,245 @@ pub mod compress {
                 SizeHint::default()
             }
         }
+
+        /// Compresses the inner body into a single compressed stream, regardless of how many
+        /// chunks of data it's made up of. Trailers from the inner body are passed through.
+        impl Body for CompressedBody<SdkBody, Box<dyn StreamingCompress>> {
+            type Data = bytes::Bytes;
+            type Error = aws_smithy_types::body::Error;
+
+            fn poll_frame(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+            ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+                let mut this = self.project();
+                if *this.is_end_stream {
+                    return Poll::Ready(this.pending_trailers.take().map(Ok));
+                }
+                loop {
+                    match ready!(this.body.as_mut().poll_frame(cx)) {
+                        Some(Ok(f)) => {
+                            if f.is_data() {
+                                let d = f.into_data().expect("we checked for data first");
+                                let out = this.compress_request.compress_bytes(&d)?;
+                                // Compressors buffer data internally and don't always produce output
+                                if !out.is_empty() {
+                                    return Poll::Ready(Some(Ok(Frame::data(out))));
+                                }
+                            } else if f.is_trailers() {
+                                // Trailers come after all data, so the compressed stream must be
+                                // finished before they're passed along.
+                                *this.is_end_stream = true;
+                                let out = this.compress_request.finish()?;
+                                *this.pending_trailers = Some(f);
+                                if !out.is_empty() {
+                                    return Poll::Ready(Some(Ok(Frame::data(out))));
+                                }
+                                return Poll::Ready(this.pending_trailers.take().map(Ok));
+                            } else {
+                                unreachable!("Frame is either data or trailers")
+                            }
+                        }
+                        None => {
+                            *this.is_end_stream = true;
+                            let out = this.compress_request.finish()?;
+                            return Poll::Ready((!out.is_empty()).then(|| Ok(Frame::data(out))));
+                        }
+                        other => return Poll::Ready(other),
+                    }
+                }
+            }
+
+            fn is_end_stream(&self) -> bool {
+                self.is_end_stream && self.pending_trailers.is_none()
+            }
+
+            fn size_hint(&self) -> SizeHint {
+                // We can't return a hint because we don't know exactly how
+                // compression will affect the content length
+                SizeHint::default()
+            }
+        }
+    }
+}
+
+/// Functionality for decompressing an HTTP response body.
+pub mod decompress {
+    use aws_smithy_types::body::SdkBody;
+    use pin_project_lite::pin_project;
+
+    pin_project! {
+        /// A `Body` that decompresses its data with a `Decompress` implementor.
+        ///
+        /// Data is decompressed as it streams in rather than being buffered in full. The total size
+        /// of the decompressed data is limited by the `Decompress` implementor.
+        pub struct DecompressedBody<InnerBody, DecompressionImpl> {
+            #[pin]
+            body: InnerBody,
+            decompress: DecompressionImpl,
+            saw_data: bool,
+            is_end_stream: bool,
+        }
+    }
+
+    impl<D> DecompressedBody<SdkBody, D> {
+        /// Given an [`SdkBody`] and a `Box<dyn Decompress>`, create a new `DecompressedBody<SdkBody, D>`.
+        pub fn new(body: SdkBody, decompress: D) -> Self {
+            Self {
+                body,
+                decompress,
+                saw_data: false,
+                is_end_stream: false,
+            }
+        }
+    }
+
+    /// Returns the remaining decompressed data once the inner body has no more data.
+    ///
+    /// Empty bodies _(like those of `HEAD` responses)_ are allowed to declare a content-encoding,
+    /// so the decompressor is only finished if it was given any data.
+    #[cfg(any(feature = "http-body-0-4-x", feature = "http-body-1-x"))]
+    fn finish(
+        decompress: &mut dyn crate::Decompress,
+        saw_data: bool,
+    ) -> Result<Option<bytes::Bytes>, aws_smithy_types::body::Error> {
+        if !saw_data {
+            return Ok(None);
+        }
+        let remaining = decompress.finish()?;
+        Ok(if remaining.is_empty() {
+            None
+        } else {
+            Some(remaining)
+        })
+    }
+
+    /// Support for the `http-body-0-4` and `http-0-2` crates.
+    #[cfg(feature = "http-body-0-4-x")]
+    pub mod http_body_0_4_x {
+        use super::DecompressedBody;
+        use crate::Decompress;
+        use aws_smithy_types::body::SdkBody;
+        use http_0_2::HeaderMap;
+        use http_body_0_4::{Body, SizeHint};
+        use std::pin::Pin;
+        use std::task::{Context, Poll};
+
+        impl Body for DecompressedBody<SdkBody, Box<dyn Decompress>> {
+            type Data = bytes::Bytes;
+            type Error = aws_smithy_types::body::Error;
+
+            fn poll_data(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+            ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+                let mut this = self.project();
+                if *this.is_end_stream {
+                    return Poll::Ready(None);
+                }
+                loop {
+                    match this.body.as_mut().poll_data(cx)? {
+                        Poll::Ready(Some(data)) => {
+                            *this.saw_data = true;
+                            let out = this.decompress.decompress_bytes(&data[..])?;
+                            // Compressed data doesn't always produce output right away
+                            if !out.is_empty() {
+                                return Poll::Ready(Some(Ok(out)));
+                            }
+                        }
+                        Poll::Ready(None) => {
+                            *this.is_end_stream = true;
+                            return Poll::Ready(
+                                super::finish(this.decompress.as_mut(), *this.saw_data)?.map(Ok),
+                            );
+                        }
+                        Poll::Pending => return Poll::Pending,
+                    }
+                }
+            }
+
+            fn poll_trailers(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+            ) -> Poll<Result<Option<HeaderMap>, Self::Error>> {
+                let this = self.project();
+                this.body.poll_trailers(cx)
+            }
+
+            fn is_end_stream(&self) -> bool {
+                self.is_end_stream
+            }
+
+            fn size_hint(&self) -> SizeHint {
+                // We can't return a hint because we don't know exactly how
+                // decompression will affect the content length
+                SizeHint::default()
+            }
+        }
+    }
+
+    /// Support for the `http-body-1-0` and `http-1-0` crates.
+    #[cfg(feature = "http-body-1-x")]
+    pub mod http_body_1_x {
+        use crate::body::decompress::DecompressedBody;
+        use crate::Decompress;
+        use aws_smithy_types::body::SdkBody;
+        use http_body_1_0::{Body, Frame, SizeHint};
+        use std::pin::Pin;
+        use std::task::{ready, Context, Poll};
+
+        impl Body for DecompressedBody<SdkBody, Box<dyn Decompress>> {
+            type Data = bytes::Bytes;
+            type Error = aws_smithy_types::body::Error;
+
+            fn poll_frame(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+            ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+                let mut this = self.project();
+                if *this.is_end_stream {
+                    return Poll::Ready(None);
+                }
+                loop {
+                    match ready!(this.body.as_mut().poll_frame(cx)) {
+                        Some(Ok(f)) => {
+                            if f.is_data() {
+                                let d = f.into_data().expect("we checked for data first");
+                                *this.saw_data = true;
+                                let out = this.decompress.decompress_bytes(&d)?;
+                                // Compressed data doesn't always produce output right away
+                                if !out.is_empty() {
+                                    return Poll::Ready(Some(Ok(Frame::data(out))));
+                                }
+                            } else if f.is_trailers() {
+                                // Trailers aren't compressed.
+                                return Poll::Ready(Some(Ok(f)));
+                            } else {
+                                unreachable!("Frame is either data or trailers")
+                            }
+                        }
+                        None => {
+                            *this.is_end_stream = true;
+                            return Poll::Ready(
+                                super::finish(this.decompress.as_mut(), *this.saw_data)?
+                                    .map(|d| Ok(Frame::data(d))),
+                            );
+                        }
+                        other => return Poll::Ready(other),
+                    }
+                }
+            }
+
+            fn is_end_stream(&self) -> bool {
+                self.is_end_stream
+            }
+
+            fn size_hint(&self) -> SizeHint {
+                // We can't return a hint because we don't know exactly how
+                // decompression will affect the content length
+                SizeHint::default()
+            }
+        }
     }
 }
 
@@ -204,6 +505,24 @@ mod test {
             assert_eq!(COMPRESSED_OUTPUT, actual_output);
         }
 
+        #[tokio::test]
+        async fn test_streaming_body_is_compressed() {
+            let compressor = CompressionAlgorithm::Zstd
+                .into_streaming_compressor(&CompressionOptions::default());
+            let mut compressed_body =
+                CompressedBody::new(SdkBody::from(UNCOMPRESSED_INPUT), compressor);
+
+            let mut output = Vec::new();
+            while let Some(buf) = compressed_body.data().await {
+                output.extend_from_slice(&buf.unwrap());
+            }
+            assert!(compressed_body.is_end_stream());
+            assert!(compressed_body.trailers().await.unwrap().is_none());
+
+            let actual_output = zstd::stream::decode_all(&output[..]).unwrap();
+            assert_eq!(UNCOMPRESSED_INPUT, actual_output);
+        }
+
         #[tokio::test]
         async fn test_into_compressed_sdk_body() {
             let compression_options = CompressionOptions::default()
@@ -227,7 +546,11 @@ mod test {
     #[cfg(feature = "http-body-1-x")]
     mod http_body_1_x {
         use super::*;
-        use http_body_util::BodyExt;
+        use crate::body::decompress::DecompressedBody;
+        use crate::DecompressionOptions;
+        use bytes::Bytes;
+        use http_body_1_0::Frame;
+        use http_body_util::{BodyExt, StreamBody};
 
         #[tokio::test]
         async fn test_body_is_compressed() {
@@ -260,5 +583,129 @@ mod test {
             // Verify data is compressed as expected
             assert_eq!(COMPRESSED_OUTPUT, actual_output);
         }
+
+        #[tokio::test]
+        async fn test_body_is_compressed_with_zstd() {
+            let compression_options = CompressionOptions::default()
+                .with_algorithm(CompressionAlgorithm::Zstd)
+                .unwrap()
+                .with_min_compression_size_bytes(0)
+                .unwrap();
+            let compress_request =
+                CompressionAlgorithm::Zstd.into_impl_http_body_1_x(&compression_options);
+            assert_eq!("zstd", compress_request.header_value());
+            let body = SdkBody::from(UNCOMPRESSED_INPUT);
+            let compressed_body = CompressedBody::new(body, compress_request);
+
+            let actual_output = compressed_body.collect().await.unwrap().to_bytes();
+            let decompressed = zstd::stream::decode_all(&actual_output[..]).unwrap();
+            assert_eq!(UNCOMPRESSED_INPUT, &decompressed[..]);
+        }
+
+        fn chunked_body(data: &[u8], chunk_size: usize) -> SdkBody {
+            let chunks: Vec<_> = data
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    Ok::<_, std::convert::Infallible>(Frame::data(Bytes::copy_from_slice(chunk)))
+                })
+                .collect();
+            SdkBody::from_body_1_x(StreamBody::new(futures_util::stream::iter(chunks)))
+        }
+
+        fn compress(algorithm: CompressionAlgorithm, data: &[u8]) -> Vec<u8> {
+            let mut out = Vec::new();
+            algorithm
+                .into_impl_http_body_1_x(&CompressionOptions::default())
+                .compress_bytes(data, &mut out)
+                .unwrap();
+            out
+        }
+
+        #[tokio::test]
+        async fn test_chunked_body_is_decompressed() {
+            let input = UNCOMPRESSED_INPUT.repeat(100);
+            for algorithm in [
+                CompressionAlgorithm::Gzip,
+                CompressionAlgorithm::Zstd,
+                CompressionAlgorithm::Brotli,
+            ] {
+                let compressed = compress(algorithm, &input);
+                let decompress = algorithm.into_decompressor(&DecompressionOptions::default());
+                let body = DecompressedBody::new(chunked_body(&compressed, 7), decompress);
+
+                let actual_output = body.collect().await.unwrap().to_bytes();
+                assert_eq!(input, actual_output, "{algorithm:?}");
+            }
+        }
+
+        #[tokio::test]
+        async fn test_chunked_body_is_compressed_as_a_single_stream() {
+            let input = UNCOMPRESSED_INPUT.repeat(100);
+            let compression_options = CompressionOptions::default();
+            for algorithm in [
+                CompressionAlgorithm::Gzip,
+                CompressionAlgorithm::Zstd,
+                CompressionAlgorithm::Brotli,
+            ] {
+                let compressor = algorithm.into_streaming_compressor(&compression_options);
+                let body = CompressedBody::new(chunked_body(&input, 7), compressor);
+                let compressed = body.collect().await.unwrap().to_bytes();
+
+                // Decompressing with a single decompressor only works if the body was compressed
+                // as a single stream
+                let mut decompressor =
+                    algorithm.into_decompressor(&DecompressionOptions::default());
+                let mut actual_output =
+                    decompressor.decompress_bytes(&compressed).unwrap().to_vec();
+                actual_output.extend_from_slice(&decompressor.finish().unwrap());
+                assert_eq!(input, actual_output, "{algorithm:?}");
+            }
+        }
+
+        #[tokio::test]
+        async fn test_streaming_compression_passes_through_trailers() {
+            let mut trailers = http_1_0::HeaderMap::new();
+            trailers.insert("x-amz-checksum-crc32", "i9aeUg==".parse().unwrap());
+            let frames = vec![
+                Ok::<_, std::convert::Infallible>(Frame::data(Bytes::from_static(
+                    UNCOMPRESSED_INPUT,
+                ))),
+                Ok(Frame::trailers(trailers.clone())),
+            ];
+            let body = SdkBody::from_body_1_x(StreamBody::new(futures_util::stream::iter(frames)));
+            let compressor = CompressionAlgorithm::Gzip
+                .into_streaming_compressor(&CompressionOptions::default());
+            let collected = CompressedBody::new(body, compressor)
+                .collect()
+                .await
+                .unwrap();
+
+            assert_eq!(Some(&trailers), collected.trailers());
+            let compressed = collected.to_bytes();
+            let mut decompressor =
+                CompressionAlgorithm::Gzip.into_decompressor(&DecompressionOptions::default());
+            let mut actual_output = decompressor.decompress_bytes(&compressed).unwrap().to_vec();
+            actual_output.extend_from_slice(&decompressor.finish().unwrap());
+            assert_eq!(UNCOMPRESSED_INPUT, actual_output);
+        }
+
+        #[tokio::test]
+        async fn test_truncated_body_is_an_error() {
+            let input = UNCOMPRESSED_INPUT.repeat(100);
+            for algorithm in [
+                CompressionAlgorithm::Gzip,
+                CompressionAlgorithm::Brotli,
+                CompressionAlgorithm::Zstd,
+            ] {
+                let compressed = compress(algorithm, &input);
+                let truncated = &compressed[..compressed.len() / 2];
+                let decompress = algorithm.into_decompressor(&DecompressionOptions::default());
+                let body = DecompressedBody::new(chunked_body(truncated, 7), decompress);
+
+                body.collect()
+                    .await
+                    .expect_err("truncated data can't be decompressed");
+            }
+        }
     }
 }
@@ -0,0 +1,212 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use crate::decompress::SizeLimitedBuffer;
+use crate::{Compress, CompressionOptions, Decompress, StreamingCompress};
+use aws_smithy_runtime_api::box_error::BoxError;
+use brotli::enc::BrotliEncoderParams;
+use brotli::{CompressorWriter, DecompressorWriter};
+use bytes::Bytes;
+use std::io::prelude::*;
+
+/// The size of the internal buffers used by the brotli compressor and decompressor.
+const BUFFER_SIZE: usize = 4096;
+/// The base-2 logarithm of the sliding window size used when compressing a stream.
+///
+/// This is the default used by the reference brotli implementation.
+const LG_WINDOW_SIZE: u32 = 22;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Brotli {
+    quality: i32,
+}
+
+impl Default for Brotli {
+    fn default() -> Self {
+        Self {
+            quality: BrotliEncoderParams::default().quality,
+        }
+    }
+}
+
+impl Brotli {
+    fn compress_bytes(&self, bytes: &[u8], mut writer: impl Write) -> Result<(), BoxError> {
+        let params = BrotliEncoderParams {
+            quality: self.quality,
+            ..Default::default()
+        };
+        brotli::BrotliCompress(&mut &bytes[..], &mut writer, &params)?;
+
+        Ok(())
+    }
+}
+
+impl Compress for Brotli {
+    fn compress_bytes(&mut self, bytes: &[u8], writer: &mut dyn Write) -> Result<(), BoxError> {
+        Brotli::compress_bytes(self, bytes, writer).map_err(Into::into)
+    }
+}
+
+#[cfg(feature = "http-body-0-4-x")]
+mod http_body_0_4_x {
+    use crate::http::http_body_0_4_x::CompressRequest;
+
+    impl CompressRequest for super::Brotli {
+        fn header_value(&self) -> http_0_2::HeaderValue {
+            http_0_2::HeaderValue::from_static(crate::BROTLI_NAME)
+        }
+    }
+}
+
+#[cfg(feature = "http-body-1-x")]
+mod http_body_1_x {
+    use crate::http::http_body_1_x::CompressRequest;
+
+    impl CompressRequest for super::Brotli {
+        fn header_value(&self) -> http_1_0::HeaderValue {
+            http_1_0::HeaderValue::from_static(crate::BROTLI_NAME)
+        }
+    }
+}
+
+impl From<&CompressionOptions> for Brotli {
+    fn from(options: &CompressionOptions) -> Self {
+        Brotli {
+            // Levels are validated by `CompressionOptions` so this can't overflow
+            quality: options.level as i32,
+        }
+    }
+}
+
+impl From<CompressionOptions> for Brotli {
+    fn from(options: CompressionOptions) -> Self {
+        Brotli::from(&options)
+    }
+}
+
+/// Compresses a stream of data into a single brotli stream.
+pub(crate) struct BrotliStreamingCompressor {
+    // Brotli can only be finished by consuming the writer, so this is `None` once finished
+    encoder: Option<CompressorWriter<Vec<u8>>>,
+}
+
+impl From<&CompressionOptions> for BrotliStreamingCompressor {
+    fn from(options: &CompressionOptions) -> Self {
+        Self {
+            encoder: Some(CompressorWriter::new(
+                Vec::new(),
+                BUFFER_SIZE,
+                options.level,
+                LG_WINDOW_SIZE,
+            )),
+        }
+    }
+}
+
+impl StreamingCompress for BrotliStreamingCompressor {
+    fn compress_bytes(&mut self, bytes: &[u8]) -> Result<Bytes, BoxError> {
+        let encoder = self
+            .encoder
+            .as_mut()
+            .ok_or("brotli compressor was already finished")?;
+        encoder.write_all(bytes)?;
+        Ok(std::mem::take(encoder.get_mut()).into())
+    }
+
+    fn finish(&mut self) -> Result<Bytes, BoxError> {
+        let encoder = self
+            .encoder
+            .take()
+            .ok_or("brotli compressor was already finished")?;
+        Ok(encoder.into_inner().into())
+    }
+}
+
+/// Incrementally decompresses brotli data.
+pub(crate) struct BrotliDecompressor {
+    decoder: DecompressorWriter<SizeLimitedBuffer>,
+}
+
+impl BrotliDecompressor {
+    pub(crate) fn new(max_decompressed_size_bytes: u64) -> Self {
+        Self {
+            decoder: DecompressorWriter::new(
+                SizeLimitedBuffer::new(max_decompressed_size_bytes),
+                BUFFER_SIZE,
+            ),
+        }
+    }
+}
+
+impl Decompress for BrotliDecompressor {
+    fn decompress_bytes(&mut self, bytes: &[u8]) -> Result<Bytes, BoxError> {
+        if let Err(err) = self
+            .decoder
+            .write_all(bytes)
+            .and_then(|_| self.decoder.flush())
+        {
+            return Err(self.decoder.get_ref().map_err(err));
+        }
+        Ok(self.decoder.get_mut().take())
+    }
+
+    fn finish(&mut self) -> Result<Bytes, BoxError> {
+        if let Err(err) = self.decoder.close() {
+            return Err(self.decoder.get_ref().map_err(err));
+        }
+        Ok(self.decoder.get_mut().take())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Brotli;
+    use crate::{CompressionAlgorithm, CompressionOptions};
+    use pretty_assertions::assert_eq;
+    use std::io::Read;
+
+    fn gettysburg_address() -> &'static [u8] {
+        include_bytes!("../test-data/gettysburg_address.txt")
+    }
+
+    fn decompress(compressed: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        brotli::Decompressor::new(compressed, 4096)
+            .read_to_end(&mut out)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn test_brotli_compression() {
+        let options = CompressionOptions::default()
+            .with_algorithm(CompressionAlgorithm::Brotli)
+            .unwrap();
+        let brotli = Brotli::from(&options);
+        let mut compressed_output = Vec::new();
+        brotli
+            .compress_bytes(gettysburg_address(), &mut compressed_output)
+            .expect("compression succeeds");
+
+        assert!(compressed_output.len() < gettysburg_address().len());
+        assert_eq!(gettysburg_address(), &decompress(&compressed_output)[..]);
+    }
+
+    #[test]
+    fn test_brotli_compression_max_quality() {
+        let options = CompressionOptions::default()
+            .with_algorithm(CompressionAlgorithm::Brotli)
+            .unwrap()
+            .with_level(11)
+            .unwrap();
+        let brotli = Brotli::from(&options);
+        let mut compressed_output = Vec::new();
+        brotli
+            .compress_bytes(gettysburg_address(), &mut compressed_output)
+            .expect("compression succeeds");
+
+        assert_eq!(gettysburg_address(), &decompress(&compressed_output)[..]);
+    }
+}
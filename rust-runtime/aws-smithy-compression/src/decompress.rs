@@ -0,0 +1,99 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use crate::DecompressedSizeLimitExceeded;
+use aws_smithy_runtime_api::box_error::BoxError;
+use bytes::Bytes;
+use std::io::{self, Write};
+
+/// A [`Write`] implementor that buffers decompressed output and refuses to grow past a limit.
+///
+/// Decompressors write into this buffer, and the decompressed data is taken out of it after each
+/// chunk of compressed input. Because the limit is checked on every write, a single small chunk
+/// of compressed input can't expand into an unbounded amount of memory.
+#[derive(Debug)]
+pub(crate) struct SizeLimitedBuffer {
+    buffer: Vec<u8>,
+    total_written: u64,
+    limit: u64,
+}
+
+impl SizeLimitedBuffer {
+    pub(crate) fn new(limit: u64) -> Self {
+        Self {
+            buffer: Vec::new(),
+            total_written: 0,
+            limit,
+        }
+    }
+
+    /// Take the data buffered so far.
+    pub(crate) fn take(&mut self) -> Bytes {
+        std::mem::take(&mut self.buffer).into()
+    }
+
+    /// If the limit was exceeded, return the corresponding error. Otherwise, return `err`.
+    ///
+    /// Decompressors may wrap or replace the IO error returned by [`SizeLimitedBuffer::write`],
+    /// so this is used to recover the original error.
+    pub(crate) fn map_err(&self, err: io::Error) -> BoxError {
+        if self.total_written > self.limit {
+            DecompressedSizeLimitExceeded { limit: self.limit }.into()
+        } else {
+            err.into()
+        }
+    }
+}
+
+impl Write for SizeLimitedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.total_written = self.total_written.saturating_add(buf.len() as u64);
+        if self.total_written > self.limit {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                DecompressedSizeLimitExceeded { limit: self.limit },
+            ));
+        }
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SizeLimitedBuffer;
+    use crate::DecompressedSizeLimitExceeded;
+    use std::io::Write;
+
+    #[test]
+    fn test_writes_within_limit_are_buffered() {
+        let mut buffer = SizeLimitedBuffer::new(10);
+        buffer.write_all(b"hello").unwrap();
+        buffer.write_all(b"world").unwrap();
+        assert_eq!(&b"helloworld"[..], &buffer.take()[..]);
+        assert!(buffer.take().is_empty());
+    }
+
+    #[test]
+    fn test_limit_applies_across_takes() {
+        let mut buffer = SizeLimitedBuffer::new(10);
+        buffer.write_all(b"hello").unwrap();
+        let _ = buffer.take();
+        buffer.write_all(b"world").unwrap();
+        let _ = buffer.take();
+        let err = buffer.write_all(b"!").expect_err("limit exceeded");
+        let err = buffer.map_err(err);
+        assert_eq!(
+            10,
+            err.downcast_ref::<DecompressedSizeLimitExceeded>()
+                .expect("correct error type")
+                .limit()
+        );
+    }
+}
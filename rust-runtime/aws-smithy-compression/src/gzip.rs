@@ -3,9 +3,11 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
-use crate::{Compress, CompressionOptions};
+use crate::decompress::SizeLimitedBuffer;
+use crate::{Compress, CompressionOptions, Decompress, StreamingCompress};
 use aws_smithy_runtime_api::box_error::BoxError;
-use flate2::write::GzEncoder;
+use bytes::Bytes;
+use flate2::write::{GzDecoder, GzEncoder};
 use std::io::prelude::*;
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
@@ -67,6 +69,64 @@ impl From<CompressionOptions> for Gzip {
     }
 }
 
+/// Compresses a stream of data into a single gzip stream.
+pub(crate) struct GzipStreamingCompressor {
+    encoder: GzEncoder<Vec<u8>>,
+}
+
+impl From<&CompressionOptions> for GzipStreamingCompressor {
+    fn from(options: &CompressionOptions) -> Self {
+        Self {
+            encoder: GzEncoder::new(Vec::new(), flate2::Compression::new(options.level)),
+        }
+    }
+}
+
+impl StreamingCompress for GzipStreamingCompressor {
+    fn compress_bytes(&mut self, bytes: &[u8]) -> Result<Bytes, BoxError> {
+        self.encoder.write_all(bytes)?;
+        Ok(std::mem::take(self.encoder.get_mut()).into())
+    }
+
+    fn finish(&mut self) -> Result<Bytes, BoxError> {
+        self.encoder.try_finish()?;
+        Ok(std::mem::take(self.encoder.get_mut()).into())
+    }
+}
+
+/// Incrementally decompresses gzip data.
+pub(crate) struct GzipDecompressor {
+    decoder: GzDecoder<SizeLimitedBuffer>,
+}
+
+impl GzipDecompressor {
+    pub(crate) fn new(max_decompressed_size_bytes: u64) -> Self {
+        Self {
+            decoder: GzDecoder::new(SizeLimitedBuffer::new(max_decompressed_size_bytes)),
+        }
+    }
+}
+
+impl Decompress for GzipDecompressor {
+    fn decompress_bytes(&mut self, bytes: &[u8]) -> Result<Bytes, BoxError> {
+        if let Err(err) = self
+            .decoder
+            .write_all(bytes)
+            .and_then(|_| self.decoder.flush())
+        {
+            return Err(self.decoder.get_ref().map_err(err));
+        }
+        Ok(self.decoder.get_mut().take())
+    }
+
+    fn finish(&mut self) -> Result<Bytes, BoxError> {
+        if let Err(err) = self.decoder.try_finish() {
+            return Err(self.decoder.get_ref().map_err(err));
+        }
+        Ok(self.decoder.get_mut().take())
+    }
+}
+
 // Windows line-endings will cause the compression test to fail.
 #[cfg(all(test, not(windows)))]
 mod tests {
@@ -0,0 +1,231 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Interceptors for compressing requests and decompressing responses
+
+use crate::body::decompress::DecompressedBody;
+use crate::{CompressionAlgorithm, DecompressionOptions};
+use aws_smithy_runtime_api::box_error::BoxError;
+use aws_smithy_runtime_api::client::interceptors::context::BeforeDeserializationInterceptorContextMut;
+use aws_smithy_runtime_api::client::interceptors::Intercept;
+use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
+use aws_smithy_types::body::SdkBody;
+use aws_smithy_types::config_bag::ConfigBag;
+
+const CONTENT_ENCODING: &str = "content-encoding";
+const CONTENT_LENGTH: &str = "content-length";
+
+/// Interceptor that transparently decompresses response bodies.
+///
+/// Responses with a `Content-Encoding` of `gzip`, `zstd`, or `br` have their bodies replaced with
+/// a body that decompresses data as it streams in. The `Content-Encoding` and `Content-Length`
+/// headers are removed since they no longer describe the body. Responses with any other
+/// content-encoding are left untouched.
+///
+/// Decompression is configured by storing [`DecompressionOptions`] in the config bag. This can be
+/// done per-operation with a config override. If no options are stored, the defaults are used.
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct ResponseDecompressionInterceptor;
+
+impl ResponseDecompressionInterceptor {
+    /// Create a new `ResponseDecompressionInterceptor`.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Intercept for ResponseDecompressionInterceptor {
+    fn name(&self) -> &'static str {
+        "ResponseDecompressionInterceptor"
+    }
+
+    fn modify_before_deserialization(
+        &self,
+        context: &mut BeforeDeserializationInterceptorContextMut<'_>,
+        _runtime_components: &RuntimeComponents,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let options = cfg
+            .load::<DecompressionOptions>()
+            .cloned()
+            .unwrap_or_default();
+        if !options.is_enabled() {
+            tracing::trace!("response decompression is disabled and will not be applied");
+            return Ok(());
+        }
+
+        let response = context.response_mut();
+        let algorithm = match response.headers().get(CONTENT_ENCODING) {
+            Some(content_encoding) => match content_encoding.trim().parse::<CompressionAlgorithm>()
+            {
+                Ok(algorithm) => algorithm,
+                Err(_) => {
+                    tracing::trace!(
+                        content_encoding,
+                        "response content-encoding is not supported and will not be decompressed"
+                    );
+                    return Ok(());
+                }
+            },
+            None => return Ok(()),
+        };
+        tracing::trace!(
+            algorithm = algorithm.as_str(),
+            "decompressing response body..."
+        );
+
+        response.headers_mut().remove(CONTENT_ENCODING);
+        response.headers_mut().remove(CONTENT_LENGTH);
+        let body = response.take_body();
+        *response.body_mut() = body.map(move |body| {
+            SdkBody::from_body_0_4(DecompressedBody::new(
+                body,
+                algorithm.into_decompressor(&options),
+            ))
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ResponseDecompressionInterceptor;
+    use crate::{DecompressedSizeLimitExceeded, DecompressionOptions};
+    use aws_smithy_runtime_api::client::interceptors::context::{Input, InterceptorContext};
+    use aws_smithy_runtime_api::client::interceptors::Intercept;
+    use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, HttpResponse};
+    use aws_smithy_runtime_api::client::runtime_components::RuntimeComponentsBuilder;
+    use aws_smithy_types::body::SdkBody;
+    use aws_smithy_types::byte_stream::ByteStream;
+    use aws_smithy_types::config_bag::{ConfigBag, Layer};
+    use std::io::Write;
+
+    const UNCOMPRESSED_INPUT: &[u8] = b"hello world";
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn context(content_encoding: Option<&str>, body: SdkBody) -> InterceptorContext {
+        let mut context = InterceptorContext::new(Input::doesnt_matter());
+        context.enter_serialization_phase();
+        context.set_request(HttpRequest::empty());
+        let _ = context.take_input();
+        context.enter_before_transmit_phase();
+        context.enter_transmit_phase();
+        let _ = context.take_request();
+        let mut response = HttpResponse::new(200.try_into().unwrap(), body);
+        if let Some(content_encoding) = content_encoding {
+            response
+                .headers_mut()
+                .insert("content-encoding", content_encoding.to_owned());
+        }
+        context.set_response(response);
+        context.enter_before_deserialization_phase();
+        context
+    }
+
+    fn run_interceptor(context: &mut InterceptorContext, cfg: &mut ConfigBag) {
+        let rc = RuntimeComponentsBuilder::for_tests().build().unwrap();
+        let mut ctx = Into::into(context);
+        ResponseDecompressionInterceptor::new()
+            .modify_before_deserialization(&mut ctx, &rc, cfg)
+            .unwrap();
+    }
+
+    async fn read_body(context: &mut InterceptorContext) -> Result<Vec<u8>, String> {
+        let body = context.response_mut().unwrap().take_body();
+        ByteStream::new(body)
+            .collect()
+            .await
+            .map(|data| data.to_vec())
+            .map_err(|err| format!("{:?}", err))
+    }
+
+    #[tokio::test]
+    async fn test_gzip_response_is_decompressed() {
+        let mut cfg = ConfigBag::base();
+        let mut context = context(Some("gzip"), SdkBody::from(gzip(UNCOMPRESSED_INPUT)));
+        run_interceptor(&mut context, &mut cfg);
+
+        let headers = context.response().unwrap().headers();
+        assert!(headers.get("content-encoding").is_none());
+        assert!(headers.get("content-length").is_none());
+        assert_eq!(UNCOMPRESSED_INPUT, read_body(&mut context).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_zstd_response_is_decompressed() {
+        let mut cfg = ConfigBag::base();
+        let compressed = zstd::stream::encode_all(UNCOMPRESSED_INPUT, 3).unwrap();
+        let mut context = context(Some("zstd"), SdkBody::from(compressed));
+        run_interceptor(&mut context, &mut cfg);
+
+        assert_eq!(UNCOMPRESSED_INPUT, read_body(&mut context).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_uncompressed_response_is_untouched() {
+        let mut cfg = ConfigBag::base();
+        let mut plain = context(None, SdkBody::from(UNCOMPRESSED_INPUT));
+        run_interceptor(&mut plain, &mut cfg);
+        assert_eq!(UNCOMPRESSED_INPUT, read_body(&mut plain).await.unwrap());
+
+        let mut identity = context(Some("identity"), SdkBody::from(UNCOMPRESSED_INPUT));
+        run_interceptor(&mut identity, &mut cfg);
+        assert_eq!(
+            Some("identity"),
+            identity
+                .response()
+                .unwrap()
+                .headers()
+                .get("content-encoding")
+        );
+        assert_eq!(UNCOMPRESSED_INPUT, read_body(&mut identity).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_empty_compressed_response() {
+        let mut cfg = ConfigBag::base();
+        let mut context = context(Some("gzip"), SdkBody::empty());
+        run_interceptor(&mut context, &mut cfg);
+        assert!(read_body(&mut context).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_decompression_can_be_disabled() {
+        let mut cfg = ConfigBag::base();
+        let mut layer = Layer::new("test");
+        layer.store_put(DecompressionOptions::default().with_enabled(false));
+        cfg.push_layer(layer);
+        let compressed = gzip(UNCOMPRESSED_INPUT);
+        let mut context = context(Some("gzip"), SdkBody::from(compressed.clone()));
+        run_interceptor(&mut context, &mut cfg);
+
+        assert_eq!(compressed, read_body(&mut context).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_decompressed_size_is_limited() {
+        let mut cfg = ConfigBag::base();
+        let mut layer = Layer::new("test");
+        layer.store_put(DecompressionOptions::default().with_max_decompressed_size_bytes(1024));
+        cfg.push_layer(layer);
+        // A megabyte of zeros compresses down to about a kilobyte
+        let compressed = gzip(&vec![0; 1024 * 1024]);
+        assert!(compressed.len() < 2048);
+        let mut context = context(Some("gzip"), SdkBody::from(compressed));
+        run_interceptor(&mut context, &mut cfg);
+
+        let body = context.response_mut().unwrap().take_body();
+        let err = ByteStream::new(body).collect().await.expect_err("too big");
+        let source = std::error::Error::source(&err).expect("has a source");
+        assert!(source.is::<DecompressedSizeLimitExceeded>(), "{:?}", err);
+    }
+}
@@ -18,20 +18,38 @@
 
 use aws_smithy_runtime_api::box_error::BoxError;
 use aws_smithy_types::config_bag::{Storable, StoreReplace};
+use bytes::Bytes;
+use std::error::Error;
+use std::fmt;
 use std::io::Write;
+use std::ops::RangeInclusive;
 use std::str::FromStr;
 
 pub mod body;
+mod brotli;
+mod decompress;
 mod gzip;
 pub mod http;
+#[cfg(feature = "http-body-0-4-x")]
+pub mod interceptor;
+mod zstd;
 
 // Valid compression algorithm names
 /// The name of the `gzip` algorithm.
 pub const GZIP_NAME: &str = "gzip";
+/// The name of the `zstd` algorithm.
+pub const ZSTD_NAME: &str = "zstd";
+/// The name of the `brotli` algorithm.
+///
+/// This is the token used in the `Content-Encoding` header, as registered with IANA.
+pub const BROTLI_NAME: &str = "br";
 
 /// The maximum-allowable value per internal standards is 10 Megabytes.
 const MAX_MIN_COMPRESSION_SIZE_BYTES: u32 = 10_485_760;
 
+/// The default limit on the size of a decompressed response body is 1 Gigabyte.
+const DEFAULT_MAX_DECOMPRESSED_SIZE_BYTES: u64 = 1_073_741_824;
+
 /// Types implementing this trait can compress data.
 ///
 /// Compression algorithms are used reduce the size of data. This trait
@@ -44,11 +62,127 @@ pub trait Compress: Send + Sync {
     fn compress_bytes(&mut self, bytes: &[u8], writer: &mut dyn Write) -> Result<(), BoxError>;
 }
 
+/// Types implementing this trait can compress a stream of data.
+///
+/// Unlike [`Compress`], which compresses each slice of bytes on its own, a streaming compressor
+/// carries state across calls. This allows a body of unknown length to be compressed into a single
+/// compressed stream as it is read, without buffering it in memory. Data is passed to
+/// [`compress_bytes`](StreamingCompress::compress_bytes) as it arrives and
+/// [`finish`](StreamingCompress::finish) is called once all of it has been seen.
+pub trait StreamingCompress: Send + Sync {
+    /// Given a slice of bytes, return whatever compressed bytes are available so far.
+    ///
+    /// The returned bytes may be empty if the compressor is waiting for more data.
+    fn compress_bytes(&mut self, bytes: &[u8]) -> Result<Bytes, BoxError>;
+
+    /// Signal that there is no more data and return the remaining compressed bytes.
+    fn finish(&mut self) -> Result<Bytes, BoxError>;
+}
+
+/// Types implementing this trait can decompress data.
+///
+/// Decompression is incremental so that a body can be decompressed as it streams in. Compressed
+/// data is passed to [`decompress_bytes`](Decompress::decompress_bytes) as it arrives and
+/// [`finish`](Decompress::finish) is called once all of it has been seen. This trait requires
+/// Send + Sync because trait implementors are often used in an async context.
+pub trait Decompress: Send + Sync {
+    /// Given a slice of compressed bytes, return whatever decompressed bytes are available so far.
+    ///
+    /// The returned bytes may be empty if more compressed data is needed to make progress.
+    fn decompress_bytes(&mut self, bytes: &[u8]) -> Result<Bytes, BoxError>;
+
+    /// Signal that there is no more compressed data and return any remaining decompressed bytes.
+    ///
+    /// Implementors should return an error if the compressed data was truncated.
+    fn finish(&mut self) -> Result<Bytes, BoxError>;
+}
+
+/// An error returned when decompressed data would exceed the configured maximum size.
+///
+/// Highly compressible data _(a so-called "zip bomb")_ can expand to many times its compressed
+/// size. Decompression stops with this error rather than allocating an unbounded amount of memory.
+#[derive(Debug)]
+pub struct DecompressedSizeLimitExceeded {
+    limit: u64,
+}
+
+impl DecompressedSizeLimitExceeded {
+    /// The limit that was exceeded, in bytes.
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+}
+
+impl fmt::Display for DecompressedSizeLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "decompressed data exceeded the maximum allowed size of {} bytes",
+            self.limit
+        )
+    }
+}
+
+impl Error for DecompressedSizeLimitExceeded {}
+
+/// Options for configuring response decompression.
+///
+/// Store these in the config bag to configure decompression for a client or a single operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DecompressionOptions {
+    max_decompressed_size_bytes: u64,
+    enabled: bool,
+}
+
+impl Default for DecompressionOptions {
+    fn default() -> Self {
+        Self {
+            max_decompressed_size_bytes: DEFAULT_MAX_DECOMPRESSED_SIZE_BYTES,
+            enabled: true,
+        }
+    }
+}
+
+impl DecompressionOptions {
+    /// The maximum size of decompressed data.
+    ///
+    /// Decompression fails with [`DecompressedSizeLimitExceeded`] if this would be exceeded.
+    pub fn max_decompressed_size_bytes(&self) -> u64 {
+        self.max_decompressed_size_bytes
+    }
+
+    /// Whether decompression is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Set whether decompression is enabled.
+    pub fn with_enabled(self, enabled: bool) -> Self {
+        Self { enabled, ..self }
+    }
+
+    /// Set the maximum size of decompressed data.
+    ///
+    /// The default is `1_073_741_824` _(1 GiB)_.
+    pub fn with_max_decompressed_size_bytes(self, max_decompressed_size_bytes: u64) -> Self {
+        Self {
+            max_decompressed_size_bytes,
+            ..self
+        }
+    }
+}
+
+impl Storable for DecompressionOptions {
+    type Storer = StoreReplace<Self>;
+}
+
 /// Options for configuring request compression.
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[non_exhaustive]
 pub struct CompressionOptions {
-    /// Valid values are 0-9 with lower values configuring less (but faster) compression
+    algorithm: CompressionAlgorithm,
+    /// Valid values depend on the algorithm, with lower values configuring less (but faster) compression
     level: u32,
     min_compression_size_bytes: u32,
     enabled: bool,
@@ -57,6 +191,7 @@ pub struct CompressionOptions {
 impl Default for CompressionOptions {
     fn default() -> Self {
         Self {
+            algorithm: CompressionAlgorithm::Gzip,
             level: 6,
             min_compression_size_bytes: 10240,
             enabled: true,
@@ -65,6 +200,11 @@ impl Default for CompressionOptions {
 }
 
 impl CompressionOptions {
+    /// The compression algorithm to use.
+    pub fn algorithm(&self) -> CompressionAlgorithm {
+        self.algorithm
+    }
+
     /// The compression level to use.
     pub fn level(&self) -> u32 {
         self.level
@@ -87,11 +227,22 @@ impl CompressionOptions {
         Self { enabled, ..self }
     }
 
+    /// Set the compression algorithm.
+    ///
+    /// Returns an error if the currently configured level is outside the new algorithm's
+    /// [level range](CompressionAlgorithm::level_range). The default level is valid for
+    /// every supported algorithm, so prefer setting the algorithm before the level.
+    pub fn with_algorithm(self, algorithm: CompressionAlgorithm) -> Result<Self, BoxError> {
+        Self::validate_level(algorithm, self.level)?;
+        Ok(Self { algorithm, ..self })
+    }
+
     /// Set the compression level.
     ///
-    /// Valid values are `0..=9` with lower values configuring less _(but faster)_ compression
+    /// Valid values depend on the configured algorithm _(see [`CompressionAlgorithm::level_range`])_,
+    /// with lower values configuring less _(but faster)_ compression
     pub fn with_level(self, level: u32) -> Result<Self, BoxError> {
-        Self::validate_level(level)?;
+        Self::validate_level(self.algorithm, level)?;
         Ok(Self { level, ..self })
     }
 
@@ -110,11 +261,15 @@ impl CompressionOptions {
         })
     }
 
-    fn validate_level(level: u32) -> Result<(), BoxError> {
-        if level > 9 {
+    fn validate_level(algorithm: CompressionAlgorithm, level: u32) -> Result<(), BoxError> {
+        let range = algorithm.level_range();
+        if !range.contains(&level) {
             return Err(format!(
-                "compression level `{}` is invalid, valid values are 0..=9",
-                level
+                "compression level `{}` is invalid for `{}`, valid values are {}..={}",
+                level,
+                algorithm.as_str(),
+                range.start(),
+                range.end()
             )
             .into());
         };
@@ -145,6 +300,10 @@ impl Storable for CompressionOptions {
 pub enum CompressionAlgorithm {
     /// The [gzip](https://en.wikipedia.org/wiki/Gzip) compression algorithm
     Gzip,
+    /// The [zstd](https://en.wikipedia.org/wiki/Zstd) compression algorithm
+    Zstd,
+    /// The [brotli](https://en.wikipedia.org/wiki/Brotli) compression algorithm
+    Brotli,
 }
 
 impl FromStr for CompressionAlgorithm {
@@ -154,11 +313,17 @@ impl FromStr for CompressionAlgorithm {
     ///
     /// Valid algorithm names are:
     /// - "gzip"
+    /// - "zstd"
+    /// - "br"
     ///
     /// Passing an invalid name will return an error.
     fn from_str(compression_algorithm: &str) -> Result<Self, Self::Err> {
         if compression_algorithm.eq_ignore_ascii_case(GZIP_NAME) {
             Ok(Self::Gzip)
+        } else if compression_algorithm.eq_ignore_ascii_case(ZSTD_NAME) {
+            Ok(Self::Zstd)
+        } else if compression_algorithm.eq_ignore_ascii_case(BROTLI_NAME) {
+            Ok(Self::Brotli)
         } else {
             Err(format!("unknown compression algorithm `{compression_algorithm}`").into())
         }
@@ -167,24 +332,52 @@ impl FromStr for CompressionAlgorithm {
 
 impl CompressionAlgorithm {
     #[cfg(feature = "http-body-0-4-x")]
-    /// Return the `HttpChecksum` implementor for this algorithm.
+    /// Return the `CompressRequest` implementor for this algorithm.
     pub fn into_impl_http_body_0_4_x(
         self,
         options: &CompressionOptions,
     ) -> Box<dyn http::http_body_0_4_x::CompressRequest> {
         match self {
             Self::Gzip => Box::new(gzip::Gzip::from(options)),
+            Self::Zstd => Box::new(zstd::Zstd::from(options)),
+            Self::Brotli => Box::new(brotli::Brotli::from(options)),
         }
     }
 
     #[cfg(feature = "http-body-1-x")]
-    /// Return the `HttpChecksum` implementor for this algorithm.
+    /// Return the `CompressRequest` implementor for this algorithm.
     pub fn into_impl_http_body_1_x(
         self,
         options: &CompressionOptions,
     ) -> Box<dyn http::http_body_1_x::CompressRequest> {
         match self {
             Self::Gzip => Box::new(gzip::Gzip::from(options)),
+            Self::Zstd => Box::new(zstd::Zstd::from(options)),
+            Self::Brotli => Box::new(brotli::Brotli::from(options)),
+        }
+    }
+
+    /// Return a [`StreamingCompress`] implementor for this algorithm.
+    ///
+    /// Use this to compress bodies that are streaming or of unknown length.
+    pub fn into_streaming_compressor(
+        self,
+        options: &CompressionOptions,
+    ) -> Box<dyn StreamingCompress> {
+        match self {
+            Self::Gzip => Box::new(gzip::GzipStreamingCompressor::from(options)),
+            Self::Zstd => Box::new(zstd::ZstdStreamingCompressor::from(options)),
+            Self::Brotli => Box::new(brotli::BrotliStreamingCompressor::from(options)),
+        }
+    }
+
+    /// Return a [`Decompress`] implementor for this algorithm.
+    pub fn into_decompressor(self, options: &DecompressionOptions) -> Box<dyn Decompress> {
+        let limit = options.max_decompressed_size_bytes();
+        match self {
+            Self::Gzip => Box::new(gzip::GzipDecompressor::new(limit)),
+            Self::Zstd => Box::new(zstd::ZstdDecompressor::new(limit)),
+            Self::Brotli => Box::new(brotli::BrotliDecompressor::new(limit)),
         }
     }
 
@@ -192,13 +385,28 @@ impl CompressionAlgorithm {
     pub fn as_str(&self) -> &'static str {
         match self {
             Self::Gzip { .. } => GZIP_NAME,
+            Self::Zstd => ZSTD_NAME,
+            Self::Brotli => BROTLI_NAME,
+        }
+    }
+
+    /// Return the range of compression levels supported by this algorithm.
+    ///
+    /// - gzip: `0..=9`
+    /// - zstd: `1..=22`
+    /// - brotli: `0..=11`
+    pub fn level_range(&self) -> RangeInclusive<u32> {
+        match self {
+            Self::Gzip => 0..=9,
+            Self::Zstd => 1..=22,
+            Self::Brotli => 0..=11,
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::CompressionAlgorithm;
+    use crate::{CompressionAlgorithm, CompressionOptions};
     use pretty_assertions::assert_eq;
 
     #[test]
@@ -217,4 +425,62 @@ mod tests {
         let algo = "gzip".parse::<CompressionAlgorithm>().unwrap();
         assert_eq!("gzip", algo.as_str());
     }
+
+    #[test]
+    fn test_compression_algorithm_from_str_zstd() {
+        let algo = "zstd".parse::<CompressionAlgorithm>().unwrap();
+        assert_eq!(CompressionAlgorithm::Zstd, algo);
+        assert_eq!("zstd", algo.as_str());
+    }
+
+    #[test]
+    fn test_compression_algorithm_from_str_brotli() {
+        let algo = "BR".parse::<CompressionAlgorithm>().unwrap();
+        assert_eq!(CompressionAlgorithm::Brotli, algo);
+        assert_eq!("br", algo.as_str());
+    }
+
+    #[test]
+    fn test_level_is_validated_against_algorithm() {
+        let error = CompressionOptions::default()
+            .with_level(10)
+            .expect_err("10 is out of range for gzip");
+        assert_eq!(
+            "compression level `10` is invalid for `gzip`, valid values are 0..=9",
+            error.to_string()
+        );
+
+        let options = CompressionOptions::default()
+            .with_algorithm(CompressionAlgorithm::Zstd)
+            .unwrap()
+            .with_level(19)
+            .unwrap();
+        assert_eq!(19, options.level());
+
+        let error = CompressionOptions::default()
+            .with_algorithm(CompressionAlgorithm::Brotli)
+            .unwrap()
+            .with_level(12)
+            .expect_err("12 is out of range for brotli");
+        assert_eq!(
+            "compression level `12` is invalid for `br`, valid values are 0..=11",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn test_changing_algorithm_revalidates_level() {
+        let options = CompressionOptions::default()
+            .with_algorithm(CompressionAlgorithm::Zstd)
+            .unwrap()
+            .with_level(15)
+            .unwrap();
+        let error = options
+            .with_algorithm(CompressionAlgorithm::Gzip)
+            .expect_err("15 is out of range for gzip");
+        assert_eq!(
+            "compression level `15` is invalid for `gzip`, valid values are 0..=9",
+            error.to_string()
+        );
+    }
 }
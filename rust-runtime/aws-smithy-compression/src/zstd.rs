@@ -0,0 +1,206 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use crate::decompress::SizeLimitedBuffer;
+use crate::{Compress, CompressionOptions, Decompress, StreamingCompress};
+use aws_smithy_runtime_api::box_error::BoxError;
+use bytes::Bytes;
+use std::io::prelude::*;
+use zstd::stream::raw::{self, InBuffer, Operation, OutBuffer};
+use zstd::stream::write::Encoder;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Zstd {
+    level: i32,
+}
+
+impl Default for Zstd {
+    fn default() -> Self {
+        Self {
+            level: zstd::DEFAULT_COMPRESSION_LEVEL,
+        }
+    }
+}
+
+impl Zstd {
+    fn compress_bytes(&self, bytes: &[u8], writer: impl Write) -> Result<(), BoxError> {
+        let mut encoder = Encoder::new(writer, self.level)?;
+        encoder.write_all(bytes)?;
+        encoder.finish()?;
+
+        Ok(())
+    }
+}
+
+impl Compress for Zstd {
+    fn compress_bytes(&mut self, bytes: &[u8], writer: &mut dyn Write) -> Result<(), BoxError> {
+        Zstd::compress_bytes(self, bytes, writer).map_err(Into::into)
+    }
+}
+
+#[cfg(feature = "http-body-0-4-x")]
+mod http_body_0_4_x {
+    use crate::http::http_body_0_4_x::CompressRequest;
+
+    impl CompressRequest for super::Zstd {
+        fn header_value(&self) -> http_0_2::HeaderValue {
+            http_0_2::HeaderValue::from_static(crate::ZSTD_NAME)
+        }
+    }
+}
+
+#[cfg(feature = "http-body-1-x")]
+mod http_body_1_x {
+    use crate::http::http_body_1_x::CompressRequest;
+
+    impl CompressRequest for super::Zstd {
+        fn header_value(&self) -> http_1_0::HeaderValue {
+            http_1_0::HeaderValue::from_static(crate::ZSTD_NAME)
+        }
+    }
+}
+
+impl From<&CompressionOptions> for Zstd {
+    fn from(options: &CompressionOptions) -> Self {
+        Zstd {
+            // Levels are validated by `CompressionOptions` so this can't overflow
+            level: options.level as i32,
+        }
+    }
+}
+
+impl From<CompressionOptions> for Zstd {
+    fn from(options: CompressionOptions) -> Self {
+        Zstd::from(&options)
+    }
+}
+
+/// Compresses a stream of data into a single zstd frame.
+pub(crate) struct ZstdStreamingCompressor {
+    encoder: Encoder<'static, Vec<u8>>,
+}
+
+impl From<&CompressionOptions> for ZstdStreamingCompressor {
+    fn from(options: &CompressionOptions) -> Self {
+        Self {
+            // Levels are validated by `CompressionOptions` so this can't overflow
+            encoder: Encoder::new(Vec::new(), options.level as i32)
+                .expect("creating a zstd compression context only fails when out of memory"),
+        }
+    }
+}
+
+impl StreamingCompress for ZstdStreamingCompressor {
+    fn compress_bytes(&mut self, bytes: &[u8]) -> Result<Bytes, BoxError> {
+        self.encoder.write_all(bytes)?;
+        Ok(std::mem::take(self.encoder.get_mut()).into())
+    }
+
+    fn finish(&mut self) -> Result<Bytes, BoxError> {
+        self.encoder.do_finish()?;
+        Ok(std::mem::take(self.encoder.get_mut()).into())
+    }
+}
+
+/// Incrementally decompresses zstd data.
+pub(crate) struct ZstdDecompressor {
+    decoder: raw::Decoder<'static>,
+    output: SizeLimitedBuffer,
+    /// Whether the input seen so far ends with a complete frame
+    frame_complete: bool,
+}
+
+impl ZstdDecompressor {
+    pub(crate) fn new(max_decompressed_size_bytes: u64) -> Self {
+        Self {
+            decoder: raw::Decoder::new()
+                .expect("creating a zstd decompression context only fails when out of memory"),
+            output: SizeLimitedBuffer::new(max_decompressed_size_bytes),
+            frame_complete: false,
+        }
+    }
+
+    /// Decompress all of `bytes`, along with any output the decoder is still holding on to.
+    fn run(&mut self, bytes: &[u8]) -> Result<(), BoxError> {
+        let mut input = InBuffer::around(bytes);
+        let mut scratch = vec![0; zstd::zstd_safe::DCtx::out_size()];
+        loop {
+            let mut output = OutBuffer::around(&mut scratch[..]);
+            // The decoder returns a hint of how much more input it needs, which is zero once a
+            // frame has been fully decoded and flushed.
+            let remaining_hint = self.decoder.run(&mut input, &mut output)?;
+            let (written, output_full) = (output.pos(), output.pos() == output.capacity());
+            if let Err(err) = self.output.write_all(&scratch[..written]) {
+                return Err(self.output.map_err(err));
+            }
+            if input.pos() < bytes.len() || output_full {
+                continue;
+            }
+            // Once a frame is complete, the decoder asks for the next frame's header. Only a run
+            // that made progress says anything about whether the data ends mid-frame.
+            if !bytes.is_empty() || written > 0 {
+                self.frame_complete = remaining_hint == 0;
+            }
+            return Ok(());
+        }
+    }
+}
+
+impl Decompress for ZstdDecompressor {
+    fn decompress_bytes(&mut self, bytes: &[u8]) -> Result<Bytes, BoxError> {
+        self.run(bytes)?;
+        Ok(self.output.take())
+    }
+
+    fn finish(&mut self) -> Result<Bytes, BoxError> {
+        self.run(&[])?;
+        if !self.frame_complete {
+            return Err("zstd data ended in the middle of a frame".into());
+        }
+        Ok(self.output.take())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Zstd;
+    use crate::{CompressionAlgorithm, CompressionOptions};
+    use pretty_assertions::assert_eq;
+
+    fn gettysburg_address() -> &'static [u8] {
+        include_bytes!("../test-data/gettysburg_address.txt")
+    }
+
+    #[test]
+    fn test_zstd_compression() {
+        let options = CompressionOptions::default()
+            .with_algorithm(CompressionAlgorithm::Zstd)
+            .unwrap();
+        let zstd = Zstd::from(&options);
+        let mut compressed_output = Vec::new();
+        zstd.compress_bytes(gettysburg_address(), &mut compressed_output)
+            .expect("compression succeeds");
+
+        assert!(compressed_output.len() < gettysburg_address().len());
+        let uncompressed_actual = zstd::stream::decode_all(&compressed_output[..]).unwrap();
+        assert_eq!(gettysburg_address(), &uncompressed_actual[..]);
+    }
+
+    #[test]
+    fn test_zstd_compression_max_level() {
+        let options = CompressionOptions::default()
+            .with_algorithm(CompressionAlgorithm::Zstd)
+            .unwrap()
+            .with_level(22)
+            .unwrap();
+        let zstd = Zstd::from(&options);
+        let mut compressed_output = Vec::new();
+        zstd.compress_bytes(gettysburg_address(), &mut compressed_output)
+            .expect("compression succeeds");
+
+        let uncompressed_actual = zstd::stream::decode_all(&compressed_output[..]).unwrap();
+        assert_eq!(gettysburg_address(), &uncompressed_actual[..]);
+    }
+}
@@ -0,0 +1,244 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use crate::rule::{MockResponse, Rule};
+use aws_smithy_runtime_api::box_error::BoxError;
+use aws_smithy_runtime_api::client::interceptors::context::{
+    BeforeDeserializationInterceptorContextMut, BeforeSerializationInterceptorContextMut,
+    FinalizerInterceptorContextMut,
+};
+use aws_smithy_runtime_api::client::interceptors::Intercept;
+use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
+use aws_smithy_runtime_api::http::{Response, StatusCode};
+use aws_smithy_types::body::SdkBody;
+use aws_smithy_types::config_bag::{ConfigBag, Storable, StoreReplace};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// Describes how rules are selected for each operation.
+///
+/// A rule handles every attempt of the operation it's selected for, returning the next response
+/// in its sequence for each attempt.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RuleMode {
+    /// Use the first matching rule that isn't exhausted. Rules remain available afterwards.
+    MatchAny,
+    /// Use each rule for exactly one operation, in the order the rules were added.
+    ///
+    /// Panics if the next rule doesn't match the operation.
+    Sequential,
+    /// Use each rule for exactly one operation, in any order.
+    ///
+    /// The first matching rule is used, then removed, which suits code that sends requests
+    /// concurrently.
+    Unordered,
+}
+
+/// Interceptor which produces mock responses based on a list of rules
+///
+/// By default, the interceptor is strict: it panics when an operation matches no rule. Use
+/// [`allow_passthrough`](MockResponseInterceptor::allow_passthrough) to send those operations to
+/// the client's HTTP client instead.
+///
+/// Clones share their rules, so a clone can be kept to [`verify`](Self::verify) expectations
+/// after the original has been added to a client.
+#[derive(Clone)]
+pub struct MockResponseInterceptor {
+    rules: Arc<Mutex<VecDeque<Rule>>>,
+    all_rules: Vec<Rule>,
+    rule_mode: RuleMode,
+    must_match: bool,
+}
+
+impl fmt::Debug for MockResponseInterceptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} rules", self.rules.lock().unwrap().len())
+    }
+}
+
+impl Default for MockResponseInterceptor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+struct ActiveRule(Rule);
+
+impl Storable for ActiveRule {
+    type Storer = StoreReplace<ActiveRule>;
+}
+
+/// Modeled response to apply once the attempt completes
+struct ActiveResponse(MockResponse);
+
+impl fmt::Debug for ActiveResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ActiveResponse")
+    }
+}
+
+impl Storable for ActiveResponse {
+    type Storer = StoreReplace<ActiveResponse>;
+}
+
+impl MockResponseInterceptor {
+    /// Creates a new interceptor with no rules, using [`RuleMode::MatchAny`].
+    pub fn new() -> Self {
+        Self {
+            rules: Default::default(),
+            all_rules: Vec::new(),
+            rule_mode: RuleMode::MatchAny,
+            must_match: true,
+        }
+    }
+
+    /// Add a rule to the interceptor.
+    ///
+    /// Rules are matched in order—this rule will only apply if all previous rules do not match.
+    pub fn with_rule(mut self, rule: &Rule) -> Self {
+        self.rules.lock().unwrap().push_back(rule.clone());
+        self.all_rules.push(rule.clone());
+        self
+    }
+
+    /// Set the [`RuleMode`] to use when selecting rules.
+    pub fn rule_mode(mut self, rule_mode: RuleMode) -> Self {
+        self.rule_mode = rule_mode;
+        self
+    }
+
+    /// Send operations that match no rule to the client's HTTP client, rather than panicking.
+    pub fn allow_passthrough(mut self) -> Self {
+        self.must_match = false;
+        self
+    }
+
+    /// Asserts that the expectations of every rule added to this interceptor were met.
+    ///
+    /// # Panics
+    ///
+    /// Panics with a description of the unmet expectations.
+    #[track_caller]
+    pub fn verify(&self) {
+        let unmet: Vec<_> = self
+            .all_rules
+            .iter()
+            .enumerate()
+            .flat_map(|(i, rule)| {
+                rule.unmet_expectations()
+                    .into_iter()
+                    .map(move |failure| format!("rule {i}: {failure}"))
+            })
+            .collect();
+        if !unmet.is_empty() {
+            panic!(
+                "rule expectations were not met:\n  - {}",
+                unmet.join("\n  - ")
+            );
+        }
+    }
+}
+
+impl Intercept for MockResponseInterceptor {
+    fn name(&self) -> &'static str {
+        "MockResponseInterceptor"
+    }
+
+    fn modify_before_serialization(
+        &self,
+        context: &mut BeforeSerializationInterceptorContextMut<'_>,
+        _runtime_components: &RuntimeComponents,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let mut rules = self.rules.lock().unwrap();
+        let rule = match self.rule_mode {
+            RuleMode::Sequential => {
+                let rule = rules
+                    .pop_front()
+                    .expect("no more rules but a new request was received");
+                if !rule.matches(context.input()) {
+                    panic!(
+                        "In order matching was enforced but the next rule did not match {:?}",
+                        context.input()
+                    );
+                }
+                Some(rule)
+            }
+            RuleMode::Unordered => rules
+                .iter()
+                .position(|rule| rule.matches(context.input()))
+                .and_then(|index| rules.remove(index)),
+            RuleMode::MatchAny => rules
+                .iter()
+                .find(|rule| rule.matches(context.input()))
+                .cloned(),
+        };
+        match rule {
+            Some(rule) => {
+                rule.check_request(context.input());
+                cfg.interceptor_state().store_put(ActiveRule(rule));
+            }
+            None => {
+                if self.must_match {
+                    panic!(
+                        "must_match was enabled but no rules matches {:?}",
+                        context.input()
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn modify_before_deserialization(
+        &self,
+        context: &mut BeforeDeserializationInterceptorContextMut<'_>,
+        _runtime_components: &RuntimeComponents,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let Some(rule) = cfg.load::<ActiveRule>().map(|active| active.0.clone()) else {
+            return Ok(());
+        };
+        let response = rule.next_response().ok_or_else(|| {
+            format!(
+                "mock rule was exhausted after returning {} response(s)",
+                rule.num_calls()
+            )
+        })?;
+        match response {
+            MockResponse::Http(response) => *context.response_mut() = response(),
+            modeled @ MockResponse::Modeled(_) => {
+                cfg.interceptor_state().store_put(ActiveResponse(modeled));
+            }
+        }
+        Ok(())
+    }
+
+    fn modify_before_attempt_completion(
+        &self,
+        context: &mut FinalizerInterceptorContextMut<'_>,
+        _runtime_components: &RuntimeComponents,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let output = match cfg.load::<ActiveResponse>() {
+            Some(ActiveResponse(MockResponse::Modeled(output))) => output.clone(),
+            _ => return Ok(()),
+        };
+        let result = output();
+        cfg.interceptor_state().unset::<ActiveResponse>();
+        if result.is_err() {
+            // the orchestrator will panic if no response is present
+            context.inner_mut().set_response(Response::new(
+                StatusCode::try_from(500).unwrap(),
+                SdkBody::from("stubbed error response"),
+            ))
+        }
+        context.inner_mut().set_output_or_error(result);
+        Ok(())
+    }
+}
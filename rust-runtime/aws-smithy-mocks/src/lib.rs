@@ -0,0 +1,155 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+/* Automatically managed default lints */
+#![cfg_attr(docsrs, feature(doc_auto_cfg))]
+/* End of automatically managed default lints */
+//! Testing utilities for mocking smithy-rs generated clients.
+//!
+//! Rules created with the [`mock!`] macro match operation inputs and return modeled outputs,
+//! modeled errors, or HTTP responses. A rule can return a [sequence](RuleBuilder::sequence) of
+//! responses, one per attempt, to test retries. HTTP responses are deserialized as though they
+//! came from the service, which makes them the highest fidelity option.
+//!
+//! Rules are added to a [`MockResponseInterceptor`], usually with the [`mock_client!`] macro.
+//! Expectations set with [`RuleBuilder::times`], [`RuleBuilder::never`], and
+//! [`RuleBuilder::expect_requests`] are checked with [`Rule::verify`].
+
+#![warn(
+    missing_docs,
+    rustdoc::missing_crate_level_docs,
+    unreachable_pub,
+    rust_2018_idioms
+)]
+
+mod interceptor;
+mod rule;
+
+pub use interceptor::{MockResponseInterceptor, RuleMode};
+pub use rule::{ResponseSequenceBuilder, Rule, RuleBuilder};
+
+use aws_smithy_runtime_api::client::http::{
+    http_client_fn, HttpConnector, HttpConnectorFuture, SharedHttpClient, SharedHttpConnector,
+};
+use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, HttpResponse};
+use aws_smithy_runtime_api::http::StatusCode;
+use aws_smithy_types::body::SdkBody;
+
+// why do we need a macro for this?
+// We want customers to be able to provide an ergonomic way to say the method they're looking for,
+// `Client::list_buckets`, e.g. But there isn't enough information on that type to recover everything.
+// This macro commits a small amount of crimes to recover that type information so we can construct
+// a rule that can intercept these operations.
+
+/// `mock!` macro that produces a [`RuleBuilder`] from a client invocation
+///
+/// # Examples
+/// **Mock and return a success response**:
+/// ```rust,ignore
+/// use aws_sdk_s3::operation::get_object::GetObjectOutput;
+/// use aws_sdk_s3::Client;
+/// use aws_smithy_types::byte_stream::ByteStream;
+/// use aws_smithy_mocks::mock;
+/// let get_object_happy_path = mock!(Client::get_object)
+///   .match_requests(|req|req.bucket() == Some("test-bucket") && req.key() == Some("test-key"))
+///   .then_output(||GetObjectOutput::builder().body(ByteStream::from_static(b"12345-abcde")).build());
+/// ```
+///
+/// **Fail twice with a retryable HTTP response, then succeed**:
+/// ```rust,ignore
+/// use aws_sdk_s3::operation::get_object::GetObjectOutput;
+/// use aws_sdk_s3::Client;
+/// use aws_smithy_mocks::mock;
+/// let flaky_get_object = mock!(Client::get_object)
+///   .sequence()
+///   .http_status(503, None)
+///   .repeat(2)
+///   .output(||GetObjectOutput::builder().build())
+///   .build();
+/// ```
+#[macro_export]
+macro_rules! mock {
+    ($operation: expr) => {
+        #[allow(unreachable_code)]
+        {
+            $crate::RuleBuilder::new(
+                // We don't actually want to run this code, so we put it in a closure. The closure
+                // has the types we want which makes this whole thing type-safe (and the IDE can even
+                // figure out the right input/output types in inference!)
+                // The code generated here is:
+                // `Client::list_buckets(todo!())`
+                || $operation(todo!()).as_input().clone().build().unwrap(),
+                || $operation(todo!()).send(),
+            )
+        }
+    };
+}
+
+/// `mock_client!` macro produces a Client configured with a number of Rules and appropriate test default configuration.
+///
+/// The client's HTTP client is replaced with one that returns empty `200 OK` responses, so no
+/// requests leave the process, even for operations that no rule matches when passthrough is allowed.
+/// An optional closure can customize the config builder.
+///
+/// # Examples
+/// **Create a client that uses a mock failure and then a success**:
+/// ```rust,ignore
+/// use aws_sdk_s3::operation::get_object::{GetObjectOutput, GetObjectError};
+/// use aws_sdk_s3::types::error::NoSuchKey;
+/// use aws_sdk_s3::Client;
+/// use aws_smithy_types::byte_stream::ByteStream;
+/// use aws_smithy_mocks::{mock_client, mock, RuleMode};
+/// let get_object_error_path = mock!(Client::get_object)
+///   .then_error(||GetObjectError::NoSuchKey(NoSuchKey::builder().build()));
+/// let get_object_happy_path = mock!(Client::get_object)
+///   .then_output(||GetObjectOutput::builder().body(ByteStream::from_static(b"12345-abcde")).build());
+/// let client = mock_client!(aws_sdk_s3, RuleMode::Sequential, &[&get_object_error_path, &get_object_happy_path]);
+/// ```
+#[macro_export]
+macro_rules! mock_client {
+    ($aws_crate: ident, $rules: expr) => {
+        $crate::mock_client!($aws_crate, $crate::RuleMode::Sequential, $rules)
+    };
+    ($aws_crate: ident, $rule_mode: expr, $rules: expr) => {
+        $crate::mock_client!($aws_crate, $rule_mode, $rules, |conf| conf)
+    };
+    ($aws_crate: ident, $rule_mode: expr, $rules: expr, $additional_configuration: expr) => {{
+        let mut mock_response_interceptor =
+            $crate::MockResponseInterceptor::new().rule_mode($rule_mode);
+        for rule in $rules {
+            mock_response_interceptor = mock_response_interceptor.with_rule(rule)
+        }
+        let additional_configuration = $additional_configuration;
+        $aws_crate::client::Client::from_conf(
+            additional_configuration(
+                $aws_crate::config::Config::builder()
+                    .with_test_defaults()
+                    .region($aws_crate::config::Region::from_static("us-east-1"))
+                    .http_client($crate::create_mock_http_client())
+                    .interceptor(mock_response_interceptor),
+            )
+            .build(),
+        )
+    }};
+}
+
+/// Returns an HTTP client that responds to every request with an empty `200 OK` response
+///
+/// Mock responses replace these responses, so they're never seen by operations that match a rule.
+pub fn create_mock_http_client() -> SharedHttpClient {
+    #[derive(Debug)]
+    struct EmptyOkConnector;
+
+    impl HttpConnector for EmptyOkConnector {
+        fn call(&self, _request: HttpRequest) -> HttpConnectorFuture {
+            HttpConnectorFuture::ready(Ok(HttpResponse::new(
+                StatusCode::try_from(200).unwrap(),
+                SdkBody::empty(),
+            )))
+        }
+    }
+
+    http_client_fn(|_settings, _components| SharedHttpConnector::new(EmptyOkConnector))
+}
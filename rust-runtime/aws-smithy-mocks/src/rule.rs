@@ -0,0 +1,351 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use aws_smithy_runtime_api::client::interceptors::context::{Error, Input, Output};
+use aws_smithy_runtime_api::client::orchestrator::{HttpResponse, OrchestratorError};
+use aws_smithy_runtime_api::client::result::SdkError;
+use aws_smithy_runtime_api::http::StatusCode;
+use aws_smithy_types::body::SdkBody;
+use std::fmt;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+type MatchFn = Arc<dyn Fn(&Input) -> bool + Send + Sync>;
+type CheckFn = Arc<dyn Fn(&Input) -> Option<String> + Send + Sync>;
+type OutputFn = Arc<dyn Fn() -> Result<Output, OrchestratorError<Error>> + Send + Sync>;
+
+/// A response returned by a [`Rule`] for a single attempt
+#[derive(Clone)]
+pub(crate) enum MockResponse {
+    /// An HTTP response that's deserialized as though it came from the service
+    Http(Arc<dyn Fn() -> HttpResponse + Send + Sync>),
+    /// A modeled output or error that replaces the result of the attempt
+    Modeled(OutputFn),
+}
+
+/// Builder for a [`Rule`]. This is normally constructed with the [`mock!`](crate::mock) macro.
+pub struct RuleBuilder<I, O, E> {
+    _ty: PhantomData<(I, O, E)>,
+    input_filter: MatchFn,
+    request_check: Option<CheckFn>,
+    expected_calls: Option<usize>,
+}
+
+impl<I, O, E> RuleBuilder<I, O, E>
+where
+    I: Send + Sync + fmt::Debug + 'static,
+    O: Send + Sync + fmt::Debug + 'static,
+    E: Send + Sync + fmt::Debug + std::error::Error + 'static,
+{
+    /// Creates a new [`RuleBuilder`]. This is normally constructed with the [`mock!`](crate::mock) macro.
+    pub fn new<F, R>(_input_hint: impl Fn() -> I, _output_hint: impl Fn() -> F) -> Self
+    where
+        F: Future<Output = Result<O, SdkError<E, R>>>,
+    {
+        Self {
+            _ty: Default::default(),
+            input_filter: Arc::new(|i: &Input| i.downcast_ref::<I>().is_some()),
+            request_check: None,
+            expected_calls: None,
+        }
+    }
+
+    /// Add an additional filter to constrain which inputs match this rule.
+    pub fn match_requests(mut self, filter: impl Fn(&I) -> bool + Send + Sync + 'static) -> Self {
+        self.input_filter = Arc::new(move |i: &Input| match i.downcast_ref::<I>() {
+            Some(typed_input) => filter(typed_input),
+            _ => false,
+        });
+        self
+    }
+
+    /// Expect every request handled by this rule to satisfy `expectation`.
+    ///
+    /// Unlike [`match_requests`](Self::match_requests), this doesn't affect which requests the rule
+    /// handles. Requests that don't satisfy the expectation are reported by [`Rule::verify`].
+    pub fn expect_requests(
+        mut self,
+        expectation: impl Fn(&I) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.request_check = Some(Arc::new(move |i: &Input| {
+            let typed_input = i.downcast_ref::<I>()?;
+            if expectation(typed_input) {
+                None
+            } else {
+                Some(format!(
+                    "request did not satisfy expectation: {typed_input:?}"
+                ))
+            }
+        }));
+        self
+    }
+
+    /// Expect this rule to return exactly `n` responses.
+    ///
+    /// Each attempt, including retries, counts as a call. The expectation is checked by
+    /// [`Rule::verify`].
+    pub fn times(mut self, n: usize) -> Self {
+        self.expected_calls = Some(n);
+        self
+    }
+
+    /// Expect this rule to never be used. The expectation is checked by [`Rule::verify`].
+    pub fn never(self) -> Self {
+        self.times(0)
+    }
+
+    /// Start building a sequence of responses, which this rule returns in order for each attempt.
+    ///
+    /// Once the sequence is exhausted, the rule no longer matches requests.
+    pub fn sequence(self) -> ResponseSequenceBuilder<I, O, E> {
+        ResponseSequenceBuilder {
+            rule: self,
+            responses: Vec::new(),
+        }
+    }
+
+    /// If the rule matches, then return a specific HTTP response.
+    ///
+    /// This is the recommended way of testing error behavior.
+    pub fn then_http_response(
+        self,
+        response: impl Fn() -> HttpResponse + Send + Sync + 'static,
+    ) -> Rule {
+        self.sequence().http_response(response).build_repeating()
+    }
+
+    /// If a rule matches, then return a specific output
+    pub fn then_output(self, output: impl Fn() -> O + Send + Sync + 'static) -> Rule {
+        self.sequence().output(output).build_repeating()
+    }
+
+    /// If a rule matches, then return a specific error
+    ///
+    /// Although this _basically_ works, using `then_http_response` is strongly recommended to
+    /// create a higher fidelity mock. Error handling is quite complex in practice and returning errors
+    /// directly often will not perfectly capture the way the error is actually returned to the SDK.
+    pub fn then_error(self, error: impl Fn() -> E + Send + Sync + 'static) -> Rule {
+        self.sequence().error(error).build_repeating()
+    }
+}
+
+impl<I, O, E> fmt::Debug for RuleBuilder<I, O, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RuleBuilder")
+            .field("expected_calls", &self.expected_calls)
+            .finish()
+    }
+}
+
+/// Builder for a [`Rule`] that returns a sequence of responses
+///
+/// Created with [`RuleBuilder::sequence`].
+pub struct ResponseSequenceBuilder<I, O, E> {
+    rule: RuleBuilder<I, O, E>,
+    responses: Vec<MockResponse>,
+}
+
+impl<I, O, E> ResponseSequenceBuilder<I, O, E>
+where
+    I: Send + Sync + fmt::Debug + 'static,
+    O: Send + Sync + fmt::Debug + 'static,
+    E: Send + Sync + fmt::Debug + std::error::Error + 'static,
+{
+    /// Return a modeled output next.
+    pub fn output(mut self, output: impl Fn() -> O + Send + Sync + 'static) -> Self {
+        self.responses.push(MockResponse::Modeled(Arc::new(move || {
+            Ok(Output::erase(output()))
+        })));
+        self
+    }
+
+    /// Return a modeled error next.
+    pub fn error(mut self, error: impl Fn() -> E + Send + Sync + 'static) -> Self {
+        self.responses.push(MockResponse::Modeled(Arc::new(move || {
+            Err(OrchestratorError::operation(Error::erase(error())))
+        })));
+        self
+    }
+
+    /// Return an HTTP response next.
+    pub fn http_response(
+        mut self,
+        response: impl Fn() -> HttpResponse + Send + Sync + 'static,
+    ) -> Self {
+        self.responses.push(MockResponse::Http(Arc::new(response)));
+        self
+    }
+
+    /// Return an HTTP response with the given status and body next.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `status` isn't a valid HTTP status code.
+    pub fn http_status(self, status: u16, body: Option<&'static str>) -> Self {
+        let status = StatusCode::try_from(status).expect("valid status code");
+        self.http_response(move || {
+            HttpResponse::new(
+                status,
+                body.map(SdkBody::from).unwrap_or_else(SdkBody::empty),
+            )
+        })
+    }
+
+    /// Repeat the most recently added response so that it's returned `n` times in total.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no response has been added yet, or if `n` is zero.
+    pub fn repeat(mut self, n: usize) -> Self {
+        assert!(n > 0, "a response must be returned at least once");
+        let last = self
+            .responses
+            .last()
+            .expect("repeat must follow a response")
+            .clone();
+        self.responses.extend(std::iter::repeat(last).take(n - 1));
+        self
+    }
+
+    /// Build the rule.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sequence is empty.
+    pub fn build(self) -> Rule {
+        self.build_rule(false)
+    }
+
+    fn build_repeating(self) -> Rule {
+        self.build_rule(true)
+    }
+
+    fn build_rule(self, repeat_last: bool) -> Rule {
+        assert!(
+            !self.responses.is_empty(),
+            "a rule must have at least one response"
+        );
+        Rule {
+            matcher: self.rule.input_filter,
+            responses: self.responses.into(),
+            repeat_last,
+            used_count: Default::default(),
+            expectations: Arc::new(Expectations {
+                request_check: self.rule.request_check,
+                expected_calls: self.rule.expected_calls,
+                failures: Default::default(),
+            }),
+        }
+    }
+}
+
+impl<I, O, E> fmt::Debug for ResponseSequenceBuilder<I, O, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseSequenceBuilder")
+            .field("responses", &self.responses.len())
+            .finish()
+    }
+}
+
+struct Expectations {
+    request_check: Option<CheckFn>,
+    expected_calls: Option<usize>,
+    failures: Mutex<Vec<String>>,
+}
+
+/// A rule that matches requests and returns mock responses for them
+///
+/// Rules are created with the [`mock!`](crate::mock) macro and [`RuleBuilder`], and are cheap to
+/// clone. Clones share their call count and expectations, so a rule can be inspected after the
+/// client using it has handled requests.
+#[derive(Clone)]
+pub struct Rule {
+    matcher: MatchFn,
+    responses: Arc<[MockResponse]>,
+    repeat_last: bool,
+    used_count: Arc<AtomicUsize>,
+    expectations: Arc<Expectations>,
+}
+
+impl fmt::Debug for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Rule")
+            .field("responses", &self.responses.len())
+            .field("num_calls", &self.num_calls())
+            .finish()
+    }
+}
+
+impl Rule {
+    pub(crate) fn matches(&self, input: &Input) -> bool {
+        !self.is_exhausted() && (self.matcher)(input)
+    }
+
+    /// Records any request expectation failures for a request this rule will handle
+    pub(crate) fn check_request(&self, input: &Input) {
+        if let Some(check) = &self.expectations.request_check {
+            if let Some(failure) = check(input) {
+                self.expectations.failures.lock().unwrap().push(failure);
+            }
+        }
+    }
+
+    /// Returns the response for the next attempt, or `None` if the sequence is exhausted
+    pub(crate) fn next_response(&self) -> Option<MockResponse> {
+        let len = self.responses.len();
+        let repeat_last = self.repeat_last;
+        let index = self
+            .used_count
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                (n < len || repeat_last).then_some(n + 1)
+            })
+            .ok()?;
+        Some(self.responses[index.min(len - 1)].clone())
+    }
+
+    /// Returns the number of times this rule has returned a response.
+    pub fn num_calls(&self) -> usize {
+        self.used_count.load(Ordering::SeqCst)
+    }
+
+    /// Returns true if this rule has returned every response in its sequence.
+    ///
+    /// Rules created with `then_output`, `then_error`, or `then_http_response` are never exhausted.
+    pub fn is_exhausted(&self) -> bool {
+        !self.repeat_last && self.num_calls() >= self.responses.len()
+    }
+
+    /// Returns a description of every unmet expectation set on this rule's [`RuleBuilder`].
+    pub fn unmet_expectations(&self) -> Vec<String> {
+        let mut unmet = self.expectations.failures.lock().unwrap().clone();
+        if let Some(expected) = self.expectations.expected_calls {
+            let actual = self.num_calls();
+            if actual != expected {
+                unmet.push(format!(
+                    "expected {expected} call(s) but there were {actual}"
+                ));
+            }
+        }
+        unmet
+    }
+
+    /// Asserts that every expectation set on this rule's [`RuleBuilder`] was met.
+    ///
+    /// # Panics
+    ///
+    /// Panics with a description of the unmet expectations.
+    #[track_caller]
+    pub fn verify(&self) {
+        let unmet = self.unmet_expectations();
+        if !unmet.is_empty() {
+            panic!(
+                "rule expectations were not met:\n  - {}",
+                unmet.join("\n  - ")
+            );
+        }
+    }
+}
@@ -0,0 +1,199 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+// Generated clients aren't available in this repository, so these tests build rules by hand and
+// drive them with an `Operation` instead of using the `mock!` and `mock_client!` macros.
+
+use aws_smithy_async::test_util::InstantSleep;
+use aws_smithy_mocks::{
+    create_mock_http_client, MockResponseInterceptor, Rule, RuleBuilder, RuleMode,
+};
+use aws_smithy_runtime::client::orchestrator::operation::Operation;
+use aws_smithy_runtime::client::retries::classifiers::HttpStatusCodeClassifier;
+use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, HttpResponse, OrchestratorError};
+use aws_smithy_runtime_api::client::result::SdkError;
+use aws_smithy_types::body::SdkBody;
+use aws_smithy_types::retry::RetryConfig;
+use aws_smithy_types::timeout::TimeoutConfig;
+use std::fmt;
+
+#[derive(Debug)]
+struct TestError(u16);
+
+impl fmt::Display for TestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "service returned {}", self.0)
+    }
+}
+
+impl std::error::Error for TestError {}
+
+/// Equivalent to `mock!(Client::echo)` for an operation that takes and returns a `String`
+fn mock_echo() -> RuleBuilder<String, String, TestError> {
+    RuleBuilder::new(String::new, || {
+        std::future::ready(Ok::<_, SdkError<TestError, HttpResponse>>(String::new()))
+    })
+}
+
+fn echo(interceptor: MockResponseInterceptor) -> Operation<String, String, TestError> {
+    Operation::builder()
+        .service_name("test")
+        .operation_name("echo")
+        .http_client(create_mock_http_client())
+        .endpoint_url("http://localhost:1234")
+        .no_auth()
+        .standard_retry(&RetryConfig::standard())
+        .retry_classifier(HttpStatusCodeClassifier::default())
+        .timeout_config(TimeoutConfig::disabled())
+        .sleep_impl(InstantSleep::unlogged())
+        .interceptor(interceptor)
+        .serializer(|input: String| Ok(HttpRequest::new(SdkBody::from(input))))
+        .deserializer(|response| match u16::from(response.status()) {
+            200 => Ok(String::from_utf8(response.body().bytes().unwrap().to_vec()).unwrap()),
+            status => Err(OrchestratorError::operation(TestError(status))),
+        })
+        .build()
+}
+
+fn interceptor(mode: RuleMode, rules: &[&Rule]) -> MockResponseInterceptor {
+    rules
+        .iter()
+        .fold(MockResponseInterceptor::new().rule_mode(mode), |i, rule| {
+            i.with_rule(rule)
+        })
+}
+
+#[tokio::test]
+async fn sequences_run_through_retries() {
+    let flaky = mock_echo()
+        .times(3)
+        .sequence()
+        .http_status(503, None)
+        .repeat(2)
+        .http_status(200, Some("finally"))
+        .build();
+    let operation = echo(interceptor(RuleMode::Sequential, &[&flaky]));
+
+    let output = operation.invoke("hello".into()).await.unwrap();
+    assert_eq!("finally", output);
+    assert_eq!(3, flaky.num_calls());
+    assert!(flaky.is_exhausted());
+    flaky.verify();
+}
+
+#[tokio::test]
+async fn exhausted_sequences_fail_the_attempt() {
+    let flaky = mock_echo().sequence().http_status(503, None).build();
+    let operation = echo(interceptor(RuleMode::MatchAny, &[&flaky]));
+
+    let err = operation.invoke("hello".into()).await.unwrap_err();
+    assert!(
+        format!("{:?}", err).contains("exhausted after returning 1 response"),
+        "{err:?}"
+    );
+}
+
+#[tokio::test]
+async fn modeled_outputs_and_errors() {
+    let hello = mock_echo()
+        .match_requests(|input| input == "hello")
+        .then_output(|| "world".to_string());
+    let error = mock_echo().then_error(|| TestError(400));
+    let operation = echo(interceptor(RuleMode::MatchAny, &[&hello, &error]));
+
+    for _ in 0..2 {
+        assert_eq!("world", operation.invoke("hello".into()).await.unwrap());
+    }
+    let err = operation.invoke("goodbye".into()).await.unwrap_err();
+    assert_eq!(400, err.as_service_error().unwrap().0);
+    assert_eq!(2, hello.num_calls());
+    // modeled errors are paired with a stubbed `500` response, which the classifier retries
+    assert_eq!(3, error.num_calls());
+    assert!(!hello.is_exhausted());
+}
+
+#[tokio::test]
+async fn unmet_expectations_are_reported() {
+    let called_twice = mock_echo()
+        .times(1)
+        .expect_requests(|input| input.starts_with('h'))
+        .then_output(|| "ok".to_string());
+    let unused = mock_echo()
+        .match_requests(|input| input == "unused")
+        .never()
+        .then_output(|| "ok".to_string());
+    let interceptor = interceptor(RuleMode::MatchAny, &[&unused, &called_twice]);
+    let operation = echo(interceptor);
+
+    operation.invoke("hello".into()).await.unwrap();
+    operation.invoke("goodbye".into()).await.unwrap();
+
+    unused.verify();
+    let unmet = called_twice.unmet_expectations();
+    assert_eq!(2, unmet.len(), "{unmet:?}");
+    assert!(unmet[0].contains("\"goodbye\""), "{unmet:?}");
+    assert_eq!("expected 1 call(s) but there were 2", unmet[1]);
+}
+
+#[tokio::test]
+#[should_panic(expected = "rule 1: expected 0 call(s) but there were 1")]
+async fn interceptor_verifies_every_rule() {
+    let used = mock_echo()
+        .match_requests(|input| input == "used")
+        .times(1)
+        .then_output(|| "ok".to_string());
+    let never = mock_echo().never().then_output(|| "ok".to_string());
+    let interceptor = interceptor(RuleMode::MatchAny, &[&used, &never]);
+    let operation = echo(interceptor.clone());
+
+    operation.invoke("used".into()).await.unwrap();
+    operation.invoke("other".into()).await.unwrap();
+    interceptor.verify();
+}
+
+#[tokio::test]
+async fn unordered_rules_are_each_used_once() {
+    let a = mock_echo()
+        .match_requests(|input| input == "a")
+        .then_output(|| "A".to_string());
+    let b = mock_echo()
+        .match_requests(|input| input == "b")
+        .then_output(|| "B".to_string());
+    let operation = echo(interceptor(RuleMode::Unordered, &[&a, &b]));
+
+    assert_eq!("B", operation.invoke("b".into()).await.unwrap());
+    assert_eq!("A", operation.invoke("a".into()).await.unwrap());
+}
+
+#[tokio::test]
+#[should_panic(expected = "next rule did not match")]
+async fn sequential_rules_must_match_in_order() {
+    let a = mock_echo()
+        .match_requests(|input| input == "a")
+        .then_output(|| "A".to_string());
+    let b = mock_echo()
+        .match_requests(|input| input == "b")
+        .then_output(|| "B".to_string());
+    let operation = echo(interceptor(RuleMode::Sequential, &[&a, &b]));
+
+    let _ = operation.invoke("b".into()).await;
+}
+
+#[tokio::test]
+#[should_panic(expected = "no rules matches")]
+async fn strict_mode_fails_unmatched_requests() {
+    let a = mock_echo()
+        .match_requests(|input| input == "a")
+        .then_output(|| "A".to_string());
+    let operation = echo(interceptor(RuleMode::MatchAny, &[&a]));
+
+    let _ = operation.invoke("b".into()).await;
+}
+
+#[tokio::test]
+async fn passthrough_uses_the_http_client() {
+    let operation = echo(MockResponseInterceptor::new().allow_passthrough());
+    assert_eq!("", operation.invoke("b".into()).await.unwrap());
+}
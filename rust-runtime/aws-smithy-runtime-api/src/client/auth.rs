@@ -7,7 +7,7 @@
 
 use crate::box_error::BoxError;
 use crate::client::identity::{Identity, SharedIdentityResolver};
-use crate::client::orchestrator::HttpRequest;
+use crate::client::orchestrator::{HttpRequest, HttpResponse};
 use crate::client::runtime_components::sealed::ValidateConfig;
 use crate::client::runtime_components::{GetIdentityResolver, RuntimeComponents};
 use crate::impl_shared_conversions;
@@ -202,6 +202,22 @@ pub trait Sign: Send + Sync + fmt::Debug {
         runtime_components: &RuntimeComponents,
         config_bag: &ConfigBag,
     ) -> Result<(), BoxError>;
+
+    /// Inspect the response to a request signed by this signer.
+    ///
+    /// Challenge-response schemes, such as HTTP digest auth, can only sign a request once the
+    /// server has issued a challenge. Returning `true` tells the orchestrator that the response
+    /// was such a challenge, and that the request should be signed and sent again. The default
+    /// implementation ignores the response.
+    fn handle_response(
+        &self,
+        response: &HttpResponse,
+        runtime_components: &RuntimeComponents,
+        config_bag: &ConfigBag,
+    ) -> bool {
+        let _ = (response, runtime_components, config_bag);
+        false
+    }
 }
 
 /// Endpoint configuration for the selected auth scheme.
@@ -7,7 +7,7 @@ use crate::box_error::BoxError;
 use crate::client::runtime_components::sealed::ValidateConfig;
 use crate::client::runtime_components::{RuntimeComponents, RuntimeComponentsBuilder};
 use crate::impl_shared_conversions;
-use aws_smithy_types::config_bag::ConfigBag;
+use aws_smithy_types::config_bag::{ConfigBag, Storable, StoreReplace};
 use std::any::Any;
 use std::fmt;
 use std::fmt::Debug;
@@ -82,6 +82,22 @@ pub trait ResolveCachedIdentity: fmt::Debug + Send + Sync {
         let _ = (runtime_components, cfg);
         Ok(())
     }
+
+    /// Removes the cached identity for the given partition, so that the next request resolves a new identity.
+    ///
+    /// This is useful when a service rejects an identity before it expires, for example because
+    /// it was revoked. Interceptors can find the partition of the identity used for the current
+    /// request attempt with [`IdentityCacheHandle`].
+    ///
+    /// By default, this does nothing.
+    fn invalidate(&self, partition: IdentityCachePartition) {
+        let _ = partition;
+    }
+
+    /// Removes every cached identity, so that the next request for each partition resolves a new identity.
+    ///
+    /// By default, this does nothing.
+    fn invalidate_all(&self) {}
 }
 
 /// Shared identity cache.
@@ -105,6 +121,46 @@ impl ResolveCachedIdentity for SharedIdentityCache {
         self.0
             .resolve_cached_identity(resolver, runtime_components, config_bag)
     }
+
+    fn invalidate(&self, partition: IdentityCachePartition) {
+        self.0.invalidate(partition)
+    }
+
+    fn invalidate_all(&self) {
+        self.0.invalidate_all()
+    }
+}
+
+/// Handle to the identity cache partition that the identity for the current request attempt came from
+///
+/// The orchestrator stores this in the config bag after resolving an identity, so that
+/// interceptors can invalidate the identity when the service rejects it. The next attempt then
+/// resolves a new identity.
+#[derive(Clone, Debug)]
+pub struct IdentityCacheHandle {
+    cache: SharedIdentityCache,
+    partition: IdentityCachePartition,
+}
+
+impl IdentityCacheHandle {
+    /// Creates a handle to the given partition of an identity cache.
+    pub fn new(cache: SharedIdentityCache, partition: IdentityCachePartition) -> Self {
+        Self { cache, partition }
+    }
+
+    /// Returns the cache partition of the identity.
+    pub fn partition(&self) -> IdentityCachePartition {
+        self.partition
+    }
+
+    /// Removes the identity from the cache, so that the next request resolves a new identity.
+    pub fn invalidate(&self) {
+        self.cache.invalidate(self.partition)
+    }
+}
+
+impl Storable for IdentityCacheHandle {
+    type Storer = StoreReplace<Self>;
 }
 
 impl ValidateConfig for SharedIdentityResolver {}
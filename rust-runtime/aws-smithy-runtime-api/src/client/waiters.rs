@@ -49,6 +49,9 @@ pub mod error {
         /// Note: If retry is configured, this means that the operation failed
         /// after retrying the configured number of attempts.
         OperationFailed(OperationFailed<E>),
+
+        /// Waiting was cancelled before completion.
+        Cancelled(Cancelled),
     }
 
     impl<O, E> WaiterError<O, E> {
@@ -66,7 +69,7 @@ pub mod error {
         fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
             match self {
                 Self::ConstructionFailure(inner) => Some(&*inner.source),
-                Self::ExceededMaxWait(_) => None,
+                Self::ExceededMaxWait(_) | Self::Cancelled(_) => None,
                 Self::FailureState(inner) => match &inner.final_poll.result {
                     Ok(_) => None,
                     Err(err) => Some(err),
@@ -85,6 +88,7 @@ pub mod error {
                 }
                 Self::FailureState(_) => f.write_str("waiting failed"),
                 Self::OperationFailed(_) => f.write_str("operation failed while waiting"),
+                Self::Cancelled(_) => f.write_str("waiting was cancelled"),
             }
         }
     }
@@ -96,9 +100,9 @@ pub mod error {
     {
         fn meta(&self) -> &ErrorMetadata {
             match self {
-                WaiterError::ConstructionFailure(_) | WaiterError::ExceededMaxWait(_) => {
-                    &EMPTY_ERROR_METADATA
-                }
+                WaiterError::ConstructionFailure(_)
+                | WaiterError::ExceededMaxWait(_)
+                | WaiterError::Cancelled(_) => &EMPTY_ERROR_METADATA,
                 WaiterError::FailureState(inner) => inner
                     .final_poll()
                     .as_result()
@@ -144,6 +148,33 @@ pub mod error {
         }
     }
 
+    /// Error context for [`WaiterError::Cancelled`].
+    #[derive(Debug)]
+    pub struct Cancelled {
+        elapsed: Duration,
+        poll_count: u32,
+    }
+
+    impl Cancelled {
+        /// Creates new error context.
+        pub fn new(elapsed: Duration, poll_count: u32) -> Self {
+            Self {
+                elapsed,
+                poll_count,
+            }
+        }
+
+        /// How much time elapsed before waiting was cancelled.
+        pub fn elapsed(&self) -> Duration {
+            self.elapsed
+        }
+
+        /// Returns the number of polling operations that completed before waiting was cancelled.
+        pub fn poll_count(&self) -> u32 {
+            self.poll_count
+        }
+    }
+
     /// Error context for [`WaiterError::FailureState`].
     #[derive(Debug)]
     #[non_exhaustive]
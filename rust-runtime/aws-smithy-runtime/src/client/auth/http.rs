@@ -16,10 +16,13 @@ use aws_smithy_runtime_api::client::auth::{
 };
 use aws_smithy_runtime_api::client::identity::http::{Login, Token};
 use aws_smithy_runtime_api::client::identity::{Identity, SharedIdentityResolver};
-use aws_smithy_runtime_api::client::orchestrator::HttpRequest;
+use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, HttpResponse};
 use aws_smithy_runtime_api::client::runtime_components::{GetIdentityResolver, RuntimeComponents};
 use aws_smithy_types::base64::encode;
 use aws_smithy_types::config_bag::ConfigBag;
+use std::fmt::Write as _;
+use std::sync::Mutex;
+use tracing::trace;
 
 /// Destination for the API key
 #[derive(Copy, Clone, Debug)]
@@ -231,6 +234,15 @@ impl Sign for BearerAuthSigner {
 }
 
 /// Auth implementation for Smithy's `@httpDigestAuth` auth scheme
+///
+/// Implements [RFC 7616](https://www.rfc-editor.org/rfc/rfc7616) with the `MD5`, `MD5-sess`,
+/// `SHA-256`, and `SHA-256-sess` algorithms, and the `auth` and `auth-int` qualities of protection.
+///
+/// Digest auth can only sign a request once the server has issued a challenge. The first request
+/// is sent without credentials, and when the server responds with a `401` and a
+/// `WWW-Authenticate: Digest` challenge, the orchestrator signs the request and sends it again.
+/// The challenge is kept for subsequent requests, which are signed right away with an incremented
+/// nonce count until the server issues a new nonce.
 #[derive(Debug, Default)]
 pub struct DigestAuthScheme {
     signer: DigestAuthSigner,
@@ -239,9 +251,7 @@ pub struct DigestAuthScheme {
 impl DigestAuthScheme {
     /// Creates a new `DigestAuthScheme`.
     pub fn new() -> Self {
-        Self {
-            signer: DigestAuthSigner,
-        }
+        Self::default()
     }
 }
 
@@ -263,23 +273,383 @@ impl AuthScheme for DigestAuthScheme {
 }
 
 #[derive(Debug, Default)]
-struct DigestAuthSigner;
+struct DigestAuthSigner {
+    challenge: Mutex<Option<DigestChallenge>>,
+}
 
 impl Sign for DigestAuthSigner {
     fn sign_http_request(
         &self,
-        _request: &mut HttpRequest,
-        _identity: &Identity,
+        request: &mut HttpRequest,
+        identity: &Identity,
         _auth_scheme_endpoint_config: AuthSchemeEndpointConfig<'_>,
         _runtime_components: &RuntimeComponents,
         _config_bag: &ConfigBag,
     ) -> Result<(), BoxError> {
-        unimplemented!(
-            "support for signing with Smithy's `@httpDigestAuth` auth scheme is not implemented yet"
-        )
+        let login = identity
+            .data::<Login>()
+            .ok_or("HTTP digest auth requires a `Login` identity")?;
+        let mut challenge = self.challenge.lock().unwrap();
+        let Some(challenge) = challenge.as_mut() else {
+            trace!("no digest challenge received yet; sending the request without credentials");
+            return Ok(());
+        };
+        challenge.nonce_count += 1;
+
+        let uri: http_02x::Uri = request.uri().parse()?;
+        let uri = uri.path_and_query().map(|pq| pq.as_str()).unwrap_or("/");
+        let cnonce = hex(&fastrand::u128(..).to_be_bytes());
+        let authorization = challenge.authorization(
+            login,
+            request.method(),
+            uri,
+            request.body().bytes(),
+            &cnonce,
+        )?;
+        request.headers_mut().insert(
+            http_02x::header::AUTHORIZATION,
+            http_02x::HeaderValue::from_str(&authorization).map_err(|_| {
+                "Digest credentials contain characters that can't be included in a HTTP header"
+            })?,
+        );
+        Ok(())
+    }
+
+    fn handle_response(
+        &self,
+        response: &HttpResponse,
+        _runtime_components: &RuntimeComponents,
+        _config_bag: &ConfigBag,
+    ) -> bool {
+        let mut challenge = self.challenge.lock().unwrap();
+        if response.status().as_u16() == 401 {
+            let Some(new_challenge) =
+                DigestChallenge::from_headers(response.headers().get_all("www-authenticate"))
+            else {
+                return false;
+            };
+            // Only answer the challenge if it's something the last request didn't already answer.
+            // Otherwise, the credentials were rejected and signing again won't help.
+            let answer = match challenge.as_ref() {
+                None => true,
+                Some(previous) => new_challenge.stale || previous.nonce != new_challenge.nonce,
+            };
+            trace!(challenge = ?new_challenge, answer, "received digest challenge");
+            *challenge = Some(new_challenge);
+            return answer;
+        }
+
+        // Servers can hand out the nonce to use for the next request ahead of time
+        if let (Some(challenge), Some(info)) = (
+            challenge.as_mut(),
+            response.headers().get("authentication-info"),
+        ) {
+            let next_nonce = parse_auth_params(info)
+                .into_iter()
+                .next()
+                .and_then(|(_, params)| {
+                    params
+                        .into_iter()
+                        .find_map(|(name, value)| (name == "nextnonce").then_some(value))
+                });
+            if let Some(next_nonce) = next_nonce {
+                challenge.nonce = next_nonce;
+                challenge.nonce_count = 0;
+            }
+        }
+        false
     }
 }
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum DigestAlgorithm {
+    Md5,
+    Md5Sess,
+    Sha256,
+    Sha256Sess,
+}
+
+impl DigestAlgorithm {
+    fn parse(value: &str) -> Option<Self> {
+        [Self::Md5, Self::Md5Sess, Self::Sha256, Self::Sha256Sess]
+            .into_iter()
+            .find(|algorithm| algorithm.as_str().eq_ignore_ascii_case(value))
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Md5 => "MD5",
+            Self::Md5Sess => "MD5-sess",
+            Self::Sha256 => "SHA-256",
+            Self::Sha256Sess => "SHA-256-sess",
+        }
+    }
+
+    fn is_session(self) -> bool {
+        matches!(self, Self::Md5Sess | Self::Sha256Sess)
+    }
+
+    /// Servers offer one challenge per algorithm, and the strongest one should be answered.
+    fn strength(self) -> u8 {
+        match self {
+            Self::Md5 | Self::Md5Sess => 0,
+            Self::Sha256 | Self::Sha256Sess => 1,
+        }
+    }
+
+    fn hash(self, data: impl AsRef<[u8]>) -> String {
+        use md5::Digest as _;
+        match self {
+            Self::Md5 | Self::Md5Sess => hex(&md5::Md5::digest(data)),
+            Self::Sha256 | Self::Sha256Sess => hex(&sha2::Sha256::digest(data)),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Qop {
+    Auth,
+    AuthInt,
+}
+
+impl Qop {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Auth => "auth",
+            Self::AuthInt => "auth-int",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct DigestChallenge {
+    realm: String,
+    nonce: String,
+    opaque: Option<String>,
+    algorithm: DigestAlgorithm,
+    /// Empty if the server didn't send a `qop`, as in the original RFC 2069 scheme
+    qop: Vec<Qop>,
+    userhash: bool,
+    stale: bool,
+    /// Number of requests signed with the current nonce
+    nonce_count: u32,
+}
+
+impl DigestChallenge {
+    /// Picks the strongest supported digest challenge out of `WWW-Authenticate` header values.
+    fn from_headers<'a>(headers: impl Iterator<Item = &'a str>) -> Option<Self> {
+        headers
+            .flat_map(parse_auth_params)
+            .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("digest"))
+            .filter_map(|(_, params)| Self::from_params(params))
+            .max_by_key(|challenge| challenge.algorithm.strength())
+    }
+
+    fn from_params(params: Vec<(String, String)>) -> Option<Self> {
+        let (mut realm, mut nonce, mut opaque) = (None, None, None);
+        let mut algorithm = DigestAlgorithm::Md5;
+        let (mut qop, mut userhash, mut stale) = (Vec::new(), false, false);
+        for (name, value) in params {
+            match name.as_str() {
+                "realm" => realm = Some(value),
+                "nonce" => nonce = Some(value),
+                "opaque" => opaque = Some(value),
+                "algorithm" => algorithm = DigestAlgorithm::parse(&value)?,
+                "qop" => {
+                    let offered: Vec<_> = value.split(',').map(str::trim).collect();
+                    qop = [Qop::Auth, Qop::AuthInt]
+                        .into_iter()
+                        .filter(|q| offered.contains(&q.as_str()))
+                        .collect();
+                    // None of the offered qualities of protection are supported
+                    if qop.is_empty() {
+                        return None;
+                    }
+                }
+                "userhash" => userhash = value.eq_ignore_ascii_case("true"),
+                "stale" => stale = value.eq_ignore_ascii_case("true"),
+                _ => {}
+            }
+        }
+        Some(Self {
+            realm: realm?,
+            nonce: nonce?,
+            opaque,
+            algorithm,
+            qop,
+            userhash,
+            stale,
+            nonce_count: 0,
+        })
+    }
+
+    /// Returns the `Authorization` header value answering this challenge.
+    fn authorization(
+        &self,
+        login: &Login,
+        method: &str,
+        uri: &str,
+        body: Option<&[u8]>,
+        cnonce: &str,
+    ) -> Result<String, BoxError> {
+        let algorithm = self.algorithm;
+        // `auth-int` protects the body too, but needs it in memory to hash it
+        let qop = match body {
+            Some(_) if self.qop.contains(&Qop::AuthInt) => Some(Qop::AuthInt),
+            _ if self.qop.contains(&Qop::Auth) => Some(Qop::Auth),
+            _ if self.qop.is_empty() => None,
+            _ => {
+                return Err(
+                    "HTTP digest auth with `qop=auth-int` requires a request body that is loaded into memory"
+                        .into(),
+                )
+            }
+        };
+        let (realm, nonce) = (&self.realm, &self.nonce);
+        let nc = format!("{:08x}", self.nonce_count);
+
+        let mut ha1 = algorithm.hash(format!("{}:{realm}:{}", login.user(), login.password()));
+        if algorithm.is_session() {
+            ha1 = algorithm.hash(format!("{ha1}:{nonce}:{cnonce}"));
+        }
+        let ha2 = match qop {
+            Some(Qop::AuthInt) => algorithm.hash(format!(
+                "{method}:{uri}:{}",
+                algorithm.hash(body.unwrap_or_default())
+            )),
+            _ => algorithm.hash(format!("{method}:{uri}")),
+        };
+        let response = match qop {
+            Some(qop) => algorithm.hash(format!(
+                "{ha1}:{nonce}:{nc}:{cnonce}:{}:{ha2}",
+                qop.as_str()
+            )),
+            None => algorithm.hash(format!("{ha1}:{nonce}:{ha2}")),
+        };
+
+        let mut header = String::from("Digest ");
+        if self.userhash {
+            let username = algorithm.hash(format!("{}:{realm}", login.user()));
+            write!(header, "username={}", quote(&username)).unwrap();
+        } else if login.user().is_ascii() {
+            write!(header, "username={}", quote(login.user())).unwrap();
+        } else {
+            write!(header, "username*=UTF-8''{}", ext_encode(login.user())).unwrap();
+        }
+        write!(header, ", realm={}", quote(realm)).unwrap();
+        write!(header, ", uri={}", quote(uri)).unwrap();
+        write!(header, ", algorithm={}", algorithm.as_str()).unwrap();
+        write!(header, ", nonce={}", quote(nonce)).unwrap();
+        if let Some(qop) = qop {
+            write!(header, ", nc={nc}").unwrap();
+            write!(header, ", cnonce={}", quote(cnonce)).unwrap();
+            write!(header, ", qop={}", qop.as_str()).unwrap();
+        }
+        write!(header, ", response={}", quote(&response)).unwrap();
+        if let Some(opaque) = &self.opaque {
+            write!(header, ", opaque={}", quote(opaque)).unwrap();
+        }
+        if self.userhash {
+            header.push_str(", userhash=true");
+        }
+        Ok(header)
+    }
+}
+
+/// Parses the challenges in a `WWW-Authenticate` header value.
+///
+/// Returns each auth scheme along with its (lowercased) parameter names and unquoted values.
+/// Parameters that come before any auth scheme, as in an `Authentication-Info` header value,
+/// are returned under an empty auth scheme.
+fn parse_auth_params(header: &str) -> Vec<(&str, Vec<(String, String)>)> {
+    let mut challenges = vec![("", Vec::new())];
+    let mut rest = header;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+        if rest.is_empty() {
+            break;
+        }
+        let (token, after_token) = split_token(rest);
+        if token.is_empty() {
+            // Skip over a stray `=`, such as token68 padding
+            rest = &rest[1..];
+            continue;
+        }
+        rest = after_token.trim_start();
+        if let Some(after_equals) = rest.strip_prefix('=') {
+            rest = after_equals.trim_start();
+            let value = if let Some(quoted) = rest.strip_prefix('"') {
+                let (value, after_value) = split_quoted(quoted);
+                rest = after_value;
+                value
+            } else {
+                let (value, after_value) = split_token(rest);
+                rest = after_value;
+                value.to_string()
+            };
+            let (_, params) = challenges.last_mut().expect("never empty");
+            params.push((token.to_ascii_lowercase(), value));
+        } else {
+            challenges.push((token, Vec::new()));
+        }
+    }
+    if challenges[0].1.is_empty() {
+        challenges.remove(0);
+    }
+    challenges
+}
+
+fn split_token(value: &str) -> (&str, &str) {
+    value.split_at(
+        value
+            .find(|c: char| c == ',' || c == '=' || c.is_whitespace())
+            .unwrap_or(value.len()),
+    )
+}
+
+/// Unescapes a quoted string, given the input after its opening quote.
+fn split_quoted(value: &str) -> (String, &str) {
+    let mut unquoted = String::new();
+    let mut escaped = false;
+    for (index, c) in value.char_indices() {
+        match c {
+            _ if escaped => {
+                unquoted.push(c);
+                escaped = false;
+            }
+            '\\' => escaped = true,
+            '"' => return (unquoted, &value[index + 1..]),
+            _ => unquoted.push(c),
+        }
+    }
+    (unquoted, "")
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Encodes a value for an RFC 8187 extended parameter, such as `username*`.
+fn ext_encode(value: &str) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            write!(encoded, "%{byte:02X}").unwrap();
+        }
+    }
+    encoded
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        write!(encoded, "{byte:02x}").unwrap();
+    }
+    encoded
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -432,4 +802,276 @@ mod tests {
             request.headers().get("Authorization").unwrap()
         );
     }
+
+    fn rfc7616_challenge(algorithm: DigestAlgorithm) -> DigestChallenge {
+        DigestChallenge {
+            realm: "http-auth@example.org".into(),
+            nonce: "7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v".into(),
+            opaque: Some("FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS".into()),
+            algorithm,
+            qop: vec![Qop::Auth],
+            userhash: false,
+            stale: false,
+            nonce_count: 1,
+        }
+    }
+
+    fn digest_response(status: u16, header: Option<(&str, &str)>) -> HttpResponse {
+        let mut response = http_02x::Response::builder().status(status);
+        if let Some((name, value)) = header {
+            response = response.header(name, value);
+        }
+        response.body(SdkBody::empty()).unwrap().try_into().unwrap()
+    }
+
+    fn sign_digest(signer: &DigestAuthSigner) -> Option<String> {
+        let runtime_components = RuntimeComponentsBuilder::for_tests().build().unwrap();
+        let identity = Identity::new(Login::new("Mufasa", "Circle of Life", None), None);
+        let mut request: HttpRequest = http_02x::Request::builder()
+            .uri("http://example.com/dir/index.html")
+            .body(SdkBody::empty())
+            .unwrap()
+            .try_into()
+            .unwrap();
+        signer
+            .sign_http_request(
+                &mut request,
+                &identity,
+                AuthSchemeEndpointConfig::empty(),
+                &runtime_components,
+                &ConfigBag::base(),
+            )
+            .expect("success");
+        request
+            .headers()
+            .get("Authorization")
+            .map(ToString::to_string)
+    }
+
+    // Test vectors from https://www.rfc-editor.org/rfc/rfc7616#section-3.9.1
+    #[test]
+    fn test_digest_auth_rfc7616_md5() {
+        let login = Login::new("Mufasa", "Circle of Life", None);
+        let authorization = rfc7616_challenge(DigestAlgorithm::Md5)
+            .authorization(
+                &login,
+                "GET",
+                "/dir/index.html",
+                None,
+                "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ",
+            )
+            .expect("success");
+        assert_eq!(
+            "Digest username=\"Mufasa\", realm=\"http-auth@example.org\", uri=\"/dir/index.html\", \
+            algorithm=MD5, nonce=\"7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v\", nc=00000001, \
+            cnonce=\"f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ\", qop=auth, \
+            response=\"8ca523f5e9506fed4657c9700eebdbec\", opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\"",
+            authorization
+        );
+    }
+
+    #[test]
+    fn test_digest_auth_rfc7616_sha256() {
+        let login = Login::new("Mufasa", "Circle of Life", None);
+        let authorization = rfc7616_challenge(DigestAlgorithm::Sha256)
+            .authorization(
+                &login,
+                "GET",
+                "/dir/index.html",
+                None,
+                "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ",
+            )
+            .expect("success");
+        assert!(authorization.contains(", algorithm=SHA-256,"));
+        assert!(authorization.contains(
+            ", response=\"753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1\","
+        ));
+    }
+
+    #[test]
+    fn test_digest_auth_parse_challenges() {
+        let challenge = DigestChallenge::from_headers(
+            [
+                r#"Basic realm="basic", Digest realm="a \"quoted\" realm", qop="auth,auth-int", algorithm=MD5, nonce="n1""#,
+                r#"Digest realm="a \"quoted\" realm",qop="auth", algorithm=SHA-256, nonce="n2", opaque="o", userhash=true"#,
+                r#"Digest realm="unsupported", algorithm=SHA-512-256, nonce="n3""#,
+            ]
+            .into_iter(),
+        )
+        .expect("supported challenge");
+        assert_eq!(r#"a "quoted" realm"#, challenge.realm);
+        assert_eq!("n2", challenge.nonce);
+        assert_eq!(Some("o"), challenge.opaque.as_deref());
+        assert_eq!(DigestAlgorithm::Sha256, challenge.algorithm);
+        assert_eq!(vec![Qop::Auth], challenge.qop);
+        assert!(challenge.userhash);
+
+        assert!(DigestChallenge::from_headers(
+            [r#"Basic realm="basic", Bearer realm="bearer""#].into_iter()
+        )
+        .is_none());
+        assert!(DigestChallenge::from_headers(
+            [r#"Digest realm="r", nonce="n", qop="other""#].into_iter()
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_digest_auth_qop_auth_int() {
+        let login = Login::new("Mufasa", "Circle of Life", None);
+        let mut challenge = rfc7616_challenge(DigestAlgorithm::Sha256);
+        challenge.qop = vec![Qop::Auth, Qop::AuthInt];
+
+        let in_memory = challenge
+            .authorization(&login, "POST", "/", Some(b"body"), "cnonce")
+            .expect("success");
+        assert!(in_memory.contains(", qop=auth-int,"));
+        let other_body = challenge
+            .authorization(&login, "POST", "/", Some(b"other body"), "cnonce")
+            .expect("success");
+        assert_ne!(in_memory, other_body);
+
+        // Streaming bodies can't be hashed, so fall back to `auth`
+        let streaming = challenge
+            .authorization(&login, "POST", "/", None, "cnonce")
+            .expect("success");
+        assert!(streaming.contains(", qop=auth,"));
+
+        challenge.qop = vec![Qop::AuthInt];
+        challenge
+            .authorization(&login, "POST", "/", None, "cnonce")
+            .expect_err("auth-int requires an in-memory body");
+    }
+
+    #[test]
+    fn test_digest_auth_answers_challenges() {
+        let signer = DigestAuthSigner::default();
+        let runtime_components = RuntimeComponentsBuilder::for_tests().build().unwrap();
+        let config_bag = ConfigBag::base();
+        let handle_response = |response: HttpResponse| {
+            signer.handle_response(&response, &runtime_components, &config_bag)
+        };
+        let challenge = |nonce: &str, stale: bool| {
+            let header = format!(
+                r#"Digest realm="r", qop="auth", algorithm=SHA-256, nonce="{nonce}", stale={stale}"#
+            );
+            handle_response(digest_response(
+                401,
+                Some(("WWW-Authenticate", header.as_str())),
+            ))
+        };
+
+        // Nothing to answer before the first challenge
+        assert_eq!(None, sign_digest(&signer));
+        assert!(challenge("n1", false));
+        let first = sign_digest(&signer).expect("signed");
+        assert!(first.contains(r#"nonce="n1", nc=00000001,"#));
+        let second = sign_digest(&signer).expect("signed");
+        assert!(second.contains(r#"nonce="n1", nc=00000002,"#));
+
+        // The same nonce being challenged again means the credentials were rejected
+        assert!(!challenge("n1", false));
+        assert!(challenge("n1", true));
+        assert!(challenge("n2", false));
+        assert!(sign_digest(&signer)
+            .unwrap()
+            .contains(r#"nonce="n2", nc=00000001,"#));
+
+        assert!(!handle_response(digest_response(
+            200,
+            Some(("Authentication-Info", r#"qop=auth, nextnonce="n3""#)),
+        )));
+        assert!(sign_digest(&signer)
+            .unwrap()
+            .contains(r#"nonce="n3", nc=00000001,"#));
+        assert!(!handle_response(digest_response(401, None)));
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_digest_auth_operation() {
+        use crate::client::http::test_util::{ReplayEvent, StaticReplayClient};
+        use crate::client::orchestrator::operation::Operation;
+        use aws_smithy_runtime_api::client::auth::http::HTTP_DIGEST_AUTH_SCHEME_ID;
+        use aws_smithy_runtime_api::client::auth::static_resolver::StaticAuthSchemeOptionResolver;
+        use aws_smithy_runtime_api::client::orchestrator::OrchestratorError;
+        use aws_smithy_runtime_api::client::runtime_plugin::StaticRuntimePlugin;
+        use aws_smithy_types::timeout::TimeoutConfig;
+        use std::convert::Infallible;
+
+        let event = |status: u16, header: Option<(&str, &str)>| {
+            let mut response = http_02x::Response::builder().status(status);
+            if let Some((name, value)) = header {
+                response = response.header(name, value);
+            }
+            ReplayEvent::new(
+                http_02x::Request::builder()
+                    .uri("http://localhost:1234/dir/index.html")
+                    .body(SdkBody::empty())
+                    .unwrap(),
+                response.body(SdkBody::empty()).unwrap(),
+            )
+        };
+        let http_client = StaticReplayClient::new(vec![
+            event(
+                401,
+                Some((
+                    "WWW-Authenticate",
+                    r#"Digest realm="http-auth@example.org", qop="auth", algorithm=SHA-256, nonce="n1""#,
+                )),
+            ),
+            event(200, None),
+            event(200, None),
+        ]);
+        let operation = Operation::builder()
+            .service_name("test")
+            .operation_name("test")
+            .http_client(http_client.clone())
+            .endpoint_url("http://localhost:1234")
+            .no_auth()
+            .no_retry()
+            .timeout_config(TimeoutConfig::disabled())
+            .runtime_plugin(
+                StaticRuntimePlugin::new().with_runtime_components(
+                    RuntimeComponentsBuilder::new("test_digest_auth_operation")
+                        .with_auth_scheme_option_resolver(Some(
+                            StaticAuthSchemeOptionResolver::new(vec![HTTP_DIGEST_AUTH_SCHEME_ID]),
+                        ))
+                        .with_auth_scheme(DigestAuthScheme::new())
+                        .with_identity_resolver(
+                            HTTP_DIGEST_AUTH_SCHEME_ID,
+                            Login::new("Mufasa", "Circle of Life", None),
+                        ),
+                ),
+            )
+            .serializer(|_: ()| {
+                let mut request = HttpRequest::empty();
+                request.set_uri("/dir/index.html").unwrap();
+                Ok(request)
+            })
+            .deserializer::<_, Infallible>(|response| {
+                if response.status().is_success() {
+                    Ok(())
+                } else {
+                    Err(OrchestratorError::other("unauthorized"))
+                }
+            })
+            .build();
+
+        operation.invoke(()).await.expect("challenge answered");
+        operation.invoke(()).await.expect("signed up front");
+
+        let authorizations: Vec<_> = http_client
+            .actual_requests()
+            .map(|request| request.headers().get("Authorization"))
+            .collect();
+        assert_eq!(3, authorizations.len());
+        assert_eq!(None, authorizations[0]);
+        assert!(authorizations[1]
+            .unwrap()
+            .contains(r#"nonce="n1", nc=00000001,"#));
+        assert!(authorizations[2]
+            .unwrap()
+            .contains(r#"nonce="n1", nc=00000002,"#));
+    }
 }
@@ -27,6 +27,9 @@
 //! - [`infallible_client_fn`]: Allows you to create a client from an infallible function
 //! that takes a request and returns a response.
 //! - [`NeverClient`]: Useful for testing timeouts, where you want the client to never respond.
+//! - [`ChaosHttpClient`]: Wraps a real client and injects faults, such as IO errors, throttling
+//! responses, and slow or disconnected response bodies. Useful for soak testing retries and
+//! stalled stream protection.
 //!
 #![cfg_attr(
     feature = "connector-hyper-0-14-x",
@@ -51,6 +54,9 @@ pub use infallible::infallible_client_fn;
 mod never;
 pub use never::NeverClient;
 
+mod chaos;
+pub use chaos::{ChaosFault, ChaosHttpClient};
+
 #[cfg(feature = "connector-hyper-0-14-x")]
 pub use never::NeverTcpConnector;
 
@@ -0,0 +1,565 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! HTTP client that injects faults into the traffic of another client
+
+use aws_smithy_async::rt::sleep::{AsyncSleep, SharedAsyncSleep, Sleep};
+use aws_smithy_runtime_api::box_error::BoxError;
+use aws_smithy_runtime_api::client::connector_metadata::ConnectorMetadata;
+use aws_smithy_runtime_api::client::http::{
+    HttpClient, HttpConnector, HttpConnectorFuture, HttpConnectorSettings, SharedHttpClient,
+    SharedHttpConnector,
+};
+use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, HttpResponse};
+use aws_smithy_runtime_api::client::result::ConnectorError;
+use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
+use aws_smithy_runtime_api::http::StatusCode;
+use aws_smithy_runtime_api::shared::IntoShared;
+use aws_smithy_types::body::SdkBody;
+use bytes::Bytes;
+use http_body_04x::{Body, SizeHint};
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// A fault that [`ChaosHttpClient`] can inject into a call
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChaosFault {
+    /// Fail the call with a timeout error, as a connector does when its connect timeout elapses.
+    ///
+    /// The request isn't sent to the inner client.
+    ConnectTimeout,
+    /// Fail the call with an IO error, as though the connection was reset.
+    ///
+    /// The request isn't sent to the inner client.
+    IoError,
+    /// Respond with an empty response that has the given status, such as a `503` to simulate
+    /// throttling.
+    ///
+    /// The request isn't sent to the inner client.
+    ErrorResponse {
+        /// The status code of the response
+        status: u16,
+    },
+    /// Deliver the response body from the inner client `chunk_size` bytes at a time, waiting
+    /// `delay` before each chunk.
+    ///
+    /// This requires an async sleep implementation in the runtime components.
+    TrickleBody {
+        /// The maximum number of bytes in each chunk
+        chunk_size: usize,
+        /// How long to wait before each chunk
+        delay: Duration,
+    },
+    /// Disconnect after `after` bytes of the response body from the inner client were delivered.
+    ///
+    /// Reading the body fails with an IO error once the limit is reached.
+    Disconnect {
+        /// The number of body bytes to deliver before disconnecting
+        after: usize,
+    },
+}
+
+impl ChaosFault {
+    /// A `503 Service Unavailable` response, which services commonly use for throttling.
+    pub fn throttling() -> Self {
+        ChaosFault::ErrorResponse { status: 503 }
+    }
+}
+
+#[derive(Debug)]
+struct ChaosState {
+    rng: fastrand::Rng,
+    calls: usize,
+    injected: Vec<(usize, ChaosFault)>,
+}
+
+/// HTTP client that wraps another client and injects faults into its calls.
+///
+/// Faults are injected either at random, with [`with_fault`](ChaosHttpClient::with_fault), or on
+/// specific calls, with [`with_fault_on_call`](ChaosHttpClient::with_fault_on_call). Calls without a
+/// fault are passed through to the inner client unchanged. This is useful for soak testing retry
+/// strategies, stalled stream protection, and connection poisoning against a local server.
+///
+/// Random faults are reproducible when a seed is set with
+/// [`with_seed`](ChaosHttpClient::with_seed).
+///
+/// # Examples
+///
+/// ```rust
+/// use aws_smithy_runtime::client::http::test_util::{infallible_client_fn, ChaosFault, ChaosHttpClient};
+/// use std::time::Duration;
+///
+/// let inner = infallible_client_fn(|_req| http_02x::Response::builder().status(200).body("OK!").unwrap());
+/// let http_client = ChaosHttpClient::new(inner)
+///     .with_seed(1234)
+///     .with_fault(ChaosFault::throttling(), 0.2)
+///     .with_fault(ChaosFault::Disconnect { after: 1 }, 0.1)
+///     .with_fault_on_call(0, ChaosFault::ConnectTimeout);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ChaosHttpClient {
+    inner: SharedHttpClient,
+    random_faults: Vec<(ChaosFault, f64)>,
+    scheduled_faults: HashMap<usize, ChaosFault>,
+    state: Arc<Mutex<ChaosState>>,
+}
+
+impl ChaosHttpClient {
+    /// Creates a client that passes every call through to `inner` until faults are added.
+    pub fn new(inner: impl HttpClient + 'static) -> Self {
+        Self {
+            inner: inner.into_shared(),
+            random_faults: Vec::new(),
+            scheduled_faults: HashMap::new(),
+            state: Arc::new(Mutex::new(ChaosState {
+                rng: fastrand::Rng::new(),
+                calls: 0,
+                injected: Vec::new(),
+            })),
+        }
+    }
+
+    /// Seeds the random number generator used to pick random faults.
+    pub fn with_seed(self, seed: u64) -> Self {
+        self.state.lock().unwrap().rng = fastrand::Rng::with_seed(seed);
+        self
+    }
+
+    /// Injects `fault` into each call with the given probability.
+    ///
+    /// At most one fault is injected into a call, so the probabilities of every random fault
+    /// are cumulative.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `probability` isn't between 0 and 1, or if the probabilities of every random
+    /// fault add up to more than 1.
+    pub fn with_fault(mut self, fault: ChaosFault, probability: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&probability),
+            "fault probability must be between 0 and 1"
+        );
+        self.random_faults.push((fault, probability));
+        let total: f64 = self.random_faults.iter().map(|(_, p)| p).sum();
+        assert!(
+            total <= 1.0 + f64::EPSILON,
+            "the probabilities of every fault add up to {total}, which is more than 1"
+        );
+        self
+    }
+
+    /// Injects `fault` into the call with the given index, replacing any random fault.
+    ///
+    /// Calls are numbered in the order they're made, starting from zero. Retries are separate
+    /// calls.
+    pub fn with_fault_on_call(mut self, index: usize, fault: ChaosFault) -> Self {
+        self.scheduled_faults.insert(index, fault);
+        self
+    }
+
+    /// Returns the number of calls made to this client.
+    pub fn num_calls(&self) -> usize {
+        self.state.lock().unwrap().calls
+    }
+
+    /// Returns each injected fault along with the index of the call it was injected into.
+    pub fn injected_faults(&self) -> Vec<(usize, ChaosFault)> {
+        self.state.lock().unwrap().injected.clone()
+    }
+
+    fn next_fault(&self) -> Option<ChaosFault> {
+        let mut state = self.state.lock().unwrap();
+        let index = state.calls;
+        state.calls += 1;
+        let fault = match self.scheduled_faults.get(&index) {
+            Some(fault) => Some(fault.clone()),
+            None => {
+                let mut roll = state.rng.f64();
+                self.random_faults
+                    .iter()
+                    .find(|(_, probability)| {
+                        roll -= probability;
+                        roll < 0.0
+                    })
+                    .map(|(fault, _)| fault.clone())
+            }
+        };
+        if let Some(fault) = &fault {
+            state.injected.push((index, fault.clone()));
+        }
+        fault
+    }
+}
+
+impl HttpClient for ChaosHttpClient {
+    fn http_connector(
+        &self,
+        settings: &HttpConnectorSettings,
+        components: &RuntimeComponents,
+    ) -> SharedHttpConnector {
+        ChaosConnector {
+            client: self.clone(),
+            inner: self.inner.http_connector(settings, components),
+            sleep_impl: components.sleep_impl(),
+        }
+        .into_shared()
+    }
+
+    fn connector_metadata(&self) -> Option<ConnectorMetadata> {
+        Some(ConnectorMetadata::new("chaos-client", None))
+    }
+}
+
+#[derive(Debug)]
+struct ChaosConnector {
+    client: ChaosHttpClient,
+    inner: SharedHttpConnector,
+    sleep_impl: Option<SharedAsyncSleep>,
+}
+
+impl HttpConnector for ChaosConnector {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let fault = match self.client.next_fault() {
+            None => return self.inner.call(request),
+            Some(fault) => fault,
+        };
+        let body_fault = match fault {
+            ChaosFault::ConnectTimeout => {
+                return HttpConnectorFuture::ready(Err(ConnectorError::timeout(
+                    "connect timeout injected by ChaosHttpClient".into(),
+                )))
+            }
+            ChaosFault::IoError => {
+                return HttpConnectorFuture::ready(Err(ConnectorError::io(
+                    io::Error::from(io::ErrorKind::ConnectionReset).into(),
+                )))
+            }
+            ChaosFault::ErrorResponse { status } => {
+                let response = StatusCode::try_from(status)
+                    .map(|status| HttpResponse::new(status, SdkBody::empty()))
+                    .map_err(|err| ConnectorError::other(err.into(), None));
+                return HttpConnectorFuture::ready(response);
+            }
+            ChaosFault::TrickleBody { chunk_size, delay } => {
+                let sleep_impl =
+                    match &self.sleep_impl {
+                        Some(sleep_impl) => sleep_impl.clone(),
+                        None => return HttpConnectorFuture::ready(Err(ConnectorError::other(
+                            "ChaosHttpClient needs an async sleep implementation to trickle bodies"
+                                .into(),
+                            None,
+                        ))),
+                    };
+                BodyFault::Trickle {
+                    chunk_size: chunk_size.max(1),
+                    delay,
+                    sleep_impl,
+                }
+            }
+            ChaosFault::Disconnect { after } => BodyFault::Disconnect { after },
+        };
+        let response = self.inner.call(request);
+        HttpConnectorFuture::new(async move {
+            let mut response = response.await?;
+            let body = response.take_body();
+            *response.body_mut() = SdkBody::from_body_0_4(ChaosBody {
+                inner: body,
+                fault: body_fault,
+                buffered: Bytes::new(),
+                delivered: 0,
+                sleep: None,
+            });
+            Ok(response)
+        })
+    }
+}
+
+#[derive(Debug)]
+enum BodyFault {
+    Trickle {
+        chunk_size: usize,
+        delay: Duration,
+        sleep_impl: SharedAsyncSleep,
+    },
+    Disconnect {
+        after: usize,
+    },
+}
+
+/// Response body that applies a [`BodyFault`] to the body it wraps
+struct ChaosBody {
+    inner: SdkBody,
+    fault: BodyFault,
+    buffered: Bytes,
+    delivered: usize,
+    sleep: Option<Sleep>,
+}
+
+impl ChaosBody {
+    fn poll_trickle(
+        &mut self,
+        cx: &mut Context<'_>,
+        chunk_size: usize,
+        delay: Duration,
+        sleep_impl: &SharedAsyncSleep,
+    ) -> Poll<Option<Result<Bytes, BoxError>>> {
+        if self.buffered.is_empty() {
+            match ready_chunk(Pin::new(&mut self.inner).poll_data(cx)) {
+                Poll::Ready(Some(Ok(data))) => self.buffered = data,
+                other => return other,
+            }
+        }
+        let sleep = self.sleep.get_or_insert_with(|| sleep_impl.sleep(delay));
+        if Pin::new(sleep).poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+        self.sleep = None;
+        let chunk = self.buffered.split_to(chunk_size.min(self.buffered.len()));
+        Poll::Ready(Some(Ok(chunk)))
+    }
+
+    fn poll_disconnect(
+        &mut self,
+        cx: &mut Context<'_>,
+        after: usize,
+    ) -> Poll<Option<Result<Bytes, BoxError>>> {
+        if self.delivered >= after {
+            return Poll::Ready(Some(Err(io::Error::new(
+                io::ErrorKind::ConnectionReset,
+                "disconnect injected by ChaosHttpClient",
+            )
+            .into())));
+        }
+        match ready_chunk(Pin::new(&mut self.inner).poll_data(cx)) {
+            Poll::Ready(Some(Ok(mut data))) => {
+                data.truncate(after - self.delivered);
+                self.delivered += data.len();
+                Poll::Ready(Some(Ok(data)))
+            }
+            // the body ended before the disconnect
+            other => other,
+        }
+    }
+}
+
+fn ready_chunk(
+    poll: Poll<Option<Result<Bytes, aws_smithy_types::body::Error>>>,
+) -> Poll<Option<Result<Bytes, BoxError>>> {
+    poll.map(|chunk| chunk.map(|result| result.map_err(BoxError::from)))
+}
+
+impl Body for ChaosBody {
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let this = self.get_mut();
+        match &this.fault {
+            BodyFault::Trickle {
+                chunk_size,
+                delay,
+                sleep_impl,
+            } => {
+                let (chunk_size, delay, sleep_impl) = (*chunk_size, *delay, sleep_impl.clone());
+                this.poll_trickle(cx, chunk_size, delay, &sleep_impl)
+            }
+            BodyFault::Disconnect { after } => {
+                let after = *after;
+                this.poll_disconnect(cx, after)
+            }
+        }
+    }
+
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<http_02x::HeaderMap>, Self::Error>> {
+        let this = self.get_mut();
+        if matches!(this.fault, BodyFault::Disconnect { after } if this.delivered >= after) {
+            return Poll::Ready(Ok(None));
+        }
+        Pin::new(&mut this.inner)
+            .poll_trailers(cx)
+            .map_err(BoxError::from)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.buffered.is_empty() && self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        match self.fault {
+            // the body may end early, so only the upper bound is known
+            BodyFault::Disconnect { .. } => {
+                let mut hint = SizeHint::new();
+                if let Some(upper) = self.inner.size_hint().upper() {
+                    hint.set_upper(upper);
+                }
+                hint
+            }
+            BodyFault::Trickle { .. } => {
+                let inner = self.inner.size_hint();
+                let buffered = self.buffered.len() as u64;
+                let mut hint = SizeHint::new();
+                hint.set_lower(inner.lower() + buffered);
+                if let Some(upper) = inner.upper() {
+                    hint.set_upper(upper + buffered);
+                }
+                hint
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChaosFault, ChaosHttpClient};
+    use crate::client::http::test_util::infallible_client_fn;
+    use aws_smithy_async::test_util::instant_time_and_sleep;
+    use aws_smithy_async::time::TimeSource;
+    use aws_smithy_runtime_api::client::http::{
+        HttpClient, HttpConnector, HttpConnectorSettings, SharedHttpConnector,
+    };
+    use aws_smithy_runtime_api::client::orchestrator::HttpRequest;
+    use aws_smithy_runtime_api::client::runtime_components::RuntimeComponentsBuilder;
+    use aws_smithy_types::body::SdkBody;
+    use aws_smithy_types::byte_stream::ByteStream;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn connector(client: &ChaosHttpClient) -> SharedHttpConnector {
+        let (_, sleep) = instant_time_and_sleep(UNIX_EPOCH);
+        let components = RuntimeComponentsBuilder::for_tests()
+            .with_sleep_impl(Some(sleep))
+            .build()
+            .unwrap();
+        client.http_connector(&HttpConnectorSettings::default(), &components)
+    }
+
+    fn hello_world() -> ChaosHttpClient {
+        ChaosHttpClient::new(infallible_client_fn(|_req| {
+            http_02x::Response::builder()
+                .status(200)
+                .body("hello world")
+                .unwrap()
+        }))
+    }
+
+    fn request() -> HttpRequest {
+        HttpRequest::new(SdkBody::empty())
+    }
+
+    #[tokio::test]
+    async fn scheduled_faults() {
+        let client = hello_world()
+            .with_fault_on_call(0, ChaosFault::ConnectTimeout)
+            .with_fault_on_call(1, ChaosFault::IoError)
+            .with_fault_on_call(2, ChaosFault::throttling());
+        let connector = connector(&client);
+
+        assert!(connector.call(request()).await.unwrap_err().is_timeout());
+        assert!(connector.call(request()).await.unwrap_err().is_io());
+        assert_eq!(
+            503,
+            connector.call(request()).await.unwrap().status().as_u16()
+        );
+        let response = connector.call(request()).await.unwrap();
+        assert_eq!(b"hello world", response.body().bytes().unwrap());
+
+        assert_eq!(4, client.num_calls());
+        assert_eq!(
+            vec![
+                (0, ChaosFault::ConnectTimeout),
+                (1, ChaosFault::IoError),
+                (2, ChaosFault::throttling()),
+            ],
+            client.injected_faults()
+        );
+    }
+
+    #[tokio::test]
+    async fn random_faults_are_reproducible() {
+        let injected = || async {
+            let client = hello_world()
+                .with_seed(42)
+                .with_fault(ChaosFault::IoError, 0.3)
+                .with_fault(ChaosFault::throttling(), 0.2);
+            let connector = connector(&client);
+            for _ in 0..100 {
+                let _ = connector.call(request()).await;
+            }
+            client.injected_faults()
+        };
+        let first = injected().await;
+        assert_eq!(first, injected().await);
+        let io_errors = first
+            .iter()
+            .filter(|(_, fault)| *fault == ChaosFault::IoError)
+            .count();
+        assert!((15..=45).contains(&io_errors), "{io_errors}");
+        assert!(first.len() < 75, "{}", first.len());
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "more than 1")]
+    async fn probabilities_cannot_exceed_one() {
+        let _ = hello_world()
+            .with_fault(ChaosFault::IoError, 0.6)
+            .with_fault(ChaosFault::ConnectTimeout, 0.6);
+    }
+
+    #[tokio::test]
+    async fn trickle_body() {
+        let (time_source, sleep) = instant_time_and_sleep(UNIX_EPOCH);
+        let client = hello_world().with_fault_on_call(
+            0,
+            ChaosFault::TrickleBody {
+                chunk_size: 4,
+                delay: Duration::from_secs(1),
+            },
+        );
+        let components = RuntimeComponentsBuilder::for_tests()
+            .with_sleep_impl(Some(sleep))
+            .build()
+            .unwrap();
+        let connector = client.http_connector(&HttpConnectorSettings::default(), &components);
+
+        let response = connector.call(request()).await.unwrap();
+        let body = ByteStream::new(response.into_body())
+            .collect()
+            .await
+            .unwrap();
+        assert_eq!(b"hello world", &body.into_bytes()[..]);
+        // "hello world" is delivered in three chunks
+        assert_eq!(
+            Duration::from_secs(3),
+            time_source.now().duration_since(UNIX_EPOCH).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn disconnect_mid_stream() {
+        let client = hello_world().with_fault_on_call(0, ChaosFault::Disconnect { after: 5 });
+        let connector = connector(&client);
+
+        let response = connector.call(request()).await.unwrap();
+        let mut body = ByteStream::new(response.into_body());
+        assert_eq!(b"hello", &body.next().await.unwrap().unwrap()[..]);
+        let err = body.next().await.unwrap().unwrap_err();
+        assert!(format!(
+            "{}",
+            aws_smithy_types::error::display::DisplayErrorContext(&err)
+        )
+        .contains("disconnect injected by ChaosHttpClient"));
+    }
+}
@@ -17,12 +17,19 @@ use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::Path;
+use std::time::Duration;
 
+mod cassette;
+mod matching;
 mod record;
+mod redact;
 mod replay;
 
+pub use cassette::{CassetteClient, CassetteClientBuilder, CassetteMode, MODE_ENV_VAR};
+pub use matching::{MatchRequest, RequestMatcher, VOLATILE_HEADERS};
 pub use record::RecordingClient;
-pub use replay::ReplayingClient;
+pub use redact::{RedactionRules, REDACTED};
+pub use replay::{Fault, ReplayingClient};
 
 /// A complete traffic recording
 ///
@@ -40,10 +47,30 @@ impl NetworkTraffic {
         &self.events
     }
 
+    /// Serialization version this traffic was recorded with
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
     /// Create a NetworkTraffic instance from a file
+    ///
+    /// Fails with a descriptive error if the file was written with an unsupported [`Version`].
     pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = path.as_ref();
         let contents = std::fs::read_to_string(path)?;
-        Ok(serde_json::from_str(&contents)?)
+        let traffic: serde_json::Value = serde_json::from_str(&contents)?;
+        if let Some(version) = traffic.get("version") {
+            if serde_json::from_value::<Version>(version.clone()).is_err() {
+                return Err(format!(
+                    "{} was recorded with unsupported DVR format version {version}; \
+                     the latest supported version is {:?}",
+                    path.display(),
+                    Version::CURRENT
+                )
+                .into());
+            }
+        }
+        Ok(serde_json::from_value(traffic)?)
     }
 
     /// Create a NetworkTraffic instance from a file
@@ -90,9 +117,17 @@ impl NetworkTraffic {
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Version {
     /// Initial network traffic version
+    ///
+    /// Events may also record the delay since the previous event on their connection. Older
+    /// recordings without delays still load.
     V0,
 }
 
+impl Version {
+    /// The version new recordings are written with
+    pub const CURRENT: Version = Version::V0;
+}
+
 /// A network traffic recording may contain multiple different connections occurring simultaneously
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, Hash, PartialEq, Eq)]
 pub struct ConnectionId(usize);
@@ -105,6 +140,17 @@ pub struct ConnectionId(usize);
 pub struct Event {
     connection_id: ConnectionId,
     action: Action,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    delay_ms: Option<u64>,
+}
+
+impl Event {
+    /// Time between the previous event on this connection and this event, if it was recorded
+    ///
+    /// See [`RecordingClient::with_timing`].
+    pub fn delay(&self) -> Option<Duration> {
+        self.delay_ms.map(Duration::from_millis)
+    }
 }
 
 /// An initial HTTP request, roughly equivalent to `http::Request<()>`
@@ -0,0 +1,466 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use super::{
+    ConnectionId, Event, MatchRequest, NetworkTraffic, RecordingClient, RedactionRules,
+    ReplayingClient,
+};
+use aws_smithy_runtime_api::box_error::BoxError;
+use aws_smithy_runtime_api::client::connector_metadata::ConnectorMetadata;
+use aws_smithy_runtime_api::client::http::{
+    HttpClient, HttpConnector, HttpConnectorFuture, HttpConnectorSettings, SharedHttpConnector,
+};
+use aws_smithy_runtime_api::client::orchestrator::HttpRequest;
+use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
+use aws_smithy_runtime_api::shared::IntoShared;
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Environment variable that selects the [`CassetteMode`]
+pub const MODE_ENV_VAR: &str = "SMITHY_DVR_MODE";
+
+/// Whether a [`CassetteClient`] records or replays traffic
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Send requests to the real connector and overwrite the cassette
+    Record,
+    /// Replay the cassette, which must exist
+    Replay,
+    /// Replay the cassette if it exists, or else record it
+    Auto,
+}
+
+impl CassetteMode {
+    /// Reads the mode from the `SMITHY_DVR_MODE` environment variable
+    ///
+    /// Accepts `record`, `replay`, or `auto`, and defaults to `auto` when unset.
+    pub fn from_env() -> Result<Self, BoxError> {
+        match std::env::var(MODE_ENV_VAR) {
+            Ok(mode) => mode.parse(),
+            Err(std::env::VarError::NotPresent) => Ok(CassetteMode::Auto),
+            Err(err) => Err(format!("invalid {MODE_ENV_VAR}: {err}").into()),
+        }
+    }
+}
+
+impl FromStr for CassetteMode {
+    type Err = BoxError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "record" => Ok(CassetteMode::Record),
+            "replay" => Ok(CassetteMode::Replay),
+            "auto" => Ok(CassetteMode::Auto),
+            _ => Err(format!(
+                "invalid {MODE_ENV_VAR} `{s}`: expected `record`, `replay`, or `auto`"
+            )
+            .into()),
+        }
+    }
+}
+
+/// Builder for [`CassetteClient`]
+#[derive(Debug)]
+pub struct CassetteClientBuilder {
+    path: PathBuf,
+    mode: Option<CassetteMode>,
+    redaction: Option<RedactionRules>,
+    matcher: Option<Arc<dyn MatchRequest>>,
+    rerecord: BTreeSet<usize>,
+}
+
+impl CassetteClientBuilder {
+    /// Sets the mode, instead of reading it from `SMITHY_DVR_MODE`
+    pub fn mode(mut self, mode: CassetteMode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Redacts traffic with the given rules when it's saved, and before requests are validated
+    pub fn redaction(mut self, rules: RedactionRules) -> Self {
+        self.redaction = Some(rules);
+        self
+    }
+
+    /// Matches replayed requests with `matcher` instead of by connection order
+    ///
+    /// See [`ReplayingClient::with_matcher`].
+    pub fn matcher(mut self, matcher: impl MatchRequest + 'static) -> Self {
+        self.matcher = Some(Arc::new(matcher));
+        self
+    }
+
+    /// Re-records the connection at `index` when replaying, leaving the rest of the cassette as is
+    ///
+    /// Connections are numbered in the order their requests are sent, starting from zero. The
+    /// re-recorded connection replaces the stale one when the cassette is
+    /// [saved](CassetteClient::save).
+    pub fn rerecord(mut self, index: usize) -> Self {
+        self.rerecord.insert(index);
+        self
+    }
+
+    /// Builds the client, loading the cassette if it will be replayed
+    ///
+    /// `real_connector` is only called when recording, so it can be a local stand-in for a real
+    /// service.
+    pub fn build(
+        self,
+        real_connector: impl HttpConnector + 'static,
+    ) -> Result<CassetteClient, Box<dyn Error>> {
+        let mode = match self.mode {
+            Some(mode) => mode,
+            None => CassetteMode::from_env().map_err(|err| -> Box<dyn Error> { err })?,
+        };
+        let mode = match mode {
+            CassetteMode::Auto if self.path.exists() => CassetteMode::Replay,
+            CassetteMode::Auto => CassetteMode::Record,
+            mode => mode,
+        };
+        let mut recorder = RecordingClient::new(real_connector);
+        if let Some(rules) = &self.redaction {
+            recorder = recorder.with_redaction(rules.clone());
+        }
+        let (cassette, replayer) = match mode {
+            CassetteMode::Replay => {
+                let cassette = NetworkTraffic::from_file(&self.path).map_err(|err| {
+                    format!("failed to load cassette {}: {err}", self.path.display())
+                })?;
+                let mut replayer =
+                    ReplayingClient::new(renumber_without(&cassette.events, &self.rerecord));
+                if let Some(rules) = self.redaction {
+                    replayer = replayer.with_redaction(rules);
+                }
+                if let Some(matcher) = self.matcher {
+                    replayer = replayer.with_matcher(matcher);
+                }
+                (Some(Arc::new(cassette)), Some(replayer))
+            }
+            _ => (None, None),
+        };
+        Ok(CassetteClient {
+            path: self.path,
+            cassette,
+            recorder,
+            replayer,
+            rerecord: Arc::new(self.rerecord),
+            num_calls: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+}
+
+/// Removes the given connections from `events`, and renumbers the remaining connections so that
+/// they're replayed in the same order
+fn renumber_without(events: &[Event], removed: &BTreeSet<usize>) -> Vec<Event> {
+    let kept: BTreeSet<_> = events
+        .iter()
+        .map(|e| e.connection_id.0)
+        .filter(|id| !removed.contains(id))
+        .collect();
+    let new_ids: HashMap<_, _> = kept
+        .into_iter()
+        .enumerate()
+        .map(|(new, old)| (old, ConnectionId(new)))
+        .collect();
+    events
+        .iter()
+        .filter_map(|event| {
+            Some(Event {
+                connection_id: *new_ids.get(&event.connection_id.0)?,
+                ..event.clone()
+            })
+        })
+        .collect()
+}
+
+/// Record-or-replay client backed by a traffic recording on disk
+///
+/// In [`Replay`](CassetteMode::Replay) mode, requests are answered by a [`ReplayingClient`]. In
+/// [`Record`](CassetteMode::Record) mode, requests are sent to a real connector through a
+/// [`RecordingClient`], and [`save`](CassetteClient::save) writes the traffic to the cassette.
+/// [`Auto`](CassetteMode::Auto) mode replays the cassette if it exists and records it otherwise.
+/// The mode is read from the `SMITHY_DVR_MODE` environment variable unless set on the builder.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use aws_smithy_runtime::client::http::test_util::dvr::CassetteClient;
+///
+/// #[tokio::test]
+/// async fn list_buckets() {
+///     let http_client = CassetteClient::builder("tests/data/list-buckets.json")
+///         .build(real_connector())
+///         .unwrap();
+///     let client = Client::from_conf(config_with(http_client.clone()));
+///     client.list_buckets().send().await.unwrap();
+///
+///     // Writes the cassette if it was recorded; does nothing when it was replayed
+///     http_client.save().unwrap();
+/// }
+/// ```
+#[derive(Clone)]
+pub struct CassetteClient {
+    path: PathBuf,
+    cassette: Option<Arc<NetworkTraffic>>,
+    recorder: RecordingClient,
+    replayer: Option<ReplayingClient>,
+    rerecord: Arc<BTreeSet<usize>>,
+    num_calls: Arc<AtomicUsize>,
+}
+
+// Like `ReplayingClient`, this avoids printing recorded traffic that may look like secrets.
+impl fmt::Debug for CassetteClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CassetteClient")
+            .field("path", &self.path)
+            .field("mode", &self.mode())
+            .finish()
+    }
+}
+
+impl CassetteClient {
+    /// Returns a builder for a client using the cassette at `path`
+    pub fn builder(path: impl Into<PathBuf>) -> CassetteClientBuilder {
+        CassetteClientBuilder {
+            path: path.into(),
+            mode: None,
+            redaction: None,
+            matcher: None,
+            rerecord: BTreeSet::new(),
+        }
+    }
+
+    /// Whether this client is recording or replaying
+    ///
+    /// This is never [`CassetteMode::Auto`].
+    pub fn mode(&self) -> CassetteMode {
+        match self.replayer {
+            Some(_) => CassetteMode::Replay,
+            None => CassetteMode::Record,
+        }
+    }
+
+    /// The client replaying the cassette, for validating requests after a test
+    ///
+    /// Connections being re-recorded aren't replayed, so they're excluded.
+    pub fn replaying_client(&self) -> Option<&ReplayingClient> {
+        self.replayer.as_ref()
+    }
+
+    /// Writes recorded traffic to the cassette
+    ///
+    /// When replaying, only re-recorded connections are written, replacing the stale ones. If
+    /// nothing was recorded, the cassette is left untouched. Response bodies must be read to
+    /// completion before saving, or they won't be included.
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let recorded = self.recorder.network_traffic();
+        let cassette = match &self.cassette {
+            None => recorded,
+            Some(_) if recorded.events.is_empty() => return Ok(()),
+            Some(cassette) => {
+                // The recorder numbers re-recorded connections in the order they were sent
+                let stale: Vec<_> = self.rerecord.iter().copied().collect();
+                let rerecorded: HashMap<_, _> = recorded
+                    .events
+                    .iter()
+                    .map(|e| (e.connection_id, ConnectionId(stale[e.connection_id.0])))
+                    .collect();
+                let mut events: Vec<_> = cassette
+                    .events
+                    .iter()
+                    .filter(|e| !rerecorded.values().any(|id| *id == e.connection_id))
+                    .cloned()
+                    .collect();
+                events.extend(recorded.events.into_iter().map(|e| Event {
+                    connection_id: rerecorded[&e.connection_id],
+                    ..e
+                }));
+                NetworkTraffic {
+                    events,
+                    docs: cassette.docs.clone(),
+                    version: recorded.version,
+                }
+            }
+        };
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        cassette.write_to_file(&self.path)
+    }
+}
+
+impl HttpConnector for CassetteClient {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let Some(replayer) = &self.replayer else {
+            return self.recorder.call(request);
+        };
+        let index = self.num_calls.fetch_add(1, Ordering::Relaxed);
+        if self.rerecord.contains(&index) {
+            tracing::debug!("re-recording connection {index}");
+            self.recorder.call(request)
+        } else {
+            replayer.call(request)
+        }
+    }
+}
+
+impl HttpClient for CassetteClient {
+    fn http_connector(
+        &self,
+        _: &HttpConnectorSettings,
+        _: &RuntimeComponents,
+    ) -> SharedHttpConnector {
+        self.clone().into_shared()
+    }
+
+    fn connector_metadata(&self) -> Option<ConnectorMetadata> {
+        Some(ConnectorMetadata::new("cassette-client", None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CassetteClient, CassetteMode};
+    use crate::client::http::test_util::dvr::NetworkTraffic;
+    use crate::client::http::test_util::infallible_client_fn;
+    use aws_smithy_runtime_api::client::http::{
+        HttpClient, HttpConnector, HttpConnectorSettings, SharedHttpConnector,
+    };
+    use aws_smithy_runtime_api::client::runtime_components::RuntimeComponentsBuilder;
+    use aws_smithy_types::body::SdkBody;
+    use aws_smithy_types::byte_stream::ByteStream;
+    use std::path::PathBuf;
+
+    fn server(greeting: &'static str) -> SharedHttpConnector {
+        infallible_client_fn(move |req| {
+            http_02x::Response::builder()
+                .status(200)
+                .body(format!("{greeting} from {}", req.uri().path()))
+                .unwrap()
+        })
+        .http_connector(
+            &HttpConnectorSettings::default(),
+            &RuntimeComponentsBuilder::for_tests().build().unwrap(),
+        )
+    }
+
+    async fn send(connector: &impl HttpConnector, path: &str) -> String {
+        let request = http_02x::Request::get(format!("https://example.com/{path}"))
+            .body(SdkBody::empty())
+            .unwrap();
+        let response = connector.call(request.try_into().unwrap()).await.unwrap();
+        let body = ByteStream::new(response.into_body())
+            .collect()
+            .await
+            .unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    fn cassette_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir()
+            .join("smithy-dvr-cassettes")
+            .join(format!("{name}-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn parse_mode() {
+        assert_eq!(CassetteMode::Record, "record".parse().unwrap());
+        assert_eq!(CassetteMode::Replay, "REPLAY".parse().unwrap());
+        assert_eq!(CassetteMode::Auto, "auto".parse().unwrap());
+        assert!("rewind".parse::<CassetteMode>().is_err());
+    }
+
+    #[tokio::test]
+    async fn auto_records_then_replays() {
+        let path = cassette_path("auto");
+        let client = CassetteClient::builder(&path)
+            .mode(CassetteMode::Auto)
+            .build(server("hello"))
+            .unwrap();
+        assert_eq!(CassetteMode::Record, client.mode());
+        assert_eq!("hello from /a", send(&client, "a").await);
+        assert_eq!("hello from /b", send(&client, "b").await);
+        client.save().unwrap();
+
+        let client = CassetteClient::builder(&path)
+            .mode(CassetteMode::Auto)
+            .build(server("goodbye"))
+            .unwrap();
+        assert_eq!(CassetteMode::Replay, client.mode());
+        assert_eq!("hello from /a", send(&client, "a").await);
+        assert_eq!("hello from /b", send(&client, "b").await);
+        client.save().unwrap();
+        assert_eq!(
+            2,
+            client
+                .replaying_client()
+                .unwrap()
+                .clone()
+                .take_requests()
+                .await
+                .len()
+        );
+    }
+
+    #[tokio::test]
+    async fn rerecords_a_single_connection() {
+        let path = cassette_path("rerecord");
+        let client = CassetteClient::builder(&path)
+            .mode(CassetteMode::Record)
+            .build(server("hello"))
+            .unwrap();
+        for path in ["a", "b", "c"] {
+            send(&client, path).await;
+        }
+        client.save().unwrap();
+
+        let client = CassetteClient::builder(&path)
+            .mode(CassetteMode::Replay)
+            .rerecord(1)
+            .build(server("goodbye"))
+            .unwrap();
+        assert_eq!("hello from /a", send(&client, "a").await);
+        assert_eq!("goodbye from /b", send(&client, "b").await);
+        assert_eq!("hello from /c", send(&client, "c").await);
+        client.save().unwrap();
+
+        let client = CassetteClient::builder(&path)
+            .mode(CassetteMode::Replay)
+            .build(server("unused"))
+            .unwrap();
+        assert_eq!("hello from /a", send(&client, "a").await);
+        assert_eq!("goodbye from /b", send(&client, "b").await);
+        assert_eq!("hello from /c", send(&client, "c").await);
+    }
+
+    #[test]
+    fn replay_requires_a_cassette() {
+        let err = CassetteClient::builder(cassette_path("missing"))
+            .mode(CassetteMode::Replay)
+            .build(server("hello"))
+            .unwrap_err();
+        assert!(err.to_string().contains("failed to load cassette"), "{err}");
+    }
+
+    #[test]
+    fn unsupported_versions_are_rejected() {
+        let path = cassette_path("version");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, r#"{"events": [], "docs": null, "version": "V99"}"#).unwrap();
+        let err = NetworkTraffic::from_file(&path).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("unsupported DVR format version \"V99\""),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,303 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use super::REDACTED;
+use aws_smithy_protocol_test::MediaType;
+use bytes::Bytes;
+use std::fmt;
+
+/// Headers that usually differ between a recording and a replay
+///
+/// These are dates, signatures, credentials, and SDK invocation metadata.
+pub const VOLATILE_HEADERS: &[&str] = &[
+    "authorization",
+    "date",
+    "x-amz-date",
+    "x-amz-security-token",
+    "x-amz-content-sha256",
+    "amz-sdk-invocation-id",
+    "amz-sdk-request",
+    "user-agent",
+    "x-amz-user-agent",
+];
+
+/// Decides whether a recorded request can answer a request received during replay
+///
+/// A [`ReplayingClient`](super::ReplayingClient) given a matcher with
+/// [`with_matcher`](super::ReplayingClient::with_matcher) answers each request with the first
+/// unused recorded connection whose request matches it, rather than answering requests in the
+/// order they were recorded.
+pub trait MatchRequest: Send + Sync + fmt::Debug {
+    /// Returns `Ok` if `actual` matches `expected`, or else the reasons it doesn't
+    ///
+    /// When nothing matches, the candidate with the fewest reasons is reported as the closest.
+    fn match_request(
+        &self,
+        expected: &http_02x::Request<Bytes>,
+        actual: &http_02x::Request<Bytes>,
+    ) -> Result<(), Vec<String>>;
+}
+
+impl<T: MatchRequest + ?Sized> MatchRequest for std::sync::Arc<T> {
+    fn match_request(
+        &self,
+        expected: &http_02x::Request<Bytes>,
+        actual: &http_02x::Request<Bytes>,
+    ) -> Result<(), Vec<String>> {
+        (**self).match_request(expected, actual)
+    }
+}
+
+/// Built-in [`MatchRequest`] implementation
+///
+/// # Example
+///
+/// ```rust
+/// use aws_smithy_runtime::client::http::test_util::dvr::{RequestMatcher, VOLATILE_HEADERS};
+///
+/// let matcher = RequestMatcher::method_and_uri()
+///     .headers_except(VOLATILE_HEADERS)
+///     .body("application/json");
+/// ```
+#[derive(Clone, Debug)]
+pub struct RequestMatcher {
+    method_and_uri: bool,
+    ignored_headers: Option<Vec<String>>,
+    media_type: Option<String>,
+}
+
+impl RequestMatcher {
+    /// Matches any request, so requests are answered by unused connections in recorded order
+    pub fn any() -> Self {
+        Self {
+            method_and_uri: false,
+            ignored_headers: None,
+            media_type: None,
+        }
+    }
+
+    /// Matches requests with the same method and URI
+    ///
+    /// Query parameters may be in any order.
+    pub fn method_and_uri() -> Self {
+        Self {
+            method_and_uri: true,
+            ..Self::any()
+        }
+    }
+
+    /// Also require recorded headers to have the same values, except the given headers
+    ///
+    /// Recorded headers that were [redacted](super::RedactionRules) only need to be present.
+    pub fn headers_except(mut self, ignored: &[&str]) -> Self {
+        self.ignored_headers = Some(ignored.iter().map(|h| h.to_ascii_lowercase()).collect());
+        self
+    }
+
+    /// Also require bodies to be equivalent, comparing them as the given media type
+    ///
+    /// JSON, XML, CBOR, and URL encoded form bodies are compared semantically. See
+    /// [`aws_smithy_protocol_test::validate_body`].
+    pub fn body(mut self, media_type: impl Into<String>) -> Self {
+        self.media_type = Some(media_type.into());
+        self
+    }
+}
+
+impl MatchRequest for RequestMatcher {
+    fn match_request(
+        &self,
+        expected: &http_02x::Request<Bytes>,
+        actual: &http_02x::Request<Bytes>,
+    ) -> Result<(), Vec<String>> {
+        let mut reasons = Vec::new();
+        if self.method_and_uri {
+            if expected.method() != actual.method() {
+                reasons.push(format!(
+                    "method: expected `{}` but was `{}`",
+                    expected.method(),
+                    actual.method()
+                ));
+            }
+            if !uris_match(expected.uri(), actual.uri()) {
+                reasons.push(format!(
+                    "URI: expected `{}` but was `{}`",
+                    expected.uri(),
+                    actual.uri()
+                ));
+            }
+        }
+        if let Some(ignored) = &self.ignored_headers {
+            for name in expected.headers().keys() {
+                if ignored.iter().any(|i| i == name.as_str()) {
+                    continue;
+                }
+                let expected_values: Vec<_> = expected.headers().get_all(name).iter().collect();
+                let actual_values: Vec<_> = actual.headers().get_all(name).iter().collect();
+                let redacted = expected_values.iter().any(|v| *v == REDACTED);
+                if actual_values.is_empty() {
+                    reasons.push(format!("header `{name}`: expected but missing"));
+                } else if !redacted && expected_values != actual_values {
+                    reasons.push(format!(
+                        "header `{name}`: expected {expected_values:?} but was {actual_values:?}"
+                    ));
+                }
+            }
+        }
+        if let Some(media_type) = &self.media_type {
+            let body_matches = match std::str::from_utf8(expected.body()) {
+                Ok(expected_body) => aws_smithy_protocol_test::validate_body(
+                    actual.body(),
+                    expected_body,
+                    MediaType::from(media_type.as_str()),
+                )
+                .map_err(|err| err.to_string()),
+                Err(_) if expected.body() == actual.body() => Ok(()),
+                Err(_) => Err("binary bodies differ".to_string()),
+            };
+            if let Err(err) = body_matches {
+                reasons.push(format!("body: {err}"));
+            }
+        }
+        if reasons.is_empty() {
+            Ok(())
+        } else {
+            Err(reasons)
+        }
+    }
+}
+
+fn uris_match(expected: &http_02x::Uri, actual: &http_02x::Uri) -> bool {
+    fn query_params(uri: &http_02x::Uri) -> Vec<&str> {
+        let mut params: Vec<_> = uri
+            .query()
+            .unwrap_or_default()
+            .split('&')
+            .filter(|p| !p.is_empty())
+            .collect();
+        params.sort_unstable();
+        params
+    }
+    expected.scheme() == actual.scheme()
+        && expected.authority() == actual.authority()
+        && expected.path() == actual.path()
+        && query_params(expected) == query_params(actual)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MatchRequest, RequestMatcher, VOLATILE_HEADERS};
+    use crate::client::http::test_util::dvr::{RecordingClient, ReplayingClient};
+    use crate::client::http::test_util::infallible_client_fn;
+    use aws_smithy_runtime_api::client::http::{HttpClient, HttpConnector, HttpConnectorSettings};
+    use aws_smithy_runtime_api::client::runtime_components::RuntimeComponentsBuilder;
+    use aws_smithy_types::body::SdkBody;
+    use aws_smithy_types::byte_stream::ByteStream;
+    use aws_smithy_types::error::display::DisplayErrorContext;
+    use bytes::Bytes;
+
+    fn request(uri: &str, date: &str, body: &'static str) -> http_02x::Request<Bytes> {
+        http_02x::Request::post(uri)
+            .header("x-amz-date", date)
+            .header("content-type", "application/json")
+            .body(Bytes::from_static(body.as_bytes()))
+            .unwrap()
+    }
+
+    #[test]
+    fn method_and_uri_ignores_query_order() {
+        let matcher = RequestMatcher::method_and_uri();
+        let expected = request("https://example.com/a?x=1&y=2", "1", "");
+        assert!(matcher
+            .match_request(
+                &expected,
+                &request("https://example.com/a?y=2&x=1", "2", "")
+            )
+            .is_ok());
+        let reasons = matcher
+            .match_request(&expected, &request("https://example.com/b?x=1", "1", ""))
+            .unwrap_err();
+        assert_eq!(1, reasons.len());
+        assert!(reasons[0].starts_with("URI"), "{reasons:?}");
+    }
+
+    #[test]
+    fn volatile_headers_are_ignored() {
+        let expected = request("https://example.com", "1", "");
+        let actual = request("https://example.com", "2", "");
+        assert!(RequestMatcher::any()
+            .headers_except(VOLATILE_HEADERS)
+            .match_request(&expected, &actual)
+            .is_ok());
+        assert!(RequestMatcher::any()
+            .headers_except(&[])
+            .match_request(&expected, &actual)
+            .is_err());
+    }
+
+    #[test]
+    fn json_bodies_are_compared_semantically() {
+        let matcher = RequestMatcher::any().body("application/json");
+        let expected = request("https://example.com", "1", r#"{"a": 1, "b": [1, 2]}"#);
+        assert!(matcher
+            .match_request(
+                &expected,
+                &request("https://example.com", "1", r#"{"b":[1,2],"a":1}"#)
+            )
+            .is_ok());
+        assert!(matcher
+            .match_request(
+                &expected,
+                &request("https://example.com", "1", r#"{"b":[2,1],"a":1}"#)
+            )
+            .is_err());
+    }
+
+    async fn send(connector: &impl HttpConnector, path: &str) -> Result<String, String> {
+        let request = http_02x::Request::get(format!("https://example.com/{path}"))
+            .body(SdkBody::empty())
+            .unwrap();
+        let response = connector
+            .call(request.try_into().unwrap())
+            .await
+            .map_err(|err| DisplayErrorContext(err).to_string())?;
+        let body = ByteStream::new(response.into_body())
+            .collect()
+            .await
+            .unwrap();
+        Ok(String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn replays_requests_out_of_order() {
+        let server = infallible_client_fn(|req| {
+            http_02x::Response::builder()
+                .status(200)
+                .body(format!("hello from {}", req.uri().path()))
+                .unwrap()
+        })
+        .http_connector(
+            &HttpConnectorSettings::default(),
+            &RuntimeComponentsBuilder::for_tests().build().unwrap(),
+        );
+        let recording = RecordingClient::new(server);
+        send(&recording, "a").await.unwrap();
+        send(&recording, "b").await.unwrap();
+
+        let replay = ReplayingClient::new(recording.events().clone())
+            .with_matcher(RequestMatcher::method_and_uri());
+        assert_eq!("hello from /b", send(&replay, "b").await.unwrap());
+        let err = send(&replay, "c").await.unwrap_err();
+        assert!(err.contains("closest candidate was event 0"), "{err}");
+        assert!(err.contains("expected `https://example.com/a`"), "{err}");
+        assert_eq!("hello from /a", send(&replay, "a").await.unwrap());
+
+        let err = send(&replay, "a").await.unwrap_err();
+        assert!(err.contains("all recorded events have been used"), "{err}");
+        let requests = replay.take_requests().await;
+        assert_eq!("/a", requests[0].uri().path());
+        assert_eq!("/b", requests[1].uri().path());
+    }
+}
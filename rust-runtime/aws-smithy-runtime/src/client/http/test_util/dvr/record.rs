@@ -4,9 +4,10 @@
  */
 
 use super::{
-    Action, BodyData, ConnectionId, Direction, Error, Event, NetworkTraffic, Request, Response,
-    Version,
+    Action, BodyData, ConnectionId, Direction, Error, Event, NetworkTraffic, RedactionRules,
+    Request, Response, Version,
 };
+use aws_smithy_async::time::{SharedTimeSource, TimeSource};
 use aws_smithy_runtime_api::client::connector_metadata::ConnectorMetadata;
 use aws_smithy_runtime_api::client::http::{
     HttpClient, HttpConnector, HttpConnectorFuture, HttpConnectorSettings, SharedHttpConnector,
@@ -16,9 +17,11 @@ use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
 use aws_smithy_runtime_api::shared::IntoShared;
 use aws_smithy_types::body::SdkBody;
 use http_body_04x::Body;
+use std::collections::HashMap;
 use std::path::Path;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::SystemTime;
 use std::{fs, io};
 use tokio::task::JoinHandle;
 
@@ -72,6 +75,45 @@ pub struct RecordingClient {
     pub(crate) data: Arc<Mutex<Vec<Event>>>,
     pub(crate) num_events: Arc<AtomicUsize>,
     pub(crate) inner: SharedHttpConnector,
+    pub(crate) redaction: Option<Arc<RedactionRules>>,
+    pub(crate) timing: Option<Timing>,
+}
+
+/// Tracks when the last event on each connection was recorded
+#[derive(Clone, Debug)]
+pub(crate) struct Timing {
+    time_source: SharedTimeSource,
+    last_event: Arc<Mutex<HashMap<ConnectionId, SystemTime>>>,
+}
+
+impl Timing {
+    fn delay_ms(&self, connection_id: ConnectionId) -> u64 {
+        let now = self.time_source.now();
+        let last = self
+            .last_event
+            .lock()
+            .unwrap()
+            .insert(connection_id, now)
+            .unwrap_or(now);
+        now.duration_since(last).unwrap_or_default().as_millis() as u64
+    }
+}
+
+#[derive(Clone, Debug)]
+struct EventBus {
+    events: Arc<Mutex<Vec<Event>>>,
+    timing: Option<Timing>,
+}
+
+impl EventBus {
+    fn push(&self, connection_id: ConnectionId, action: Action) {
+        let delay_ms = self.timing.as_ref().map(|t| t.delay_ms(connection_id));
+        self.events.lock().unwrap().push(Event {
+            connection_id,
+            action,
+            delay_ms,
+        });
+    }
 }
 
 #[cfg(feature = "tls-rustls")]
@@ -83,6 +125,8 @@ impl RecordingClient {
             data: Default::default(),
             num_events: Arc::new(AtomicUsize::new(0)),
             inner: SharedHttpConnector::new(HyperConnector::builder().build_https()),
+            redaction: None,
+            timing: None,
         }
     }
 }
@@ -94,6 +138,35 @@ impl RecordingClient {
             data: Default::default(),
             num_events: Arc::new(AtomicUsize::new(0)),
             inner: underlying_connector.into_shared(),
+            redaction: None,
+            timing: None,
+        }
+    }
+
+    /// Redact recorded traffic with the given rules when it's written out
+    ///
+    /// [`events`](Self::events) still returns the traffic as it was sent and received.
+    pub fn with_redaction(mut self, rules: RedactionRules) -> Self {
+        self.redaction = Some(Arc::new(rules));
+        self
+    }
+
+    /// Record the delay between events on each connection, measured with `time_source`
+    ///
+    /// A [`ReplayingClient`](super::ReplayingClient) can reproduce these delays with
+    /// [`with_timing`](super::ReplayingClient::with_timing).
+    pub fn with_timing(mut self, time_source: impl TimeSource + 'static) -> Self {
+        self.timing = Some(Timing {
+            time_source: SharedTimeSource::new(time_source),
+            last_event: Default::default(),
+        });
+        self
+    }
+
+    fn event_bus(&self) -> EventBus {
+        EventBus {
+            events: self.data.clone(),
+            timing: self.timing.clone(),
         }
     }
 
@@ -104,11 +177,15 @@ impl RecordingClient {
 
     /// NetworkTraffic struct suitable for serialization
     pub fn network_traffic(&self) -> NetworkTraffic {
-        NetworkTraffic {
+        let mut traffic = NetworkTraffic {
             events: self.events().clone(),
             docs: Some("todo docs".into()),
-            version: Version::V0,
+            version: Version::CURRENT,
+        };
+        if let Some(rules) = &self.redaction {
+            traffic.redact(rules);
         }
+        traffic
     }
 
     /// Dump the network traffic to a file
@@ -128,7 +205,7 @@ fn record_body(
     body: &mut SdkBody,
     event_id: ConnectionId,
     direction: Direction,
-    event_bus: Arc<Mutex<Vec<Event>>>,
+    event_bus: EventBus,
 ) -> JoinHandle<()> {
     let (sender, output_body) = hyper_0_14::Body::channel();
     let real_body = std::mem::replace(body, SdkBody::from_body_0_4(output_body));
@@ -139,45 +216,45 @@ fn record_body(
             let data = real_body.data().await;
             match data {
                 Some(Ok(data)) => {
-                    event_bus.lock().unwrap().push(Event {
-                        connection_id: event_id,
-                        action: Action::Data {
+                    event_bus.push(
+                        event_id,
+                        Action::Data {
                             data: BodyData::from(data.clone()),
                             direction,
                         },
-                    });
+                    );
                     // This happens if the real connection is closed during recording.
                     // Need to think more carefully if this is the correct thing to log in this
                     // case.
                     if sender.send_data(data).await.is_err() {
-                        event_bus.lock().unwrap().push(Event {
-                            connection_id: event_id,
-                            action: Action::Eof {
+                        event_bus.push(
+                            event_id,
+                            Action::Eof {
                                 direction: direction.opposite(),
                                 ok: false,
                             },
-                        })
+                        )
                     };
                 }
                 None => {
-                    event_bus.lock().unwrap().push(Event {
-                        connection_id: event_id,
-                        action: Action::Eof {
+                    event_bus.push(
+                        event_id,
+                        Action::Eof {
                             ok: true,
                             direction,
                         },
-                    });
+                    );
                     drop(sender);
                     break;
                 }
                 Some(Err(_err)) => {
-                    event_bus.lock().unwrap().push(Event {
-                        connection_id: event_id,
-                        action: Action::Eof {
+                    event_bus.push(
+                        event_id,
+                        Action::Eof {
                             ok: false,
                             direction,
                         },
-                    });
+                    );
                     sender.abort();
                     break;
                 }
@@ -196,12 +273,13 @@ impl HttpConnector for RecordingClient {
         // the channel should be closed.
 
         // Phase 1: the initial http request
-        self.data.lock().unwrap().push(Event {
-            connection_id: event_id,
-            action: Action::Request {
+        let events = self.event_bus();
+        events.push(
+            event_id,
+            Action::Request {
                 request: Request::from(&request),
             },
-        });
+        );
 
         // Phase 2: Swap out the real request body for one that will log all traffic that passes
         // through it
@@ -210,9 +288,8 @@ impl HttpConnector for RecordingClient {
             request.body_mut(),
             event_id,
             Direction::Request,
-            self.data.clone(),
+            events.clone(),
         );
-        let events = self.data.clone();
         // create a channel we'll use to stream the data while reading it
         let resp_fut = self.inner.call(request);
         let fut = async move {
@@ -220,24 +297,24 @@ impl HttpConnector for RecordingClient {
             match resp {
                 Ok(mut resp) => {
                     // push the initial response event
-                    events.lock().unwrap().push(Event {
-                        connection_id: event_id,
-                        action: Action::Response {
+                    events.push(
+                        event_id,
+                        Action::Response {
                             response: Ok(Response::from(&resp)),
                         },
-                    });
+                    );
 
                     // instrument the body and record traffic
                     record_body(resp.body_mut(), event_id, Direction::Response, events);
                     Ok(resp)
                 }
                 Err(e) => {
-                    events.lock().unwrap().push(Event {
-                        connection_id: event_id,
-                        action: Action::Response {
+                    events.push(
+                        event_id,
+                        Action::Response {
                             response: Err(Error(format!("{}", &e))),
                         },
-                    });
+                    );
                     Err(e)
                 }
             }
@@ -0,0 +1,609 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use super::{Action, BodyData, ConnectionId, Direction, NetworkTraffic};
+use bytes::Bytes;
+use indexmap::IndexMap;
+use regex_lite::Regex;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// The value that redacted data is replaced with
+pub const REDACTED: &str = "**REDACTED**";
+
+/// Headers that are redacted by default because they carry credentials
+const DEFAULT_REDACTED_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "x-amz-security-token",
+    "cookie",
+    "set-cookie",
+];
+
+/// Rules for removing secrets and PII from recorded traffic
+///
+/// Rules are applied to recordings made by a [`RecordingClient`](super::RecordingClient) when
+/// they're written out, and to existing recordings with [`NetworkTraffic::redact`]. A
+/// [`ReplayingClient`](super::ReplayingClient) given the same rules applies them to the requests
+/// it receives before validating them, so redacted recordings still validate.
+///
+/// By default, the `Authorization`, `Proxy-Authorization`, `X-Amz-Security-Token`, `Cookie`, and
+/// `Set-Cookie` headers are redacted.
+///
+/// # Example
+///
+/// ```rust
+/// use aws_smithy_runtime::client::http::test_util::dvr::RedactionRules;
+///
+/// let rules = RedactionRules::new()
+///     .redact_header("x-api-key")
+///     .mask_json_path("$.Credentials.SecretAccessKey")
+///     .mask_xml_path("/AssumeRoleResponse/AssumeRoleResult/Credentials/SessionToken")
+///     .replace_regex(r"\d{12}", "123456789012");
+/// ```
+#[derive(Clone, Debug)]
+pub struct RedactionRules {
+    redacted_headers: Vec<String>,
+    allowed_headers: Option<Vec<String>>,
+    json_paths: Vec<JsonPath>,
+    xml_paths: Vec<XmlPath>,
+    replacements: Vec<(Regex, String)>,
+}
+
+impl Default for RedactionRules {
+    fn default() -> Self {
+        Self {
+            redacted_headers: DEFAULT_REDACTED_HEADERS
+                .iter()
+                .map(|h| h.to_string())
+                .collect(),
+            allowed_headers: None,
+            json_paths: Vec::new(),
+            xml_paths: Vec::new(),
+            replacements: Vec::new(),
+        }
+    }
+}
+
+impl RedactionRules {
+    /// Creates rules that redact the default credential headers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Redact the value of the given header
+    pub fn redact_header(mut self, name: impl Into<String>) -> Self {
+        self.redacted_headers.push(name.into().to_ascii_lowercase());
+        self
+    }
+
+    /// Only record the values of the given headers, redacting all others
+    ///
+    /// Headers that are explicitly redacted stay redacted even if they're allowed.
+    pub fn allow_headers(mut self, names: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.allowed_headers
+            .get_or_insert_with(Vec::new)
+            .extend(names.into_iter().map(|n| n.into().to_ascii_lowercase()));
+        self
+    }
+
+    /// Mask the values at the given path in JSON bodies
+    ///
+    /// Paths are written as `$.key.other_key`. `[n]` selects an array element and `*` or `[*]`
+    /// selects every field of an object or element of an array.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `path` isn't a valid path.
+    pub fn mask_json_path(mut self, path: &str) -> Self {
+        self.json_paths.push(JsonPath::parse(path));
+        self
+    }
+
+    /// Mask the contents of the elements at the given path in XML bodies
+    ///
+    /// Paths are written as `/Root/Child`, matching element names without their namespace
+    /// prefix. A path starting with `//` matches elements at any depth, and `*` matches any
+    /// element name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `path` isn't a valid path.
+    pub fn mask_xml_path(mut self, path: &str) -> Self {
+        self.xml_paths.push(XmlPath::parse(path));
+        self
+    }
+
+    /// Replace everything matching `pattern` in URIs, header values, and bodies with `replacement`
+    ///
+    /// `replacement` may refer to capture groups with `$name` or `$1`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pattern` isn't a valid regular expression.
+    pub fn replace_regex(mut self, pattern: &str, replacement: impl Into<String>) -> Self {
+        let regex = Regex::new(pattern).expect("invalid regular expression");
+        self.replacements.push((regex, replacement.into()));
+        self
+    }
+
+    fn has_body_rules(&self) -> bool {
+        !self.json_paths.is_empty() || !self.xml_paths.is_empty() || !self.replacements.is_empty()
+    }
+
+    fn is_header_redacted(&self, name: &str) -> bool {
+        let name = name.to_ascii_lowercase();
+        self.redacted_headers.contains(&name)
+            || matches!(&self.allowed_headers, Some(allowed) if !allowed.contains(&name))
+    }
+
+    pub(super) fn redact_text(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for (regex, replacement) in &self.replacements {
+            text = regex.replace_all(&text, replacement.as_str()).into_owned();
+        }
+        text
+    }
+
+    pub(super) fn redact_header_value(&self, name: &str, value: &str) -> String {
+        if self.is_header_redacted(name) {
+            REDACTED.to_string()
+        } else {
+            self.redact_text(value)
+        }
+    }
+
+    pub(super) fn redact_headers(&self, headers: &mut IndexMap<String, Vec<String>>) {
+        for (name, values) in headers.iter_mut() {
+            for value in values {
+                *value = self.redact_header_value(name, value);
+            }
+        }
+    }
+
+    /// Redacts a body, returning `None` if nothing was redacted
+    pub(super) fn redact_body(&self, body: &[u8]) -> Option<Vec<u8>> {
+        if !self.has_body_rules() {
+            return None;
+        }
+        let text = std::str::from_utf8(body).ok()?;
+        let mut redacted = None;
+        if !self.json_paths.is_empty() {
+            if let Ok(mut json) = serde_json::from_str::<serde_json::Value>(text) {
+                let mut masked = false;
+                for path in &self.json_paths {
+                    masked |= path.mask(&mut json);
+                }
+                if masked {
+                    redacted = Some(json.to_string());
+                }
+            }
+        }
+        if redacted.is_none() && !self.xml_paths.is_empty() && text.trim_start().starts_with('<') {
+            redacted = mask_xml(text, &self.xml_paths);
+        }
+        let redacted = self.redact_text(redacted.as_deref().unwrap_or(text));
+        (redacted != text).then(|| redacted.into_bytes())
+    }
+}
+
+impl NetworkTraffic {
+    /// Redact secrets and PII from this traffic according to the given rules
+    ///
+    /// Redacted bodies are recorded as a single data event, and `content-length` headers are
+    /// corrected to match.
+    pub fn redact(&mut self, rules: &RedactionRules) {
+        for event in &mut self.events {
+            match &mut event.action {
+                Action::Request { request } => {
+                    request.uri = rules.redact_text(&request.uri);
+                    rules.redact_headers(&mut request.headers);
+                }
+                Action::Response {
+                    response: Ok(response),
+                } => rules.redact_headers(&mut response.headers),
+                _ => {}
+            }
+        }
+
+        if rules.has_body_rules() {
+            let mut bodies: HashMap<(ConnectionId, Direction), Vec<u8>> = HashMap::new();
+            for event in &self.events {
+                if let Action::Data { data, direction } = &event.action {
+                    bodies
+                        .entry((event.connection_id, *direction))
+                        .or_default()
+                        .extend(data.copy_to_vec());
+                }
+            }
+            let mut redacted: HashMap<_, _> = bodies
+                .into_iter()
+                .filter_map(|(key, body)| Some((key, rules.redact_body(&body)?)))
+                .collect();
+            let mut replaced = HashMap::new();
+            self.events.retain_mut(|event| {
+                let Action::Data { data, direction } = &mut event.action else {
+                    return true;
+                };
+                let key = (event.connection_id, *direction);
+                if let Some(body) = redacted.remove(&key) {
+                    *data = BodyData::from(Bytes::from(body));
+                    replaced.insert(key, ());
+                    true
+                } else {
+                    // The whole redacted body was put in the first data event
+                    !replaced.contains_key(&key)
+                }
+            });
+        }
+        self.correct_content_lengths();
+    }
+}
+
+impl RedactionRules {
+    /// Redacts a request the same way a recorded request would have been redacted
+    pub(super) fn redact_request(
+        &self,
+        mut request: http_02x::Request<Bytes>,
+    ) -> http_02x::Request<Bytes> {
+        if let Ok(uri) = self.redact_text(&request.uri().to_string()).parse() {
+            *request.uri_mut() = uri;
+        }
+        for (name, value) in request.headers_mut().iter_mut() {
+            let Ok(original) = value.to_str() else {
+                continue;
+            };
+            if let Ok(redacted) = self.redact_header_value(name.as_str(), original).parse() {
+                *value = redacted;
+            }
+        }
+        if let Some(body) = self.redact_body(request.body()) {
+            *request.body_mut() = Bytes::from(body);
+        }
+        request
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum JsonSegment {
+    Key(String),
+    Index(usize),
+    Wildcard,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct JsonPath(Vec<JsonSegment>);
+
+impl JsonPath {
+    fn parse(path: &str) -> Self {
+        let invalid = || panic!("invalid JSON path `{path}`");
+        let mut rest = path.strip_prefix('$').unwrap_or(path);
+        if !rest.is_empty() && !rest.starts_with(['.', '[']) {
+            // Allow the leading `$.` to be left out
+            return Self::parse(&format!("$.{path}"));
+        }
+        let mut segments = Vec::new();
+        while !rest.is_empty() {
+            if let Some(after_dot) = rest.strip_prefix('.') {
+                let end = after_dot.find(['.', '[']).unwrap_or(after_dot.len());
+                let key = &after_dot[..end];
+                if key.is_empty() {
+                    invalid();
+                }
+                segments.push(if key == "*" {
+                    JsonSegment::Wildcard
+                } else {
+                    JsonSegment::Key(key.to_string())
+                });
+                rest = &after_dot[end..];
+            } else if let Some(after_bracket) = rest.strip_prefix('[') {
+                let Some(end) = after_bracket.find(']') else {
+                    invalid();
+                    unreachable!()
+                };
+                let index = &after_bracket[..end];
+                segments.push(match index {
+                    "*" => JsonSegment::Wildcard,
+                    index => match index.parse() {
+                        Ok(index) => JsonSegment::Index(index),
+                        Err(_) => {
+                            let key = index.trim_matches(|c| c == '\'' || c == '"');
+                            JsonSegment::Key(key.to_string())
+                        }
+                    },
+                });
+                rest = &after_bracket[end + 1..];
+            } else {
+                invalid();
+            }
+        }
+        if segments.is_empty() {
+            invalid();
+        }
+        Self(segments)
+    }
+
+    fn mask(&self, value: &mut serde_json::Value) -> bool {
+        fn mask(value: &mut serde_json::Value, segments: &[JsonSegment]) -> bool {
+            let Some((segment, rest)) = segments.split_first() else {
+                *value = serde_json::Value::String(REDACTED.to_string());
+                return true;
+            };
+            match (segment, value) {
+                (JsonSegment::Key(key), serde_json::Value::Object(object)) => {
+                    object.get_mut(key).map_or(false, |v| mask(v, rest))
+                }
+                (JsonSegment::Index(index), serde_json::Value::Array(array)) => {
+                    array.get_mut(*index).map_or(false, |v| mask(v, rest))
+                }
+                (JsonSegment::Wildcard, serde_json::Value::Object(object)) => object
+                    .values_mut()
+                    .fold(false, |masked, v| mask(v, rest) | masked),
+                (JsonSegment::Wildcard, serde_json::Value::Array(array)) => array
+                    .iter_mut()
+                    .fold(false, |masked, v| mask(v, rest) | masked),
+                _ => false,
+            }
+        }
+        mask(value, &self.0)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct XmlPath {
+    segments: Vec<String>,
+    any_depth: bool,
+}
+
+impl XmlPath {
+    fn parse(path: &str) -> Self {
+        let (rest, any_depth) = match path.strip_prefix("//") {
+            Some(rest) => (rest, true),
+            None => (
+                path.strip_prefix('/')
+                    .unwrap_or_else(|| panic!("invalid XML path `{path}`")),
+                false,
+            ),
+        };
+        let segments: Vec<_> = rest.split('/').map(str::to_string).collect();
+        if segments.iter().any(String::is_empty) {
+            panic!("invalid XML path `{path}`");
+        }
+        Self {
+            segments,
+            any_depth,
+        }
+    }
+
+    fn matches(&self, stack: &[(String, Option<usize>)]) -> bool {
+        if stack.len() < self.segments.len()
+            || (!self.any_depth && stack.len() != self.segments.len())
+        {
+            return false;
+        }
+        stack[stack.len() - self.segments.len()..]
+            .iter()
+            .zip(&self.segments)
+            .all(|((name, _), segment)| segment == "*" || name == segment)
+    }
+}
+
+/// Masks the contents of matching elements, returning `None` if nothing matched or the XML
+/// couldn't be scanned
+fn mask_xml(xml: &str, paths: &[XmlPath]) -> Option<String> {
+    // Element names, and where their content starts if they match a path
+    let mut stack: Vec<(String, Option<usize>)> = Vec::new();
+    let mut masked: Vec<Range<usize>> = Vec::new();
+    let mut position = 0;
+    while let Some(offset) = xml[position..].find('<') {
+        let start = position + offset;
+        let rest = &xml[start..];
+        let skip_to = |terminator: &str| Some(start + rest.find(terminator)? + terminator.len());
+        if rest.starts_with("<?") {
+            position = skip_to("?>")?;
+        } else if rest.starts_with("<!--") {
+            position = skip_to("-->")?;
+        } else if rest.starts_with("<![CDATA[") {
+            position = skip_to("]]>")?;
+        } else if rest.starts_with("<!") {
+            position = skip_to(">")?;
+        } else {
+            let end = start + rest.find('>')?;
+            let tag = &xml[start + 1..end];
+            if tag.starts_with('/') {
+                let (_, content_start) = stack.pop()?;
+                if let Some(content_start) = content_start {
+                    // Masking an element masks everything inside it
+                    masked.retain(|range| range.start < content_start);
+                    masked.push(content_start..start);
+                }
+            } else if !tag.ends_with('/') {
+                let name = tag.split(char::is_whitespace).next()?;
+                let local_name = name.rsplit(':').next()?;
+                stack.push((local_name.to_string(), None));
+                if paths.iter().any(|path| path.matches(&stack)) {
+                    stack.last_mut()?.1 = Some(end + 1);
+                }
+            }
+            position = end + 1;
+        }
+    }
+    if masked.is_empty() || !stack.is_empty() {
+        return None;
+    }
+    let mut out = String::with_capacity(xml.len());
+    let mut last = 0;
+    for range in masked {
+        out.push_str(&xml[last..range.start]);
+        out.push_str(REDACTED);
+        last = range.end;
+    }
+    out.push_str(&xml[last..]);
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JsonPath, JsonSegment, RedactionRules, REDACTED};
+    use crate::client::http::test_util::dvr::{
+        Action, NetworkTraffic, RecordingClient, ReplayingClient, Request, Response,
+    };
+    use aws_smithy_runtime_api::client::http::HttpConnector;
+    use aws_smithy_types::body::SdkBody;
+    use aws_smithy_types::byte_stream::ByteStream;
+    use std::fs;
+
+    async fn send(client: &impl HttpConnector, authorization: &str) {
+        let request = http_02x::Request::post("https://www.example.com")
+            .header("authorization", authorization)
+            .body(SdkBody::from("hello world"))
+            .unwrap();
+        let mut response = client.call(request.try_into().unwrap()).await.unwrap();
+        let body = std::mem::replace(response.body_mut(), SdkBody::taken());
+        ByteStream::new(body).collect().await.unwrap();
+    }
+
+    fn redact_body(rules: &RedactionRules, body: &str) -> String {
+        String::from_utf8(rules.redact_body(body.as_bytes()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn parse_json_paths() {
+        assert_eq!(
+            JsonPath(vec![
+                JsonSegment::Key("a".into()),
+                JsonSegment::Wildcard,
+                JsonSegment::Index(2),
+                JsonSegment::Key("b c".into()),
+            ]),
+            JsonPath::parse("$.a[*][2]['b c']")
+        );
+        assert_eq!(JsonPath::parse("$.a.b"), JsonPath::parse("a.b"));
+    }
+
+    #[test]
+    fn headers_are_redacted_by_deny_and_allow_lists() {
+        let rules = RedactionRules::new().redact_header("X-Api-Key");
+        assert_eq!(REDACTED, rules.redact_header_value("Authorization", "AWS4"));
+        assert_eq!(REDACTED, rules.redact_header_value("x-api-key", "secret"));
+        assert_eq!("json", rules.redact_header_value("content-type", "json"));
+
+        let rules = RedactionRules::new().allow_headers(["content-type", "authorization"]);
+        assert_eq!("json", rules.redact_header_value("content-type", "json"));
+        assert_eq!(
+            REDACTED,
+            rules.redact_header_value("x-amz-date", "20240101")
+        );
+        assert_eq!(REDACTED, rules.redact_header_value("authorization", "AWS4"));
+    }
+
+    #[test]
+    fn json_paths_are_masked() {
+        let rules = RedactionRules::new()
+            .mask_json_path("$.Credentials.SecretAccessKey")
+            .mask_json_path("$.Users[*].Email");
+        let body = r#"{"Credentials":{"AccessKeyId":"AKID","SecretAccessKey":"secret"},"Users":[{"Email":"a@example.com"},{"Email":"b@example.com"}]}"#;
+        let redacted: serde_json::Value = serde_json::from_str(&redact_body(&rules, body)).unwrap();
+        assert_eq!(
+            serde_json::json!({
+                "Credentials": {"AccessKeyId": "AKID", "SecretAccessKey": REDACTED},
+                "Users": [{"Email": REDACTED}, {"Email": REDACTED}],
+            }),
+            redacted
+        );
+        assert_eq!(None, rules.redact_body(br#"{"Other": 1}"#));
+    }
+
+    #[test]
+    fn xml_paths_are_masked() {
+        let rules = RedactionRules::new()
+            .mask_xml_path("/AssumeRoleResponse/AssumeRoleResult/Credentials/SessionToken")
+            .mask_xml_path("//Email");
+        let body = r#"<?xml version="1.0"?>
+<AssumeRoleResponse xmlns="https://sts.amazonaws.com/doc/2011-06-15/">
+  <AssumeRoleResult>
+    <Credentials><AccessKeyId>AKID</AccessKeyId><SessionToken>token</SessionToken></Credentials>
+    <User><Email>a@example.com</Email><Email/></User>
+  </AssumeRoleResult>
+</AssumeRoleResponse>"#;
+        let redacted = redact_body(&rules, body);
+        assert!(redacted.contains("<AccessKeyId>AKID</AccessKeyId>"));
+        assert!(redacted.contains(&format!("<SessionToken>{REDACTED}</SessionToken>")));
+        assert!(redacted.contains(&format!("<Email>{REDACTED}</Email><Email/>")));
+    }
+
+    #[test]
+    fn regex_replacements_apply_to_bodies() {
+        let rules = RedactionRules::new().replace_regex(r"\b\d{12}\b", "123456789012");
+        assert_eq!(
+            "arn:aws:iam::123456789012:role/test",
+            redact_body(&rules, "arn:aws:iam::999988887777:role/test")
+        );
+    }
+
+    #[test]
+    fn network_traffic_is_redacted() {
+        let mut traffic: NetworkTraffic =
+            serde_json::from_str(&fs::read_to_string("test-data/example.com.json").unwrap())
+                .unwrap();
+        let rules = RedactionRules::new()
+            .redact_header("server")
+            .replace_regex("example.com", "example.org");
+        traffic.redact(&rules);
+
+        let Action::Request {
+            request: Request { uri, .. },
+        } = &traffic.events()[0].action
+        else {
+            panic!("unexpected event")
+        };
+        assert_eq!("https://www.example.org/", uri);
+        let Action::Response {
+            response: Ok(Response { headers, .. }),
+        } = &traffic.events()[3].action
+        else {
+            panic!("unexpected event")
+        };
+        assert_eq!(&vec![REDACTED.to_string()], &headers["server"]);
+        assert_eq!(
+            &vec!["hello from example.org".len().to_string()],
+            &headers["content-length"]
+        );
+
+        let response_body: Vec<u8> = traffic
+            .events()
+            .iter()
+            .filter_map(|event| match &event.action {
+                Action::Data { data, .. } => Some(data.copy_to_vec()),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        assert!(String::from_utf8(response_body)
+            .unwrap()
+            .contains("hello from example.org"));
+    }
+
+    #[tokio::test]
+    async fn redacted_recordings_can_be_replayed() {
+        let traffic: NetworkTraffic =
+            serde_json::from_str(&fs::read_to_string("test-data/example.com.json").unwrap())
+                .unwrap();
+        let rules = RedactionRules::new().replace_regex("world", "planet");
+        let recording = RecordingClient::new(ReplayingClient::new(traffic.events().clone()))
+            .with_redaction(rules.clone());
+        send(&recording, "secret").await;
+
+        let redacted = recording.network_traffic();
+        let serialized = serde_json::to_string(&redacted).unwrap();
+        assert!(!serialized.contains("secret"));
+        assert!(!serialized.contains("hello world"));
+        assert!(serialized.contains("hello planet"));
+
+        let replay = ReplayingClient::new(redacted.events().clone()).with_redaction(rules);
+        send(&replay, "another secret").await;
+        replay.full_validate("text/plain").await.unwrap();
+    }
+}
@@ -3,8 +3,12 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
-use super::{Action, ConnectionId, Direction, Event, NetworkTraffic};
+use super::{
+    Action, ConnectionId, Direction, Event, MatchRequest, NetworkTraffic, RedactionRules, REDACTED,
+};
 use crate::client::http::test_util::replay::DEFAULT_RELAXED_HEADERS;
+use aws_smithy_async::rt::sleep::{AsyncSleep, SharedAsyncSleep};
+use aws_smithy_async::time::{SharedTimeSource, TimeSource};
 use aws_smithy_protocol_test::MediaType;
 use aws_smithy_runtime_api::client::connector_metadata::ConnectorMetadata;
 use aws_smithy_runtime_api::client::http::{
@@ -24,6 +28,7 @@ use std::ops::DerefMut;
 use std::path::Path;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 use tokio::task::JoinHandle;
 
 /// Wrapper type to enable optionally waiting for a future to complete
@@ -51,13 +56,105 @@ impl<T> Waitable<T> {
     }
 }
 
+type RecordedRequests = Arc<Mutex<HashMap<ConnectionId, Waitable<http_02x::Request<Bytes>>>>>;
+
 /// Replay traffic recorded by a [`RecordingClient`](super::RecordingClient)
+///
+/// By default, requests are answered in the order the connections were recorded. Use
+/// [`with_matcher`](ReplayingClient::with_matcher) to answer requests sent concurrently or in a
+/// different order.
 #[derive(Clone)]
 pub struct ReplayingClient {
     live_events: Arc<Mutex<HashMap<ConnectionId, VecDeque<Event>>>>,
     verifiable_events: Arc<HashMap<ConnectionId, http_02x::Request<Bytes>>>,
     num_events: Arc<AtomicUsize>,
-    recorded_requests: Arc<Mutex<HashMap<ConnectionId, Waitable<http_02x::Request<Bytes>>>>>,
+    recorded_requests: RecordedRequests,
+    redaction: Option<Arc<RedactionRules>>,
+    matcher: Option<Arc<dyn MatchRequest>>,
+    timing: bool,
+    sleep_impl: Option<SharedAsyncSleep>,
+    time_source: Option<SharedTimeSource>,
+    faults: Arc<HashMap<ConnectionId, Fault>>,
+}
+
+/// A fault to inject into a replayed connection
+///
+/// See [`ReplayingClient::with_fault`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Fault {
+    /// Fail with a connection reset instead of returning the recorded response
+    ConnectionReset,
+    /// End the response body with an error after at most `after` bytes
+    TruncatedBody {
+        /// Number of response body bytes to send before failing
+        after: usize,
+    },
+    /// Stop sending the response body after at most `after` bytes, without ending it
+    StalledBody {
+        /// Number of response body bytes to send before stalling
+        after: usize,
+    },
+}
+
+impl Fault {
+    /// Returns how many bytes of a chunk of `len` bytes to send before the body fault takes
+    /// effect, or `None` if the whole chunk should be sent
+    fn cut_off(&self, sent: usize, len: usize) -> Option<usize> {
+        match self {
+            Fault::TruncatedBody { after } | Fault::StalledBody { after }
+                if sent + len > *after =>
+            {
+                Some(after.saturating_sub(sent))
+            }
+            _ => None,
+        }
+    }
+
+    async fn end_body(&self, sender: hyper_0_14::body::Sender) {
+        match self {
+            Fault::TruncatedBody { .. } => sender.abort(),
+            Fault::StalledBody { .. } => {
+                let _sender = sender;
+                std::future::pending::<()>().await
+            }
+            Fault::ConnectionReset => unreachable!("not a body fault"),
+        }
+    }
+}
+
+/// Replays recorded delays relative to the start of a connection
+struct Pacer {
+    sleep_impl: SharedAsyncSleep,
+    start: Option<(SharedTimeSource, SystemTime)>,
+    offset: Duration,
+    slept: Duration,
+}
+
+impl Pacer {
+    async fn wait(&mut self, event: &Event) {
+        let Some(delay) = event.delay() else {
+            return;
+        };
+        self.offset += delay;
+        let elapsed = match &self.start {
+            Some((time_source, start)) => {
+                time_source.now().duration_since(*start).unwrap_or_default()
+            }
+            None => self.slept,
+        };
+        let remaining = self.offset.saturating_sub(elapsed);
+        if !remaining.is_zero() {
+            self.sleep_impl.sleep(remaining).await;
+            self.slept += remaining;
+        }
+    }
+}
+
+async fn pace(pacer: &mut Option<Pacer>, event: &Event) {
+    if let Some(pacer) = pacer {
+        pacer.wait(event).await;
+    }
 }
 
 // Ideally, this would just derive Debug, but that makes the tests in aws-config think they found AWS secrets
@@ -79,6 +176,146 @@ impl ReplayingClient {
         ConnectionId(self.num_events.fetch_add(1, Ordering::Relaxed))
     }
 
+    /// Apply the rules a recording was redacted with to requests before validating them
+    ///
+    /// Headers whose recorded value is [`REDACTED`] only need to be present, even without rules.
+    pub fn with_redaction(mut self, rules: RedactionRules) -> Self {
+        self.redaction = Some(Arc::new(rules));
+        self
+    }
+
+    /// Replay the delays between events that were recorded with
+    /// [`RecordingClient::with_timing`](super::RecordingClient::with_timing)
+    ///
+    /// Delays are waited out with the sleep implementation and time source from the client's
+    /// runtime components, so controlled time from `aws_smithy_async::test_util` can be used to
+    /// keep tests fast and deterministic. When this client is used directly as an
+    /// [`HttpConnector`], they can be set with [`with_time`](Self::with_time). Without a sleep
+    /// implementation, delays aren't replayed.
+    pub fn with_timing(mut self) -> Self {
+        self.timing = true;
+        self
+    }
+
+    /// Use the given sleep implementation and time source to replay delays
+    ///
+    /// These are overridden by the client's runtime components when this is used as an
+    /// [`HttpClient`].
+    pub fn with_time(
+        mut self,
+        sleep_impl: impl AsyncSleep + 'static,
+        time_source: impl TimeSource + 'static,
+    ) -> Self {
+        self.sleep_impl = Some(SharedAsyncSleep::new(sleep_impl));
+        self.time_source = Some(SharedTimeSource::new(time_source));
+        self
+    }
+
+    /// Inject `fault` into the connection at `index`
+    ///
+    /// Connections are numbered in the order they were recorded, starting from zero. Faults
+    /// take effect once the recording reaches the affected part of the traffic, after any
+    /// replayed delays.
+    pub fn with_fault(mut self, index: usize, fault: Fault) -> Self {
+        Arc::make_mut(&mut self.faults).insert(ConnectionId(index), fault);
+        self
+    }
+
+    fn playback(&self, event_id: ConnectionId) -> Playback {
+        let pacer = match (&self.sleep_impl, self.timing) {
+            (Some(sleep_impl), true) => Some(Pacer {
+                sleep_impl: sleep_impl.clone(),
+                start: self.time_source.clone().map(|ts| {
+                    let now = ts.now();
+                    (ts, now)
+                }),
+                offset: Duration::ZERO,
+                slept: Duration::ZERO,
+            }),
+            _ => None,
+        };
+        Playback {
+            event_id,
+            pacer,
+            fault: self.faults.get(&event_id).cloned(),
+        }
+    }
+
+    /// Answer each request with the first unused recorded connection that `matcher` accepts
+    ///
+    /// The request body is read in full before a connection is chosen. If no connection matches,
+    /// the request fails with an error describing the closest candidate.
+    pub fn with_matcher(mut self, matcher: impl MatchRequest + 'static) -> Self {
+        self.matcher = Some(Arc::new(matcher));
+        self
+    }
+
+    fn call_in_order(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let event_id = self.next_id();
+        tracing::debug!("received event {}: {request:?}", event_id.0);
+        let events = match self.live_events.lock().unwrap().remove(&event_id) {
+            Some(traffic) => traffic,
+            None => {
+                return HttpConnectorFuture::ready(Err(ConnectorError::other(
+                    format!("no data for event {}. request: {:?}", event_id.0, request).into(),
+                    None,
+                )));
+            }
+        };
+        let recorded_request = Waitable::Loading(read_request(request));
+        HttpConnectorFuture::new(replay_response(
+            self.playback(event_id),
+            events,
+            recorded_request,
+            self.recorded_requests.clone(),
+        ))
+    }
+
+    fn take_matching_events(
+        &self,
+        matcher: &dyn MatchRequest,
+        actual: &http_02x::Request<Bytes>,
+    ) -> Result<(ConnectionId, VecDeque<Event>), ConnectorError> {
+        let actual = match &self.redaction {
+            Some(rules) => rules.redact_request(copy_request(actual)),
+            None => copy_request(actual),
+        };
+        let mut live_events = self.live_events.lock().unwrap();
+        let mut candidates: Vec<_> = live_events.keys().copied().collect();
+        candidates.sort_unstable_by_key(|id| id.0);
+        let mut closest: Option<(ConnectionId, Vec<String>)> = None;
+        for id in candidates {
+            let expected = &self.verifiable_events[&id];
+            match matcher.match_request(expected, &actual) {
+                Ok(()) => return Ok((id, live_events.remove(&id).expect("candidate exists"))),
+                Err(reasons) => {
+                    tracing::debug!("event {} did not match: {reasons:?}", id.0);
+                    if closest
+                        .as_ref()
+                        .map_or(true, |(_, closest)| reasons.len() < closest.len())
+                    {
+                        closest = Some((id, reasons));
+                    }
+                }
+            }
+        }
+        let mut message = format!(
+            "no unused recorded request matched {} {}",
+            actual.method(),
+            actual.uri()
+        );
+        match closest {
+            Some((id, reasons)) => {
+                message.push_str(&format!(". closest candidate was event {}:", id.0));
+                for reason in reasons {
+                    message.push_str(&format!("\n  - {reason}"));
+                }
+            }
+            None => message.push_str(". all recorded events have been used"),
+        }
+        Err(ConnectorError::other(message.into(), None))
+    }
+
     /// Validate all headers and bodies
     pub async fn full_validate(self, media_type: &str) -> Result<(), Box<dyn Error>> {
         self.validate_body_and_headers(None, media_type).await
@@ -166,6 +403,10 @@ impl ReplayingClient {
                 ))?
                 .take()
                 .await;
+            let actual = match &self.redaction {
+                Some(rules) => rules.redact_request(actual),
+                None => actual,
+            };
             body_comparer(expected.body().as_ref(), actual.body().as_ref())?;
             let actual: HttpRequest = actual.map(SdkBody::from).try_into()?;
             aws_smithy_protocol_test::assert_uris_match(&expected.uri().to_string(), actual.uri());
@@ -182,6 +423,17 @@ impl ReplayingClient {
                 })
                 .flat_map(|key| {
                     let _ = expected.headers().get(key)?;
+                    if expected
+                        .headers()
+                        .get_all(key)
+                        .iter()
+                        .any(|h| h == REDACTED)
+                    {
+                        if !actual.headers().contains_key(key) {
+                            return Some((key, "<missing redacted header>".to_string()));
+                        }
+                        return None;
+                    }
                     Some((
                         key,
                         expected
@@ -210,11 +462,13 @@ impl ReplayingClient {
     pub async fn take_requests(self) -> Vec<http_02x::Request<Bytes>> {
         let mut recorded_requests =
             std::mem::take(self.recorded_requests.lock().unwrap().deref_mut());
-        let mut out = Vec::with_capacity(recorded_requests.len());
-        for conn_id in 0..recorded_requests.len() {
+        let mut conn_ids: Vec<_> = recorded_requests.keys().copied().collect();
+        conn_ids.sort_unstable_by_key(|id| id.0);
+        let mut out = Vec::with_capacity(conn_ids.len());
+        for conn_id in conn_ids {
             out.push(
                 recorded_requests
-                    .remove(&ConnectionId(conn_id))
+                    .remove(&conn_id)
                     .expect("should exist")
                     .take()
                     .await,
@@ -225,9 +479,7 @@ impl ReplayingClient {
 
     /// Build a replay connection from a JSON file
     pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
-        let events: NetworkTraffic =
-            serde_json::from_str(&std::fs::read_to_string(path.as_ref())?)?;
-        Ok(Self::new(events.events))
+        Ok(Self::new(NetworkTraffic::from_file(path)?.events))
     }
 
     /// Build a replay connection from a sequence of events
@@ -267,12 +519,31 @@ impl ReplayingClient {
             num_events: Arc::new(AtomicUsize::new(0)),
             recorded_requests: Default::default(),
             verifiable_events,
+            redaction: None,
+            matcher: None,
+            timing: false,
+            sleep_impl: None,
+            time_source: None,
+            faults: Default::default(),
         }
     }
 }
 
-async fn replay_body(events: VecDeque<Event>, mut sender: hyper_0_14::body::Sender) {
+struct Playback {
+    event_id: ConnectionId,
+    pacer: Option<Pacer>,
+    fault: Option<Fault>,
+}
+
+async fn replay_body(
+    events: VecDeque<Event>,
+    mut sender: hyper_0_14::body::Sender,
+    mut pacer: Option<Pacer>,
+    fault: Option<Fault>,
+) {
+    let mut sent = 0;
     for event in events {
+        pace(&mut pacer, &event).await;
         match event.action {
             Action::Request { .. } => panic!(),
             Action::Response { .. } => panic!(),
@@ -280,10 +551,24 @@ async fn replay_body(events: VecDeque<Event>, mut sender: hyper_0_14::body::Send
                 data,
                 direction: Direction::Response,
             } => {
-                sender
-                    .send_data(Bytes::from(data.into_bytes()))
-                    .await
-                    .expect("this is in memory traffic that should not fail to send");
+                let mut data = Bytes::from(data.into_bytes());
+                let body_fault = fault
+                    .as_ref()
+                    .and_then(|f| Some((f, f.cut_off(sent, data.len())?)));
+                if let Some((_, cut_off)) = body_fault {
+                    data.truncate(cut_off);
+                }
+                sent += data.len();
+                if !data.is_empty() {
+                    sender
+                        .send_data(data)
+                        .await
+                        .expect("this is in memory traffic that should not fail to send");
+                }
+                if let Some((fault, _)) = body_fault {
+                    fault.end_body(sender).await;
+                    break;
+                }
             }
             Action::Data {
                 data: _data,
@@ -298,7 +583,13 @@ async fn replay_body(events: VecDeque<Event>, mut sender: hyper_0_14::body::Send
                 ok: true,
                 ..
             } => {
-                drop(sender);
+                // The body was shorter than the fault's cut off, so the fault happens at the end
+                match &fault {
+                    Some(fault @ (Fault::TruncatedBody { .. } | Fault::StalledBody { .. })) => {
+                        fault.end_body(sender).await
+                    }
+                    _ => drop(sender),
+                }
                 break;
             }
             Action::Eof {
@@ -313,96 +604,127 @@ async fn replay_body(events: VecDeque<Event>, mut sender: hyper_0_14::body::Send
     }
 }
 
-impl HttpConnector for ReplayingClient {
-    fn call(&self, mut request: HttpRequest) -> HttpConnectorFuture {
-        use http_body_04x::Body;
-
-        let event_id = self.next_id();
-        tracing::debug!("received event {}: {request:?}", event_id.0);
-        let mut events = match self.live_events.lock().unwrap().remove(&event_id) {
-            Some(traffic) => traffic,
-            None => {
-                return HttpConnectorFuture::ready(Err(ConnectorError::other(
-                    format!("no data for event {}. request: {:?}", event_id.0, request).into(),
-                    None,
-                )));
+async fn replay_response(
+    playback: Playback,
+    mut events: VecDeque<Event>,
+    mut recorded_request: Waitable<http_02x::Request<Bytes>>,
+    recording: RecordedRequests,
+) -> Result<HttpResponse, ConnectorError> {
+    let Playback {
+        event_id,
+        mut pacer,
+        fault,
+    } = playback;
+    let _initial_request = events.pop_front().unwrap();
+    let (sender, response_body) = hyper_0_14::Body::channel();
+    let body = SdkBody::from_body_0_4(response_body);
+    let resp: Result<_, ConnectorError> = loop {
+        let event = events
+            .pop_front()
+            .expect("no events, needed a response event");
+        pace(&mut pacer, &event).await;
+        match event.action {
+            // to ensure deterministic behavior if the request EOF happens first in the log,
+            // wait for the request body to be done before returning a response.
+            Action::Eof {
+                direction: Direction::Request,
+                ..
+            } => {
+                recorded_request.wait().await;
             }
-        };
-
-        let _initial_request = events.pop_front().unwrap();
-        let (sender, response_body) = hyper_0_14::Body::channel();
-        let body = SdkBody::from_body_0_4(response_body);
-        let recording = self.recorded_requests.clone();
-        let recorded_request = tokio::spawn(async move {
-            let mut data_read = vec![];
-            while let Some(data) = request.body_mut().data().await {
-                data_read
-                    .extend_from_slice(data.expect("in memory request should not fail").as_ref())
+            Action::Request { .. } => panic!("invalid"),
+            Action::Response { .. } if fault == Some(Fault::ConnectionReset) => {
+                break Err(ConnectorError::io(
+                    std::io::Error::from(std::io::ErrorKind::ConnectionReset).into(),
+                ))
             }
-            request
-                .try_into_http02x()
-                .unwrap()
-                .map(|_body| Bytes::from(data_read))
-        });
-        let mut recorded_request = Waitable::Loading(recorded_request);
-        let fut = async move {
-            let resp: Result<_, ConnectorError> = loop {
-                let event = events
-                    .pop_front()
-                    .expect("no events, needed a response event");
-                match event.action {
-                    // to ensure deterministic behavior if the request EOF happens first in the log,
-                    // wait for the request body to be done before returning a response.
-                    Action::Eof {
-                        direction: Direction::Request,
-                        ..
-                    } => {
-                        recorded_request.wait().await;
-                    }
-                    Action::Request { .. } => panic!("invalid"),
-                    Action::Response {
-                        response: Err(error),
-                    } => break Err(ConnectorError::other(error.0.into(), None)),
-                    Action::Response {
-                        response: Ok(response),
-                    } => {
-                        let mut builder = http_02x::Response::builder().status(response.status);
-                        for (name, values) in response.headers {
-                            for value in values {
-                                builder = builder.header(&name, &value);
-                            }
-                        }
-                        tokio::spawn(async move {
-                            replay_body(events, sender).await;
-                            // insert the finalized body into
-                        });
-                        break Ok(HttpResponse::try_from(
-                            builder.body(body).expect("valid builder"),
-                        )
-                        .unwrap());
-                    }
-
-                    Action::Data {
-                        direction: Direction::Request,
-                        data: _data,
-                    } => {
-                        tracing::info!("get request data");
+            Action::Response {
+                response: Err(error),
+            } => break Err(ConnectorError::other(error.0.into(), None)),
+            Action::Response {
+                response: Ok(response),
+            } => {
+                let mut builder = http_02x::Response::builder().status(response.status);
+                for (name, values) in response.headers {
+                    for value in values {
+                        builder = builder.header(&name, &value);
                     }
-                    Action::Eof {
-                        direction: Direction::Response,
-                        ..
-                    } => panic!("got eof before response"),
-
-                    Action::Data {
-                        data: _,
-                        direction: Direction::Response,
-                    } => panic!("got response data before response"),
                 }
-            };
-            recording.lock().unwrap().insert(event_id, recorded_request);
-            resp
+                tokio::spawn(async move {
+                    replay_body(events, sender, pacer, fault).await;
+                    // insert the finalized body into
+                });
+                break Ok(
+                    HttpResponse::try_from(builder.body(body).expect("valid builder")).unwrap(),
+                );
+            }
+
+            Action::Data {
+                direction: Direction::Request,
+                data: _data,
+            } => {
+                tracing::info!("get request data");
+            }
+            Action::Eof {
+                direction: Direction::Response,
+                ..
+            } => panic!("got eof before response"),
+
+            Action::Data {
+                data: _,
+                direction: Direction::Response,
+            } => panic!("got response data before response"),
+        }
+    };
+    recording.lock().unwrap().insert(event_id, recorded_request);
+    resp
+}
+
+fn read_request(mut request: HttpRequest) -> JoinHandle<http_02x::Request<Bytes>> {
+    use http_body_04x::Body;
+
+    tokio::spawn(async move {
+        let mut data_read = vec![];
+        while let Some(data) = request.body_mut().data().await {
+            data_read.extend_from_slice(data.expect("in memory request should not fail").as_ref())
+        }
+        request
+            .try_into_http02x()
+            .unwrap()
+            .map(|_body| Bytes::from(data_read))
+    })
+}
+
+fn copy_request(request: &http_02x::Request<Bytes>) -> http_02x::Request<Bytes> {
+    let mut copy = http_02x::Request::new(request.body().clone());
+    *copy.method_mut() = request.method().clone();
+    *copy.uri_mut() = request.uri().clone();
+    *copy.version_mut() = request.version();
+    *copy.headers_mut() = request.headers().clone();
+    copy
+}
+
+impl HttpConnector for ReplayingClient {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let matcher = match &self.matcher {
+            Some(matcher) => matcher.clone(),
+            None => return self.call_in_order(request),
         };
-        HttpConnectorFuture::new(fut)
+        tracing::debug!("received request: {request:?}");
+        let this = self.clone();
+        let recorded_request = read_request(request);
+        HttpConnectorFuture::new(async move {
+            let actual = recorded_request.await.expect("join failed");
+            let (event_id, events) = this.take_matching_events(matcher.as_ref(), &actual)?;
+            tracing::debug!("matched event {}", event_id.0);
+            replay_response(
+                this.playback(event_id),
+                events,
+                Waitable::Value(actual),
+                this.recorded_requests.clone(),
+            )
+            .await
+        })
     }
 }
 
@@ -410,12 +732,127 @@ impl HttpClient for ReplayingClient {
     fn http_connector(
         &self,
         _: &HttpConnectorSettings,
-        _: &RuntimeComponents,
+        components: &RuntimeComponents,
     ) -> SharedHttpConnector {
-        self.clone().into_shared()
+        let mut client = self.clone();
+        if let Some(sleep_impl) = components.sleep_impl() {
+            client.sleep_impl = Some(sleep_impl);
+        }
+        if let Some(time_source) = components.time_source() {
+            client.time_source = Some(time_source);
+        }
+        client.into_shared()
     }
 
     fn connector_metadata(&self) -> Option<ConnectorMetadata> {
         Some(ConnectorMetadata::new("replaying-client", None))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Fault, ReplayingClient};
+    use crate::client::http::test_util::dvr::{Event, RecordingClient};
+    use crate::client::http::test_util::infallible_client_fn;
+    use aws_smithy_async::test_util::{instant_time_and_sleep, ManualTimeSource};
+    use aws_smithy_runtime_api::client::http::{HttpClient, HttpConnector, HttpConnectorSettings};
+    use aws_smithy_runtime_api::client::orchestrator::HttpResponse;
+    use aws_smithy_runtime_api::client::result::ConnectorError;
+    use aws_smithy_runtime_api::client::runtime_components::RuntimeComponentsBuilder;
+    use aws_smithy_types::body::SdkBody;
+    use aws_smithy_types::byte_stream::ByteStream;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    /// Records one request to a server that takes two seconds to respond
+    async fn record(time_source: ManualTimeSource) -> Vec<Event> {
+        let server_time = time_source.clone();
+        let server = infallible_client_fn(move |_req| {
+            server_time.advance(Duration::from_secs(2));
+            http_02x::Response::builder()
+                .status(200)
+                .body("hello world")
+                .unwrap()
+        })
+        .http_connector(
+            &HttpConnectorSettings::default(),
+            &RuntimeComponentsBuilder::for_tests().build().unwrap(),
+        );
+        let recording = RecordingClient::new(server).with_timing(time_source);
+        let response = send(&recording).await.unwrap();
+        ByteStream::new(response.into_body())
+            .collect()
+            .await
+            .unwrap();
+        let events = recording.events().clone();
+        events
+    }
+
+    async fn send(connector: &impl HttpConnector) -> Result<HttpResponse, ConnectorError> {
+        let request = http_02x::Request::get("https://example.com")
+            .body(SdkBody::empty())
+            .unwrap();
+        connector.call(request.try_into().unwrap()).await
+    }
+
+    #[tokio::test]
+    async fn replays_recorded_delays() {
+        let events = record(ManualTimeSource::new(UNIX_EPOCH)).await;
+        assert!(events.iter().all(|e| e.delay().is_some()));
+
+        let (time_source, sleep) = instant_time_and_sleep(UNIX_EPOCH);
+        let components = RuntimeComponentsBuilder::for_tests()
+            .with_sleep_impl(Some(sleep.clone()))
+            .with_time_source(Some(time_source))
+            .build()
+            .unwrap();
+        let replay = ReplayingClient::new(events)
+            .with_timing()
+            .http_connector(&HttpConnectorSettings::default(), &components);
+        let response = send(&replay).await.unwrap();
+        ByteStream::new(response.into_body())
+            .collect()
+            .await
+            .unwrap();
+        assert_eq!(vec![Duration::from_secs(2)], sleep.logs());
+    }
+
+    #[tokio::test]
+    async fn delays_are_not_replayed_by_default() {
+        let events = record(ManualTimeSource::new(UNIX_EPOCH)).await;
+        let (time_source, sleep) = instant_time_and_sleep(UNIX_EPOCH);
+        let replay = ReplayingClient::new(events).with_time(sleep.clone(), time_source);
+        send(&replay).await.unwrap();
+        assert!(sleep.logs().is_empty());
+    }
+
+    #[tokio::test]
+    async fn injects_connection_resets() {
+        let events = record(ManualTimeSource::new(UNIX_EPOCH)).await;
+        let replay = ReplayingClient::new(events).with_fault(0, Fault::ConnectionReset);
+        let err = send(&replay).await.unwrap_err();
+        assert!(err.is_io(), "{err:?}");
+        assert_eq!(1, replay.take_requests().await.len());
+    }
+
+    #[tokio::test]
+    async fn injects_truncated_bodies() {
+        use http_body_04x::Body;
+
+        let events = record(ManualTimeSource::new(UNIX_EPOCH)).await;
+        let replay = ReplayingClient::new(events).with_fault(0, Fault::TruncatedBody { after: 5 });
+        let mut body = send(&replay).await.unwrap().into_body();
+        assert_eq!(b"hello", body.data().await.unwrap().unwrap().as_ref());
+        assert!(body.data().await.unwrap().is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn injects_stalled_bodies() {
+        let events = record(ManualTimeSource::new(UNIX_EPOCH)).await;
+        let replay = ReplayingClient::new(events).with_fault(0, Fault::StalledBody { after: 5 });
+        let body = send(&replay).await.unwrap().into_body();
+        let collect = ByteStream::new(body).collect();
+        assert!(tokio::time::timeout(Duration::from_secs(60), collect)
+            .await
+            .is_err());
+    }
+}
@@ -36,14 +36,44 @@
 //! match_events!(ev!(dns), ev!(connect), ev!(http(200)))(&mock.events());
 //! # }
 //! ```
+//!
+//! For HTTP-level tests, [`WireMockServer::builder`] starts a server that routes requests by method,
+//! path, and headers. It can also serve HTTPS with a self-signed certificate and HTTP/2:
+//! ```no_run
+//! use aws_smithy_runtime::client::http::test_util::wire::{Route, RouteResponse, WireMockServer};
+//! use std::time::Duration;
+//! # async fn example() {
+//!
+//! let mock = WireMockServer::builder()
+//!     .route(Route::get("/greeting").respond_with(RouteResponse::ok().body("hello")))
+//!     .route(Route::post("/upload").respond_with(
+//!         RouteResponse::status(200)
+//!             .header("content-type", "text/plain")
+//!             .streaming_body(["part 1", "part 2"], Duration::from_millis(100)),
+//!     ))
+//!     .tls()
+//!     .http2_only()
+//!     .start()
+//!     .await;
+//!
+//! // ... send requests with `mock.http_client()` to `mock.endpoint_url()`
+//!
+//! for request in mock.requests() {
+//!     request.assert_headers(&[("content-type", "application/json")]);
+//!     request.assert_body(r#"{"hello": "world"}"#, "application/json");
+//! }
+//! # }
+//! ```
 
 #![allow(missing_docs)]
 
+mod routes;
+pub use routes::{ReceivedRequest, Route, RouteResponse, WireMockServerBuilder};
+
 use crate::client::http::hyper_014::HyperClientBuilder;
 use aws_smithy_async::future::never::Never;
 use aws_smithy_async::future::BoxFuture;
 use aws_smithy_runtime_api::client::http::SharedHttpClient;
-use aws_smithy_runtime_api::shared::IntoShared;
 use bytes::Bytes;
 use hyper_0_14::client::connect::dns::Name;
 use hyper_0_14::server::conn::AddrStream;
@@ -181,6 +211,9 @@ impl ReplayedEvent {
     }
 }
 
+/// Host name used by [`WireMockServer::endpoint_url`]. [`LoggingDnsResolver`] resolves it to the server.
+const MOCK_HOST: &str = "this-url-is-converted-to-localhost.com";
+
 /// Test server that binds to 127.0.0.1:0
 ///
 /// See the [module docs](crate::client::http::test_util::wire) for a usage example.
@@ -190,10 +223,17 @@ impl ReplayedEvent {
 /// - Use [`WireMockServer::http_client`] or [`dns_resolver`](WireMockServer::dns_resolver) to configure your client.
 /// - Make requests to [`endpoint_url`](WireMockServer::endpoint_url).
 /// - Once the test is complete, retrieve a list of events from [`WireMockServer::events`]
+///
+/// Servers started with [`WireMockServer::builder`] also record every request they receive, which
+/// can be retrieved with [`WireMockServer::requests`].
 #[derive(Debug)]
 pub struct WireMockServer {
     event_log: Arc<Mutex<Vec<RecordedEvent>>>,
+    requests: Arc<Mutex<Vec<ReceivedRequest>>>,
     bind_addr: SocketAddr,
+    // DER encoded self-signed certificate, when serving HTTPS
+    certificate: Option<Vec<u8>>,
+    http2_only: bool,
     // when the sender is dropped, that stops the server
     shutdown_hook: oneshot::Sender<()>,
 }
@@ -254,16 +294,39 @@ impl WireMockServer {
         spawn(server);
         Self {
             event_log: wire_events,
+            requests: Default::default(),
             bind_addr: listener_addr,
+            certificate: None,
+            http2_only: false,
             shutdown_hook: tx,
         }
     }
 
+    /// Returns a builder for a server that routes requests by method, path, and headers.
+    pub fn builder() -> WireMockServerBuilder {
+        WireMockServerBuilder::new()
+    }
+
     /// Retrieve the events recorded by this connection
     pub fn events(&self) -> Vec<RecordedEvent> {
         self.event_log.lock().unwrap().clone()
     }
 
+    /// Retrieve the requests received by this server, in the order they were received
+    ///
+    /// Only servers started with [`WireMockServer::builder`] record requests.
+    pub fn requests(&self) -> Vec<ReceivedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// DER encoded self-signed certificate that the server uses for HTTPS
+    ///
+    /// This is `None` unless the server was started with [`WireMockServerBuilder::tls`]. It can be
+    /// added to the root certificates of a client that isn't created with [`Self::http_client`].
+    pub fn certificate_der(&self) -> Option<&[u8]> {
+        self.certificate.as_deref()
+    }
+
     fn bind_addr(&self) -> SocketAddr {
         self.bind_addr
     }
@@ -279,23 +342,50 @@ impl WireMockServer {
 
     /// Prebuilt [`HttpClient`](aws_smithy_runtime_api::client::http::HttpClient) with correctly wired DNS resolver.
     ///
+    /// When the server uses TLS, the client trusts the server's self-signed certificate. When the
+    /// server only accepts HTTP/2, so does the client.
+    ///
     /// **Note**: This must be used in tandem with [`Self::dns_resolver`]
     pub fn http_client(&self) -> SharedHttpClient {
-        HyperClientBuilder::new()
-            .build(hyper_0_14::client::HttpConnector::new_with_resolver(
-                self.dns_resolver(),
-            ))
-            .into_shared()
+        let mut hyper_builder = hyper_0_14::Client::builder();
+        hyper_builder.http2_only(self.http2_only);
+        let builder = HyperClientBuilder::new().hyper_builder(hyper_builder);
+        let mut http_connector =
+            hyper_0_14::client::HttpConnector::new_with_resolver(self.dns_resolver());
+        match &self.certificate {
+            Some(certificate) => {
+                http_connector.enforce_http(false);
+                let mut roots = rustls::RootCertStore::empty();
+                roots
+                    .add(&rustls::Certificate(certificate.clone()))
+                    .expect("valid certificate");
+                let tls_config = rustls::ClientConfig::builder()
+                    .with_safe_defaults()
+                    .with_root_certificates(roots)
+                    .with_no_client_auth();
+                builder.build(
+                    hyper_rustls::HttpsConnectorBuilder::new()
+                        .with_tls_config(tls_config)
+                        .https_only()
+                        .enable_http1()
+                        .enable_http2()
+                        .wrap_connector(http_connector),
+                )
+            }
+            None => builder.build(http_connector),
+        }
     }
 
     /// Endpoint to use when connecting
     ///
     /// This works in tandem with the [`Self::dns_resolver`] to bind to the correct local IP Address
     pub fn endpoint_url(&self) -> String {
-        format!(
-            "http://this-url-is-converted-to-localhost.com:{}",
-            self.bind_addr().port()
-        )
+        let scheme = if self.certificate.is_some() {
+            "https"
+        } else {
+            "http"
+        };
+        format!("{scheme}://{MOCK_HOST}:{}", self.bind_addr().port())
     }
 
     /// Shuts down the mock server.
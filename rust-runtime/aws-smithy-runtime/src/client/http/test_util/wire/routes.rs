@@ -0,0 +1,410 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use super::{RecordedEvent, ReplayedEvent, WireMockServer, MOCK_HOST};
+use aws_smithy_protocol_test::{assert_ok, validate_body, validate_headers, MediaType};
+use bytes::Bytes;
+use hyper_0_14::server::conn::Http;
+use hyper_0_14::service::service_fn;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::spawn;
+use tokio::sync::oneshot;
+use tokio_rustls::TlsAcceptor;
+
+/// Builder for a [`WireMockServer`] that responds to requests according to a list of [`Route`]s
+///
+/// Requests are handled by the first route that matches them. Requests that match no route get a
+/// `404 Not Found` response.
+#[derive(Debug, Default)]
+pub struct WireMockServerBuilder {
+    routes: Vec<Route>,
+    tls: bool,
+    http2_only: bool,
+}
+
+impl WireMockServerBuilder {
+    /// Creates a builder with no routes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a route to the server.
+    pub fn route(mut self, route: Route) -> Self {
+        self.routes.push(route);
+        self
+    }
+
+    /// Serve HTTPS with a self-signed certificate instead of HTTP.
+    ///
+    /// [`WireMockServer::http_client`] trusts the certificate.
+    pub fn tls(mut self) -> Self {
+        self.tls = true;
+        self
+    }
+
+    /// Only accept HTTP/2 connections.
+    ///
+    /// Without TLS, clients must use HTTP/2 with prior knowledge.
+    pub fn http2_only(mut self) -> Self {
+        self.http2_only = true;
+        self
+    }
+
+    /// Start the server.
+    pub async fn start(self) -> WireMockServer {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let bind_addr = listener.local_addr().unwrap();
+        let (shutdown_hook, shutdown) = oneshot::channel();
+
+        let (certificate, acceptor) = if self.tls {
+            let (certificate, acceptor) = self_signed_acceptor(self.http2_only);
+            (Some(certificate), Some(acceptor))
+        } else {
+            (None, None)
+        };
+
+        let state = Arc::new(ServerState {
+            routes: self.routes,
+            event_log: Default::default(),
+            requests: Default::default(),
+        });
+        let server = WireMockServer {
+            event_log: state.event_log.clone(),
+            requests: state.requests.clone(),
+            bind_addr,
+            certificate,
+            http2_only: self.http2_only,
+            shutdown_hook,
+        };
+
+        let mut http = Http::new();
+        http.http2_only(self.http2_only);
+        let accept_loop = spawn(async move {
+            loop {
+                let stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    Err(err) => {
+                        tracing::warn!(err = %err, "failed to accept connection");
+                        continue;
+                    }
+                };
+                tracing::info!("established connection: {:?}", stream);
+                state
+                    .event_log
+                    .lock()
+                    .unwrap()
+                    .push(RecordedEvent::NewConnection);
+                let state = state.clone();
+                let service = service_fn(move |request| state.clone().handle(request));
+                let http = http.clone();
+                let acceptor = acceptor.clone();
+                spawn(async move {
+                    let result = match acceptor {
+                        Some(acceptor) => match acceptor.accept(stream).await {
+                            Ok(stream) => http.serve_connection(stream, service).await,
+                            Err(err) => {
+                                tracing::warn!(err = %err, "TLS handshake failed");
+                                return;
+                            }
+                        },
+                        None => http.serve_connection(stream, service).await,
+                    };
+                    if let Err(err) = result {
+                        tracing::debug!(err = %err, "connection closed with an error");
+                    }
+                });
+            }
+        });
+        spawn(async move {
+            shutdown.await.ok();
+            accept_loop.abort();
+            tracing::info!("server shutdown!");
+        });
+        server
+    }
+}
+
+fn self_signed_acceptor(http2_only: bool) -> (Vec<u8>, TlsAcceptor) {
+    let certificate =
+        rcgen::generate_simple_self_signed(vec![MOCK_HOST.to_string(), "localhost".to_string()])
+            .expect("valid certificate parameters");
+    let certificate_der = certificate.serialize_der().expect("valid certificate");
+    let mut config = rustls::ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![rustls::Certificate(certificate_der.clone())],
+            rustls::PrivateKey(certificate.serialize_private_key_der()),
+        )
+        .expect("valid certificate and key");
+    config.alpn_protocols = if http2_only {
+        vec![b"h2".to_vec()]
+    } else {
+        vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+    };
+    (certificate_der, TlsAcceptor::from(Arc::new(config)))
+}
+
+#[derive(Debug)]
+struct ServerState {
+    routes: Vec<Route>,
+    event_log: Arc<Mutex<Vec<RecordedEvent>>>,
+    requests: Arc<Mutex<Vec<ReceivedRequest>>>,
+}
+
+impl ServerState {
+    async fn handle(
+        self: Arc<Self>,
+        request: http_02x::Request<hyper_0_14::Body>,
+    ) -> Result<http_02x::Response<hyper_0_14::Body>, hyper_0_14::Error> {
+        let (parts, body) = request.into_parts();
+        let request = http_02x::Request::from_parts(parts, hyper_0_14::body::to_bytes(body).await?);
+        let route = self.routes.iter().position(|route| route.matches(&request));
+        let response = match route {
+            Some(index) => self.routes[index].response.clone(),
+            None => {
+                tracing::warn!("no route matched {} {}", request.method(), request.uri());
+                RouteResponse::status(404).body("no route matched the request")
+            }
+        };
+        self.requests.lock().unwrap().push(ReceivedRequest {
+            request: Arc::new(request),
+            route,
+        });
+        self.event_log
+            .lock()
+            .unwrap()
+            .push(RecordedEvent::Response(ReplayedEvent::HttpResponse {
+                status: response.status,
+                body: response.chunks.concat().into(),
+            }));
+        Ok(response.into_hyper().await)
+    }
+}
+
+/// A rule for [`WireMockServerBuilder`] that matches requests and describes how to respond to them
+///
+/// By default, a route matches every request and responds with an empty `200 OK` response.
+#[derive(Clone, Debug, Default)]
+pub struct Route {
+    method: Option<http_02x::Method>,
+    path: Option<String>,
+    headers: Vec<(String, String)>,
+    response: RouteResponse,
+}
+
+impl Route {
+    /// Creates a route that matches every request.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a route that matches `GET` requests for the given path.
+    pub fn get(path: impl Into<String>) -> Self {
+        Self::new().method("GET").path(path)
+    }
+
+    /// Creates a route that matches `PUT` requests for the given path.
+    pub fn put(path: impl Into<String>) -> Self {
+        Self::new().method("PUT").path(path)
+    }
+
+    /// Creates a route that matches `POST` requests for the given path.
+    pub fn post(path: impl Into<String>) -> Self {
+        Self::new().method("POST").path(path)
+    }
+
+    /// Only match requests with the given method.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `method` isn't a valid HTTP method.
+    pub fn method(mut self, method: &str) -> Self {
+        self.method = Some(method.parse().expect("valid HTTP method"));
+        self
+    }
+
+    /// Only match requests for the given path. The query string isn't part of the path.
+    pub fn path(mut self, path: impl Into<String>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Only match requests that have a header with the given name and value.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Respond to matching requests with `response`.
+    pub fn respond_with(mut self, response: RouteResponse) -> Self {
+        self.response = response;
+        self
+    }
+
+    fn matches(&self, request: &http_02x::Request<Bytes>) -> bool {
+        self.method
+            .as_ref()
+            .map_or(true, |method| method == request.method())
+            && self
+                .path
+                .as_ref()
+                .map_or(true, |path| path == request.uri().path())
+            && self.headers.iter().all(|(name, value)| {
+                request
+                    .headers()
+                    .get_all(name.as_str())
+                    .iter()
+                    .any(|actual| actual.as_bytes() == value.as_bytes())
+            })
+    }
+}
+
+/// The response that a [`Route`] sends
+#[derive(Clone, Debug)]
+pub struct RouteResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    chunks: Vec<Bytes>,
+    chunk_delay: Duration,
+    delay: Duration,
+}
+
+impl Default for RouteResponse {
+    fn default() -> Self {
+        Self::ok()
+    }
+}
+
+impl RouteResponse {
+    /// An empty response with the given status.
+    pub fn status(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            chunks: Vec::new(),
+            chunk_delay: Duration::ZERO,
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// An empty `200 OK` response.
+    pub fn ok() -> Self {
+        Self::status(200)
+    }
+
+    /// Add a header to the response.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Set the body of the response.
+    pub fn body(mut self, body: impl Into<Bytes>) -> Self {
+        self.chunks = vec![body.into()];
+        self.chunk_delay = Duration::ZERO;
+        self
+    }
+
+    /// Stream the body of the response in `chunks`, waiting `delay` before sending each chunk.
+    pub fn streaming_body(
+        mut self,
+        chunks: impl IntoIterator<Item = impl Into<Bytes>>,
+        delay: Duration,
+    ) -> Self {
+        self.chunks = chunks.into_iter().map(Into::into).collect();
+        self.chunk_delay = delay;
+        self
+    }
+
+    /// Wait for `delay` before sending the response.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    async fn into_hyper(self) -> http_02x::Response<hyper_0_14::Body> {
+        if !self.delay.is_zero() {
+            tokio::time::sleep(self.delay).await;
+        }
+        let body = if self.chunk_delay.is_zero() {
+            hyper_0_14::Body::from(Bytes::from(self.chunks.concat()))
+        } else {
+            let (mut sender, body) = hyper_0_14::Body::channel();
+            let (chunks, delay) = (self.chunks, self.chunk_delay);
+            spawn(async move {
+                for chunk in chunks {
+                    tokio::time::sleep(delay).await;
+                    if sender.send_data(chunk).await.is_err() {
+                        tracing::debug!("client stopped reading the streaming body");
+                        return;
+                    }
+                }
+            });
+            body
+        };
+        let mut builder = http_02x::Response::builder().status(self.status);
+        for (name, value) in &self.headers {
+            builder = builder.header(name, value);
+        }
+        builder.body(body).expect("valid response")
+    }
+}
+
+/// A request received by a [`WireMockServer`]
+#[derive(Clone, Debug)]
+pub struct ReceivedRequest {
+    request: Arc<http_02x::Request<Bytes>>,
+    route: Option<usize>,
+}
+
+impl ReceivedRequest {
+    /// The request, including its full body
+    pub fn request(&self) -> &http_02x::Request<Bytes> {
+        &self.request
+    }
+
+    /// The body of the request
+    pub fn body(&self) -> &Bytes {
+        self.request.body()
+    }
+
+    /// The index of the route that handled this request, or `None` if no route matched it
+    pub fn route(&self) -> Option<usize> {
+        self.route
+    }
+
+    /// Asserts that the request has the given headers.
+    ///
+    /// # Panics
+    ///
+    /// Panics with a description of the missing or mismatched headers.
+    #[track_caller]
+    pub fn assert_headers(&self, expected: &[(&str, &str)]) {
+        assert_ok(validate_headers(
+            self.request.headers(),
+            expected.iter().copied(),
+        ));
+    }
+
+    /// Asserts that the request body is equivalent to `expected`, comparing them as `media_type`.
+    ///
+    /// JSON, XML, CBOR, and URL encoded form bodies are compared semantically. See
+    /// [`aws_smithy_protocol_test::validate_body`].
+    ///
+    /// # Panics
+    ///
+    /// Panics with a diff if the bodies aren't equivalent.
+    #[track_caller]
+    pub fn assert_body(&self, expected: &str, media_type: &str) {
+        assert_ok(validate_body(
+            self.body(),
+            expected,
+            MediaType::from(media_type),
+        ));
+    }
+}
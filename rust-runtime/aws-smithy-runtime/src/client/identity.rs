@@ -4,7 +4,16 @@
  */
 
 mod cache;
-pub use cache::{IdentityCache, LazyCacheBuilder};
+#[cfg(feature = "http-auth")]
+pub use cache::TokenCodec;
+#[cfg(feature = "rt-tokio")]
+pub use cache::{EagerCacheBuilder, InvalidEagerCacheConfig, RefreshTask};
+pub use cache::{
+    IdentityCache, IdentityCipher, IdentityCodec, LazyCacheBuilder, PersistentCacheBuilder,
+};
 
 /// Identity resolver implementation for "no auth".
 pub mod no_auth;
+
+#[cfg(feature = "http-auth")]
+pub mod oauth2;
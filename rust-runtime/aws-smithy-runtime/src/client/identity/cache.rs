@@ -10,9 +10,17 @@ use aws_smithy_runtime_api::client::identity::{
 use aws_smithy_runtime_api::shared::IntoShared;
 use aws_smithy_types::config_bag::ConfigBag;
 
+#[cfg(feature = "rt-tokio")]
+mod eager;
 mod lazy;
+mod persistent;
 use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
+#[cfg(feature = "rt-tokio")]
+pub use eager::{EagerCacheBuilder, InvalidEagerCacheConfig, RefreshTask};
 pub use lazy::LazyCacheBuilder;
+#[cfg(feature = "http-auth")]
+pub use persistent::TokenCodec;
+pub use persistent::{IdentityCipher, IdentityCodec, PersistentCacheBuilder};
 
 /// Identity cache configuration.
 ///
@@ -59,6 +67,31 @@ pub use lazy::LazyCacheBuilder;
 /// let client = some_service::Client::new(config);
 /// # */
 /// ```
+///
+/// Bounding the memory used by lazy caching when there are many identity resolvers:
+/// ```no_run
+/// use aws_smithy_runtime::client::identity::IdentityCache;
+/// use std::time::Duration;
+///
+/// # /*
+/// let config = some_service::Config::builder()
+///     .identity_cache(
+/// # */
+/// # drop(
+///         IdentityCache::lazy()
+///             // keep at most 1000 identities
+///             .max_partitions(1000)
+///             // evict identities that haven't been used for an hour
+///             .partition_idle_timeout(Duration::from_secs(60 * 60))
+///             .build()
+/// # );
+/// # /*
+///     )
+///     // ...
+///     .build();
+/// let client = some_service::Client::new(config);
+/// # */
+/// ```
 #[non_exhaustive]
 pub struct IdentityCache;
 
@@ -74,6 +107,49 @@ impl IdentityCache {
     pub fn lazy() -> LazyCacheBuilder {
         LazyCacheBuilder::new()
     }
+
+    /// Configure an eager identity cache.
+    ///
+    /// Identities are loaded and then cached when a request is made, and are refreshed in the
+    /// background before they expire.
+    ///
+    /// # Examples
+    ///
+    /// Refreshing identities 10 minutes before they expire:
+    /// ```no_run
+    /// use aws_smithy_runtime::client::identity::IdentityCache;
+    /// use std::time::Duration;
+    ///
+    /// # /*
+    /// let config = some_service::Config::builder()
+    ///     .identity_cache(
+    /// # */
+    /// # drop(
+    ///         IdentityCache::eager()
+    ///             // refresh identities 10 minutes before they expire
+    ///             .refresh_time(Duration::from_secs(10 * 60))
+    ///             .build()
+    ///             .expect("valid config")
+    /// # );
+    /// # /*
+    ///     )
+    ///     // ...
+    ///     .build();
+    /// let client = some_service::Client::new(config);
+    /// # */
+    /// ```
+    #[cfg(feature = "rt-tokio")]
+    pub fn eager() -> EagerCacheBuilder {
+        EagerCacheBuilder::new()
+    }
+
+    /// Configure a persistent identity cache.
+    ///
+    /// Identities are loaded when a request is made, and are cached in memory and in a file so
+    /// that other processes can reuse them until they expire.
+    pub fn persistent() -> PersistentCacheBuilder {
+        PersistentCacheBuilder::new()
+    }
 }
 
 #[derive(Clone, Debug)]
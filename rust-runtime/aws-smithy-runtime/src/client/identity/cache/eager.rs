@@ -0,0 +1,795 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use super::lazy::{CachePartitions, TimedOutError};
+use super::IdentityCache;
+use crate::expiring_cache::ExpiringCache;
+use aws_smithy_async::future::timeout::Timeout;
+use aws_smithy_async::rt::sleep::{AsyncSleep, SharedAsyncSleep};
+use aws_smithy_async::time::SharedTimeSource;
+use aws_smithy_runtime_api::box_error::BoxError;
+use aws_smithy_runtime_api::client::identity::{
+    Identity, IdentityCachePartition, IdentityFuture, ResolveCachedIdentity, ResolveIdentity,
+    SharedIdentityCache, SharedIdentityResolver,
+};
+use aws_smithy_runtime_api::client::runtime_components::{
+    RuntimeComponents, RuntimeComponentsBuilder,
+};
+use aws_smithy_runtime_api::shared::IntoShared;
+use aws_smithy_types::config_bag::ConfigBag;
+use aws_smithy_types::DateTime;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, SystemTime};
+use tokio::sync::oneshot;
+use tracing::Instrument;
+
+const DEFAULT_LOAD_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_EXPIRATION: Duration = Duration::from_secs(15 * 60);
+const DEFAULT_BUFFER_TIME: Duration = Duration::from_secs(10);
+const DEFAULT_REFRESH_TIME: Duration = Duration::from_secs(5 * 60);
+const DEFAULT_ERROR_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_ERROR_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Builder for eager identity caching.
+///
+/// An eager identity cache loads an identity when the first request needs it, and then refreshes
+/// it in the background before it expires, so that requests don't wait for identity resolution.
+/// If a background refresh fails, the cached identity keeps being used until it expires, and the
+/// refresh is retried with exponential backoff.
+///
+/// Background refreshes are spawned with the [`spawner`](EagerCacheBuilder::spawner), or on the
+/// Tokio runtime that the cache is built in, and sleep with the client's [`AsyncSleep`]
+/// implementation. The identity resolver is given an empty [`ConfigBag`] when
+/// refreshing in the background, so resolvers that read request configuration from the config
+/// bag should use [lazy caching](super::IdentityCache::lazy) instead.
+#[derive(Default, Debug)]
+pub struct EagerCacheBuilder {
+    spawner: Option<Spawner>,
+    load_timeout: Option<Duration>,
+    buffer_time: Option<Duration>,
+    refresh_time: Option<Duration>,
+    error_backoff: Option<Duration>,
+    default_expiration: Option<Duration>,
+}
+
+impl EagerCacheBuilder {
+    /// Create a new builder.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Function that spawns the background refresh tasks.
+    ///
+    /// The function is called once for every cache partition that is refreshed, and must run the
+    /// task to completion, for example with `tokio::spawn`. Tasks end on their own when their
+    /// partition is invalidated or the cache is dropped.
+    ///
+    /// Defaults to spawning tasks on the Tokio runtime that [`build`](EagerCacheBuilder::build)
+    /// is called in.
+    pub fn spawner(mut self, spawn: impl Fn(RefreshTask) + Send + Sync + 'static) -> Self {
+        self.spawner = Some(Spawner(Arc::new(spawn)));
+        self
+    }
+
+    /// Timeout for identity resolution.
+    ///
+    /// Defaults to 5 seconds.
+    pub fn load_timeout(mut self, timeout: Duration) -> Self {
+        self.set_load_timeout(Some(timeout));
+        self
+    }
+
+    /// Timeout for identity resolution.
+    ///
+    /// Defaults to 5 seconds.
+    pub fn set_load_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.load_timeout = timeout;
+        self
+    }
+
+    /// Amount of time before the actual identity expiration time where the identity is considered expired.
+    ///
+    /// Requests that find an expired identity in the cache wait for a new identity to be loaded.
+    /// This only happens when background refreshes have failed until the identity expired.
+    ///
+    /// Defaults to 10 seconds.
+    pub fn buffer_time(mut self, buffer_time: Duration) -> Self {
+        self.set_buffer_time(Some(buffer_time));
+        self
+    }
+
+    /// Amount of time before the actual identity expiration time where the identity is considered expired.
+    ///
+    /// Requests that find an expired identity in the cache wait for a new identity to be loaded.
+    /// This only happens when background refreshes have failed until the identity expired.
+    ///
+    /// Defaults to 10 seconds.
+    pub fn set_buffer_time(&mut self, buffer_time: Option<Duration>) -> &mut Self {
+        self.buffer_time = buffer_time;
+        self
+    }
+
+    /// Amount of time before the actual identity expiration time where the identity is refreshed in the background.
+    ///
+    /// For example, if the identity is expiring in 15 minutes, and the refresh time is 5 minutes,
+    /// then a new identity is loaded in the background after 10 minutes. This must be longer than
+    /// the buffer time.
+    ///
+    /// Defaults to 5 minutes.
+    pub fn refresh_time(mut self, refresh_time: Duration) -> Self {
+        self.set_refresh_time(Some(refresh_time));
+        self
+    }
+
+    /// Amount of time before the actual identity expiration time where the identity is refreshed in the background.
+    ///
+    /// For example, if the identity is expiring in 15 minutes, and the refresh time is 5 minutes,
+    /// then a new identity is loaded in the background after 10 minutes. This must be longer than
+    /// the buffer time.
+    ///
+    /// Defaults to 5 minutes.
+    pub fn set_refresh_time(&mut self, refresh_time: Option<Duration>) -> &mut Self {
+        self.refresh_time = refresh_time;
+        self
+    }
+
+    /// Amount of time to wait before retrying a failed background refresh.
+    ///
+    /// The delay doubles after each consecutive failure, up to one minute.
+    ///
+    /// Defaults to 1 second.
+    pub fn error_backoff(mut self, error_backoff: Duration) -> Self {
+        self.set_error_backoff(Some(error_backoff));
+        self
+    }
+
+    /// Amount of time to wait before retrying a failed background refresh.
+    ///
+    /// The delay doubles after each consecutive failure, up to one minute.
+    ///
+    /// Defaults to 1 second.
+    pub fn set_error_backoff(&mut self, error_backoff: Option<Duration>) -> &mut Self {
+        self.error_backoff = error_backoff;
+        self
+    }
+
+    /// Default expiration time to set on an identity if it doesn't have an expiration time.
+    ///
+    /// This is only used if the resolved identity doesn't have an expiration time set.
+    /// This must be at least 15 minutes.
+    ///
+    /// Defaults to 15 minutes.
+    pub fn default_expiration(mut self, duration: Duration) -> Self {
+        self.set_default_expiration(Some(duration));
+        self
+    }
+
+    /// Default expiration time to set on an identity if it doesn't have an expiration time.
+    ///
+    /// This is only used if the resolved identity doesn't have an expiration time set.
+    /// This must be at least 15 minutes.
+    ///
+    /// Defaults to 15 minutes.
+    pub fn set_default_expiration(&mut self, duration: Option<Duration>) -> &mut Self {
+        self.default_expiration = duration;
+        self
+    }
+
+    /// Builds a [`SharedIdentityCache`] from this builder.
+    ///
+    /// Returns an error if given values are not valid, or if no spawner was given and this isn't
+    /// called within a Tokio runtime.
+    pub fn build(self) -> Result<SharedIdentityCache, InvalidEagerCacheConfig> {
+        let default_expiration = self.default_expiration.unwrap_or(DEFAULT_EXPIRATION);
+        if default_expiration < DEFAULT_EXPIRATION {
+            return Err(InvalidEagerCacheConfig::new(
+                "default_expiration must be at least 15 minutes",
+            ));
+        }
+        let buffer_time = self.buffer_time.unwrap_or(DEFAULT_BUFFER_TIME);
+        let refresh_time = self.refresh_time.unwrap_or(DEFAULT_REFRESH_TIME);
+        if refresh_time <= buffer_time {
+            return Err(InvalidEagerCacheConfig::new(
+                "refresh_time must be longer than buffer_time",
+            ));
+        }
+        let spawner = match self.spawner {
+            Some(spawner) => spawner,
+            None => {
+                let runtime = tokio::runtime::Handle::try_current().map_err(|_| {
+                    InvalidEagerCacheConfig::new(
+                        "background refreshes need a spawner; set one with `spawner`, \
+                        or build the cache within a Tokio runtime",
+                    )
+                })?;
+                Spawner(Arc::new(move |task| {
+                    runtime.spawn(task);
+                }))
+            }
+        };
+        Ok(EagerCache::new(
+            Settings {
+                load_timeout: self.load_timeout.unwrap_or(DEFAULT_LOAD_TIMEOUT),
+                buffer_time,
+                refresh_time,
+                error_backoff: self.error_backoff.unwrap_or(DEFAULT_ERROR_BACKOFF),
+                default_expiration,
+            },
+            spawner,
+        )
+        .into_shared())
+    }
+}
+
+/// Failure to build an eager identity cache because a parameter was out of range.
+#[derive(Debug)]
+pub struct InvalidEagerCacheConfig {
+    message: String,
+}
+
+impl InvalidEagerCacheConfig {
+    fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for InvalidEagerCacheConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid eager identity cache config: {}", self.message)
+    }
+}
+
+impl std::error::Error for InvalidEagerCacheConfig {}
+
+/// A background refresh task, passed to the [spawner](EagerCacheBuilder::spawner) to run.
+pub type RefreshTask = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+#[derive(Clone)]
+struct Spawner(Arc<dyn Fn(RefreshTask) + Send + Sync>);
+
+impl fmt::Debug for Spawner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Spawner")
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Settings {
+    load_timeout: Duration,
+    buffer_time: Duration,
+    refresh_time: Duration,
+    error_backoff: Duration,
+    default_expiration: Duration,
+}
+
+#[derive(Debug)]
+struct EagerCache {
+    partitions: CachePartitions,
+    /// Dropping a partition's sender stops its refresh task
+    refresh_tasks: Mutex<HashMap<IdentityCachePartition, oneshot::Sender<()>>>,
+    spawner: Spawner,
+    settings: Settings,
+    /// Refresh tasks only hold a weak reference to this, so that they can tell when the cache is gone
+    alive: Arc<()>,
+}
+
+impl EagerCache {
+    fn new(settings: Settings, spawner: Spawner) -> Self {
+        Self {
+            partitions: CachePartitions::new(settings.buffer_time, None, None),
+            refresh_tasks: Default::default(),
+            spawner,
+            settings,
+            alive: Arc::new(()),
+        }
+    }
+
+    /// Starts refreshing the given partition in the background, unless it's already being refreshed.
+    fn start_refreshing(
+        &self,
+        partition: IdentityCachePartition,
+        cache: ExpiringCache<Identity, BoxError>,
+        resolver: SharedIdentityResolver,
+        runtime_components: &RuntimeComponents,
+    ) {
+        let mut refresh_tasks = self.refresh_tasks.lock().unwrap();
+        if refresh_tasks.contains_key(&partition) {
+            return;
+        }
+        // The runtime components hold this cache, which the task mustn't keep alive
+        let runtime_components = match runtime_components
+            .to_builder()
+            .with_identity_cache(Some(IdentityCache::no_cache()))
+            .build()
+        {
+            Ok(runtime_components) => runtime_components,
+            Err(err) => {
+                tracing::warn!(
+                    partition=?partition,
+                    err=%err,
+                    "identities won't be refreshed in the background"
+                );
+                return;
+            }
+        };
+        let refresher = Refresher {
+            cache,
+            resolver,
+            runtime_components,
+            settings: self.settings,
+            cache_alive: Arc::downgrade(&self.alive),
+        };
+        let (stop_tx, stop_rx) = oneshot::channel();
+        let span = tracing::debug_span!("eager_identity_refresh", partition=?partition);
+        // The refresh loop never ends on its own, so this only completes once it's stopped
+        let task = Timeout::new(refresher.run(), stop_rx);
+        (self.spawner.0)(Box::pin(
+            async move {
+                let _ = task.await;
+            }
+            .instrument(span),
+        ));
+        refresh_tasks.insert(partition, stop_tx);
+    }
+}
+
+fn validate_components(
+    time_source: Option<SharedTimeSource>,
+    sleep_impl: Option<SharedAsyncSleep>,
+) -> Result<(), BoxError> {
+    const DISABLE: &str = " If this isn't possible, then disable identity caching by calling \
+        the `identity_cache` method on config with `IdentityCache::no_cache()`";
+    if time_source.is_none() {
+        return Err(format!(
+            "Eager identity caching requires a time source to be configured. \
+            Set a time source using the `time_source` method on config.{DISABLE}"
+        )
+        .into());
+    }
+    if sleep_impl.is_none() {
+        return Err(format!(
+            "Eager identity caching requires an async sleep implementation to be configured. \
+            Set a sleep impl using the `sleep_impl` method on config.{DISABLE}"
+        )
+        .into());
+    }
+    Ok(())
+}
+
+impl ResolveCachedIdentity for EagerCache {
+    fn validate_base_client_config(
+        &self,
+        runtime_components: &RuntimeComponentsBuilder,
+        _cfg: &ConfigBag,
+    ) -> Result<(), BoxError> {
+        validate_components(
+            runtime_components.time_source(),
+            runtime_components.sleep_impl(),
+        )
+    }
+
+    fn validate_final_config(
+        &self,
+        runtime_components: &RuntimeComponents,
+        _cfg: &ConfigBag,
+    ) -> Result<(), BoxError> {
+        validate_components(
+            runtime_components.time_source(),
+            runtime_components.sleep_impl(),
+        )
+    }
+
+    fn resolve_cached_identity<'a>(
+        &'a self,
+        resolver: SharedIdentityResolver,
+        runtime_components: &'a RuntimeComponents,
+        config_bag: &'a ConfigBag,
+    ) -> IdentityFuture<'a> {
+        let time_source = runtime_components.time_source().expect("validated");
+        let now = time_source.now();
+        let partition = resolver.cache_partition();
+        let cache = self.partitions.partition(partition, now);
+
+        IdentityFuture::new(async move {
+            if let Some(identity) = cache.yield_or_clear_if_expired(now).await {
+                tracing::debug!(
+                    buffer_time=?self.settings.buffer_time,
+                    cached_expiration=?identity.expiration(),
+                    now=?now,
+                    "loaded identity from cache"
+                );
+                return Ok(identity);
+            }
+            // Either this is the first request for this partition, or background refreshes have
+            // failed until the identity expired. Load the identity before returning it.
+            let identity = cache
+                .get_or_load(|| {
+                    load_identity(&resolver, runtime_components, config_bag, &self.settings)
+                        .instrument(tracing::info_span!("eager_load_identity"))
+                })
+                .await?;
+            self.start_refreshing(partition, cache, resolver, runtime_components);
+            Ok(identity)
+        })
+    }
+
+    fn invalidate(&self, partition: IdentityCachePartition) {
+        // Stop refreshing the invalidated identity. The next request for this partition will
+        // load a new identity and start refreshing it again.
+        self.refresh_tasks.lock().unwrap().remove(&partition);
+        if self.partitions.remove(partition) {
+            tracing::debug!(partition=?partition, "invalidated cached identity");
+        }
+    }
+
+    fn invalidate_all(&self) {
+        self.refresh_tasks.lock().unwrap().clear();
+        tracing::debug!("invalidated all cached identities");
+        self.partitions.clear();
+    }
+}
+
+/// Resolves an identity with a timeout, and returns it along with its expiration time.
+async fn load_identity(
+    resolver: &SharedIdentityResolver,
+    runtime_components: &RuntimeComponents,
+    config_bag: &ConfigBag,
+    settings: &Settings,
+) -> Result<(Identity, SystemTime), BoxError> {
+    let (time_source, sleep_impl) = (
+        runtime_components.time_source().expect("validated"),
+        runtime_components.sleep_impl().expect("validated"),
+    );
+    let start_time = time_source.now();
+    let timeout = sleep_impl.sleep(settings.load_timeout);
+    let identity = match Timeout::new(
+        resolver.resolve_identity(runtime_components, config_bag),
+        timeout,
+    )
+    .await
+    {
+        Ok(result) => result?,
+        Err(_err) => match resolver.fallback_on_interrupt() {
+            Some(identity) => identity,
+            None => return Err(TimedOutError(settings.load_timeout).into()),
+        },
+    };
+    let expiration = identity
+        .expiration()
+        .unwrap_or(start_time + settings.default_expiration);
+    tracing::debug!(
+        new_expiration=%DateTime::from(expiration),
+        valid_for=?expiration.duration_since(time_source.now()).unwrap_or_default(),
+        "loaded new identity (took {:?})",
+        time_source.now().duration_since(start_time).unwrap_or_default()
+    );
+    Ok((identity, expiration))
+}
+
+/// Background task that refreshes a cache partition before its identity expires
+struct Refresher {
+    cache: ExpiringCache<Identity, BoxError>,
+    resolver: SharedIdentityResolver,
+    /// The client's runtime components, without the eager cache itself
+    runtime_components: RuntimeComponents,
+    settings: Settings,
+    cache_alive: Weak<()>,
+}
+
+impl Refresher {
+    async fn run(self) {
+        let (time_source, sleep_impl) = (
+            self.runtime_components.time_source().expect("validated"),
+            self.runtime_components.sleep_impl().expect("validated"),
+        );
+        // Background refreshes aren't part of a request, so there is no request config to give
+        // to the resolver.
+        let config_bag = ConfigBag::base();
+        let mut backoff = self.settings.error_backoff;
+        loop {
+            // The identity may have been replaced by a request since the last refresh, so always
+            // check the cache for the current expiration time.
+            if let Some(expiration) = self.cache.expiration().await {
+                let refresh_at = expiration
+                    .checked_sub(self.settings.refresh_time)
+                    .unwrap_or(SystemTime::UNIX_EPOCH);
+                if let Ok(delay) = refresh_at.duration_since(time_source.now()) {
+                    sleep_impl.sleep(delay).await;
+                }
+            }
+            if self.cache_alive.strong_count() == 0 {
+                tracing::debug!("the identity cache was dropped; no longer refreshing");
+                return;
+            }
+            match load_identity(
+                &self.resolver,
+                &self.runtime_components,
+                &config_bag,
+                &self.settings,
+            )
+            .await
+            {
+                Ok((identity, expiration)) => {
+                    self.cache.set(identity, expiration).await;
+                    backoff = self.settings.error_backoff;
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        err = %err,
+                        retry_in = ?backoff,
+                        "failed to refresh identity in the background; the cached identity will be used until it expires"
+                    );
+                    sleep_impl.sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_ERROR_BACKOFF);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "client", feature = "http-auth"))]
+mod tests {
+    use super::*;
+    use aws_smithy_async::test_util::tick_advance_sleep::{
+        tick_advance_time_and_sleep, TickAdvanceTime,
+    };
+    use aws_smithy_runtime_api::client::identity::http::Token;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::UNIX_EPOCH;
+
+    /// Resolver that returns the next result from a list, and counts how often it's called
+    #[derive(Debug)]
+    struct Resolver {
+        results: Mutex<Vec<Result<Identity, BoxError>>>,
+        calls: Arc<AtomicUsize>,
+    }
+    impl ResolveIdentity for Resolver {
+        fn resolve_identity<'a>(
+            &'a self,
+            _: &'a RuntimeComponents,
+            _config_bag: &'a ConfigBag,
+        ) -> IdentityFuture<'a> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let mut results = self.results.lock().unwrap();
+            if results.is_empty() {
+                IdentityFuture::ready(Err("no more identities".into()))
+            } else {
+                IdentityFuture::ready(results.remove(0))
+            }
+        }
+    }
+
+    fn epoch_secs(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    fn test_identity(expired_secs: u64) -> Identity {
+        let expiration = Some(epoch_secs(expired_secs));
+        Identity::new(Token::new("test", expiration), expiration)
+    }
+
+    struct TestSetup {
+        cache: SharedIdentityCache,
+        resolver: SharedIdentityResolver,
+        calls: Arc<AtomicUsize>,
+        components: RuntimeComponents,
+        time: TickAdvanceTime,
+    }
+
+    impl TestSetup {
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+
+        async fn resolve(&self) -> Result<Identity, BoxError> {
+            self.cache
+                .resolve_cached_identity(
+                    self.resolver.clone(),
+                    &self.components,
+                    &ConfigBag::base(),
+                )
+                .await
+        }
+
+        async fn expect_identity(&self, expired_secs: u64) {
+            let identity = self.resolve().await.expect("expected identity");
+            assert_eq!(Some(epoch_secs(expired_secs)), identity.expiration());
+        }
+    }
+
+    fn test_setup(results: Vec<Result<Identity, BoxError>>) -> TestSetup {
+        let (time, sleep) = tick_advance_time_and_sleep();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let resolver = SharedIdentityResolver::new(Resolver {
+            results: Mutex::new(results),
+            calls: calls.clone(),
+        });
+        let cache = EagerCacheBuilder::new()
+            .refresh_time(Duration::from_secs(100))
+            .error_backoff(Duration::from_secs(10))
+            .build()
+            .unwrap();
+        // Like in a real client, the runtime components hold the cache
+        let components = RuntimeComponentsBuilder::for_tests()
+            .with_time_source(Some(time.clone()))
+            .with_sleep_impl(Some(sleep))
+            .with_identity_cache(Some(cache.clone()))
+            .build()
+            .unwrap();
+        TestSetup {
+            cache,
+            resolver,
+            calls,
+            components,
+            time,
+        }
+    }
+
+    #[tokio::test]
+    async fn refreshes_before_expiry() {
+        let setup = test_setup(vec![
+            Ok(test_identity(1000)),
+            Ok(test_identity(2000)),
+            Ok(test_identity(3000)),
+        ]);
+
+        setup.expect_identity(1000).await;
+        assert_eq!(1, setup.calls());
+
+        // The first identity is refreshed 100 seconds before it expires
+        setup.time.tick(Duration::from_secs(899)).await;
+        setup.expect_identity(1000).await;
+        assert_eq!(1, setup.calls());
+        setup.time.tick(Duration::from_secs(1)).await;
+        assert_eq!(2, setup.calls());
+        setup.expect_identity(2000).await;
+
+        setup.time.tick(Duration::from_secs(1000)).await;
+        assert_eq!(3, setup.calls());
+        setup.expect_identity(3000).await;
+    }
+
+    #[tokio::test]
+    async fn invalidate_stops_refreshing() {
+        let setup = test_setup(vec![Ok(test_identity(1000)), Ok(test_identity(2000))]);
+
+        setup.expect_identity(1000).await;
+        setup.cache.invalidate(setup.resolver.cache_partition());
+        // The invalidated identity is no longer refreshed in the background
+        setup.time.tick(Duration::from_secs(900)).await;
+        assert_eq!(1, setup.calls());
+        // The next request loads a new identity
+        setup.expect_identity(2000).await;
+        assert_eq!(2, setup.calls());
+    }
+
+    #[tokio::test]
+    async fn keeps_serving_the_cached_identity_when_refreshes_fail() {
+        let setup = test_setup(vec![
+            Ok(test_identity(1000)),
+            Err("failed".into()),
+            Err("failed".into()),
+            Ok(test_identity(2000)),
+        ]);
+
+        setup.expect_identity(1000).await;
+        // The refresh at 900 fails, and is retried after 10 and then 20 seconds
+        setup.time.tick(Duration::from_secs(900)).await;
+        assert_eq!(2, setup.calls());
+        setup.expect_identity(1000).await;
+        setup.time.tick(Duration::from_secs(10)).await;
+        assert_eq!(3, setup.calls());
+        setup.expect_identity(1000).await;
+        setup.time.tick(Duration::from_secs(19)).await;
+        assert_eq!(3, setup.calls());
+        setup.time.tick(Duration::from_secs(1)).await;
+        assert_eq!(4, setup.calls());
+        setup.expect_identity(2000).await;
+    }
+
+    #[tokio::test]
+    async fn loads_when_the_identity_expires() {
+        let setup = test_setup(vec![
+            Ok(test_identity(1000)),
+            Err("failed".into()),
+            Err("failed".into()),
+            Err("failed".into()),
+            Err("failed".into()),
+        ]);
+
+        setup.expect_identity(1000).await;
+        // Refreshes fail at 900, 910, 930, and 970, and the identity expires at 990 due to the
+        // buffer time
+        setup.time.tick(Duration::from_secs(990)).await;
+        assert_eq!(5, setup.calls());
+        let err = setup
+            .resolve()
+            .await
+            .expect_err("the identity expired and can't be loaded");
+        assert_eq!("no more identities", err.to_string());
+    }
+
+    #[tokio::test]
+    async fn dropping_the_cache_stops_refreshing() {
+        let setup = test_setup(vec![Ok(test_identity(1000)), Ok(test_identity(2000))]);
+
+        setup.expect_identity(1000).await;
+        let (time, calls) = (setup.time.clone(), setup.calls.clone());
+        drop(setup);
+        time.tick(Duration::from_secs(1000)).await;
+        assert_eq!(1, calls.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn invalid_config_is_rejected() {
+        let err = EagerCacheBuilder::new()
+            .spawner(|_| {})
+            .buffer_time(Duration::from_secs(60))
+            .refresh_time(Duration::from_secs(30))
+            .build()
+            .expect_err("invalid refresh time");
+        assert_eq!(
+            "invalid eager identity cache config: refresh_time must be longer than buffer_time",
+            err.to_string()
+        );
+        EagerCacheBuilder::new()
+            .spawner(|_| {})
+            .default_expiration(Duration::from_secs(60))
+            .build()
+            .expect_err("invalid default expiration");
+    }
+
+    #[test]
+    fn requires_a_spawner_outside_of_a_tokio_runtime() {
+        let err = EagerCacheBuilder::new()
+            .build()
+            .expect_err("no runtime to spawn refreshes on");
+        assert!(err.to_string().contains("need a spawner"), "{err}");
+        EagerCacheBuilder::new()
+            .spawner(|_| {})
+            .build()
+            .expect("the spawner is used instead of a runtime");
+    }
+
+    #[tokio::test]
+    async fn refreshes_run_on_the_given_spawner() {
+        let (time, sleep) = tick_advance_time_and_sleep();
+        let spawned = Arc::new(AtomicUsize::new(0));
+        let cache = EagerCacheBuilder::new()
+            .refresh_time(Duration::from_secs(100))
+            .spawner({
+                let spawned = spawned.clone();
+                move |task| {
+                    spawned.fetch_add(1, Ordering::SeqCst);
+                    tokio::spawn(task);
+                }
+            })
+            .build()
+            .unwrap();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let resolver = SharedIdentityResolver::new(Resolver {
+            results: Mutex::new(vec![Ok(test_identity(1000)), Ok(test_identity(2000))]),
+            calls: calls.clone(),
+        });
+        let components = RuntimeComponentsBuilder::for_tests()
+            .with_time_source(Some(time.clone()))
+            .with_sleep_impl(Some(sleep))
+            .build()
+            .unwrap();
+        cache
+            .resolve_cached_identity(resolver, &components, &ConfigBag::base())
+            .await
+            .unwrap();
+        assert_eq!(1, spawned.load(Ordering::SeqCst));
+        time.tick(Duration::from_secs(900)).await;
+        assert_eq!(2, calls.load(Ordering::SeqCst));
+    }
+}
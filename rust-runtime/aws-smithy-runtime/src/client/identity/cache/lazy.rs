@@ -18,8 +18,9 @@ use aws_smithy_types::config_bag::ConfigBag;
 use aws_smithy_types::DateTime;
 use std::collections::HashMap;
 use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::RwLock;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 use tracing::Instrument;
 
 const DEFAULT_LOAD_TIMEOUT: Duration = Duration::from_secs(5);
@@ -36,6 +37,8 @@ pub struct LazyCacheBuilder {
     buffer_time: Option<Duration>,
     buffer_time_jitter_fraction: Option<fn() -> f64>,
     default_expiration: Option<Duration>,
+    max_partitions: Option<usize>,
+    partition_idle_timeout: Option<Duration>,
 }
 
 impl LazyCacheBuilder {
@@ -161,6 +164,50 @@ impl LazyCacheBuilder {
         self
     }
 
+    /// Maximum number of identity cache partitions to keep.
+    ///
+    /// Every identity resolver gets its own cache partition. When adding a partition would exceed
+    /// this limit, the least recently used partition is evicted, and its identity will be resolved
+    /// again the next time it is needed. This must be at least 1.
+    ///
+    /// Defaults to no limit.
+    pub fn max_partitions(mut self, max_partitions: usize) -> Self {
+        self.set_max_partitions(Some(max_partitions));
+        self
+    }
+
+    /// Maximum number of identity cache partitions to keep.
+    ///
+    /// Every identity resolver gets its own cache partition. When adding a partition would exceed
+    /// this limit, the least recently used partition is evicted, and its identity will be resolved
+    /// again the next time it is needed. This must be at least 1.
+    ///
+    /// Defaults to no limit.
+    pub fn set_max_partitions(&mut self, max_partitions: Option<usize>) -> &mut Self {
+        self.max_partitions = max_partitions;
+        self
+    }
+
+    /// Amount of time a cache partition can go unused before it is evicted.
+    ///
+    /// Idle partitions are evicted when a new partition is added to the cache.
+    ///
+    /// Defaults to never evicting idle partitions.
+    pub fn partition_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.set_partition_idle_timeout(Some(timeout));
+        self
+    }
+
+    /// Amount of time a cache partition can go unused before it is evicted.
+    ///
+    /// Idle partitions are evicted when a new partition is added to the cache.
+    ///
+    /// Defaults to never evicting idle partitions.
+    pub fn set_partition_idle_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.partition_idle_timeout = timeout;
+        self
+    }
+
     /// Builds a [`SharedIdentityCache`] from this builder.
     ///
     /// # Panics
@@ -172,47 +219,138 @@ impl LazyCacheBuilder {
             default_expiration >= DEFAULT_EXPIRATION,
             "default_expiration must be at least 15 minutes"
         );
+        assert!(
+            self.max_partitions != Some(0),
+            "max_partitions must be at least 1"
+        );
         LazyCache::new(
             self.load_timeout.unwrap_or(DEFAULT_LOAD_TIMEOUT),
             self.buffer_time.unwrap_or(DEFAULT_BUFFER_TIME),
             self.buffer_time_jitter_fraction
                 .unwrap_or(DEFAULT_BUFFER_TIME_JITTER_FRACTION),
             default_expiration,
+            self.max_partitions,
+            self.partition_idle_timeout,
         )
         .into_shared()
     }
 }
 
 #[derive(Debug)]
-struct CachePartitions {
-    partitions: RwLock<HashMap<IdentityCachePartition, ExpiringCache<Identity, BoxError>>>,
+struct Partition {
+    cache: ExpiringCache<Identity, BoxError>,
+    /// Value of `CachePartitions::uses` the last time this partition was used
+    last_use: AtomicU64,
+    /// Seconds since the Unix epoch when this partition was last used
+    last_used_at: AtomicU64,
+}
+
+impl Partition {
+    fn touch(&self, use_count: u64, now: SystemTime) {
+        self.last_use.store(use_count, Ordering::Relaxed);
+        self.last_used_at.store(epoch_secs(now), Ordering::Relaxed);
+    }
+}
+
+fn epoch_secs(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[derive(Debug)]
+pub(super) struct CachePartitions {
+    partitions: RwLock<HashMap<IdentityCachePartition, Partition>>,
     buffer_time: Duration,
+    max_partitions: Option<usize>,
+    idle_timeout: Option<Duration>,
+    uses: AtomicU64,
 }
 
 impl CachePartitions {
-    fn new(buffer_time: Duration) -> Self {
+    pub(super) fn new(
+        buffer_time: Duration,
+        max_partitions: Option<usize>,
+        idle_timeout: Option<Duration>,
+    ) -> Self {
         Self {
             partitions: RwLock::new(HashMap::new()),
             buffer_time,
+            max_partitions,
+            idle_timeout,
+            uses: AtomicU64::new(0),
         }
     }
 
-    fn partition(&self, key: IdentityCachePartition) -> ExpiringCache<Identity, BoxError> {
-        let mut partition = self.partitions.read().unwrap().get(&key).cloned();
-        // Add the partition to the cache if it doesn't already exist.
-        // Partitions will never be removed.
-        if partition.is_none() {
-            let mut partitions = self.partitions.write().unwrap();
-            // Another thread could have inserted the partition before we acquired the lock,
-            // so double check before inserting it.
-            partitions
-                .entry(key)
-                .or_insert_with(|| ExpiringCache::new(self.buffer_time));
-            drop(partitions);
-
-            partition = self.partitions.read().unwrap().get(&key).cloned();
+    pub(super) fn partition(
+        &self,
+        key: IdentityCachePartition,
+        now: SystemTime,
+    ) -> ExpiringCache<Identity, BoxError> {
+        let use_count = self.uses.fetch_add(1, Ordering::Relaxed);
+        if let Some(partition) = self.partitions.read().unwrap().get(&key) {
+            partition.touch(use_count, now);
+            return partition.cache.clone();
+        }
+
+        // Add the partition to the cache if it doesn't already exist. Partitions are only evicted
+        // when adding new partitions, or when they are invalidated.
+        let mut partitions = self.partitions.write().unwrap();
+        // Another thread could have inserted the partition before we acquired the lock,
+        // so double check before inserting it.
+        if !partitions.contains_key(&key) {
+            self.evict(&mut partitions, now);
+        }
+        let partition = partitions.entry(key).or_insert_with(|| Partition {
+            cache: ExpiringCache::new(self.buffer_time),
+            last_use: AtomicU64::new(use_count),
+            last_used_at: AtomicU64::new(epoch_secs(now)),
+        });
+        partition.touch(use_count, now);
+        partition.cache.clone()
+    }
+
+    /// Makes room for a new partition by evicting idle and least recently used partitions
+    fn evict(&self, partitions: &mut HashMap<IdentityCachePartition, Partition>, now: SystemTime) {
+        if let Some(idle_timeout) = self.idle_timeout {
+            let now = epoch_secs(now);
+            partitions.retain(|key, partition| {
+                let idle_for = now.saturating_sub(partition.last_used_at.load(Ordering::Relaxed));
+                let keep = idle_for < idle_timeout.as_secs();
+                if !keep {
+                    tracing::debug!(partition=?key, "evicting idle identity cache partition");
+                }
+                keep
+            });
         }
-        partition.expect("inserted above if not present")
+        if let Some(max_partitions) = self.max_partitions {
+            while partitions.len() >= max_partitions {
+                let least_recently_used = partitions
+                    .iter()
+                    .min_by_key(|(_, partition)| partition.last_use.load(Ordering::Relaxed))
+                    .map(|(key, _)| *key)
+                    .expect("max_partitions is at least one");
+                tracing::debug!(partition=?least_recently_used, "evicting least recently used identity cache partition");
+                partitions.remove(&least_recently_used);
+            }
+        }
+    }
+
+    /// Removes a partition, so that its identity is resolved again on next use.
+    ///
+    /// Returns true if the partition existed.
+    pub(super) fn remove(&self, key: IdentityCachePartition) -> bool {
+        self.partitions.write().unwrap().remove(&key).is_some()
+    }
+
+    /// Removes every partition.
+    pub(super) fn clear(&self) {
+        self.partitions.write().unwrap().clear();
+    }
+
+    #[cfg(all(test, feature = "client", feature = "http-auth"))]
+    fn len(&self) -> usize {
+        self.partitions.read().unwrap().len()
     }
 }
 
@@ -231,9 +369,11 @@ impl LazyCache {
         buffer_time: Duration,
         buffer_time_jitter_fraction: fn() -> f64,
         default_expiration: Duration,
+        max_partitions: Option<usize>,
+        partition_idle_timeout: Option<Duration>,
     ) -> Self {
         Self {
-            partitions: CachePartitions::new(buffer_time),
+            partitions: CachePartitions::new(buffer_time, max_partitions, partition_idle_timeout),
             load_timeout,
             buffer_time,
             buffer_time_jitter_fraction,
@@ -305,7 +445,7 @@ impl ResolveCachedIdentity for LazyCache {
         let timeout_future = sleep_impl.sleep(self.load_timeout);
         let load_timeout = self.load_timeout;
         let partition = resolver.cache_partition();
-        let cache = self.partitions.partition(partition);
+        let cache = self.partitions.partition(partition, now);
         let default_expiration = self.default_expiration;
 
         IdentityFuture::new(async move {
@@ -374,10 +514,21 @@ impl ResolveCachedIdentity for LazyCache {
             }
         })
     }
+
+    fn invalidate(&self, partition: IdentityCachePartition) {
+        if self.partitions.remove(partition) {
+            tracing::debug!(partition=?partition, "invalidated cached identity");
+        }
+    }
+
+    fn invalidate_all(&self) {
+        tracing::debug!("invalidated all cached identities");
+        self.partitions.clear();
+    }
 }
 
 #[derive(Debug)]
-struct TimedOutError(Duration);
+pub(super) struct TimedOutError(pub(super) Duration);
 
 impl std::error::Error for TimedOutError {}
 
@@ -458,6 +609,8 @@ mod tests {
             DEFAULT_BUFFER_TIME,
             buffer_time_jitter_fraction,
             DEFAULT_EXPIRATION,
+            None,
+            None,
         );
         (cache, identity_resolver)
     }
@@ -503,6 +656,8 @@ mod tests {
             DEFAULT_BUFFER_TIME,
             BUFFER_TIME_NO_JITTER,
             DEFAULT_EXPIRATION,
+            None,
+            None,
         );
         assert_eq!(
             epoch_secs(1000),
@@ -641,6 +796,8 @@ mod tests {
             DEFAULT_BUFFER_TIME,
             BUFFER_TIME_NO_JITTER,
             DEFAULT_EXPIRATION,
+            None,
+            None,
         );
 
         let err: BoxError = cache
@@ -768,4 +925,143 @@ mod tests {
         assert_eq!(1, resolver_a_calls.load(Ordering::Relaxed));
         assert_eq!(1, resolver_b_calls.load(Ordering::Relaxed));
     }
+
+    fn counting_resolver(token: &'static str) -> (SharedIdentityResolver, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let resolver = resolver_fn({
+            let calls = calls.clone();
+            move || {
+                calls.fetch_add(1, Ordering::Relaxed);
+                let expiration = Some(epoch_secs(10_000));
+                IdentityFuture::ready(Ok(Identity::new(Token::new(token, expiration), expiration)))
+            }
+        });
+        (resolver, calls)
+    }
+
+    fn bounded_cache(
+        max_partitions: Option<usize>,
+        partition_idle_timeout: Option<Duration>,
+    ) -> LazyCache {
+        LazyCache::new(
+            DEFAULT_LOAD_TIMEOUT,
+            DEFAULT_BUFFER_TIME,
+            BUFFER_TIME_NO_JITTER,
+            DEFAULT_EXPIRATION,
+            max_partitions,
+            partition_idle_timeout,
+        )
+    }
+
+    #[tokio::test]
+    async fn invalidate() {
+        let components = RuntimeComponentsBuilder::for_tests()
+            .with_time_source(Some(ManualTimeSource::new(epoch_secs(0))))
+            .with_sleep_impl(Some(TokioSleep::new()))
+            .build()
+            .unwrap();
+        let config_bag = ConfigBag::base();
+        let cache = bounded_cache(None, None);
+        let (resolver_a, calls_a) = counting_resolver("A");
+        let (resolver_b, calls_b) = counting_resolver("B");
+
+        for resolver in [&resolver_a, &resolver_a, &resolver_b] {
+            cache
+                .resolve_cached_identity(resolver.clone(), &components, &config_bag)
+                .await
+                .unwrap();
+        }
+        assert_eq!(1, calls_a.load(Ordering::Relaxed));
+        assert_eq!(1, calls_b.load(Ordering::Relaxed));
+
+        // Invalidating A should only cause A to be resolved again
+        cache.invalidate(resolver_a.cache_partition());
+        for resolver in [&resolver_a, &resolver_b] {
+            cache
+                .resolve_cached_identity(resolver.clone(), &components, &config_bag)
+                .await
+                .unwrap();
+        }
+        assert_eq!(2, calls_a.load(Ordering::Relaxed));
+        assert_eq!(1, calls_b.load(Ordering::Relaxed));
+
+        cache.invalidate_all();
+        assert_eq!(0, cache.partitions.len());
+        for resolver in [&resolver_a, &resolver_b] {
+            cache
+                .resolve_cached_identity(resolver.clone(), &components, &config_bag)
+                .await
+                .unwrap();
+        }
+        assert_eq!(3, calls_a.load(Ordering::Relaxed));
+        assert_eq!(2, calls_b.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn evict_least_recently_used_partition() {
+        let components = RuntimeComponentsBuilder::for_tests()
+            .with_time_source(Some(ManualTimeSource::new(epoch_secs(0))))
+            .with_sleep_impl(Some(TokioSleep::new()))
+            .build()
+            .unwrap();
+        let config_bag = ConfigBag::base();
+        let cache = bounded_cache(Some(2), None);
+        let (resolver_a, calls_a) = counting_resolver("A");
+        let (resolver_b, calls_b) = counting_resolver("B");
+        let (resolver_c, calls_c) = counting_resolver("C");
+
+        // Using A after B makes B the least recently used partition when C is added
+        for resolver in [&resolver_a, &resolver_b, &resolver_a, &resolver_c] {
+            cache
+                .resolve_cached_identity(resolver.clone(), &components, &config_bag)
+                .await
+                .unwrap();
+        }
+        assert_eq!(2, cache.partitions.len());
+        assert_eq!(1, calls_a.load(Ordering::Relaxed));
+        assert_eq!(1, calls_b.load(Ordering::Relaxed));
+        assert_eq!(1, calls_c.load(Ordering::Relaxed));
+
+        // A is still cached, but B has to be resolved again
+        for resolver in [&resolver_a, &resolver_b] {
+            cache
+                .resolve_cached_identity(resolver.clone(), &components, &config_bag)
+                .await
+                .unwrap();
+        }
+        assert_eq!(2, cache.partitions.len());
+        assert_eq!(1, calls_a.load(Ordering::Relaxed));
+        assert_eq!(2, calls_b.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn evict_idle_partitions() {
+        let time = ManualTimeSource::new(epoch_secs(0));
+        let components = RuntimeComponentsBuilder::for_tests()
+            .with_time_source(Some(time.clone()))
+            .with_sleep_impl(Some(TokioSleep::new()))
+            .build()
+            .unwrap();
+        let config_bag = ConfigBag::base();
+        let cache = bounded_cache(None, Some(Duration::from_secs(60)));
+        let (resolver_a, calls_a) = counting_resolver("A");
+        let (resolver_b, _) = counting_resolver("B");
+
+        cache
+            .resolve_cached_identity(resolver_a.clone(), &components, &config_bag)
+            .await
+            .unwrap();
+        time.advance(Duration::from_secs(120));
+        cache
+            .resolve_cached_identity(resolver_b.clone(), &components, &config_bag)
+            .await
+            .unwrap();
+        assert_eq!(1, cache.partitions.len(), "A should have been evicted");
+
+        cache
+            .resolve_cached_identity(resolver_a.clone(), &components, &config_bag)
+            .await
+            .unwrap();
+        assert_eq!(2, calls_a.load(Ordering::Relaxed));
+    }
 }
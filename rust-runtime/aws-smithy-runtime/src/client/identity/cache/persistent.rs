@@ -0,0 +1,1144 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use super::lazy::{CachePartitions, TimedOutError};
+use aws_smithy_async::future::timeout::Timeout;
+use aws_smithy_async::rt::sleep::{AsyncSleep, SharedAsyncSleep};
+use aws_smithy_async::time::SharedTimeSource;
+use aws_smithy_runtime_api::box_error::BoxError;
+use aws_smithy_runtime_api::client::identity::{
+    Identity, IdentityCachePartition, IdentityFuture, ResolveCachedIdentity, ResolveIdentity,
+    SharedIdentityCache, SharedIdentityResolver,
+};
+use aws_smithy_runtime_api::client::runtime_components::{
+    RuntimeComponents, RuntimeComponentsBuilder,
+};
+use aws_smithy_runtime_api::shared::IntoShared;
+use aws_smithy_types::config_bag::ConfigBag;
+use aws_smithy_types::DateTime;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tracing::Instrument;
+
+const DEFAULT_LOAD_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_EXPIRATION: Duration = Duration::from_secs(15 * 60);
+const DEFAULT_BUFFER_TIME: Duration = Duration::from_secs(10);
+const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// First line of every cache file, followed by the resolver key, the codec kind, and the expiration time
+const FILE_FORMAT: &str = "smithy-identity-cache/2";
+
+/// Converts identities to and from bytes so that they can be persisted.
+///
+/// [`Identity`] data is type erased, so the persistent identity cache needs a codec that knows
+/// the concrete identity type, for example `TokenCodec` for HTTP bearer tokens.
+pub trait IdentityCodec: fmt::Debug + Send + Sync {
+    /// Name of the format produced by this codec.
+    ///
+    /// The name is stored alongside the persisted identity, and a cache file with a different
+    /// name is ignored. It must not contain whitespace.
+    fn kind(&self) -> &str;
+
+    /// Converts an identity to bytes.
+    ///
+    /// An error is returned if the identity isn't of the type this codec supports.
+    fn encode(&self, identity: &Identity) -> Result<Vec<u8>, BoxError>;
+
+    /// Converts bytes produced by [`encode`](IdentityCodec::encode) back into an identity.
+    ///
+    /// The `expiration` is the time the cached identity expires, which should be set on the
+    /// decoded identity.
+    fn decode(&self, data: &[u8], expiration: SystemTime) -> Result<Identity, BoxError>;
+}
+
+/// Encrypts persisted identities at rest.
+///
+/// Identities often contain secrets, and the persistent identity cache writes them to disk. By
+/// default, cache files are only readable by the current user on Unix systems, but are otherwise
+/// not protected. Implement this trait to encrypt them, for example with a key from the operating
+/// system's keychain.
+pub trait IdentityCipher: fmt::Debug + Send + Sync {
+    /// Encrypts an encoded identity before it is written to disk.
+    fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, BoxError>;
+
+    /// Decrypts an encoded identity after it is read from disk.
+    ///
+    /// If this fails, the cached identity is ignored and a new identity is resolved.
+    fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, BoxError>;
+}
+
+/// [`IdentityCodec`] for HTTP bearer token and API key identities.
+///
+/// # Examples
+///
+/// Sharing bearer tokens between invocations of a command line tool:
+/// ```no_run
+/// use aws_smithy_runtime::client::identity::{IdentityCache, TokenCodec};
+/// use aws_smithy_runtime_api::client::identity::SharedIdentityResolver;
+///
+/// # /*
+/// let token_provider = SharedIdentityResolver::new(MyTokenProvider::new("default-profile"));
+/// let config = some_service::Config::builder()
+///     .token_provider(token_provider.clone())
+///     .identity_cache(
+/// # */
+/// # let token_provider = SharedIdentityResolver::new(
+/// #     aws_smithy_runtime_api::client::identity::http::Token::new("example", None),
+/// # );
+/// # drop(
+///         IdentityCache::persistent()
+///             .directory("/home/user/.my-tool/cache")
+///             .resolver("default-profile", &token_provider)
+///             .codec(TokenCodec::new())
+///             .build()
+/// # );
+/// # /*
+///     )
+///     // ...
+///     .build();
+/// let client = some_service::Client::new(config);
+/// # */
+/// ```
+#[cfg(feature = "http-auth")]
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct TokenCodec;
+
+#[cfg(feature = "http-auth")]
+impl TokenCodec {
+    /// Creates a new `TokenCodec`.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[cfg(feature = "http-auth")]
+impl IdentityCodec for TokenCodec {
+    fn kind(&self) -> &str {
+        "http-token"
+    }
+
+    fn encode(&self, identity: &Identity) -> Result<Vec<u8>, BoxError> {
+        use aws_smithy_runtime_api::client::identity::http::Token;
+        let token = identity
+            .data::<Token>()
+            .ok_or("TokenCodec can only encode `Token` identities")?;
+        Ok(token.token().as_bytes().to_vec())
+    }
+
+    fn decode(&self, data: &[u8], expiration: SystemTime) -> Result<Identity, BoxError> {
+        use aws_smithy_runtime_api::client::identity::http::Token;
+        let token = std::str::from_utf8(data)?;
+        Ok(Identity::new(
+            Token::new(token, Some(expiration)),
+            Some(expiration),
+        ))
+    }
+}
+
+/// Builder for persistent identity caching.
+///
+/// A persistent identity cache writes identities to a file, so that they can be reused by other
+/// processes, or by the same program the next time it runs. This avoids resolving identities on
+/// every invocation of short-lived programs such as command line tools.
+///
+/// Only the identities of registered resolvers are persisted. Each resolver is registered with a
+/// key, and its identity is stored in `<directory>/<key>.identity`. The key must be stable across
+/// processes, and should be different for each distinct identity configuration, such as each
+/// profile. The key is also recorded in the cache file, and a file written for a different key is
+/// never loaded. Identities from resolvers that aren't registered are only cached in memory.
+///
+/// While an identity is resolved, a `<directory>/<key>.lock` file is held so that other processes
+/// wait for the identity instead of resolving it at the same time. Cache files are replaced
+/// atomically, so readers never see a partially written identity. When running on a Tokio
+/// runtime with the `rt-tokio` feature enabled, file system access happens on Tokio's blocking
+/// thread pool instead of the async executor.
+#[derive(Default, Debug)]
+pub struct PersistentCacheBuilder {
+    directory: Option<PathBuf>,
+    resolvers: Vec<(String, IdentityCachePartition)>,
+    codec: Option<Arc<dyn IdentityCodec>>,
+    cipher: Option<Arc<dyn IdentityCipher>>,
+    load_timeout: Option<Duration>,
+    buffer_time: Option<Duration>,
+    lock_timeout: Option<Duration>,
+    default_expiration: Option<Duration>,
+}
+
+impl PersistentCacheBuilder {
+    /// Create a new builder.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Directory to store cache files in.
+    ///
+    /// The directory is created if it doesn't exist. This is required.
+    pub fn directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.set_directory(Some(directory.into()));
+        self
+    }
+
+    /// Directory to store cache files in.
+    ///
+    /// The directory is created if it doesn't exist. This is required.
+    pub fn set_directory(&mut self, directory: Option<PathBuf>) -> &mut Self {
+        self.directory = directory;
+        self
+    }
+
+    /// Registers an identity resolver whose identity is persisted under the given key.
+    ///
+    /// The same resolver must also be given to the client config, for example by cloning the
+    /// [`SharedIdentityResolver`]. The key is used as the cache file name, so it may only contain
+    /// ASCII letters, digits, `-`, `_`, and `.`, and must not start with a `.`. Each resolver
+    /// needs a different key. At least one resolver is required.
+    pub fn resolver(mut self, key: impl Into<String>, resolver: &SharedIdentityResolver) -> Self {
+        self.push_resolver(key.into(), resolver);
+        self
+    }
+
+    /// Registers an identity resolver whose identity is persisted under the given key.
+    ///
+    /// The same resolver must also be given to the client config, for example by cloning the
+    /// [`SharedIdentityResolver`]. The key is used as the cache file name, so it may only contain
+    /// ASCII letters, digits, `-`, `_`, and `.`, and must not start with a `.`. Each resolver
+    /// needs a different key. At least one resolver is required.
+    pub fn push_resolver(&mut self, key: String, resolver: &SharedIdentityResolver) -> &mut Self {
+        self.resolvers.push((key, resolver.cache_partition()));
+        self
+    }
+
+    /// Codec that converts identities to and from bytes.
+    ///
+    /// This is required.
+    pub fn codec(mut self, codec: impl IdentityCodec + 'static) -> Self {
+        self.set_codec(Some(Arc::new(codec)));
+        self
+    }
+
+    /// Codec that converts identities to and from bytes.
+    ///
+    /// This is required.
+    pub fn set_codec(&mut self, codec: Option<Arc<dyn IdentityCodec>>) -> &mut Self {
+        self.codec = codec;
+        self
+    }
+
+    /// Cipher that encrypts identities before they are written to disk.
+    ///
+    /// Defaults to storing identities unencrypted.
+    pub fn cipher(mut self, cipher: impl IdentityCipher + 'static) -> Self {
+        self.set_cipher(Some(Arc::new(cipher)));
+        self
+    }
+
+    /// Cipher that encrypts identities before they are written to disk.
+    ///
+    /// Defaults to storing identities unencrypted.
+    pub fn set_cipher(&mut self, cipher: Option<Arc<dyn IdentityCipher>>) -> &mut Self {
+        self.cipher = cipher;
+        self
+    }
+
+    /// Timeout for identity resolution.
+    ///
+    /// Defaults to 5 seconds.
+    pub fn load_timeout(mut self, timeout: Duration) -> Self {
+        self.set_load_timeout(Some(timeout));
+        self
+    }
+
+    /// Timeout for identity resolution.
+    ///
+    /// Defaults to 5 seconds.
+    pub fn set_load_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.load_timeout = timeout;
+        self
+    }
+
+    /// Amount of time before the actual identity expiration time where the identity is considered expired.
+    ///
+    /// Defaults to 10 seconds.
+    pub fn buffer_time(mut self, buffer_time: Duration) -> Self {
+        self.set_buffer_time(Some(buffer_time));
+        self
+    }
+
+    /// Amount of time before the actual identity expiration time where the identity is considered expired.
+    ///
+    /// Defaults to 10 seconds.
+    pub fn set_buffer_time(&mut self, buffer_time: Option<Duration>) -> &mut Self {
+        self.buffer_time = buffer_time;
+        self
+    }
+
+    /// Amount of time to wait for another process to finish resolving the identity.
+    ///
+    /// If the lock file is still held after this long, the identity is resolved without it. A
+    /// lock file older than this is assumed to have been left behind by a process that exited,
+    /// and is removed.
+    ///
+    /// Defaults to 10 seconds.
+    pub fn lock_timeout(mut self, timeout: Duration) -> Self {
+        self.set_lock_timeout(Some(timeout));
+        self
+    }
+
+    /// Amount of time to wait for another process to finish resolving the identity.
+    ///
+    /// If the lock file is still held after this long, the identity is resolved without it. A
+    /// lock file older than this is assumed to have been left behind by a process that exited,
+    /// and is removed.
+    ///
+    /// Defaults to 10 seconds.
+    pub fn set_lock_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.lock_timeout = timeout;
+        self
+    }
+
+    /// Default expiration time to set on an identity if it doesn't have an expiration time.
+    ///
+    /// This is only used if the resolved identity doesn't have an expiration time set.
+    /// This must be at least 15 minutes.
+    ///
+    /// Defaults to 15 minutes.
+    pub fn default_expiration(mut self, duration: Duration) -> Self {
+        self.set_default_expiration(Some(duration));
+        self
+    }
+
+    /// Default expiration time to set on an identity if it doesn't have an expiration time.
+    ///
+    /// This is only used if the resolved identity doesn't have an expiration time set.
+    /// This must be at least 15 minutes.
+    ///
+    /// Defaults to 15 minutes.
+    pub fn set_default_expiration(&mut self, duration: Option<Duration>) -> &mut Self {
+        self.default_expiration = duration;
+        self
+    }
+
+    /// Builds a [`SharedIdentityCache`] from this builder.
+    ///
+    /// # Panics
+    ///
+    /// This builder will panic if required fields are not given, or if given values are not valid.
+    pub fn build(self) -> SharedIdentityCache {
+        let directory = self.directory.expect("directory is required");
+        assert!(
+            !self.resolvers.is_empty(),
+            "at least one resolver is required"
+        );
+        let codec = self.codec.expect("codec is required");
+        assert!(
+            !codec.kind().is_empty() && !codec.kind().contains(char::is_whitespace),
+            "codec kind must not be empty or contain whitespace"
+        );
+        let default_expiration = self.default_expiration.unwrap_or(DEFAULT_EXPIRATION);
+        assert!(
+            default_expiration >= DEFAULT_EXPIRATION,
+            "default_expiration must be at least 15 minutes"
+        );
+        let mut stores = HashMap::new();
+        for (key, partition) in self.resolvers {
+            assert!(
+                !key.is_empty()
+                    && !key.starts_with('.')
+                    && key
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')),
+                "key may only contain ASCII letters, digits, `-`, `_`, and `.`, and must not start with `.`"
+            );
+            assert!(
+                stores.values().all(|store: &FileStore| store.key != key),
+                "each resolver must be registered with a different key"
+            );
+            let store = FileStore {
+                identity_path: directory.join(format!("{key}.identity")),
+                lock_path: directory.join(format!("{key}.lock")),
+                directory: directory.clone(),
+                key,
+                codec: codec.clone(),
+                cipher: self.cipher.clone(),
+            };
+            assert!(
+                stores.insert(partition, store).is_none(),
+                "each resolver may only be registered once"
+            );
+        }
+        let buffer_time = self.buffer_time.unwrap_or(DEFAULT_BUFFER_TIME);
+        PersistentCache {
+            partitions: CachePartitions::new(buffer_time, None, None),
+            stores,
+            load_timeout: self.load_timeout.unwrap_or(DEFAULT_LOAD_TIMEOUT),
+            buffer_time,
+            lock_timeout: self.lock_timeout.unwrap_or(DEFAULT_LOCK_TIMEOUT),
+            default_expiration,
+        }
+        .into_shared()
+    }
+}
+
+#[derive(Debug)]
+struct PersistentCache {
+    partitions: CachePartitions,
+    /// Cache files of the registered identity resolvers
+    stores: HashMap<IdentityCachePartition, FileStore>,
+    load_timeout: Duration,
+    buffer_time: Duration,
+    lock_timeout: Duration,
+    default_expiration: Duration,
+}
+
+impl PersistentCache {
+    /// Resolves an identity with a timeout, and returns it along with its expiration time.
+    async fn resolve(
+        &self,
+        resolver: &SharedIdentityResolver,
+        runtime_components: &RuntimeComponents,
+        config_bag: &ConfigBag,
+        now: SystemTime,
+    ) -> Result<(Identity, SystemTime), BoxError> {
+        let sleep_impl = runtime_components.sleep_impl().expect("validated");
+        let identity = match Timeout::new(
+            resolver.resolve_identity(runtime_components, config_bag),
+            sleep_impl.sleep(self.load_timeout),
+        )
+        .await
+        {
+            Ok(result) => result?,
+            Err(_err) => match resolver.fallback_on_interrupt() {
+                Some(identity) => identity,
+                None => return Err(TimedOutError(self.load_timeout).into()),
+            },
+        };
+        let expiration = identity
+            .expiration()
+            .unwrap_or(now + self.default_expiration);
+        Ok((identity, expiration))
+    }
+
+    /// Loads the identity from disk, or resolves and persists it if there is no usable identity on disk.
+    async fn load_persisted(
+        &self,
+        store: &FileStore,
+        resolver: &SharedIdentityResolver,
+        runtime_components: &RuntimeComponents,
+        config_bag: &ConfigBag,
+    ) -> Result<(Identity, SystemTime), BoxError> {
+        let (time_source, sleep_impl) = (
+            runtime_components.time_source().expect("validated"),
+            runtime_components.sleep_impl().expect("validated"),
+        );
+        if let Some(loaded) = store.read(time_source.now(), self.buffer_time).await {
+            return Ok(loaded);
+        }
+
+        let lock = store
+            .lock(&time_source, &sleep_impl, self.lock_timeout)
+            .await;
+        // Another process may have persisted an identity while we waited for the lock
+        let result = match store.read(time_source.now(), self.buffer_time).await {
+            Some(loaded) => Ok(loaded),
+            None => {
+                self.resolve_and_persist(
+                    store,
+                    resolver,
+                    runtime_components,
+                    config_bag,
+                    time_source.now(),
+                )
+                .await
+            }
+        };
+        if let Some(lock) = lock {
+            lock.release().await;
+        }
+        result
+    }
+
+    async fn resolve_and_persist(
+        &self,
+        store: &FileStore,
+        resolver: &SharedIdentityResolver,
+        runtime_components: &RuntimeComponents,
+        config_bag: &ConfigBag,
+        now: SystemTime,
+    ) -> Result<(Identity, SystemTime), BoxError> {
+        let (identity, expiration) = self
+            .resolve(resolver, runtime_components, config_bag, now)
+            .await?;
+        if let Err(err) = store.write(&identity, expiration).await {
+            tracing::warn!(
+                path = %store.identity_path.display(),
+                err = %err,
+                "failed to persist identity; it will only be cached in memory"
+            );
+        }
+        Ok((identity, expiration))
+    }
+}
+
+fn validate_components(
+    time_source: Option<SharedTimeSource>,
+    sleep_impl: Option<SharedAsyncSleep>,
+) -> Result<(), BoxError> {
+    const DISABLE: &str = " If this isn't possible, then disable identity caching by calling \
+        the `identity_cache` method on config with `IdentityCache::no_cache()`";
+    if time_source.is_none() {
+        return Err(format!(
+            "Persistent identity caching requires a time source to be configured. \
+            Set a time source using the `time_source` method on config.{DISABLE}"
+        )
+        .into());
+    }
+    if sleep_impl.is_none() {
+        return Err(format!(
+            "Persistent identity caching requires an async sleep implementation to be configured. \
+            Set a sleep impl using the `sleep_impl` method on config.{DISABLE}"
+        )
+        .into());
+    }
+    Ok(())
+}
+
+impl ResolveCachedIdentity for PersistentCache {
+    fn validate_base_client_config(
+        &self,
+        runtime_components: &RuntimeComponentsBuilder,
+        _cfg: &ConfigBag,
+    ) -> Result<(), BoxError> {
+        validate_components(
+            runtime_components.time_source(),
+            runtime_components.sleep_impl(),
+        )
+    }
+
+    fn validate_final_config(
+        &self,
+        runtime_components: &RuntimeComponents,
+        _cfg: &ConfigBag,
+    ) -> Result<(), BoxError> {
+        validate_components(
+            runtime_components.time_source(),
+            runtime_components.sleep_impl(),
+        )
+    }
+
+    fn resolve_cached_identity<'a>(
+        &'a self,
+        resolver: SharedIdentityResolver,
+        runtime_components: &'a RuntimeComponents,
+        config_bag: &'a ConfigBag,
+    ) -> IdentityFuture<'a> {
+        let time_source = runtime_components.time_source().expect("validated");
+        let now = time_source.now();
+        let partition = resolver.cache_partition();
+        let cache = self.partitions.partition(partition, now);
+        let store = self.stores.get(&partition);
+
+        IdentityFuture::new(async move {
+            if let Some(identity) = cache.yield_or_clear_if_expired(now).await {
+                tracing::debug!(
+                    buffer_time=?self.buffer_time,
+                    cached_expiration=?identity.expiration(),
+                    now=?now,
+                    "loaded identity from cache"
+                );
+                return Ok(identity);
+            }
+            cache
+                .get_or_load(|| {
+                    async move {
+                        let (identity, expiration) = if let Some(store) = store {
+                            self.load_persisted(store, &resolver, runtime_components, config_bag)
+                                .await?
+                        } else {
+                            self.resolve(&resolver, runtime_components, config_bag, now)
+                                .await?
+                        };
+                        tracing::debug!(
+                            new_expiration=%DateTime::from(expiration),
+                            partition=?partition,
+                            "identity cache miss occurred; added new identity"
+                        );
+                        Ok((identity, expiration))
+                    }
+                    .instrument(tracing::info_span!("persistent_load_identity"))
+                })
+                .await
+        })
+    }
+
+    fn invalidate(&self, partition: IdentityCachePartition) {
+        self.partitions.remove(partition);
+        if let Some(store) = self.stores.get(&partition) {
+            store.remove();
+        }
+    }
+
+    fn invalidate_all(&self) {
+        self.partitions.clear();
+        for store in self.stores.values() {
+            store.remove();
+        }
+    }
+}
+
+/// Runs blocking file system work on Tokio's blocking thread pool when running on a Tokio
+/// runtime, and directly otherwise.
+async fn blocking<T: Send + 'static>(
+    work: impl FnOnce() -> io::Result<T> + Send + 'static,
+) -> io::Result<T> {
+    #[cfg(feature = "rt-tokio")]
+    if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+        return match runtime.spawn_blocking(work).await {
+            Ok(result) => result,
+            Err(join_failure) => Err(io::Error::new(io::ErrorKind::Other, join_failure)),
+        };
+    }
+    work()
+}
+
+/// Reads and writes the cache file of a single registered identity resolver
+#[derive(Debug)]
+struct FileStore {
+    directory: PathBuf,
+    /// Key the resolver was registered with
+    key: String,
+    identity_path: PathBuf,
+    lock_path: PathBuf,
+    codec: Arc<dyn IdentityCodec>,
+    cipher: Option<Arc<dyn IdentityCipher>>,
+}
+
+impl FileStore {
+    /// Reads the persisted identity if it exists and hasn't expired.
+    ///
+    /// Problems reading the file are logged, and treated as if there was no persisted identity.
+    async fn read(&self, now: SystemTime, buffer_time: Duration) -> Option<(Identity, SystemTime)> {
+        let identity_path = self.identity_path.clone();
+        let contents = match blocking(move || fs::read(identity_path)).await {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return None,
+            Err(err) => {
+                tracing::warn!(path = %self.identity_path.display(), err = %err, "failed to read persisted identity");
+                return None;
+            }
+        };
+        match self.parse(&contents, now, buffer_time) {
+            Ok(Some((identity, expiration))) => {
+                tracing::debug!(path = %self.identity_path.display(), "loaded persisted identity");
+                Some((identity, expiration))
+            }
+            Ok(None) => None,
+            Err(err) => {
+                tracing::warn!(path = %self.identity_path.display(), err = %err, "ignoring invalid persisted identity");
+                None
+            }
+        }
+    }
+
+    fn parse(
+        &self,
+        contents: &[u8],
+        now: SystemTime,
+        buffer_time: Duration,
+    ) -> Result<Option<(Identity, SystemTime)>, BoxError> {
+        let header_end = contents
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or("missing header")?;
+        let header = std::str::from_utf8(&contents[..header_end])?;
+        let mut fields = header.split(' ');
+        if fields.next() != Some(FILE_FORMAT) {
+            return Err("unsupported file format".into());
+        }
+        if fields.next() != Some(self.key.as_str()) {
+            tracing::warn!(path = %self.identity_path.display(), "persisted identity belongs to a different resolver");
+            return Ok(None);
+        }
+        if fields.next() != Some(self.codec.kind()) {
+            tracing::debug!(path = %self.identity_path.display(), "persisted identity was written by a different codec");
+            return Ok(None);
+        }
+        let expiration: u64 = fields.next().ok_or("missing expiration")?.parse()?;
+        let expiration = SystemTime::UNIX_EPOCH + Duration::from_secs(expiration);
+        if expiration <= now + buffer_time {
+            tracing::debug!(path = %self.identity_path.display(), "persisted identity has expired");
+            return Ok(None);
+        }
+
+        let payload = &contents[header_end + 1..];
+        let identity = match &self.cipher {
+            Some(cipher) => self.codec.decode(&cipher.decrypt(payload)?, expiration)?,
+            None => self.codec.decode(payload, expiration)?,
+        };
+        Ok(Some((identity, expiration)))
+    }
+
+    /// Atomically replaces the persisted identity.
+    async fn write(&self, identity: &Identity, expiration: SystemTime) -> Result<(), BoxError> {
+        let mut payload = self.codec.encode(identity)?;
+        if let Some(cipher) = &self.cipher {
+            payload = cipher.encrypt(&payload)?;
+        }
+        let expiration = expiration
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let header = format!(
+            "{FILE_FORMAT} {} {} {expiration}",
+            self.key,
+            self.codec.kind()
+        );
+
+        let (directory, identity_path) = (self.directory.clone(), self.identity_path.clone());
+        blocking(move || {
+            fs::create_dir_all(&directory)?;
+            // Write to a temporary file in the same directory, and then rename it over the cache
+            // file so that readers never see a partially written file.
+            let temp_path = directory.join(format!(
+                ".{}.{:016x}.tmp",
+                file_name(&identity_path),
+                fastrand::u64(..)
+            ));
+            let result = (|| {
+                let mut file = private_file_options()
+                    .write(true)
+                    .create_new(true)
+                    .open(&temp_path)?;
+                writeln!(file, "{header}")?;
+                file.write_all(&payload)?;
+                file.sync_all()?;
+                fs::rename(&temp_path, &identity_path)
+            })();
+            if result.is_err() {
+                let _ = fs::remove_file(&temp_path);
+            }
+            result
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Deletes the persisted identity.
+    ///
+    /// Unlike the other file system access, this happens directly because invalidation isn't
+    /// async, and the identity must be gone before it could be loaded again.
+    fn remove(&self) {
+        match fs::remove_file(&self.identity_path) {
+            Ok(()) => {
+                tracing::debug!(path = %self.identity_path.display(), "removed persisted identity")
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => {
+                tracing::warn!(path = %self.identity_path.display(), err = %err, "failed to remove persisted identity")
+            }
+        }
+    }
+
+    /// Acquires the lock file, waiting up to `timeout` for another process to release it.
+    ///
+    /// Returns `None` if the lock couldn't be acquired.
+    async fn lock(
+        &self,
+        time_source: &SharedTimeSource,
+        sleep_impl: &SharedAsyncSleep,
+        timeout: Duration,
+    ) -> Option<LockFile> {
+        let directory = self.directory.clone();
+        if let Err(err) = blocking(move || fs::create_dir_all(directory)).await {
+            tracing::warn!(path = %self.directory.display(), err = %err, "failed to create identity cache directory");
+            return None;
+        }
+        let acquire = async {
+            loop {
+                let (lock_path, now) = (self.lock_path.clone(), time_source.now());
+                match blocking(move || try_lock(&lock_path, now, timeout)).await {
+                    Ok(true) => return Some(LockFile(Some(self.lock_path.clone()))),
+                    Ok(false) => {}
+                    Err(err) => {
+                        tracing::warn!(path = %self.lock_path.display(), err = %err, "failed to create lock file");
+                        return None;
+                    }
+                }
+                sleep_impl.sleep(LOCK_POLL_INTERVAL).await;
+            }
+        };
+        match Timeout::new(acquire, sleep_impl.sleep(timeout)).await {
+            Ok(lock) => lock,
+            Err(_) => {
+                tracing::warn!(
+                    path = %self.lock_path.display(),
+                    "timed out waiting for another process to resolve the identity; resolving it without the lock"
+                );
+                None
+            }
+        }
+    }
+}
+
+/// Tries to create the lock file, and returns whether it was created.
+///
+/// A lock file older than `timeout` is assumed to have been left behind by a process that
+/// exited, and is removed.
+fn try_lock(lock_path: &Path, now: SystemTime, timeout: Duration) -> io::Result<bool> {
+    loop {
+        match private_file_options()
+            .write(true)
+            .create_new(true)
+            .open(lock_path)
+        {
+            Ok(_) => return Ok(true),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                let stale = fs::metadata(lock_path)
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+                    .and_then(|modified| now.duration_since(modified).ok())
+                    .map(|age| age > timeout)
+                    .unwrap_or(false);
+                if !stale {
+                    return Ok(false);
+                }
+                tracing::debug!(path = %lock_path.display(), "removing stale lock file");
+                let _ = fs::remove_file(lock_path);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Lock file that is removed when released or dropped
+struct LockFile(Option<PathBuf>);
+
+impl LockFile {
+    /// Removes the lock file.
+    async fn release(mut self) {
+        if let Some(path) = self.0.take() {
+            let _ = blocking(move || fs::remove_file(path)).await;
+        }
+    }
+}
+
+impl Drop for LockFile {
+    fn drop(&mut self) {
+        // Only reached if the load was cancelled before the lock was released
+        if let Some(path) = self.0.take() {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Options for creating files that are only readable by the current user
+fn private_file_options() -> OpenOptions {
+    #[allow(unused_mut)]
+    let mut options = OpenOptions::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+}
+
+#[cfg(all(test, feature = "client", feature = "http-auth"))]
+mod tests {
+    use super::*;
+    use aws_smithy_async::rt::sleep::TokioSleep;
+    use aws_smithy_async::test_util::ManualTimeSource;
+    use aws_smithy_runtime_api::client::identity::http::Token;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::UNIX_EPOCH;
+
+    /// Temporary cache directory that is deleted when dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "smithy-identity-cache-test-{:016x}",
+                fastrand::u64(..)
+            ));
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[derive(Debug)]
+    struct Resolver {
+        token: &'static str,
+        calls: Arc<AtomicUsize>,
+    }
+    impl ResolveIdentity for Resolver {
+        fn resolve_identity<'a>(
+            &'a self,
+            _: &'a RuntimeComponents,
+            _config_bag: &'a ConfigBag,
+        ) -> IdentityFuture<'a> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let expiration = Some(epoch_secs(1000));
+            IdentityFuture::ready(Ok(Identity::new(
+                Token::new(self.token, expiration),
+                expiration,
+            )))
+        }
+    }
+
+    fn resolver(token: &'static str) -> (SharedIdentityResolver, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let resolver = SharedIdentityResolver::new(Resolver {
+            token,
+            calls: calls.clone(),
+        });
+        (resolver, calls)
+    }
+
+    /// Reverses the bytes of the payload, or fails to decrypt if `fail` is set
+    #[derive(Debug)]
+    struct ReverseCipher {
+        fail: bool,
+    }
+    impl IdentityCipher for ReverseCipher {
+        fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, BoxError> {
+            Ok(plaintext.iter().rev().copied().collect())
+        }
+
+        fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, BoxError> {
+            if self.fail {
+                return Err("wrong key".into());
+            }
+            Ok(ciphertext.iter().rev().copied().collect())
+        }
+    }
+
+    fn epoch_secs(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    fn components(time: &ManualTimeSource) -> RuntimeComponents {
+        RuntimeComponentsBuilder::for_tests()
+            .with_time_source(Some(time.clone()))
+            .with_sleep_impl(Some(TokioSleep::new()))
+            .build()
+            .unwrap()
+    }
+
+    fn builder(dir: &TempDir, resolver: &SharedIdentityResolver) -> PersistentCacheBuilder {
+        PersistentCacheBuilder::new()
+            .directory(&dir.0)
+            .resolver("test-profile", resolver)
+            .codec(TokenCodec::new())
+    }
+
+    async fn resolve_token(
+        cache: &SharedIdentityCache,
+        resolver: &SharedIdentityResolver,
+        components: &RuntimeComponents,
+    ) -> String {
+        let identity = cache
+            .resolve_cached_identity(resolver.clone(), components, &ConfigBag::base())
+            .await
+            .expect("success");
+        identity.data::<Token>().unwrap().token().to_string()
+    }
+
+    #[tokio::test]
+    async fn identities_are_shared_across_caches() {
+        let dir = TempDir::new();
+        let time = ManualTimeSource::new(epoch_secs(100));
+        let components = components(&time);
+
+        let (resolver_a, calls_a) = resolver("A");
+        let cache_a = builder(&dir, &resolver_a).build();
+        assert_eq!("A", resolve_token(&cache_a, &resolver_a, &components).await);
+        assert_eq!("A", resolve_token(&cache_a, &resolver_a, &components).await);
+        assert_eq!(1, calls_a.load(Ordering::SeqCst));
+        assert!(!dir.0.join("test-profile.lock").exists());
+
+        // A separate cache, as created by another process, loads the persisted identity
+        let (resolver_b, calls_b) = resolver("B");
+        let cache_b = builder(&dir, &resolver_b).build();
+        assert_eq!("A", resolve_token(&cache_b, &resolver_b, &components).await);
+        assert_eq!(0, calls_b.load(Ordering::SeqCst));
+
+        // Once the persisted identity expires, a new identity is resolved and persisted
+        time.set_time(epoch_secs(995));
+        assert_eq!("B", resolve_token(&cache_b, &resolver_b, &components).await);
+        assert_eq!(1, calls_b.load(Ordering::SeqCst));
+        let contents = fs::read_to_string(dir.0.join("test-profile.identity")).unwrap();
+        assert_eq!(
+            "smithy-identity-cache/2 test-profile http-token 1000\nB",
+            contents
+        );
+    }
+
+    #[tokio::test]
+    async fn encrypts_persisted_identities() {
+        let dir = TempDir::new();
+        let time = ManualTimeSource::new(epoch_secs(100));
+        let components = components(&time);
+
+        let (resolver_a, _) = resolver("secret");
+        let cache = builder(&dir, &resolver_a)
+            .cipher(ReverseCipher { fail: false })
+            .build();
+        resolve_token(&cache, &resolver_a, &components).await;
+        let contents = fs::read_to_string(dir.0.join("test-profile.identity")).unwrap();
+        assert_eq!(
+            "smithy-identity-cache/2 test-profile http-token 1000\nterces",
+            contents
+        );
+
+        let (resolver_b, calls_b) = resolver("B");
+        let cache = builder(&dir, &resolver_b)
+            .cipher(ReverseCipher { fail: false })
+            .build();
+        assert_eq!(
+            "secret",
+            resolve_token(&cache, &resolver_b, &components).await
+        );
+        assert_eq!(0, calls_b.load(Ordering::SeqCst));
+
+        // Identities that can't be decrypted are replaced
+        let (resolver_c, calls_c) = resolver("C");
+        let cache = builder(&dir, &resolver_c)
+            .cipher(ReverseCipher { fail: true })
+            .build();
+        assert_eq!("C", resolve_token(&cache, &resolver_c, &components).await);
+        assert_eq!(1, calls_c.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn only_registered_resolvers_are_persisted() {
+        let dir = TempDir::new();
+        let time = ManualTimeSource::new(epoch_secs(100));
+        let components = components(&time);
+
+        let (resolver_a, _) = resolver("A");
+        let (resolver_b, calls_b) = resolver("B");
+        let (resolver_c, calls_c) = resolver("C");
+        let cache = builder(&dir, &resolver_a)
+            .resolver("other-profile", &resolver_b)
+            .build();
+        // Resolvers are persisted in their own files, in any order
+        assert_eq!("B", resolve_token(&cache, &resolver_b, &components).await);
+        assert_eq!("A", resolve_token(&cache, &resolver_a, &components).await);
+        assert_eq!("C", resolve_token(&cache, &resolver_c, &components).await);
+        assert_eq!("C", resolve_token(&cache, &resolver_c, &components).await);
+        assert_eq!(1, calls_b.load(Ordering::SeqCst));
+        assert_eq!(1, calls_c.load(Ordering::SeqCst));
+        let contents = fs::read_to_string(dir.0.join("test-profile.identity")).unwrap();
+        assert_eq!(
+            "smithy-identity-cache/2 test-profile http-token 1000\nA",
+            contents
+        );
+        let contents = fs::read_to_string(dir.0.join("other-profile.identity")).unwrap();
+        assert_eq!(
+            "smithy-identity-cache/2 other-profile http-token 1000\nB",
+            contents
+        );
+        let mut files: Vec<_> = fs::read_dir(&dir.0)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        files.sort();
+        assert_eq!(
+            vec!["other-profile.identity", "test-profile.identity"],
+            files
+        );
+    }
+
+    #[tokio::test]
+    async fn ignores_identities_persisted_for_a_different_resolver() {
+        let dir = TempDir::new();
+        let time = ManualTimeSource::new(epoch_secs(100));
+        let components = components(&time);
+
+        fs::create_dir_all(&dir.0).unwrap();
+        fs::write(
+            dir.0.join("test-profile.identity"),
+            "smithy-identity-cache/2 other-profile http-token 1000\nB",
+        )
+        .unwrap();
+
+        let (resolver_a, calls_a) = resolver("A");
+        let cache = builder(&dir, &resolver_a).build();
+        assert_eq!("A", resolve_token(&cache, &resolver_a, &components).await);
+        assert_eq!(1, calls_a.load(Ordering::SeqCst));
+        let contents = fs::read_to_string(dir.0.join("test-profile.identity")).unwrap();
+        assert_eq!(
+            "smithy-identity-cache/2 test-profile http-token 1000\nA",
+            contents
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "each resolver must be registered with a different key")]
+    fn resolver_keys_must_be_unique() {
+        let dir = TempDir::new();
+        let (resolver_a, _) = resolver("A");
+        let (resolver_b, _) = resolver("B");
+        builder(&dir, &resolver_a)
+            .resolver("test-profile", &resolver_b)
+            .build();
+    }
+
+    #[tokio::test]
+    async fn invalidate_removes_the_persisted_identity() {
+        let dir = TempDir::new();
+        let time = ManualTimeSource::new(epoch_secs(100));
+        let components = components(&time);
+
+        let (resolver_a, calls_a) = resolver("A");
+        let cache = builder(&dir, &resolver_a).build();
+        resolve_token(&cache, &resolver_a, &components).await;
+        assert!(dir.0.join("test-profile.identity").exists());
+
+        cache.invalidate(resolver_a.cache_partition());
+        assert!(!dir.0.join("test-profile.identity").exists());
+        resolve_token(&cache, &resolver_a, &components).await;
+        assert_eq!(2, calls_a.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn resolves_without_the_lock_after_timing_out() {
+        let dir = TempDir::new();
+        let time = ManualTimeSource::new(epoch_secs(100));
+        let components = components(&time);
+
+        // Simulate another process holding the lock
+        fs::create_dir_all(&dir.0).unwrap();
+        fs::write(dir.0.join("test-profile.lock"), "").unwrap();
+
+        let (resolver_a, calls_a) = resolver("A");
+        let cache = builder(&dir, &resolver_a)
+            .lock_timeout(Duration::from_millis(100))
+            .build();
+        assert_eq!("A", resolve_token(&cache, &resolver_a, &components).await);
+        assert_eq!(1, calls_a.load(Ordering::SeqCst));
+        // The lock file belongs to the other process, so it isn't removed
+        assert!(dir.0.join("test-profile.lock").exists());
+        assert!(dir.0.join("test-profile.identity").exists());
+    }
+}
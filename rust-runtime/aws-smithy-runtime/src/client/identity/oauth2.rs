@@ -0,0 +1,634 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Identity resolvers that request access tokens from an OAuth 2.0 authorization server.
+//!
+//! [`OAuth2TokenResolver`] calls the authorization server's token endpoint with the client's
+//! [`HttpClient`], and resolves the access token as a [`Token`] identity for the HTTP bearer auth
+//! scheme. The token's `expires_in` is used as the identity expiration, so the client's identity
+//! cache requests a new token before the current one expires.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use aws_smithy_runtime::client::identity::oauth2::OAuth2TokenResolver;
+//!
+//! let resolver = OAuth2TokenResolver::client_credentials(
+//!     "https://auth.example.com/oauth2/token",
+//!     "my-client-id",
+//!     "my-client-secret",
+//! )
+//! .scope("orders:read")
+//! .build();
+//! # /*
+//! let config = some_service::Config::builder()
+//!     .bearer_token_resolver(resolver)
+//!     // ...
+//!     .build();
+//! # */
+//! # drop(resolver);
+//! ```
+
+use aws_smithy_http::query::fmt_string;
+use aws_smithy_json::deserialize::token::skip_value;
+use aws_smithy_json::deserialize::{json_token_iter, Token as JsonToken};
+use aws_smithy_runtime_api::box_error::BoxError;
+use aws_smithy_runtime_api::client::http::{HttpClient, HttpConnector, HttpConnectorSettings};
+use aws_smithy_runtime_api::client::identity::http::Token;
+use aws_smithy_runtime_api::client::identity::{Identity, IdentityFuture, ResolveIdentity};
+use aws_smithy_runtime_api::client::orchestrator::HttpRequest;
+use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
+use aws_smithy_types::base64;
+use aws_smithy_types::body::SdkBody;
+use aws_smithy_types::byte_stream::ByteStream;
+use aws_smithy_types::config_bag::ConfigBag;
+use std::borrow::Cow;
+use std::error::Error as StdError;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+const CLIENT_CREDENTIALS_GRANT: &str = "client_credentials";
+const REFRESH_TOKEN_GRANT: &str = "refresh_token";
+const JWT_BEARER_GRANT: &str = "urn:ietf:params:oauth:grant-type:jwt-bearer";
+
+/// Provides the signed JWT that is exchanged for an access token in the JWT bearer grant.
+///
+/// See [RFC 7523](https://www.rfc-editor.org/rfc/rfc7523) for the claims the assertion must have.
+pub trait ProvideAssertion: fmt::Debug + Send + Sync {
+    /// Returns a signed JWT assertion.
+    ///
+    /// `now` is the current time according to the client's time source, which should be used for
+    /// the `iat` and `exp` claims.
+    fn assertion(&self, now: SystemTime) -> Result<String, BoxError>;
+}
+
+/// How the client authenticates with the token endpoint
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum ClientAuthentication {
+    /// Send the client ID and secret in an HTTP basic `Authorization` header (`client_secret_basic`)
+    #[default]
+    Basic,
+    /// Send the client ID and secret as form parameters in the request body (`client_secret_post`)
+    RequestBody,
+}
+
+enum Grant {
+    ClientCredentials,
+    /// The refresh token is replaced when the authorization server rotates it
+    RefreshToken(Mutex<String>),
+    JwtBearer(Arc<dyn ProvideAssertion>),
+}
+
+impl fmt::Debug for Grant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ClientCredentials => f.write_str("ClientCredentials"),
+            Self::RefreshToken(_) => f.write_str("RefreshToken(** redacted **)"),
+            Self::JwtBearer(provider) => f.debug_tuple("JwtBearer").field(provider).finish(),
+        }
+    }
+}
+
+/// Identity resolver that requests access tokens from an OAuth 2.0 token endpoint.
+///
+/// Resolved identities are [`Token`]s that expire when the access token does. Tokens without an
+/// `expires_in` are given the identity cache's default expiration.
+pub struct OAuth2TokenResolver {
+    token_endpoint: String,
+    grant: Grant,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+    client_authentication: ClientAuthentication,
+    scopes: Vec<String>,
+    parameters: Vec<(String, String)>,
+}
+
+impl fmt::Debug for OAuth2TokenResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OAuth2TokenResolver")
+            .field("token_endpoint", &self.token_endpoint)
+            .field("grant", &self.grant)
+            .field("client_id", &self.client_id)
+            .field("client_secret", &"** redacted **")
+            .field("client_authentication", &self.client_authentication)
+            .field("scopes", &self.scopes)
+            .field("parameters", &self.parameters)
+            .finish()
+    }
+}
+
+impl OAuth2TokenResolver {
+    /// Creates a resolver that uses the client credentials grant.
+    pub fn client_credentials(
+        token_endpoint: impl Into<String>,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+    ) -> OAuth2TokenResolverBuilder {
+        OAuth2TokenResolverBuilder::new(token_endpoint.into(), Grant::ClientCredentials)
+            .client_id(client_id)
+            .client_secret(client_secret)
+    }
+
+    /// Creates a resolver that uses the refresh token grant.
+    ///
+    /// If the authorization server returns a new refresh token, it is used for the next request.
+    pub fn refresh_token(
+        token_endpoint: impl Into<String>,
+        refresh_token: impl Into<String>,
+    ) -> OAuth2TokenResolverBuilder {
+        OAuth2TokenResolverBuilder::new(
+            token_endpoint.into(),
+            Grant::RefreshToken(Mutex::new(refresh_token.into())),
+        )
+    }
+
+    /// Creates a resolver that uses the JWT bearer grant.
+    pub fn jwt_bearer(
+        token_endpoint: impl Into<String>,
+        assertion: impl ProvideAssertion + 'static,
+    ) -> OAuth2TokenResolverBuilder {
+        OAuth2TokenResolverBuilder::new(
+            token_endpoint.into(),
+            Grant::JwtBearer(Arc::new(assertion)),
+        )
+    }
+
+    fn token_request(&self, now: SystemTime) -> Result<HttpRequest, BoxError> {
+        let mut form: Vec<(&str, Cow<'_, str>)> = Vec::new();
+        match &self.grant {
+            Grant::ClientCredentials => {
+                form.push(("grant_type", CLIENT_CREDENTIALS_GRANT.into()));
+            }
+            Grant::RefreshToken(refresh_token) => {
+                form.push(("grant_type", REFRESH_TOKEN_GRANT.into()));
+                form.push((
+                    "refresh_token",
+                    refresh_token.lock().unwrap().clone().into(),
+                ));
+            }
+            Grant::JwtBearer(provider) => {
+                form.push(("grant_type", JWT_BEARER_GRANT.into()));
+                form.push(("assertion", provider.assertion(now)?.into()));
+            }
+        }
+        if !self.scopes.is_empty() {
+            form.push(("scope", self.scopes.join(" ").into()));
+        }
+        for (name, value) in &self.parameters {
+            form.push((name, value.into()));
+        }
+
+        let mut request = http_02x::Request::builder()
+            .method("POST")
+            .uri(&self.token_endpoint)
+            .header("content-type", "application/x-www-form-urlencoded")
+            .header("accept", "application/json");
+        match (
+            self.client_authentication,
+            &self.client_id,
+            &self.client_secret,
+        ) {
+            (ClientAuthentication::Basic, Some(client_id), Some(client_secret)) => {
+                let credentials =
+                    format!("{}:{}", fmt_string(client_id), fmt_string(client_secret));
+                request = request.header(
+                    "authorization",
+                    format!("Basic {}", base64::encode(credentials)),
+                );
+            }
+            (_, client_id, client_secret) => {
+                // Public clients, and clients using `client_secret_post`, identify themselves
+                // in the request body
+                if let Some(client_id) = client_id {
+                    form.push(("client_id", client_id.into()));
+                }
+                if let Some(client_secret) = client_secret {
+                    form.push(("client_secret", client_secret.into()));
+                }
+            }
+        }
+
+        let body = form
+            .iter()
+            .map(|(name, value)| format!("{}={}", fmt_string(name), fmt_string(value)))
+            .collect::<Vec<_>>()
+            .join("&");
+        Ok(request.body(SdkBody::from(body))?.try_into()?)
+    }
+
+    async fn resolve(&self, runtime_components: &RuntimeComponents) -> Result<Identity, BoxError> {
+        let http_client = runtime_components
+            .http_client()
+            .ok_or("OAuth2TokenResolver requires an HTTP client to be configured")?;
+        let time_source = runtime_components
+            .time_source()
+            .ok_or("OAuth2TokenResolver requires a time source to be configured")?;
+        let now = time_source.now();
+        let request = self.token_request(now)?;
+
+        let connector =
+            http_client.http_connector(&HttpConnectorSettings::default(), runtime_components);
+        let response = connector.call(request).await?;
+        let status = response.status();
+        let body = ByteStream::new(response.into_body())
+            .collect()
+            .await?
+            .into_bytes();
+        if !status.is_success() {
+            return Err(TokenEndpointError::from_response(status.as_u16(), &body).into());
+        }
+
+        let response = parse_token_response(&body)?;
+        if let Some(token_type) = &response.token_type {
+            if !token_type.eq_ignore_ascii_case("bearer") {
+                return Err(format!("unsupported OAuth 2.0 token type `{token_type}`").into());
+            }
+        }
+        if let (Grant::RefreshToken(refresh_token), Some(new_refresh_token)) =
+            (&self.grant, response.refresh_token)
+        {
+            *refresh_token.lock().unwrap() = new_refresh_token;
+        }
+        let expiration = response
+            .expires_in
+            .map(|expires_in| now + Duration::from_secs(expires_in));
+        tracing::debug!(token_endpoint = %self.token_endpoint, expiration = ?expiration, "received OAuth 2.0 access token");
+        Ok(Identity::new(
+            Token::new(response.access_token, expiration),
+            expiration,
+        ))
+    }
+}
+
+impl ResolveIdentity for OAuth2TokenResolver {
+    fn resolve_identity<'a>(
+        &'a self,
+        runtime_components: &'a RuntimeComponents,
+        _config_bag: &'a ConfigBag,
+    ) -> IdentityFuture<'a> {
+        IdentityFuture::new(self.resolve(runtime_components))
+    }
+}
+
+/// Builder for [`OAuth2TokenResolver`].
+#[derive(Debug)]
+pub struct OAuth2TokenResolverBuilder {
+    resolver: OAuth2TokenResolver,
+}
+
+impl OAuth2TokenResolverBuilder {
+    fn new(token_endpoint: String, grant: Grant) -> Self {
+        Self {
+            resolver: OAuth2TokenResolver {
+                token_endpoint,
+                grant,
+                client_id: None,
+                client_secret: None,
+                client_authentication: ClientAuthentication::default(),
+                scopes: Vec::new(),
+                parameters: Vec::new(),
+            },
+        }
+    }
+
+    /// Sets the client ID.
+    pub fn client_id(mut self, client_id: impl Into<String>) -> Self {
+        self.resolver.client_id = Some(client_id.into());
+        self
+    }
+
+    /// Sets the client secret of a confidential client.
+    pub fn client_secret(mut self, client_secret: impl Into<String>) -> Self {
+        self.resolver.client_secret = Some(client_secret.into());
+        self
+    }
+
+    /// Sets how the client ID and secret are sent to the token endpoint.
+    ///
+    /// Defaults to [`ClientAuthentication::Basic`].
+    pub fn client_authentication(mut self, client_authentication: ClientAuthentication) -> Self {
+        self.resolver.client_authentication = client_authentication;
+        self
+    }
+
+    /// Adds a scope to request.
+    pub fn scope(mut self, scope: impl Into<String>) -> Self {
+        self.resolver.scopes.push(scope.into());
+        self
+    }
+
+    /// Adds a form parameter to token requests, such as `audience` or `resource`.
+    pub fn parameter(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.resolver.parameters.push((name.into(), value.into()));
+        self
+    }
+
+    /// Builds the resolver.
+    pub fn build(self) -> OAuth2TokenResolver {
+        self.resolver
+    }
+}
+
+/// Error response from an OAuth 2.0 token endpoint
+#[derive(Debug)]
+pub struct TokenEndpointError {
+    status: u16,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+impl TokenEndpointError {
+    fn from_response(status: u16, body: &[u8]) -> Self {
+        let mut error = None;
+        let mut error_description = None;
+        // Not every server returns a JSON error response, so ignore unparseable bodies
+        let _ = json_parse_loop(body, |key, value| {
+            if let JsonToken::ValueString { value, .. } = value {
+                match key.as_ref() {
+                    "error" => error = Some(value.to_unescaped()?.into_owned()),
+                    "error_description" => {
+                        error_description = Some(value.to_unescaped()?.into_owned())
+                    }
+                    _ => {}
+                }
+            }
+            Ok(())
+        });
+        Self {
+            status,
+            error,
+            error_description,
+        }
+    }
+
+    /// HTTP status code of the response.
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// OAuth 2.0 error code, such as `invalid_grant` or `invalid_client`.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Human readable description of the error.
+    pub fn error_description(&self) -> Option<&str> {
+        self.error_description.as_deref()
+    }
+}
+
+impl fmt::Display for TokenEndpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "OAuth 2.0 token endpoint returned HTTP {}", self.status)?;
+        if let Some(error) = &self.error {
+            write!(f, ": {error}")?;
+        }
+        if let Some(description) = &self.error_description {
+            write!(f, " ({description})")?;
+        }
+        Ok(())
+    }
+}
+
+impl StdError for TokenEndpointError {}
+
+#[derive(Debug)]
+struct TokenResponse {
+    access_token: String,
+    token_type: Option<String>,
+    expires_in: Option<u64>,
+    refresh_token: Option<String>,
+}
+
+fn parse_token_response(body: &[u8]) -> Result<TokenResponse, BoxError> {
+    let mut access_token = None;
+    let mut token_type = None;
+    let mut expires_in = None;
+    let mut refresh_token = None;
+    json_parse_loop(body, |key, value| {
+        match (key.as_ref(), value) {
+            ("access_token", JsonToken::ValueString { value, .. }) => {
+                access_token = Some(value.to_unescaped()?.into_owned())
+            }
+            ("token_type", JsonToken::ValueString { value, .. }) => {
+                token_type = Some(value.to_unescaped()?.into_owned())
+            }
+            ("expires_in", JsonToken::ValueNumber { value, .. }) => {
+                expires_in = Some(u64::try_from(*value)?)
+            }
+            // Some authorization servers send `expires_in` as a string
+            ("expires_in", JsonToken::ValueString { value, .. }) => {
+                expires_in = Some(value.to_unescaped()?.parse()?)
+            }
+            ("refresh_token", JsonToken::ValueString { value, .. }) => {
+                refresh_token = Some(value.to_unescaped()?.into_owned())
+            }
+            _ => {}
+        }
+        Ok(())
+    })?;
+    Ok(TokenResponse {
+        access_token: access_token.ok_or("token response is missing `access_token`")?,
+        token_type,
+        expires_in,
+        refresh_token,
+    })
+}
+
+fn json_parse_loop<'a>(
+    input: &'a [u8],
+    mut f: impl FnMut(Cow<'a, str>, &JsonToken<'a>) -> Result<(), BoxError>,
+) -> Result<(), BoxError> {
+    let mut tokens = json_token_iter(input).peekable();
+    if !matches!(
+        tokens.next().transpose()?,
+        Some(JsonToken::StartObject { .. })
+    ) {
+        return Err("expected a JSON document starting with `{`".into());
+    }
+    loop {
+        match tokens.next().transpose()? {
+            Some(JsonToken::EndObject { .. }) => break,
+            Some(JsonToken::ObjectKey { key, .. }) => {
+                if let Some(Ok(token)) = tokens.peek() {
+                    f(key.to_unescaped()?, token)?;
+                }
+                skip_value(&mut tokens)?;
+            }
+            other => return Err(format!("expected object key, found: {other:?}").into()),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use super::*;
+    use crate::client::http::test_util::{ReplayEvent, StaticReplayClient};
+    use aws_smithy_async::test_util::ManualTimeSource;
+    use aws_smithy_runtime_api::client::runtime_components::RuntimeComponentsBuilder;
+    use std::time::UNIX_EPOCH;
+
+    const ENDPOINT: &str = "https://auth.example.com/oauth2/token";
+
+    fn token_request(authorization: Option<&str>, body: &str) -> http_02x::Request<SdkBody> {
+        let mut request = http_02x::Request::builder()
+            .method("POST")
+            .uri(ENDPOINT)
+            .header("content-type", "application/x-www-form-urlencoded")
+            .header("accept", "application/json");
+        if let Some(authorization) = authorization {
+            request = request.header("authorization", authorization);
+        }
+        request.body(SdkBody::from(body)).unwrap()
+    }
+
+    fn response(status: u16, body: &str) -> http_02x::Response<SdkBody> {
+        http_02x::Response::builder()
+            .status(status)
+            .body(SdkBody::from(body))
+            .unwrap()
+    }
+
+    fn components(http_client: &StaticReplayClient) -> RuntimeComponents {
+        RuntimeComponentsBuilder::for_tests()
+            .with_http_client(Some(http_client.clone()))
+            .with_time_source(Some(ManualTimeSource::new(UNIX_EPOCH)))
+            .build()
+            .unwrap()
+    }
+
+    async fn resolve(
+        resolver: &OAuth2TokenResolver,
+        components: &RuntimeComponents,
+    ) -> Result<Identity, BoxError> {
+        resolver
+            .resolve_identity(components, &ConfigBag::base())
+            .await
+    }
+
+    #[tokio::test]
+    async fn client_credentials() {
+        let http_client = StaticReplayClient::new(vec![ReplayEvent::new(
+            token_request(
+                // base64("client:s%20cret")
+                Some("Basic Y2xpZW50OnMlMjBjcmV0"),
+                "grant_type=client_credentials&scope=orders%3Aread%20orders%3Awrite&audience=orders",
+            ),
+            response(
+                200,
+                r#"{"access_token":"abc","token_type":"Bearer","expires_in":3600}"#,
+            ),
+        )]);
+        let resolver = OAuth2TokenResolver::client_credentials(ENDPOINT, "client", "s cret")
+            .scope("orders:read")
+            .scope("orders:write")
+            .parameter("audience", "orders")
+            .build();
+
+        let identity = resolve(&resolver, &components(&http_client)).await.unwrap();
+        let expiration = UNIX_EPOCH + Duration::from_secs(3600);
+        assert_eq!("abc", identity.data::<Token>().unwrap().token());
+        assert_eq!(Some(expiration), identity.expiration());
+        http_client.assert_requests_match(&[]);
+    }
+
+    #[tokio::test]
+    async fn refresh_token_rotation() {
+        let http_client = StaticReplayClient::new(vec![
+            ReplayEvent::new(
+                token_request(
+                    None,
+                    "grant_type=refresh_token&refresh_token=r1&client_id=app",
+                ),
+                response(200, r#"{"access_token":"a1","refresh_token":"r2"}"#),
+            ),
+            ReplayEvent::new(
+                token_request(
+                    None,
+                    "grant_type=refresh_token&refresh_token=r2&client_id=app",
+                ),
+                response(200, r#"{"access_token":"a2","expires_in":"60"}"#),
+            ),
+        ]);
+        let resolver = OAuth2TokenResolver::refresh_token(ENDPOINT, "r1")
+            .client_id("app")
+            .build();
+        let components = components(&http_client);
+
+        let identity = resolve(&resolver, &components).await.unwrap();
+        assert_eq!("a1", identity.data::<Token>().unwrap().token());
+        assert_eq!(None, identity.expiration());
+        let identity = resolve(&resolver, &components).await.unwrap();
+        assert_eq!("a2", identity.data::<Token>().unwrap().token());
+        assert_eq!(
+            Some(UNIX_EPOCH + Duration::from_secs(60)),
+            identity.expiration()
+        );
+        http_client.assert_requests_match(&[]);
+    }
+
+    #[tokio::test]
+    async fn jwt_bearer() {
+        #[derive(Debug)]
+        struct Assertion;
+        impl ProvideAssertion for Assertion {
+            fn assertion(&self, now: SystemTime) -> Result<String, BoxError> {
+                let iat = now.duration_since(UNIX_EPOCH).unwrap().as_secs();
+                Ok(format!("jwt-issued-at-{iat}"))
+            }
+        }
+
+        let http_client = StaticReplayClient::new(vec![ReplayEvent::new(
+            token_request(
+                None,
+                "grant_type=urn%3Aietf%3Aparams%3Aoauth%3Agrant-type%3Ajwt-bearer&assertion=jwt-issued-at-0&client_id=app&client_secret=secret",
+            ),
+            response(200, r#"{"access_token":"abc","token_type":"bearer"}"#),
+        )]);
+        let resolver = OAuth2TokenResolver::jwt_bearer(ENDPOINT, Assertion)
+            .client_id("app")
+            .client_secret("secret")
+            .client_authentication(ClientAuthentication::RequestBody)
+            .build();
+
+        let identity = resolve(&resolver, &components(&http_client)).await.unwrap();
+        assert_eq!("abc", identity.data::<Token>().unwrap().token());
+        http_client.assert_requests_match(&[]);
+    }
+
+    #[tokio::test]
+    async fn token_endpoint_errors() {
+        let http_client = StaticReplayClient::new(vec![
+            ReplayEvent::new(
+                token_request(None, "grant_type=refresh_token&refresh_token=r1"),
+                response(
+                    400,
+                    r#"{"error":"invalid_grant","error_description":"refresh token expired"}"#,
+                ),
+            ),
+            ReplayEvent::new(
+                token_request(None, "grant_type=refresh_token&refresh_token=r1"),
+                response(200, r#"{"access_token":"abc","token_type":"mac"}"#),
+            ),
+        ]);
+        let resolver = OAuth2TokenResolver::refresh_token(ENDPOINT, "r1").build();
+        let components = components(&http_client);
+
+        let err = resolve(&resolver, &components).await.unwrap_err();
+        let err = err.downcast_ref::<TokenEndpointError>().unwrap();
+        assert_eq!(400, err.status());
+        assert_eq!(Some("invalid_grant"), err.error());
+        assert_eq!(
+            "OAuth 2.0 token endpoint returned HTTP 400: invalid_grant (refresh token expired)",
+            err.to_string()
+        );
+
+        let err = resolve(&resolver, &components).await.unwrap_err();
+        assert_eq!("unsupported OAuth 2.0 token type `mac`", err.to_string());
+    }
+}
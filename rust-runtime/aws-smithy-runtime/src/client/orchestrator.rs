@@ -3,7 +3,8 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
-use self::auth::orchestrate_auth;
+use self::auth::{handle_auth_response, orchestrate_auth};
+use self::hedging::PreparedHedge;
 use crate::client::interceptors::Interceptors;
 use crate::client::orchestrator::http::{log_response_body, read_body};
 use crate::client::timeout::{MaybeTimeout, MaybeTimeoutConfig, TimeoutKind};
@@ -32,13 +33,15 @@ use aws_smithy_types::byte_stream::ByteStream;
 use aws_smithy_types::config_bag::ConfigBag;
 use aws_smithy_types::timeout::{MergeTimeoutConfig, TimeoutConfig};
 use std::mem;
-use tracing::{debug, debug_span, instrument, trace, Instrument};
+use tracing::{debug, debug_span, instrument, trace, warn, Instrument};
 
 mod auth;
 
 /// Defines types that implement a trait for endpoint resolution
 pub mod endpoints;
 
+pub mod hedging;
+
 /// Defines types that work with HTTP types
 mod http;
 
@@ -284,12 +287,23 @@ async fn try_op(
     // the request in the case of retry attempts.
     ctx.save_checkpoint();
     let mut retry_delay = None;
-    for i in 1u32.. {
+    let mut answered_auth_challenge = false;
+    let mut i = 0u32;
+    loop {
+        // Answering an auth challenge resends the attempt that was challenged, so it doesn't
+        // count as a new attempt.
+        if !answered_auth_challenge {
+            i += 1;
+        }
         // Break from the loop if we can't rewind the request's state. This will always succeed the
         // first time, but will fail on subsequent iterations if the request body wasn't retryable.
         trace!("checking if context can be rewound for attempt #{i}");
         if let RewindResult::Impossible = ctx.rewind(cfg) {
-            debug!("request cannot be retried since the request body cannot be cloned");
+            if answered_auth_challenge {
+                warn!("the auth challenge received in attempt #{i} can't be answered since the request body cannot be cloned");
+            } else {
+                debug!("request cannot be retried since the request body cannot be cloned");
+            }
             break;
         }
         // Track which attempt we're currently on.
@@ -316,6 +330,17 @@ async fn try_op(
         // We continue when encountering a timeout error. The retry classifier will decide what to do with it.
         continue_on_err!([ctx] => maybe_timeout);
 
+        // Challenge-response auth schemes, such as HTTP digest auth, can only sign a request after
+        // the server issued a challenge. The challenged attempt is signed and sent again without
+        // consulting the retry strategy or advancing `RequestAttempts`, but only one challenge is
+        // answered in a row so that rejected credentials can't loop forever.
+        if handle_auth_response(ctx, runtime_components, cfg) && !answered_auth_challenge {
+            debug!("answering the auth challenge received in attempt #{i}");
+            answered_auth_challenge = true;
+            continue;
+        }
+        answered_auth_challenge = false;
+
         // If we got a retry strategy from the bag, ask it what to do.
         // If no strategy was set, we won't retry.
         let should_attempt = halt_on_err!([ctx] => runtime_components
@@ -357,7 +382,9 @@ async fn try_attempt(
         read_before_signing(ctx, runtime_components, cfg);
     });
 
-    halt_on_err!([ctx] => orchestrate_auth(ctx, runtime_components, cfg).await.map_err(OrchestratorError::other));
+    let (selected_auth_scheme, identity_cache_handle) = halt_on_err!([ctx] => orchestrate_auth(ctx, runtime_components, cfg).await.map_err(OrchestratorError::other));
+    cfg.interceptor_state().store_put(selected_auth_scheme);
+    cfg.interceptor_state().store_put(identity_cache_handle);
 
     run_interceptors!(halt_on_err: {
         read_after_signing(ctx, runtime_components, cfg);
@@ -389,12 +416,17 @@ async fn try_attempt(
             builder.build()
         };
         let connector = http_client.http_connector(&settings, runtime_components);
+        let hedge = PreparedHedge::prepare(cfg, runtime_components, &connector, &request);
         let response_future = MaybeUploadThroughputCheckFuture::new(
             cfg,
             runtime_components,
             connector.call(request),
         );
-        response_future.await.map_err(OrchestratorError::connector)
+        match hedge {
+            Some(hedge) => hedge.race(response_future).await,
+            None => response_future.await,
+        }
+        .map_err(OrchestratorError::connector)
     });
     trace!(response = ?response, "received response from service");
     ctx.set_response(response);
@@ -1345,4 +1377,136 @@ mod tests {
             .read_after_execution_called
             .load(Ordering::Relaxed));
     }
+
+    /// Answering an auth challenge resends the challenged attempt, so it shouldn't use up one of
+    /// the attempts that the retry strategy allows.
+    #[tokio::test]
+    async fn answering_auth_challenges_does_not_count_as_an_attempt() {
+        use crate::client::http::test_util::{ReplayEvent, StaticReplayClient};
+        use crate::client::identity::no_auth::NoAuthIdentityResolver;
+        use crate::client::orchestrator::operation::Operation;
+        use crate::client::retries::classifiers::HttpStatusCodeClassifier;
+        use aws_smithy_async::rt::sleep::TokioSleep;
+        use aws_smithy_runtime_api::client::auth::{
+            AuthScheme, AuthSchemeEndpointConfig, AuthSchemeId, Sign,
+        };
+        use aws_smithy_runtime_api::client::identity::{Identity, SharedIdentityResolver};
+        use aws_smithy_runtime_api::client::orchestrator::HttpResponse;
+        use aws_smithy_runtime_api::client::runtime_components::GetIdentityResolver;
+        use aws_smithy_runtime_api::client::runtime_plugin::StaticRuntimePlugin;
+        use aws_smithy_types::retry::RetryConfig;
+        use std::convert::Infallible;
+        use std::time::Duration;
+
+        const CHALLENGE_SCHEME_ID: AuthSchemeId = AuthSchemeId::new("challenge");
+
+        /// Only signs requests once the server responded with a 401 challenge
+        #[derive(Debug, Default)]
+        struct ChallengeSigner {
+            challenged: AtomicBool,
+        }
+        impl Sign for ChallengeSigner {
+            fn sign_http_request(
+                &self,
+                request: &mut HttpRequest,
+                _identity: &Identity,
+                _auth_scheme_endpoint_config: AuthSchemeEndpointConfig<'_>,
+                _runtime_components: &RuntimeComponents,
+                _config_bag: &ConfigBag,
+            ) -> Result<(), BoxError> {
+                if self.challenged.load(Ordering::SeqCst) {
+                    request.headers_mut().insert("Authorization", "answered");
+                }
+                Ok(())
+            }
+
+            fn handle_response(
+                &self,
+                response: &HttpResponse,
+                _runtime_components: &RuntimeComponents,
+                _config_bag: &ConfigBag,
+            ) -> bool {
+                let challenged = response.status().as_u16() == 401;
+                self.challenged.store(challenged, Ordering::SeqCst);
+                challenged
+            }
+        }
+
+        #[derive(Debug, Default)]
+        struct ChallengeAuthScheme {
+            signer: ChallengeSigner,
+        }
+        impl AuthScheme for ChallengeAuthScheme {
+            fn scheme_id(&self) -> AuthSchemeId {
+                CHALLENGE_SCHEME_ID
+            }
+
+            fn identity_resolver(
+                &self,
+                identity_resolvers: &dyn GetIdentityResolver,
+            ) -> Option<SharedIdentityResolver> {
+                identity_resolvers.identity_resolver(CHALLENGE_SCHEME_ID)
+            }
+
+            fn signer(&self) -> &dyn Sign {
+                &self.signer
+            }
+        }
+
+        let event = |status: u16| {
+            ReplayEvent::new(
+                http_02x::Request::builder()
+                    .uri("http://localhost:1234/")
+                    .body(SdkBody::empty())
+                    .unwrap(),
+                Response::builder()
+                    .status(status)
+                    .body(SdkBody::empty())
+                    .unwrap(),
+            )
+        };
+        let http_client = StaticReplayClient::new(vec![event(401), event(503), event(200)]);
+        let operation = Operation::builder()
+            .service_name("test")
+            .operation_name("test")
+            .http_client(http_client.clone())
+            .endpoint_url("http://localhost:1234")
+            .no_auth()
+            .standard_retry(
+                &RetryConfig::standard()
+                    .with_max_attempts(2)
+                    .with_initial_backoff(Duration::from_millis(1)),
+            )
+            .retry_classifier(HttpStatusCodeClassifier::default())
+            .sleep_impl(TokioSleep::new())
+            .timeout_config(TimeoutConfig::disabled())
+            .runtime_plugin(
+                StaticRuntimePlugin::new().with_runtime_components(
+                    RuntimeComponentsBuilder::new("answering_auth_challenges")
+                        .with_auth_scheme_option_resolver(Some(
+                            StaticAuthSchemeOptionResolver::new(vec![CHALLENGE_SCHEME_ID]),
+                        ))
+                        .with_auth_scheme(ChallengeAuthScheme::default())
+                        .with_identity_resolver(CHALLENGE_SCHEME_ID, NoAuthIdentityResolver::new()),
+                ),
+            )
+            .serializer(|_: ()| Ok(HttpRequest::empty()))
+            .deserializer::<_, Infallible>(|response| {
+                if response.status().is_success() {
+                    Ok(())
+                } else {
+                    Err(OrchestratorError::other("unsuccessful response"))
+                }
+            })
+            .build();
+
+        // 401 -> signed resend of the first attempt -> 503 -> second attempt
+        operation.invoke(()).await.expect("success");
+
+        let authorizations: Vec<_> = http_client
+            .actual_requests()
+            .map(|request| request.headers().get("Authorization"))
+            .collect();
+        assert_eq!(vec![None, Some("answered"), None], authorizations);
+    }
 }
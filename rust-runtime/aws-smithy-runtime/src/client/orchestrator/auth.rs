@@ -11,10 +11,12 @@ use aws_smithy_runtime_api::client::auth::{
     ResolveAuthSchemeOptions,
 };
 use aws_smithy_runtime_api::client::identity::ResolveIdentity;
-use aws_smithy_runtime_api::client::identity::{IdentityCacheLocation, ResolveCachedIdentity};
+use aws_smithy_runtime_api::client::identity::{
+    IdentityCacheHandle, IdentityCacheLocation, ResolveCachedIdentity,
+};
 use aws_smithy_runtime_api::client::interceptors::context::InterceptorContext;
 use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
-use aws_smithy_types::config_bag::ConfigBag;
+use aws_smithy_types::config_bag::{ConfigBag, Storable, StoreReplace};
 use aws_smithy_types::endpoint::Endpoint;
 use aws_smithy_types::Document;
 use std::borrow::Cow;
@@ -109,11 +111,23 @@ impl fmt::Display for AuthOrchestrationError {
 
 impl StdError for AuthOrchestrationError {}
 
+/// The auth scheme that signed the current request attempt.
+#[derive(Copy, Clone, Debug)]
+pub(super) struct SelectedAuthScheme(AuthSchemeId);
+
+impl Storable for SelectedAuthScheme {
+    type Storer = StoreReplace<Self>;
+}
+
+/// Resolves an identity and signs the request with it.
+///
+/// Returns the auth scheme that was selected, and a handle to the identity cache partition
+/// that the identity came from.
 pub(super) async fn orchestrate_auth(
     ctx: &mut InterceptorContext,
     runtime_components: &RuntimeComponents,
     cfg: &ConfigBag,
-) -> Result<(), BoxError> {
+) -> Result<(SelectedAuthScheme, IdentityCacheHandle), BoxError> {
     let params = cfg
         .load::<AuthSchemeOptionResolverParams>()
         .expect("auth scheme option resolver params must be set");
@@ -157,6 +171,7 @@ pub(super) async fn orchestrate_auth(
                     Ok(auth_scheme_endpoint_config) => {
                         trace!(auth_scheme_endpoint_config = ?auth_scheme_endpoint_config, "extracted auth scheme endpoint config");
 
+                        let partition = identity_resolver.cache_partition();
                         let identity = identity_cache
                             .resolve_cached_identity(identity_resolver, runtime_components, cfg)
                             .await?;
@@ -171,7 +186,10 @@ pub(super) async fn orchestrate_auth(
                             runtime_components,
                             cfg,
                         )?;
-                        return Ok(());
+                        return Ok((
+                            SelectedAuthScheme(scheme_id),
+                            IdentityCacheHandle::new(identity_cache, partition),
+                        ));
                     }
                     Err(AuthOrchestrationError::MissingEndpointConfig) => {
                         explored.push(scheme_id, ExploreResult::MissingEndpointConfig);
@@ -190,6 +208,30 @@ pub(super) async fn orchestrate_auth(
     Err(NoMatchingAuthSchemeError(explored).into())
 }
 
+/// Lets the signer of the auth scheme that signed the last attempt inspect its response.
+///
+/// Returns `true` if the signer asked for the request to be signed and sent again, which
+/// challenge-response schemes such as HTTP digest auth do after receiving a challenge.
+pub(super) fn handle_auth_response(
+    ctx: &InterceptorContext,
+    runtime_components: &RuntimeComponents,
+    cfg: &ConfigBag,
+) -> bool {
+    let (Some(response), Some(SelectedAuthScheme(scheme_id))) =
+        (ctx.response(), cfg.load::<SelectedAuthScheme>())
+    else {
+        return false;
+    };
+    runtime_components
+        .auth_scheme(*scheme_id)
+        .map(|auth_scheme| {
+            auth_scheme
+                .signer()
+                .handle_response(response, runtime_components, cfg)
+        })
+        .unwrap_or(false)
+}
+
 fn extract_endpoint_auth_scheme_config(
     endpoint: &Endpoint,
     scheme_id: AuthSchemeId,
@@ -560,7 +602,9 @@ mod tests {
         use crate::client::auth::http::{ApiKeyAuthScheme, ApiKeyLocation};
         use aws_smithy_runtime_api::client::auth::http::HTTP_API_KEY_AUTH_SCHEME_ID;
         use aws_smithy_runtime_api::client::identity::http::Token;
+        use aws_smithy_runtime_api::client::identity::IdentityCachePartition;
         use aws_smithy_types::body::SdkBody;
+        use std::sync::{Arc, Mutex};
 
         let mut ctx = InterceptorContext::new(Input::doesnt_matter());
         ctx.enter_serialization_phase();
@@ -574,8 +618,10 @@ mod tests {
         let _ = ctx.take_input();
         ctx.enter_before_transmit_phase();
 
-        #[derive(Debug)]
-        struct Cache;
+        #[derive(Debug, Default)]
+        struct Cache {
+            invalidated: Arc<Mutex<Vec<IdentityCachePartition>>>,
+        }
         impl ResolveCachedIdentity for Cache {
             fn resolve_cached_identity<'a>(
                 &'a self,
@@ -585,7 +631,14 @@ mod tests {
             ) -> IdentityFuture<'a> {
                 IdentityFuture::ready(Ok(Identity::new(Token::new("cached (pass)", None), None)))
             }
+
+            fn invalidate(&self, partition: IdentityCachePartition) {
+                self.invalidated.lock().unwrap().push(partition);
+            }
         }
+        let cache = Cache::default();
+        let invalidated = cache.invalidated.clone();
+        let identity_resolver = SharedIdentityResolver::new(Token::new("uncached (fail)", None));
 
         let runtime_components = RuntimeComponentsBuilder::for_tests()
             .with_auth_scheme(SharedAuthScheme::new(ApiKeyAuthScheme::new(
@@ -596,11 +649,8 @@ mod tests {
             .with_auth_scheme_option_resolver(Some(SharedAuthSchemeOptionResolver::new(
                 StaticAuthSchemeOptionResolver::new(vec![HTTP_API_KEY_AUTH_SCHEME_ID]),
             )))
-            .with_identity_cache(Some(Cache))
-            .with_identity_resolver(
-                HTTP_API_KEY_AUTH_SCHEME_ID,
-                SharedIdentityResolver::new(Token::new("uncached (fail)", None)),
-            )
+            .with_identity_cache(Some(cache))
+            .with_identity_resolver(HTTP_API_KEY_AUTH_SCHEME_ID, identity_resolver.clone())
             .build()
             .unwrap();
         let mut layer = Layer::new("test");
@@ -608,7 +658,7 @@ mod tests {
         layer.store_put(AuthSchemeOptionResolverParams::new("doesntmatter"));
         let config_bag = ConfigBag::of_layers(vec![layer]);
 
-        orchestrate_auth(&mut ctx, &runtime_components, &config_bag)
+        let (_, handle) = orchestrate_auth(&mut ctx, &runtime_components, &config_bag)
             .await
             .expect("success");
         assert_eq!(
@@ -619,6 +669,14 @@ mod tests {
                 .get("Authorization")
                 .unwrap()
         );
+
+        // The returned handle invalidates the partition of the resolver that was used
+        assert_eq!(identity_resolver.cache_partition(), handle.partition());
+        handle.invalidate();
+        assert_eq!(
+            vec![identity_resolver.cache_partition()],
+            *invalidated.lock().unwrap()
+        );
     }
 
     #[test]
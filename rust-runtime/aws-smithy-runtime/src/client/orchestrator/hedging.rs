@@ -0,0 +1,631 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Hedged requests for latency-sensitive operations.
+//!
+//! When hedging is enabled for an operation, the orchestrator sends a second copy of an attempt's
+//! request if the first one hasn't been answered after a delay. Whichever response arrives first is
+//! used and the other request is cancelled. Hedging happens within a single attempt, so interceptors,
+//! retry strategies, and attempt timeouts see one attempt no matter which request answered.
+//!
+//! Hedging is opt-in. It's enabled by storing a [`HedgingConfig`] in the config bag of the
+//! operations that should be hedged, for example with a runtime plugin. Only operations that are
+//! safe to send twice are hedged:
+//!
+//! - The request's HTTP method must be idempotent, or the operation must be marked as idempotent
+//!   with [`HedgingConfig::idempotent`].
+//! - The request body must be cloneable.
+//!
+//! Every hedge costs the same as a retry in the client's retry [`TokenBucket`]. No hedge is sent
+//! when the bucket is empty, so hedging can't amplify load while a service is failing.
+
+use crate::client::retries::{TokenBucket, TokenBucketPermit};
+use aws_smithy_async::rt::sleep::{AsyncSleep, Sleep};
+use aws_smithy_async::time::SharedTimeSource;
+use aws_smithy_runtime_api::client::http::HttpConnector;
+use aws_smithy_runtime_api::client::http::SharedHttpConnector;
+use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, HttpResponse};
+use aws_smithy_runtime_api::client::result::ConnectorError;
+use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
+use aws_smithy_types::config_bag::{ConfigBag, Storable, StoreReplace};
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime};
+use tracing::debug;
+
+/// HTTP methods that RFC 9110 defines as idempotent
+const IDEMPOTENT_METHODS: &[&str] = &["GET", "HEAD", "OPTIONS", "TRACE", "PUT", "DELETE"];
+/// The number of recent latencies used to calculate a percentile delay
+const MAX_LATENCY_SAMPLES: usize = 100;
+/// Until this many latencies have been observed, the initial delay is used
+const MIN_LATENCY_SAMPLES: usize = 10;
+
+/// Configuration for hedged requests.
+///
+/// Clones of a `HedgingConfig` share their observed latencies, so a config that uses a
+/// [percentile delay](HedgingConfig::percentile_delay) learns from every operation it's used for.
+#[derive(Clone, Debug)]
+pub struct HedgingConfig {
+    delay: HedgeDelay,
+    idempotent: bool,
+}
+
+#[derive(Clone, Debug)]
+enum HedgeDelay {
+    Fixed(Duration),
+    Percentile {
+        percentile: f64,
+        initial_delay: Duration,
+        latencies: Latencies,
+    },
+}
+
+impl HedgingConfig {
+    /// Creates a `HedgingConfig` that sends a hedge once a request hasn't been answered after `delay`.
+    pub fn fixed_delay(delay: Duration) -> Self {
+        Self {
+            delay: HedgeDelay::Fixed(delay),
+            idempotent: false,
+        }
+    }
+
+    /// Creates a `HedgingConfig` that sends a hedge once a request has taken longer than the given
+    /// percentile of recently observed response latencies, e.g. `0.95` for the p95 latency.
+    ///
+    /// `initial_delay` is used until enough latencies have been observed. Latencies are measured
+    /// with the client's time source, so `initial_delay` is always used when there isn't one.
+    ///
+    /// Returns an error if `percentile` isn't greater than zero and at most one.
+    pub fn percentile_delay(
+        percentile: f64,
+        initial_delay: Duration,
+    ) -> Result<Self, InvalidHedgingConfig> {
+        if !(percentile > 0.0 && percentile <= 1.0) {
+            return Err(InvalidHedgingConfig {
+                message: format!(
+                    "percentile must be greater than zero and at most one, but was {percentile}"
+                ),
+            });
+        }
+        Ok(Self {
+            delay: HedgeDelay::Percentile {
+                percentile,
+                initial_delay,
+                latencies: Latencies::default(),
+            },
+            idempotent: false,
+        })
+    }
+
+    /// Marks the operation as idempotent, allowing it to be hedged regardless of its HTTP method.
+    ///
+    /// Many protocols send every request as a `POST`, so read-only operations using them must be
+    /// marked as idempotent to be hedged.
+    pub fn idempotent(mut self, idempotent: bool) -> Self {
+        self.idempotent = idempotent;
+        self
+    }
+
+    /// Returns how long a request currently waits before it's hedged.
+    pub fn delay(&self) -> Duration {
+        match &self.delay {
+            HedgeDelay::Fixed(delay) => *delay,
+            HedgeDelay::Percentile {
+                percentile,
+                initial_delay,
+                latencies,
+            } => latencies.percentile(*percentile).unwrap_or(*initial_delay),
+        }
+    }
+
+    fn record_latency(&self, latency: Duration) {
+        if let HedgeDelay::Percentile { latencies, .. } = &self.delay {
+            latencies.record(latency);
+        }
+    }
+}
+
+impl Storable for HedgingConfig {
+    type Storer = StoreReplace<Self>;
+}
+
+/// Failure to create a [`HedgingConfig`] because a parameter was out of range.
+#[derive(Debug)]
+pub struct InvalidHedgingConfig {
+    message: String,
+}
+
+impl fmt::Display for InvalidHedgingConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid hedging config: {}", self.message)
+    }
+}
+
+impl std::error::Error for InvalidHedgingConfig {}
+
+#[derive(Clone, Debug, Default)]
+struct Latencies(Arc<Mutex<VecDeque<Duration>>>);
+
+impl Latencies {
+    fn record(&self, latency: Duration) {
+        let mut latencies = self.0.lock().unwrap();
+        if latencies.len() == MAX_LATENCY_SAMPLES {
+            latencies.pop_front();
+        }
+        latencies.push_back(latency);
+    }
+
+    fn percentile(&self, percentile: f64) -> Option<Duration> {
+        let mut latencies: Vec<_> = self.0.lock().unwrap().iter().copied().collect();
+        if latencies.len() < MIN_LATENCY_SAMPLES {
+            return None;
+        }
+        latencies.sort_unstable();
+        let index = ((latencies.len() as f64 * percentile).ceil() as usize).saturating_sub(1);
+        Some(latencies[index])
+    }
+}
+
+type ResponseFuture = Pin<Box<dyn Future<Output = Result<HttpResponse, ConnectorError>> + Send>>;
+
+/// Everything needed to send a hedge once the delay has passed
+struct Hedge {
+    request: HttpRequest,
+    connector: SharedHttpConnector,
+    token_bucket: Option<TokenBucket>,
+}
+
+/// A request that's being sent, along with when it was sent
+struct InFlight {
+    future: ResponseFuture,
+    sent_at: Option<SystemTime>,
+}
+
+/// Future that resolves to the first response received for a request or its hedge.
+pub(crate) struct HedgedResponseFuture {
+    primary: Option<InFlight>,
+    hedge: Option<InFlight>,
+    delay: Option<Sleep>,
+    pending_hedge: Option<Hedge>,
+    hedge_permit: Option<TokenBucketPermit>,
+    config: HedgingConfig,
+    time_source: Option<SharedTimeSource>,
+    first_error: Option<ConnectorError>,
+}
+
+impl fmt::Debug for HedgedResponseFuture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HedgedResponseFuture")
+            .field("hedged", &self.hedge.is_some())
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+/// A hedge that's ready to be raced against a request, created with [`PreparedHedge::prepare`].
+pub(crate) struct PreparedHedge {
+    delay: Sleep,
+    hedge: Hedge,
+    config: HedgingConfig,
+    time_source: Option<SharedTimeSource>,
+}
+
+impl PreparedHedge {
+    /// Prepares a hedge for `request` if hedging is enabled and allowed for it.
+    ///
+    /// This must be called before `request` is sent, since the hedge is a copy of it.
+    pub(crate) fn prepare(
+        cfg: &ConfigBag,
+        runtime_components: &RuntimeComponents,
+        connector: &SharedHttpConnector,
+        request: &HttpRequest,
+    ) -> Option<Self> {
+        let config = cfg.load::<HedgingConfig>()?;
+        if !config.idempotent && !IDEMPOTENT_METHODS.contains(&request.method()) {
+            debug!(
+                method = request.method(),
+                "not hedging a request that isn't idempotent"
+            );
+            return None;
+        }
+        let Some(sleep_impl) = runtime_components.sleep_impl() else {
+            debug!("not hedging because no 'async sleep' implementation was set");
+            return None;
+        };
+        let Some(request) = request.try_clone() else {
+            debug!("not hedging a request whose body can't be cloned");
+            return None;
+        };
+        Some(Self {
+            delay: sleep_impl.sleep(config.delay()),
+            hedge: Hedge {
+                request,
+                connector: connector.clone(),
+                token_bucket: cfg.load::<TokenBucket>().cloned(),
+            },
+            config: config.clone(),
+            time_source: runtime_components.time_source(),
+        })
+    }
+
+    /// Returns a future that races `primary` against the hedge.
+    pub(crate) fn race(
+        self,
+        primary: impl Future<Output = Result<HttpResponse, ConnectorError>> + Send + 'static,
+    ) -> HedgedResponseFuture {
+        HedgedResponseFuture {
+            primary: Some(InFlight {
+                future: Box::pin(primary),
+                sent_at: self.time_source.as_ref().map(|ts| ts.now()),
+            }),
+            hedge: None,
+            delay: Some(self.delay),
+            pending_hedge: Some(self.hedge),
+            hedge_permit: None,
+            config: self.config,
+            time_source: self.time_source,
+            first_error: None,
+        }
+    }
+}
+
+impl HedgedResponseFuture {
+    fn send_hedge(&mut self) {
+        let Some(hedge) = self.pending_hedge.take() else {
+            return;
+        };
+        if let Some(token_bucket) = &hedge.token_bucket {
+            match token_bucket.acquire_hedge() {
+                Some(permit) => self.hedge_permit = Some(permit),
+                None => {
+                    debug!("not hedging because no retry permits are available");
+                    return;
+                }
+            }
+        }
+        debug!("request hasn't been answered yet; sending a hedge");
+        self.hedge = Some(InFlight {
+            future: Box::pin(hedge.connector.call(hedge.request)),
+            sent_at: self.time_source.as_ref().map(|ts| ts.now()),
+        });
+    }
+
+    fn finish(
+        &mut self,
+        result: Result<HttpResponse, ConnectorError>,
+        sent_at: Option<SystemTime>,
+    ) -> Result<HttpResponse, ConnectorError> {
+        // Dropping the futures cancels whichever request didn't win
+        self.primary = None;
+        self.hedge = None;
+        self.delay = None;
+        self.pending_hedge = None;
+
+        let failed = match &result {
+            Ok(response) => {
+                response.status().is_server_error() || response.status().as_u16() == 429
+            }
+            Err(_) => true,
+        };
+        if let Some(permit) = self.hedge_permit.take() {
+            if failed {
+                // Like a failed retry, a failed hedge permanently takes its cost out of the bucket
+                permit.forget();
+            }
+        }
+        if let (false, Some(sent_at), Some(time_source)) = (failed, sent_at, &self.time_source) {
+            if let Ok(latency) = time_source.now().duration_since(sent_at) {
+                self.config.record_latency(latency);
+            }
+        }
+        result
+    }
+}
+
+impl Future for HedgedResponseFuture {
+    type Output = Result<HttpResponse, ConnectorError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        if let Some(delay) = &mut this.delay {
+            if Pin::new(delay).poll(cx).is_ready() {
+                this.delay = None;
+                this.send_hedge();
+            }
+        }
+
+        for is_hedge in [false, true] {
+            let in_flight = if is_hedge {
+                &mut this.hedge
+            } else {
+                &mut this.primary
+            };
+            let Some(request) = in_flight else {
+                continue;
+            };
+            let Poll::Ready(result) = request.future.as_mut().poll(cx) else {
+                continue;
+            };
+            let sent_at = request.sent_at;
+            *in_flight = None;
+            match result {
+                Ok(response) => {
+                    if is_hedge {
+                        debug!("the hedge was answered first");
+                    }
+                    return Poll::Ready(this.finish(Ok(response), sent_at));
+                }
+                // Wait for the other request before giving up, or send the hedge now instead of
+                // waiting for the delay
+                Err(err) if this.primary.is_some() || this.hedge.is_some() => {
+                    this.first_error.get_or_insert(err);
+                }
+                Err(err) if this.pending_hedge.is_some() => {
+                    this.first_error.get_or_insert(err);
+                    this.delay = None;
+                    this.send_hedge();
+                    if this.hedge.is_some() {
+                        cx.waker().wake_by_ref();
+                        return Poll::Pending;
+                    }
+                    let err = this.first_error.take().expect("set above");
+                    return Poll::Ready(this.finish(Err(err), None));
+                }
+                Err(err) => {
+                    let err = this.first_error.take().unwrap_or(err);
+                    return Poll::Ready(this.finish(Err(err), None));
+                }
+            }
+        }
+        Poll::Pending
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use super::{HedgingConfig, PreparedHedge};
+    use crate::client::retries::TokenBucket;
+    use aws_smithy_async::rt::sleep::{SharedAsyncSleep, TokioSleep};
+    use aws_smithy_async::time::SystemTimeSource;
+    use aws_smithy_runtime_api::client::http::{
+        HttpConnector, HttpConnectorFuture, SharedHttpConnector,
+    };
+    use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, HttpResponse};
+    use aws_smithy_runtime_api::client::result::ConnectorError;
+    use aws_smithy_runtime_api::client::runtime_components::{
+        RuntimeComponents, RuntimeComponentsBuilder,
+    };
+    use aws_smithy_types::body::SdkBody;
+    use aws_smithy_types::config_bag::{ConfigBag, Layer};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Connector whose responses take longer for earlier requests
+    #[derive(Debug, Clone)]
+    struct SlowThenFastConnector {
+        calls: Arc<AtomicUsize>,
+        latencies: Vec<Duration>,
+        status: u16,
+    }
+
+    impl SlowThenFastConnector {
+        fn new(latencies: Vec<Duration>, status: u16) -> Self {
+            Self {
+                calls: Default::default(),
+                latencies,
+                status,
+            }
+        }
+    }
+
+    impl HttpConnector for SlowThenFastConnector {
+        fn call(&self, _request: HttpRequest) -> HttpConnectorFuture {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            let latency = self.latencies[call];
+            let status = self.status;
+            HttpConnectorFuture::new(async move {
+                tokio::time::sleep(latency).await;
+                let mut response = HttpResponse::new(status.try_into().unwrap(), SdkBody::empty());
+                response.headers_mut().insert("x-call", call.to_string());
+                Ok::<_, ConnectorError>(response)
+            })
+        }
+    }
+
+    fn runtime_components() -> RuntimeComponents {
+        RuntimeComponentsBuilder::for_tests()
+            .with_sleep_impl(Some(SharedAsyncSleep::new(TokioSleep::new())))
+            .with_time_source(Some(SystemTimeSource::new()))
+            .build()
+            .unwrap()
+    }
+
+    fn cfg(config: Option<HedgingConfig>, token_bucket: Option<TokenBucket>) -> ConfigBag {
+        let mut layer = Layer::new("test");
+        if let Some(config) = config {
+            layer.store_put(config);
+        }
+        if let Some(token_bucket) = token_bucket {
+            layer.store_put(token_bucket);
+        }
+        ConfigBag::of_layers(vec![layer])
+    }
+
+    fn request(method: &str) -> HttpRequest {
+        http_02x::Request::builder()
+            .method(method)
+            .uri("http://localhost")
+            .body(SdkBody::empty())
+            .unwrap()
+            .try_into()
+            .unwrap()
+    }
+
+    async fn send(
+        connector: &SlowThenFastConnector,
+        cfg: &ConfigBag,
+        request: HttpRequest,
+    ) -> HttpResponse {
+        let connector = SharedHttpConnector::new(connector.clone());
+        let hedge = PreparedHedge::prepare(cfg, &runtime_components(), &connector, &request);
+        let primary = connector.call(request);
+        match hedge {
+            Some(hedge) => hedge.race(primary).await,
+            None => primary.await,
+        }
+        .unwrap()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn hedge_answers_first() {
+        let connector = SlowThenFastConnector::new(
+            vec![Duration::from_secs(10), Duration::from_millis(100)],
+            200,
+        );
+        let cfg = cfg(
+            Some(HedgingConfig::fixed_delay(Duration::from_millis(500))),
+            None,
+        );
+        let response = send(&connector, &cfg, request("GET")).await;
+        assert_eq!("1", response.headers().get("x-call").unwrap());
+        assert_eq!(2, connector.calls.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn no_hedge_when_answered_before_delay() {
+        let connector = SlowThenFastConnector::new(vec![Duration::from_millis(100)], 200);
+        let cfg = cfg(
+            Some(HedgingConfig::fixed_delay(Duration::from_millis(500))),
+            None,
+        );
+        let response = send(&connector, &cfg, request("GET")).await;
+        assert_eq!("0", response.headers().get("x-call").unwrap());
+        assert_eq!(1, connector.calls.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn non_idempotent_requests_are_not_hedged() {
+        let latencies = vec![Duration::from_secs(10), Duration::from_millis(100)];
+        let config = HedgingConfig::fixed_delay(Duration::from_millis(500));
+
+        let connector = SlowThenFastConnector::new(latencies.clone(), 200);
+        send(
+            &connector,
+            &cfg(Some(config.clone()), None),
+            request("POST"),
+        )
+        .await;
+        assert_eq!(1, connector.calls.load(Ordering::SeqCst));
+
+        let connector = SlowThenFastConnector::new(latencies, 200);
+        let cfg = cfg(Some(config.idempotent(true)), None);
+        let response = send(&connector, &cfg, request("POST")).await;
+        assert_eq!("1", response.headers().get("x-call").unwrap());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn hedges_spend_retry_tokens() {
+        let latencies = vec![Duration::from_secs(10), Duration::from_millis(100)];
+        let config = HedgingConfig::fixed_delay(Duration::from_millis(500));
+
+        // Failed hedges permanently remove their cost from the bucket
+        let token_bucket = TokenBucket::new(5);
+        let connector = SlowThenFastConnector::new(latencies.clone(), 503);
+        let cfg = cfg(Some(config), Some(token_bucket.clone()));
+        let response = send(&connector, &cfg, request("GET")).await;
+        assert_eq!("1", response.headers().get("x-call").unwrap());
+        assert_eq!(0, token_bucket.available_permits());
+
+        // With an empty bucket, no hedge is sent
+        let connector = SlowThenFastConnector::new(latencies, 200);
+        let response = send(&connector, &cfg, request("GET")).await;
+        assert_eq!("0", response.headers().get("x-call").unwrap());
+        assert_eq!(1, connector.calls.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn successful_hedges_return_their_tokens() {
+        let token_bucket = TokenBucket::new(5);
+        let connector = SlowThenFastConnector::new(
+            vec![Duration::from_secs(10), Duration::from_millis(100)],
+            200,
+        );
+        let cfg = cfg(
+            Some(HedgingConfig::fixed_delay(Duration::from_millis(500))),
+            Some(token_bucket.clone()),
+        );
+        send(&connector, &cfg, request("GET")).await;
+        assert_eq!(5, token_bucket.available_permits());
+    }
+
+    #[test]
+    fn percentile_delay_follows_observed_latencies() {
+        let config = HedgingConfig::percentile_delay(0.9, Duration::from_secs(1)).unwrap();
+        let clone = config.clone();
+        for millis in 1..10 {
+            clone.record_latency(Duration::from_millis(millis));
+        }
+        assert_eq!(Duration::from_secs(1), config.delay());
+
+        clone.record_latency(Duration::from_millis(10));
+        assert_eq!(Duration::from_millis(9), config.delay());
+
+        // Only recent latencies are used
+        for _ in 0..100 {
+            clone.record_latency(Duration::from_millis(50));
+        }
+        assert_eq!(Duration::from_millis(50), config.delay());
+    }
+
+    #[test]
+    fn percentile_must_be_in_range() {
+        for percentile in [0.0, 1.5, -0.5, f64::NAN] {
+            let err = HedgingConfig::percentile_delay(percentile, Duration::from_secs(1))
+                .expect_err("out of range");
+            assert_eq!(
+                format!("invalid hedging config: percentile must be greater than zero and at most one, but was {percentile}"),
+                err.to_string()
+            );
+        }
+        HedgingConfig::percentile_delay(1.0, Duration::from_secs(1)).expect("p100 is valid");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn operations_use_the_first_response() {
+        use crate::client::orchestrator::operation::Operation;
+        use aws_smithy_runtime_api::client::http::http_client_fn;
+        use std::convert::Infallible;
+
+        let connector = SlowThenFastConnector::new(
+            vec![Duration::from_secs(10), Duration::from_millis(100)],
+            200,
+        );
+        let http_connector = SharedHttpConnector::new(connector.clone());
+        let operation = Operation::builder()
+            .service_name("test")
+            .operation_name("test")
+            .http_client(http_client_fn(move |_, _| http_connector.clone()))
+            .endpoint_url("http://localhost:1234")
+            .no_auth()
+            .no_retry()
+            .timeout_config(aws_smithy_types::timeout::TimeoutConfig::disabled())
+            .sleep_impl(TokioSleep::new())
+            .hedging(HedgingConfig::fixed_delay(Duration::from_millis(500)).idempotent(true))
+            .serializer(|_: ()| Ok(HttpRequest::new(SdkBody::empty())))
+            .deserializer::<_, Infallible>(|response| {
+                Ok(response.headers().get("x-call").unwrap().to_string())
+            })
+            .build();
+
+        assert_eq!("1", operation.invoke(()).await.unwrap());
+        assert_eq!(2, connector.calls.load(Ordering::SeqCst));
+    }
+}
@@ -9,6 +9,7 @@ use crate::client::http::connection_poisoning::ConnectionPoisoningInterceptor;
 use crate::client::identity::no_auth::NoAuthIdentityResolver;
 use crate::client::identity::IdentityCache;
 use crate::client::orchestrator::endpoints::StaticUriEndpointResolver;
+use crate::client::orchestrator::hedging::HedgingConfig;
 use crate::client::retries::strategy::{NeverRetryStrategy, StandardRetryStrategy};
 use aws_smithy_async::rt::sleep::AsyncSleep;
 use aws_smithy_async::time::TimeSource;
@@ -304,6 +305,12 @@ impl<I, O, E> OperationBuilder<I, O, E> {
         self
     }
 
+    /// Enables hedged requests with the given config.
+    pub fn hedging(mut self, hedging_config: HedgingConfig) -> Self {
+        self.config.store_put(hedging_config);
+        self
+    }
+
     /// Configures the serializer for the builder.
     pub fn serializer<I2>(
         mut self,
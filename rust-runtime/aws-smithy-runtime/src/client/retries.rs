@@ -9,14 +9,20 @@ pub mod classifiers;
 /// Smithy retry strategies.
 pub mod strategy;
 
+mod circuit_breaker;
 mod client_rate_limiter;
 mod token_bucket;
 
 use aws_smithy_types::config_bag::{Storable, StoreReplace};
 use std::fmt;
 
-pub use client_rate_limiter::ClientRateLimiter;
-pub use token_bucket::TokenBucket;
+pub use circuit_breaker::{
+    CircuitBreaker, CircuitBreakerConfig, CircuitBreakerConfigBuilder, CircuitOpenError,
+    CircuitState, InvalidCircuitBreakerConfig,
+};
+pub use client_rate_limiter::{ClientRateLimiter, ClientRateLimiterSnapshot};
+pub(crate) use token_bucket::TokenBucketPermit;
+pub use token_bucket::{TokenBucket, TokenBucketStats};
 
 pub use client_rate_limiter::ClientRateLimiterPartition;
 use std::borrow::Cow;
@@ -0,0 +1,579 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! A circuit breaker that stops requests from being sent to a dependency that keeps failing.
+
+use crate::client::retries::RetryPartition;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tracing::debug;
+
+const DEFAULT_FAILURE_RATE_THRESHOLD: f64 = 0.5;
+const DEFAULT_MINIMUM_REQUESTS: u32 = 20;
+const DEFAULT_WINDOW: Duration = Duration::from_secs(30);
+const DEFAULT_OPEN_DURATION: Duration = Duration::from_secs(10);
+const DEFAULT_HALF_OPEN_MAX_REQUESTS: u32 = 1;
+/// The sliding window is divided into this many buckets
+const WINDOW_BUCKETS: u32 = 10;
+
+/// Configuration for a [`CircuitBreaker`].
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq)]
+pub struct CircuitBreakerConfig {
+    failure_rate_threshold: f64,
+    minimum_requests: u32,
+    window: Duration,
+    open_duration: Duration,
+    half_open_max_requests: u32,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_rate_threshold: DEFAULT_FAILURE_RATE_THRESHOLD,
+            minimum_requests: DEFAULT_MINIMUM_REQUESTS,
+            window: DEFAULT_WINDOW,
+            open_duration: DEFAULT_OPEN_DURATION,
+            half_open_max_requests: DEFAULT_HALF_OPEN_MAX_REQUESTS,
+        }
+    }
+}
+
+impl CircuitBreakerConfig {
+    /// Creates a new builder.
+    pub fn builder() -> CircuitBreakerConfigBuilder {
+        CircuitBreakerConfigBuilder::default()
+    }
+
+    /// Returns the fraction of failed requests within the window that opens the circuit.
+    pub fn failure_rate_threshold(&self) -> f64 {
+        self.failure_rate_threshold
+    }
+
+    /// Returns the number of requests that must have been made within the window before the
+    /// circuit can open.
+    pub fn minimum_requests(&self) -> u32 {
+        self.minimum_requests
+    }
+
+    /// Returns the length of the sliding window that the failure rate is calculated over.
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Returns how long the circuit stays open before requests are allowed to probe for recovery.
+    pub fn open_duration(&self) -> Duration {
+        self.open_duration
+    }
+
+    /// Returns how many probe requests may be in flight while the circuit is half-open.
+    pub fn half_open_max_requests(&self) -> u32 {
+        self.half_open_max_requests
+    }
+}
+
+/// Builder for [`CircuitBreakerConfig`].
+#[non_exhaustive]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CircuitBreakerConfigBuilder {
+    failure_rate_threshold: Option<f64>,
+    minimum_requests: Option<u32>,
+    window: Option<Duration>,
+    open_duration: Option<Duration>,
+    half_open_max_requests: Option<u32>,
+}
+
+impl CircuitBreakerConfigBuilder {
+    /// Sets the fraction of failed requests within the window that opens the circuit. Must be
+    /// greater than zero and at most one. Defaults to `0.5`.
+    pub fn set_failure_rate_threshold(&mut self, failure_rate_threshold: Option<f64>) -> &mut Self {
+        self.failure_rate_threshold = failure_rate_threshold;
+        self
+    }
+
+    /// Sets the fraction of failed requests within the window that opens the circuit. Must be
+    /// greater than zero and at most one. Defaults to `0.5`.
+    pub fn failure_rate_threshold(mut self, failure_rate_threshold: f64) -> Self {
+        self.set_failure_rate_threshold(Some(failure_rate_threshold));
+        self
+    }
+
+    /// Sets the number of requests that must have been made within the window before the circuit
+    /// can open. Defaults to `20`.
+    pub fn set_minimum_requests(&mut self, minimum_requests: Option<u32>) -> &mut Self {
+        self.minimum_requests = minimum_requests;
+        self
+    }
+
+    /// Sets the number of requests that must have been made within the window before the circuit
+    /// can open. Defaults to `20`.
+    pub fn minimum_requests(mut self, minimum_requests: u32) -> Self {
+        self.set_minimum_requests(Some(minimum_requests));
+        self
+    }
+
+    /// Sets the length of the sliding window that the failure rate is calculated over. Must be
+    /// non-zero. Defaults to 30 seconds.
+    pub fn set_window(&mut self, window: Option<Duration>) -> &mut Self {
+        self.window = window;
+        self
+    }
+
+    /// Sets the length of the sliding window that the failure rate is calculated over. Must be
+    /// non-zero. Defaults to 30 seconds.
+    pub fn window(mut self, window: Duration) -> Self {
+        self.set_window(Some(window));
+        self
+    }
+
+    /// Sets how long the circuit stays open before requests are allowed to probe for recovery.
+    /// Defaults to 10 seconds.
+    pub fn set_open_duration(&mut self, open_duration: Option<Duration>) -> &mut Self {
+        self.open_duration = open_duration;
+        self
+    }
+
+    /// Sets how long the circuit stays open before requests are allowed to probe for recovery.
+    /// Defaults to 10 seconds.
+    pub fn open_duration(mut self, open_duration: Duration) -> Self {
+        self.set_open_duration(Some(open_duration));
+        self
+    }
+
+    /// Sets how many probe requests may be in flight while the circuit is half-open. Must be
+    /// greater than zero. Defaults to `1`.
+    pub fn set_half_open_max_requests(&mut self, half_open_max_requests: Option<u32>) -> &mut Self {
+        self.half_open_max_requests = half_open_max_requests;
+        self
+    }
+
+    /// Sets how many probe requests may be in flight while the circuit is half-open. Must be
+    /// greater than zero. Defaults to `1`.
+    pub fn half_open_max_requests(mut self, half_open_max_requests: u32) -> Self {
+        self.set_half_open_max_requests(Some(half_open_max_requests));
+        self
+    }
+
+    /// Builds a `CircuitBreakerConfig`, or returns an error if a parameter is out of range.
+    pub fn build(self) -> Result<CircuitBreakerConfig, InvalidCircuitBreakerConfig> {
+        let failure_rate_threshold = self
+            .failure_rate_threshold
+            .unwrap_or(DEFAULT_FAILURE_RATE_THRESHOLD);
+        if !(failure_rate_threshold > 0.0 && failure_rate_threshold <= 1.0) {
+            return Err(InvalidCircuitBreakerConfig::new(format!(
+                "failure_rate_threshold must be greater than zero and at most one, but was {failure_rate_threshold}"
+            )));
+        }
+        let window = self.window.unwrap_or(DEFAULT_WINDOW);
+        if window.is_zero() {
+            return Err(InvalidCircuitBreakerConfig::new("window must be non-zero"));
+        }
+        let half_open_max_requests = self
+            .half_open_max_requests
+            .unwrap_or(DEFAULT_HALF_OPEN_MAX_REQUESTS);
+        if half_open_max_requests == 0 {
+            return Err(InvalidCircuitBreakerConfig::new(
+                "half_open_max_requests must be greater than zero",
+            ));
+        }
+        Ok(CircuitBreakerConfig {
+            failure_rate_threshold,
+            minimum_requests: self.minimum_requests.unwrap_or(DEFAULT_MINIMUM_REQUESTS),
+            window,
+            open_duration: self.open_duration.unwrap_or(DEFAULT_OPEN_DURATION),
+            half_open_max_requests,
+        })
+    }
+}
+
+/// Failure to build a [`CircuitBreakerConfig`] because a parameter was out of range.
+#[derive(Debug)]
+pub struct InvalidCircuitBreakerConfig {
+    message: String,
+}
+
+impl InvalidCircuitBreakerConfig {
+    fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for InvalidCircuitBreakerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid circuit breaker config: {}", self.message)
+    }
+}
+
+impl std::error::Error for InvalidCircuitBreakerConfig {}
+
+/// The state of a [`CircuitBreaker`].
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests are sent normally.
+    Closed,
+    /// Requests fail fast without being sent.
+    Open,
+    /// A limited number of requests are sent to probe whether the dependency has recovered.
+    HalfOpen,
+}
+
+/// The error returned when a request isn't sent because its circuit breaker is open.
+#[derive(Debug)]
+pub struct CircuitOpenError {
+    partition: RetryPartition,
+    retry_after: Duration,
+}
+
+impl CircuitOpenError {
+    /// Returns the retry partition whose circuit is open.
+    pub fn partition(&self) -> &RetryPartition {
+        &self.partition
+    }
+
+    /// Returns how long it will be until requests are allowed to probe for recovery.
+    pub fn retry_after(&self) -> Duration {
+        self.retry_after
+    }
+}
+
+impl fmt::Display for CircuitOpenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the circuit breaker for `{}` is open, so the request was not sent. Requests will be allowed again in {:?}",
+            self.partition, self.retry_after
+        )
+    }
+}
+
+impl std::error::Error for CircuitOpenError {}
+
+#[derive(Debug)]
+struct Bucket {
+    start: SystemTime,
+    successes: u32,
+    failures: u32,
+}
+
+#[derive(Debug)]
+enum State {
+    Closed,
+    Open { until: SystemTime },
+    HalfOpen { since: SystemTime, probes: u32 },
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: State,
+    buckets: VecDeque<Bucket>,
+}
+
+/// A circuit breaker for a single [`RetryPartition`].
+///
+/// The circuit starts out closed. It opens once the failure rate within a sliding window reaches
+/// the configured threshold. While it's open, requests fail fast with a [`CircuitOpenError`].
+/// After the open duration, the circuit becomes half-open and lets a limited number of probe
+/// requests through. A successful probe closes the circuit and a failed probe opens it again.
+#[derive(Clone, Debug)]
+pub struct CircuitBreaker {
+    partition: RetryPartition,
+    config: CircuitBreakerConfig,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl CircuitBreaker {
+    /// Creates a new, closed `CircuitBreaker` for the given partition.
+    pub fn new(partition: RetryPartition, config: CircuitBreakerConfig) -> Self {
+        Self {
+            partition,
+            config,
+            inner: Arc::new(Mutex::new(Inner {
+                state: State::Closed,
+                buckets: VecDeque::new(),
+            })),
+        }
+    }
+
+    /// Returns the current state of the circuit.
+    ///
+    /// An open circuit only becomes half-open once a request is attempted after the open
+    /// duration has passed.
+    pub fn state(&self) -> CircuitState {
+        match self.inner.lock().unwrap().state {
+            State::Closed => CircuitState::Closed,
+            State::Open { .. } => CircuitState::Open,
+            State::HalfOpen { .. } => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Returns the failure rate within the sliding window, or `None` if no requests were made.
+    pub fn failure_rate(&self, now: SystemTime) -> Option<f64> {
+        let mut inner = self.inner.lock().unwrap();
+        self.expire_buckets(&mut inner, now);
+        let (successes, failures) = totals(&inner.buckets);
+        let total = successes + failures;
+        (total > 0).then(|| failures as f64 / total as f64)
+    }
+
+    /// Asks for permission to send a request, returning an error if the circuit is open.
+    pub(crate) fn acquire(&self, now: SystemTime) -> Result<(), CircuitOpenError> {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.state {
+            State::Closed => Ok(()),
+            State::Open { until } => match until.duration_since(now) {
+                Ok(retry_after) if !retry_after.is_zero() => Err(self.open_error(retry_after)),
+                _ => {
+                    debug!(partition = %self.partition, "circuit breaker is half-open");
+                    inner.state = State::HalfOpen {
+                        since: now,
+                        probes: 1,
+                    };
+                    Ok(())
+                }
+            },
+            State::HalfOpen {
+                ref mut since,
+                ref mut probes,
+            } => {
+                // Probes whose outcome was never recorded (e.g. because the operation timed out)
+                // mustn't keep the circuit half-open forever
+                let elapsed = now.duration_since(*since).unwrap_or_default();
+                if elapsed >= self.config.open_duration {
+                    *since = now;
+                    *probes = 0;
+                }
+                if *probes < self.config.half_open_max_requests {
+                    *probes += 1;
+                    Ok(())
+                } else {
+                    Err(self.open_error(self.config.open_duration.saturating_sub(elapsed)))
+                }
+            }
+        }
+    }
+
+    /// Records the outcome of a request.
+    pub(crate) fn record(&self, now: SystemTime, success: bool) {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.state {
+            State::HalfOpen { .. } if success => {
+                debug!(partition = %self.partition, "probe succeeded, closing circuit breaker");
+                inner.state = State::Closed;
+                inner.buckets.clear();
+                return;
+            }
+            State::HalfOpen { .. } => {
+                debug!(partition = %self.partition, "probe failed, reopening circuit breaker");
+                self.open(&mut inner, now);
+                return;
+            }
+            // Outcomes of requests that were sent before the circuit opened don't matter
+            State::Open { .. } => return,
+            State::Closed => {}
+        }
+
+        self.expire_buckets(&mut inner, now);
+        let bucket_width = self.config.window / WINDOW_BUCKETS;
+        let needs_new_bucket = match inner.buckets.back() {
+            Some(bucket) => now.duration_since(bucket.start).unwrap_or_default() >= bucket_width,
+            None => true,
+        };
+        if needs_new_bucket {
+            inner.buckets.push_back(Bucket {
+                start: now,
+                successes: 0,
+                failures: 0,
+            });
+        }
+        let bucket = inner.buckets.back_mut().expect("pushed above");
+        if success {
+            bucket.successes += 1;
+        } else {
+            bucket.failures += 1;
+        }
+
+        let (successes, failures) = totals(&inner.buckets);
+        let total = successes + failures;
+        if total >= self.config.minimum_requests
+            && failures as f64 / total as f64 >= self.config.failure_rate_threshold
+        {
+            debug!(
+                partition = %self.partition,
+                failures,
+                total,
+                "failure rate threshold reached, opening circuit breaker"
+            );
+            self.open(&mut inner, now);
+        }
+    }
+
+    fn open(&self, inner: &mut Inner, now: SystemTime) {
+        inner.state = State::Open {
+            until: now + self.config.open_duration,
+        };
+        inner.buckets.clear();
+    }
+
+    fn open_error(&self, retry_after: Duration) -> CircuitOpenError {
+        CircuitOpenError {
+            partition: self.partition.clone(),
+            retry_after,
+        }
+    }
+
+    fn expire_buckets(&self, inner: &mut Inner, now: SystemTime) {
+        while let Some(bucket) = inner.buckets.front() {
+            if now.duration_since(bucket.start).unwrap_or_default() >= self.config.window {
+                inner.buckets.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+fn totals(buckets: &VecDeque<Bucket>) -> (u32, u32) {
+    buckets
+        .iter()
+        .fold((0, 0), |(successes, failures), bucket| {
+            (successes + bucket.successes, failures + bucket.failures)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CircuitBreaker, CircuitBreakerConfig, CircuitBreakerConfigBuilder, CircuitState};
+    use crate::client::retries::RetryPartition;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    fn circuit_breaker() -> CircuitBreaker {
+        CircuitBreaker::new(
+            RetryPartition::new("test"),
+            CircuitBreakerConfig::builder()
+                .failure_rate_threshold(0.5)
+                .minimum_requests(4)
+                .window(Duration::from_secs(10))
+                .open_duration(Duration::from_secs(5))
+                .build()
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn config_parameters_are_validated() {
+        let err = |builder: CircuitBreakerConfigBuilder| builder.build().unwrap_err().to_string();
+        assert!(
+            err(CircuitBreakerConfig::builder().failure_rate_threshold(0.0))
+                .contains("failure_rate_threshold")
+        );
+        assert!(
+            err(CircuitBreakerConfig::builder().failure_rate_threshold(1.5))
+                .contains("failure_rate_threshold")
+        );
+        assert!(err(CircuitBreakerConfig::builder().window(Duration::ZERO)).contains("window"));
+        assert!(
+            err(CircuitBreakerConfig::builder().half_open_max_requests(0))
+                .contains("half_open_max_requests")
+        );
+        assert_eq!(
+            CircuitBreakerConfig::default(),
+            CircuitBreakerConfig::builder().build().unwrap()
+        );
+    }
+
+    #[test]
+    fn opens_once_failure_rate_threshold_is_reached() {
+        let cb = circuit_breaker();
+        cb.record(at(0), true);
+        cb.record(at(0), false);
+        cb.record(at(1), true);
+        assert_eq!(CircuitState::Closed, cb.state());
+
+        cb.record(at(1), false);
+        assert_eq!(CircuitState::Open, cb.state());
+        let err = cb.acquire(at(2)).unwrap_err();
+        assert_eq!(Duration::from_secs(4), err.retry_after());
+        assert_eq!("test", err.partition().to_string());
+    }
+
+    #[test]
+    fn does_not_open_below_minimum_requests() {
+        let cb = circuit_breaker();
+        for _ in 0..3 {
+            cb.record(at(0), false);
+        }
+        assert_eq!(CircuitState::Closed, cb.state());
+        assert!(cb.acquire(at(0)).is_ok());
+    }
+
+    #[test]
+    fn old_outcomes_fall_out_of_the_window() {
+        let cb = circuit_breaker();
+        cb.record(at(0), false);
+        cb.record(at(0), false);
+        cb.record(at(0), false);
+        assert_eq!(Some(1.0), cb.failure_rate(at(5)));
+
+        // The failures are more than 10 seconds old by now
+        cb.record(at(11), false);
+        assert_eq!(CircuitState::Closed, cb.state());
+        assert_eq!(Some(1.0), cb.failure_rate(at(11)));
+        assert_eq!(None, cb.failure_rate(at(30)));
+    }
+
+    #[test]
+    fn half_opens_after_open_duration_and_closes_on_successful_probe() {
+        let cb = circuit_breaker();
+        for _ in 0..4 {
+            cb.record(at(0), false);
+        }
+        assert!(cb.acquire(at(4)).is_err());
+
+        // The first request after the open duration is a probe; others have to wait for it
+        assert!(cb.acquire(at(5)).is_ok());
+        assert_eq!(CircuitState::HalfOpen, cb.state());
+        assert!(cb.acquire(at(5)).is_err());
+
+        cb.record(at(6), true);
+        assert_eq!(CircuitState::Closed, cb.state());
+        assert!(cb.acquire(at(6)).is_ok());
+    }
+
+    #[test]
+    fn failed_probe_reopens_the_circuit() {
+        let cb = circuit_breaker();
+        for _ in 0..4 {
+            cb.record(at(0), false);
+        }
+        assert!(cb.acquire(at(5)).is_ok());
+        cb.record(at(6), false);
+        assert_eq!(CircuitState::Open, cb.state());
+        assert_eq!(
+            Duration::from_secs(5),
+            cb.acquire(at(6)).unwrap_err().retry_after()
+        );
+    }
+
+    #[test]
+    fn lost_probes_are_replaced_after_open_duration() {
+        let cb = circuit_breaker();
+        for _ in 0..4 {
+            cb.record(at(0), false);
+        }
+        assert!(cb.acquire(at(5)).is_ok());
+        // The probe's outcome is never recorded
+        assert!(cb.acquire(at(7)).is_err());
+        assert!(cb.acquire(at(10)).is_ok());
+    }
+}
@@ -9,21 +9,56 @@
 #![allow(dead_code)]
 
 use crate::client::retries::RetryPartition;
+use aws_smithy_types::config_bag::{Storable, StoreReplace};
+use aws_smithy_types::retry::AdaptiveRetryConfig;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tracing::debug;
 
 /// Represents a partition for the rate limiter, e.g. an endpoint, a region
+///
+/// Clients only share a rate limiter if their adaptive retry configs match too, so that a client
+/// never silently gets the tuning of another client in the same retry partition.
 #[non_exhaustive]
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub struct ClientRateLimiterPartition {
     retry_partition: RetryPartition,
+    tuning: AdaptiveRetryTuning,
 }
 
 impl ClientRateLimiterPartition {
-    /// Creates a `ClientRateLimiterPartition` from the given [`RetryPartition`]
+    /// Creates a `ClientRateLimiterPartition` from the given [`RetryPartition`], for rate limiters
+    /// with the default [`AdaptiveRetryConfig`]
     pub fn new(retry_partition: RetryPartition) -> Self {
-        Self { retry_partition }
+        Self {
+            retry_partition,
+            tuning: AdaptiveRetryTuning::from(&AdaptiveRetryConfig::default()),
+        }
+    }
+
+    /// Sets the [`AdaptiveRetryConfig`] of the rate limiters in this partition
+    pub fn with_adaptive_retry_config(
+        mut self,
+        adaptive_retry_config: &AdaptiveRetryConfig,
+    ) -> Self {
+        self.tuning = AdaptiveRetryTuning::from(adaptive_retry_config);
+        self
+    }
+}
+
+/// The parameters of an [`AdaptiveRetryConfig`] that affect a rate limiter, compared by their
+/// bit patterns since floats can't be hashed.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+struct AdaptiveRetryTuning([u64; 4]);
+
+impl From<&AdaptiveRetryConfig> for AdaptiveRetryTuning {
+    fn from(config: &AdaptiveRetryConfig) -> Self {
+        Self([
+            config.beta().to_bits(),
+            config.scale_constant().to_bits(),
+            config.smoothing().to_bits(),
+            config.min_fill_rate().to_bits(),
+        ])
     }
 }
 
@@ -31,20 +66,82 @@ const RETRY_COST: f64 = 5.0;
 const RETRY_TIMEOUT_COST: f64 = RETRY_COST * 2.0;
 const INITIAL_REQUEST_COST: f64 = 1.0;
 
-const MIN_FILL_RATE: f64 = 0.5;
 const MIN_CAPACITY: f64 = 1.0;
-const SMOOTH: f64 = 0.8;
-/// How much to scale back after receiving a throttling response
-const BETA: f64 = 0.7;
-/// Controls how aggressively we scale up after being throttled
-const SCALE_CONSTANT: f64 = 0.4;
 
 /// Rate limiter for adaptive retry.
+///
+/// If a `ClientRateLimiter` is stored in the config bag, the standard retry strategy uses it
+/// instead of creating one. This allows a rate limiter to be shared by specific clients, and
+/// observed with [`ClientRateLimiter::snapshot`]. Throttling errors and changes to the send rate
+/// are also emitted as `debug` events.
 #[derive(Clone, Debug)]
 pub struct ClientRateLimiter {
     inner: Arc<Mutex<Inner>>,
 }
 
+impl Storable for ClientRateLimiter {
+    type Storer = StoreReplace<Self>;
+}
+
+/// A point-in-time view of the state of a [`ClientRateLimiter`].
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClientRateLimiterSnapshot {
+    enabled: bool,
+    fill_rate: f64,
+    max_capacity: f64,
+    current_capacity: f64,
+    measured_tx_rate: f64,
+    last_max_rate: f64,
+    throttle_events: u64,
+    delayed_requests: u64,
+}
+
+impl ClientRateLimiterSnapshot {
+    /// Returns `true` if the rate limiter is limiting requests. Rate limiting is enabled once a
+    /// throttling error has been received.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Returns the rate, in tokens per second, at which the rate limiter allows requests to be
+    /// sent. An initial request costs one token, and retries cost more.
+    pub fn send_rate(&self) -> f64 {
+        self.fill_rate
+    }
+
+    /// Returns the maximum number of tokens the rate limiter can hold.
+    pub fn max_tokens(&self) -> f64 {
+        self.max_capacity
+    }
+
+    /// Returns the number of tokens that were available when the rate limiter was last used.
+    /// This is negative when requests have been delayed to pay off a token deficit.
+    pub fn available_tokens(&self) -> f64 {
+        self.current_capacity
+    }
+
+    /// Returns the smoothed rate, in requests per second, at which requests are being sent.
+    pub fn measured_send_rate(&self) -> f64 {
+        self.measured_tx_rate
+    }
+
+    /// Returns the send rate at the time of the last throttling error.
+    pub fn send_rate_at_last_throttle(&self) -> f64 {
+        self.last_max_rate
+    }
+
+    /// Returns the number of throttling errors the rate limiter has reacted to.
+    pub fn throttle_events(&self) -> u64 {
+        self.throttle_events
+    }
+
+    /// Returns the number of requests the rate limiter has delayed.
+    pub fn delayed_requests(&self) -> u64 {
+        self.delayed_requests
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct Inner {
     /// The rate at which token are replenished.
@@ -69,6 +166,18 @@ pub(crate) struct Inner {
     last_max_rate: f64,
     /// The last time when the client was throttled.
     time_of_last_throttle: f64,
+    /// How much to scale back after receiving a throttling response
+    beta: f64,
+    /// Controls how aggressively we scale up after being throttled
+    scale_constant: f64,
+    /// The weight given to the latest measurement when smoothing the measured rate
+    smooth: f64,
+    /// The lowest rate the token bucket will be refilled at
+    min_fill_rate: f64,
+    /// The number of throttling errors received
+    throttle_events: u64,
+    /// The number of requests that were delayed
+    delayed_requests: u64,
 }
 
 pub(crate) enum RequestReason {
@@ -80,13 +189,37 @@ pub(crate) enum RequestReason {
 impl ClientRateLimiter {
     /// Creates a new `ClientRateLimiter`
     pub fn new(seconds_since_unix_epoch: f64) -> Self {
+        Self::with_adaptive_retry_config(seconds_since_unix_epoch, &AdaptiveRetryConfig::default())
+    }
+
+    /// Creates a new `ClientRateLimiter` that is tuned by the given [`AdaptiveRetryConfig`]
+    pub fn with_adaptive_retry_config(
+        seconds_since_unix_epoch: f64,
+        config: &AdaptiveRetryConfig,
+    ) -> Self {
         Self::builder()
-            .tokens_retrieved_per_second(MIN_FILL_RATE)
+            .adaptive_retry_config(config.clone())
+            .tokens_retrieved_per_second(config.min_fill_rate())
             .time_of_last_throttle(seconds_since_unix_epoch)
             .previous_time_bucket(seconds_since_unix_epoch.floor())
             .build()
     }
 
+    /// Returns a snapshot of the current state of this rate limiter
+    pub fn snapshot(&self) -> ClientRateLimiterSnapshot {
+        let it = self.inner.lock().unwrap();
+        ClientRateLimiterSnapshot {
+            enabled: it.enabled,
+            fill_rate: it.fill_rate,
+            max_capacity: it.max_capacity,
+            current_capacity: it.current_capacity,
+            measured_tx_rate: it.measured_tx_rate,
+            last_max_rate: it.last_max_rate,
+            throttle_events: it.throttle_events,
+            delayed_requests: it.delayed_requests,
+        }
+    }
+
     fn builder() -> Builder {
         Builder::new()
     }
@@ -112,6 +245,7 @@ impl ClientRateLimiter {
 
         let res = if amount > it.current_capacity {
             let sleep_time = (amount - it.current_capacity) / it.fill_rate;
+            it.delayed_requests += 1;
             debug!(
                 amount,
                 it.current_capacity,
@@ -149,7 +283,13 @@ impl ClientRateLimiter {
             it.last_max_rate = rate_to_use;
             it.calculate_time_window();
             it.time_of_last_throttle = seconds_since_unix_epoch;
-            calculated_rate = cubic_throttle(rate_to_use);
+            calculated_rate = it.cubic_throttle(rate_to_use);
+            it.throttle_events += 1;
+            debug!(
+                send_rate_at_throttle = rate_to_use,
+                throttle_events = it.throttle_events,
+                "client rate limiter received a throttling error"
+            );
             it.enable_token_bucket();
         } else {
             it.calculate_time_window();
@@ -179,7 +319,7 @@ impl Inner {
         // Refill based on our current rate before we update to the new fill rate.
         self.refill(seconds_since_unix_epoch);
 
-        self.fill_rate = f64::max(new_fill_rate, MIN_FILL_RATE);
+        self.fill_rate = f64::max(new_fill_rate, self.min_fill_rate);
         self.max_capacity = f64::max(new_fill_rate, MIN_CAPACITY);
 
         debug!(
@@ -209,26 +349,27 @@ impl Inner {
         if next_time_bucket > self.last_tx_rate_bucket {
             let current_rate =
                 self.request_count as f64 / (next_time_bucket - self.last_tx_rate_bucket);
-            self.measured_tx_rate = current_rate * SMOOTH + self.measured_tx_rate * (1.0 - SMOOTH);
+            self.measured_tx_rate =
+                current_rate * self.smooth + self.measured_tx_rate * (1.0 - self.smooth);
             self.request_count = 0;
             self.last_tx_rate_bucket = next_time_bucket;
         }
     }
 
     fn calculate_time_window(&self) -> f64 {
-        let base = (self.last_max_rate * (1.0 - BETA)) / SCALE_CONSTANT;
+        let base = (self.last_max_rate * (1.0 - self.beta)) / self.scale_constant;
         base.powf(1.0 / 3.0)
     }
 
     fn cubic_success(&self, seconds_since_unix_epoch: f64) -> f64 {
         let dt =
             seconds_since_unix_epoch - self.time_of_last_throttle - self.calculate_time_window();
-        (SCALE_CONSTANT * dt.powi(3)) + self.last_max_rate
+        (self.scale_constant * dt.powi(3)) + self.last_max_rate
     }
-}
 
-fn cubic_throttle(rate_to_use: f64) -> f64 {
-    rate_to_use * BETA
+    fn cubic_throttle(&self, rate_to_use: f64) -> f64 {
+        rate_to_use * self.beta
+    }
 }
 
 #[derive(Clone, Debug, Default)]
@@ -253,6 +394,8 @@ struct Builder {
     tokens_retrieved_per_second_at_time_of_last_throttle: Option<f64>,
     ///The last time when the client was throttled.
     time_of_last_throttle: Option<f64>,
+    ///The parameters that tune the rate limiter.
+    adaptive_retry_config: Option<AdaptiveRetryConfig>,
 }
 
 impl Builder {
@@ -370,8 +513,14 @@ impl Builder {
         self.time_of_last_throttle = Some(time_of_last_throttle);
         self
     }
+    ///The parameters that tune the rate limiter.
+    fn adaptive_retry_config(mut self, adaptive_retry_config: AdaptiveRetryConfig) -> Self {
+        self.adaptive_retry_config = Some(adaptive_retry_config);
+        self
+    }
 
     fn build(self) -> ClientRateLimiter {
+        let config = self.adaptive_retry_config.unwrap_or_default();
         ClientRateLimiter {
             inner: Arc::new(Mutex::new(Inner {
                 fill_rate: self.token_refill_rate.unwrap_or_default(),
@@ -386,6 +535,12 @@ impl Builder {
                     .tokens_retrieved_per_second_at_time_of_last_throttle
                     .unwrap_or_default(),
                 time_of_last_throttle: self.time_of_last_throttle.unwrap_or_default(),
+                beta: config.beta(),
+                scale_constant: config.scale_constant(),
+                smooth: config.smoothing(),
+                min_fill_rate: config.min_fill_rate(),
+                throttle_events: 0,
+                delayed_requests: 0,
             })),
         }
     }
@@ -393,11 +548,12 @@ impl Builder {
 
 #[cfg(test)]
 mod tests {
-    use super::{cubic_throttle, ClientRateLimiter};
+    use super::ClientRateLimiter;
     use crate::client::retries::client_rate_limiter::RequestReason;
     use approx::assert_relative_eq;
     use aws_smithy_async::rt::sleep::AsyncSleep;
     use aws_smithy_async::test_util::instant_time_and_sleep;
+    use aws_smithy_types::retry::AdaptiveRetryConfig;
     use std::time::{Duration, SystemTime};
 
     const ONE_SECOND: Duration = Duration::from_secs(1);
@@ -405,7 +561,12 @@ mod tests {
 
     #[test]
     fn should_match_beta_decrease() {
-        let new_rate = cubic_throttle(10.0);
+        let new_rate = ClientRateLimiter::builder()
+            .build()
+            .inner
+            .lock()
+            .unwrap()
+            .cubic_throttle(10.0);
         assert_relative_eq!(new_rate, 7.0);
 
         let rate_limiter = ClientRateLimiter::builder()
@@ -558,7 +719,7 @@ mod tests {
             let mut inner = rate_limiter.inner.lock().unwrap();
             inner.calculate_time_window();
             if attempt.throttled {
-                calculated_rate = cubic_throttle(calculated_rate);
+                calculated_rate = inner.cubic_throttle(calculated_rate);
                 inner.time_of_last_throttle = attempt.seconds_since_unix_epoch;
                 inner.last_max_rate = calculated_rate;
             } else {
@@ -745,4 +906,54 @@ mod tests {
             max_relative = 0.0001
         );
     }
+
+    #[test]
+    fn tuned_beta_scales_back_further_after_throttling() {
+        let config = AdaptiveRetryConfig::builder().beta(0.5).build().unwrap();
+        let rate_limiter = ClientRateLimiter::builder()
+            .adaptive_retry_config(config)
+            .build();
+
+        let new_rate = rate_limiter.inner.lock().unwrap().cubic_throttle(10.0);
+        assert_relative_eq!(new_rate, 5.0);
+    }
+
+    #[test]
+    fn tuned_min_fill_rate_is_respected() {
+        let config = AdaptiveRetryConfig::builder()
+            .min_fill_rate(2.0)
+            .build()
+            .unwrap();
+        let rate_limiter = ClientRateLimiter::with_adaptive_retry_config(0.0, &config);
+
+        // A throttle with no measured traffic would drop the rate to zero without the minimum
+        rate_limiter.update_rate_limiter(0.0, true);
+        assert_relative_eq!(rate_limiter.snapshot().send_rate(), 2.0);
+    }
+
+    #[test]
+    fn snapshot_reflects_throttling() {
+        let rate_limiter = ClientRateLimiter::new(0.0);
+        let snapshot = rate_limiter.snapshot();
+        assert!(!snapshot.is_enabled());
+        assert_eq!(0, snapshot.throttle_events());
+
+        rate_limiter.update_rate_limiter(0.0, true);
+        let snapshot = rate_limiter.snapshot();
+        assert!(snapshot.is_enabled());
+        assert_eq!(1, snapshot.throttle_events());
+        assert_relative_eq!(snapshot.send_rate(), 0.5);
+        assert_relative_eq!(snapshot.max_tokens(), 1.0);
+        assert_relative_eq!(snapshot.measured_send_rate(), 0.5);
+        assert_relative_eq!(snapshot.send_rate_at_last_throttle(), 0.5);
+
+        // The bucket starts out empty, so the request has to wait for a token to be refilled
+        let delay = rate_limiter
+            .acquire_permission_to_send_a_request(0.0, RequestReason::InitialRequest)
+            .unwrap_err();
+        assert_eq!(Duration::from_secs(2), delay);
+        let snapshot = rate_limiter.snapshot();
+        assert_relative_eq!(snapshot.available_tokens(), -1.0);
+        assert_eq!(1, snapshot.delayed_requests());
+    }
 }
@@ -3,8 +3,10 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
+mod circuit_breaker;
 mod never;
 pub(crate) mod standard;
 
+pub use circuit_breaker::CircuitBreakerRetryStrategy;
 pub use never::NeverRetryStrategy;
 pub use standard::StandardRetryStrategy;
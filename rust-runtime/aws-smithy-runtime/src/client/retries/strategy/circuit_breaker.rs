@@ -0,0 +1,348 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use aws_smithy_runtime_api::box_error::BoxError;
+use aws_smithy_runtime_api::client::interceptors::context::InterceptorContext;
+use aws_smithy_runtime_api::client::retries::classifiers::{RetryAction, RetryReason};
+use aws_smithy_runtime_api::client::retries::{RetryStrategy, SharedRetryStrategy, ShouldAttempt};
+use aws_smithy_runtime_api::client::runtime_components::{
+    RuntimeComponents, RuntimeComponentsBuilder,
+};
+use aws_smithy_types::config_bag::ConfigBag;
+use aws_smithy_types::retry::ErrorKind;
+use tracing::debug;
+
+use crate::client::retries::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig};
+use crate::client::retries::classifiers::run_classifiers_on_ctx;
+use crate::client::retries::RetryPartition;
+use crate::static_partition_map::StaticPartitionMap;
+
+/// Retry partition used when no [`RetryPartition`] is set in the config bag
+const DEFAULT_PARTITION: RetryPartition = RetryPartition {
+    name: std::borrow::Cow::Borrowed("default"),
+};
+
+/// Retry strategy that wraps another retry strategy with a [`CircuitBreaker`] per [`RetryPartition`].
+///
+/// Every attempt's outcome is recorded by the circuit breaker for the operation's retry
+/// partition. Attempts that fail with a transient, server, or throttling error count as failures,
+/// and anything else counts as a success. Once the circuit opens, operations fail fast with a
+/// [`CircuitOpenError`](crate::client::retries::CircuitOpenError) and in-progress operations
+/// stop retrying. All other decisions are made by the wrapped retry strategy.
+///
+/// Circuit breakers are tracked with the client's time source, so this strategy fails config
+/// validation when the client doesn't have one.
+#[derive(Debug)]
+pub struct CircuitBreakerRetryStrategy {
+    inner: SharedRetryStrategy,
+    config: CircuitBreakerConfig,
+    circuit_breakers: StaticPartitionMap<RetryPartition, CircuitBreaker>,
+}
+
+impl CircuitBreakerRetryStrategy {
+    /// Creates a new `CircuitBreakerRetryStrategy` that wraps `inner`, using the default
+    /// [`CircuitBreakerConfig`].
+    pub fn new(inner: impl RetryStrategy + 'static) -> Self {
+        Self {
+            inner: SharedRetryStrategy::new(inner),
+            config: CircuitBreakerConfig::default(),
+            circuit_breakers: StaticPartitionMap::new(),
+        }
+    }
+
+    /// Sets the configuration for this strategy's circuit breakers.
+    pub fn with_config(mut self, config: CircuitBreakerConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Returns the circuit breaker for the given retry partition.
+    pub fn circuit_breaker(&self, partition: &RetryPartition) -> CircuitBreaker {
+        self.circuit_breakers.get_or_init(partition.clone(), || {
+            CircuitBreaker::new(partition.clone(), self.config.clone())
+        })
+    }
+
+    fn circuit_breaker_for(&self, cfg: &ConfigBag) -> CircuitBreaker {
+        self.circuit_breaker(cfg.load::<RetryPartition>().unwrap_or(&DEFAULT_PARTITION))
+    }
+}
+
+fn missing_time_source() -> BoxError {
+    "The circuit breaker retry strategy requires a time source to be configured. \
+     Set a time source using the `time_source` method on config."
+        .into()
+}
+
+impl RetryStrategy for CircuitBreakerRetryStrategy {
+    fn should_attempt_initial_request(
+        &self,
+        runtime_components: &RuntimeComponents,
+        cfg: &ConfigBag,
+    ) -> Result<ShouldAttempt, BoxError> {
+        let time_source = runtime_components
+            .time_source()
+            .ok_or_else(missing_time_source)?;
+        self.circuit_breaker_for(cfg).acquire(time_source.now())?;
+        self.inner
+            .should_attempt_initial_request(runtime_components, cfg)
+    }
+
+    fn should_attempt_retry(
+        &self,
+        ctx: &InterceptorContext,
+        runtime_components: &RuntimeComponents,
+        cfg: &ConfigBag,
+    ) -> Result<ShouldAttempt, BoxError> {
+        let time_source = runtime_components
+            .time_source()
+            .ok_or_else(missing_time_source)?;
+        let circuit_breaker = self.circuit_breaker_for(cfg);
+        let failed = matches!(
+            run_classifiers_on_ctx(runtime_components.retry_classifiers(), ctx),
+            RetryAction::RetryIndicated(RetryReason::RetryableError { kind, .. })
+                if kind != ErrorKind::ClientError
+        );
+        circuit_breaker.record(time_source.now(), !failed);
+
+        match self
+            .inner
+            .should_attempt_retry(ctx, runtime_components, cfg)?
+        {
+            ShouldAttempt::No => Ok(ShouldAttempt::No),
+            should_attempt => match circuit_breaker.acquire(time_source.now()) {
+                Ok(()) => Ok(should_attempt),
+                Err(err) => {
+                    debug!("not retrying: {err}");
+                    Ok(ShouldAttempt::No)
+                }
+            },
+        }
+    }
+
+    fn validate_base_client_config(
+        &self,
+        runtime_components: &RuntimeComponentsBuilder,
+        cfg: &ConfigBag,
+    ) -> Result<(), BoxError> {
+        runtime_components
+            .time_source()
+            .ok_or_else(missing_time_source)?;
+        self.inner
+            .validate_base_client_config(runtime_components, cfg)
+    }
+
+    fn validate_final_config(
+        &self,
+        runtime_components: &RuntimeComponents,
+        cfg: &ConfigBag,
+    ) -> Result<(), BoxError> {
+        runtime_components
+            .time_source()
+            .ok_or_else(missing_time_source)?;
+        self.inner.validate_final_config(runtime_components, cfg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CircuitBreakerRetryStrategy;
+    use crate::client::retries::strategy::{NeverRetryStrategy, StandardRetryStrategy};
+    use crate::client::retries::{
+        CircuitBreakerConfig, CircuitOpenError, CircuitState, RetryPartition,
+    };
+    use aws_smithy_async::test_util::ManualTimeSource;
+    use aws_smithy_async::time::SharedTimeSource;
+    use aws_smithy_runtime_api::client::interceptors::context::{
+        Input, InterceptorContext, Output,
+    };
+    use aws_smithy_runtime_api::client::orchestrator::OrchestratorError;
+    use aws_smithy_runtime_api::client::retries::classifiers::SharedRetryClassifier;
+    use aws_smithy_runtime_api::client::retries::{AlwaysRetry, RequestAttempts};
+    use aws_smithy_runtime_api::client::retries::{RetryStrategy, ShouldAttempt};
+    use aws_smithy_runtime_api::client::runtime_components::{
+        RuntimeComponents, RuntimeComponentsBuilder,
+    };
+    use aws_smithy_types::config_bag::{ConfigBag, Layer};
+    use aws_smithy_types::retry::{ErrorKind, RetryConfig};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn strategy(inner: impl RetryStrategy + 'static) -> CircuitBreakerRetryStrategy {
+        CircuitBreakerRetryStrategy::new(inner).with_config(
+            CircuitBreakerConfig::builder()
+                .minimum_requests(2)
+                .open_duration(Duration::from_secs(5))
+                .build()
+                .unwrap(),
+        )
+    }
+
+    fn runtime_components(time_source: &ManualTimeSource, kind: ErrorKind) -> RuntimeComponents {
+        RuntimeComponentsBuilder::for_tests()
+            .with_time_source(Some(time_source.clone()))
+            .with_retry_classifier(SharedRetryClassifier::new(AlwaysRetry(kind)))
+            .build()
+            .unwrap()
+    }
+
+    fn cfg(partition: &'static str) -> ConfigBag {
+        let mut layer = Layer::new("test");
+        layer.store_put(RetryConfig::standard().with_max_attempts(3));
+        layer.store_put(RequestAttempts::new(1));
+        layer.store_put(RetryPartition::new(partition));
+        ConfigBag::of_layers(vec![layer])
+    }
+
+    fn failed_ctx() -> InterceptorContext {
+        let mut ctx = InterceptorContext::new(Input::doesnt_matter());
+        ctx.set_output_or_error(Err(OrchestratorError::other("doesn't matter")));
+        ctx
+    }
+
+    #[test]
+    fn fails_fast_once_the_circuit_opens() {
+        let time_source = ManualTimeSource::new(UNIX_EPOCH);
+        let rc = runtime_components(&time_source, ErrorKind::ServerError);
+        let cfg = cfg("fails_fast");
+        let strategy = strategy(NeverRetryStrategy::new());
+
+        for _ in 0..2 {
+            assert_eq!(
+                ShouldAttempt::Yes,
+                strategy.should_attempt_initial_request(&rc, &cfg).unwrap()
+            );
+            assert_eq!(
+                ShouldAttempt::No,
+                strategy
+                    .should_attempt_retry(&failed_ctx(), &rc, &cfg)
+                    .unwrap()
+            );
+        }
+
+        let err = strategy
+            .should_attempt_initial_request(&rc, &cfg)
+            .unwrap_err();
+        let err = err.downcast_ref::<CircuitOpenError>().unwrap();
+        assert_eq!(Duration::from_secs(5), err.retry_after());
+
+        // Other partitions are unaffected
+        assert_eq!(
+            ShouldAttempt::Yes,
+            strategy
+                .should_attempt_initial_request(&rc, &self::cfg("other"))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn stops_retrying_once_the_circuit_opens() {
+        let time_source = ManualTimeSource::new(UNIX_EPOCH);
+        let rc = runtime_components(&time_source, ErrorKind::TransientError);
+        let cfg = cfg("stops_retrying");
+        let strategy = strategy(StandardRetryStrategy::new());
+
+        strategy.should_attempt_initial_request(&rc, &cfg).unwrap();
+        assert_ne!(
+            ShouldAttempt::No,
+            strategy
+                .should_attempt_retry(&failed_ctx(), &rc, &cfg)
+                .unwrap()
+        );
+        assert_eq!(
+            ShouldAttempt::No,
+            strategy
+                .should_attempt_retry(&failed_ctx(), &rc, &cfg)
+                .unwrap()
+        );
+        assert_eq!(
+            CircuitState::Open,
+            strategy
+                .circuit_breaker(&RetryPartition::new("stops_retrying"))
+                .state()
+        );
+    }
+
+    #[test]
+    fn client_errors_and_successes_do_not_open_the_circuit() {
+        let time_source = ManualTimeSource::new(UNIX_EPOCH);
+        let rc = runtime_components(&time_source, ErrorKind::ClientError);
+        let cfg = cfg("client_errors");
+        let strategy = strategy(NeverRetryStrategy::new());
+
+        let mut ok_ctx = InterceptorContext::new(Input::doesnt_matter());
+        ok_ctx.set_output_or_error(Ok(Output::doesnt_matter()));
+        for ctx in [failed_ctx(), ok_ctx] {
+            strategy.should_attempt_initial_request(&rc, &cfg).unwrap();
+            strategy.should_attempt_retry(&ctx, &rc, &cfg).unwrap();
+        }
+        assert_eq!(
+            ShouldAttempt::Yes,
+            strategy.should_attempt_initial_request(&rc, &cfg).unwrap()
+        );
+    }
+
+    #[test]
+    fn config_validation_requires_a_time_source() {
+        let strategy = strategy(NeverRetryStrategy::new());
+        let builder =
+            RuntimeComponentsBuilder::for_tests().with_time_source(None::<SharedTimeSource>);
+        let err = strategy
+            .validate_base_client_config(&builder, &cfg("validation"))
+            .unwrap_err();
+        assert!(err.to_string().contains("time source"), "{err}");
+
+        let rc = builder.build().unwrap();
+        assert!(strategy
+            .validate_final_config(&rc, &cfg("validation"))
+            .is_err());
+        assert!(strategy
+            .should_attempt_initial_request(&rc, &cfg("validation"))
+            .is_err());
+
+        let rc = runtime_components(&ManualTimeSource::new(UNIX_EPOCH), ErrorKind::ServerError);
+        strategy
+            .validate_final_config(&rc, &cfg("validation"))
+            .unwrap();
+    }
+
+    #[test]
+    fn probes_for_recovery_after_open_duration() {
+        let time_source = ManualTimeSource::new(UNIX_EPOCH);
+        let failing = runtime_components(&time_source, ErrorKind::ThrottlingError);
+        let cfg = cfg("probes");
+        let strategy = strategy(NeverRetryStrategy::new());
+        for _ in 0..2 {
+            strategy
+                .should_attempt_initial_request(&failing, &cfg)
+                .unwrap();
+            strategy
+                .should_attempt_retry(&failed_ctx(), &failing, &cfg)
+                .unwrap();
+        }
+        assert!(strategy
+            .should_attempt_initial_request(&failing, &cfg)
+            .is_err());
+
+        time_source.advance(Duration::from_secs(5));
+        let rc = RuntimeComponentsBuilder::for_tests()
+            .with_time_source(Some(time_source.clone()))
+            .build()
+            .unwrap();
+        assert_eq!(
+            ShouldAttempt::Yes,
+            strategy.should_attempt_initial_request(&rc, &cfg).unwrap()
+        );
+        // Only one probe is allowed at a time
+        assert!(strategy.should_attempt_initial_request(&rc, &cfg).is_err());
+
+        let mut ctx = InterceptorContext::new(Input::doesnt_matter());
+        ctx.set_output_or_error(Ok(Output::doesnt_matter()));
+        strategy.should_attempt_retry(&ctx, &rc, &cfg).unwrap();
+        assert_eq!(
+            ShouldAttempt::Yes,
+            strategy.should_attempt_initial_request(&rc, &cfg).unwrap()
+        );
+    }
+}
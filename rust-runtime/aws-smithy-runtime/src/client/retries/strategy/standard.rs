@@ -6,7 +6,6 @@
 use std::sync::Mutex;
 use std::time::{Duration, SystemTime};
 
-use tokio::sync::OwnedSemaphorePermit;
 use tracing::debug;
 
 use aws_smithy_runtime_api::box_error::BoxError;
@@ -15,14 +14,15 @@ use aws_smithy_runtime_api::client::retries::classifiers::{RetryAction, RetryRea
 use aws_smithy_runtime_api::client::retries::{RequestAttempts, RetryStrategy, ShouldAttempt};
 use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
 use aws_smithy_types::config_bag::{ConfigBag, Storable, StoreReplace};
-use aws_smithy_types::retry::{ErrorKind, RetryConfig, RetryMode};
+use aws_smithy_types::retry::{ErrorKind, RateLimiterScope, RetryConfig, RetryMode};
+use once_cell::sync::OnceCell;
 
 use crate::client::retries::classifiers::run_classifiers_on_ctx;
 use crate::client::retries::client_rate_limiter::{ClientRateLimiter, RequestReason};
 use crate::client::retries::strategy::standard::ReleaseResult::{
     APermitWasReleased, NoPermitWasReleased,
 };
-use crate::client::retries::token_bucket::TokenBucket;
+use crate::client::retries::token_bucket::{TokenBucket, TokenBucketPermit};
 use crate::client::retries::{ClientRateLimiterPartition, RetryPartition};
 use crate::static_partition_map::StaticPartitionMap;
 
@@ -32,7 +32,9 @@ static CLIENT_RATE_LIMITER: StaticPartitionMap<ClientRateLimiterPartition, Clien
 /// Retry strategy with exponential backoff, max attempts, and a token bucket.
 #[derive(Debug, Default)]
 pub struct StandardRetryStrategy {
-    retry_permit: Mutex<Option<OwnedSemaphorePermit>>,
+    retry_permit: Mutex<Option<TokenBucketPermit>>,
+    /// Rate limiter for adaptive retry when it's scoped to a single client
+    client_rate_limiter: OnceCell<ClientRateLimiter>,
 }
 
 impl Storable for StandardRetryStrategy {
@@ -56,7 +58,7 @@ impl StandardRetryStrategy {
         }
     }
 
-    fn set_retry_permit(&self, new_retry_permit: OwnedSemaphorePermit) {
+    fn set_retry_permit(&self, new_retry_permit: TokenBucketPermit) {
         let mut old_retry_permit = self.retry_permit.lock().unwrap();
         if let Some(p) = old_retry_permit.replace(new_retry_permit) {
             // Whenever we set a new retry permit, and it replaces the old one, we need to "forget"
@@ -66,25 +68,49 @@ impl StandardRetryStrategy {
     }
 
     /// Returns a [`ClientRateLimiter`] if adaptive retry is configured.
+    ///
+    /// A rate limiter stored in the config bag takes precedence. Otherwise, the rate limiter is
+    /// shared according to the configured [`RateLimiterScope`].
     fn adaptive_retry_rate_limiter(
+        &self,
         runtime_components: &RuntimeComponents,
         cfg: &ConfigBag,
     ) -> Option<ClientRateLimiter> {
         let retry_config = cfg.load::<RetryConfig>().expect("retry config is required");
         if retry_config.mode() == RetryMode::Adaptive {
+            if let Some(client_rate_limiter) = cfg.load::<ClientRateLimiter>() {
+                return Some(client_rate_limiter.clone());
+            }
             if let Some(time_source) = runtime_components.time_source() {
-                let retry_partition = cfg.load::<RetryPartition>().expect("set in default config");
                 let seconds_since_unix_epoch = time_source
                     .now()
                     .duration_since(SystemTime::UNIX_EPOCH)
                     .expect("the present takes place after the UNIX_EPOCH")
                     .as_secs_f64();
-                let client_rate_limiter_partition =
-                    ClientRateLimiterPartition::new(retry_partition.clone());
-                let client_rate_limiter = CLIENT_RATE_LIMITER
-                    .get_or_init(client_rate_limiter_partition, || {
-                        ClientRateLimiter::new(seconds_since_unix_epoch)
-                    });
+                let adaptive_retry_config = retry_config.adaptive_retry_config();
+                let new_client_rate_limiter = || {
+                    ClientRateLimiter::with_adaptive_retry_config(
+                        seconds_since_unix_epoch,
+                        adaptive_retry_config,
+                    )
+                };
+                let client_rate_limiter = match adaptive_retry_config.scope() {
+                    RateLimiterScope::Client => self
+                        .client_rate_limiter
+                        .get_or_init(new_client_rate_limiter)
+                        .clone(),
+                    _ => {
+                        let retry_partition =
+                            cfg.load::<RetryPartition>().expect("set in default config");
+                        // Clients with different configs don't share a rate limiter, even within a
+                        // retry partition
+                        let client_rate_limiter_partition =
+                            ClientRateLimiterPartition::new(retry_partition.clone())
+                                .with_adaptive_retry_config(adaptive_retry_config);
+                        CLIENT_RATE_LIMITER
+                            .get_or_init(client_rate_limiter_partition, new_client_rate_limiter)
+                    }
+                };
                 return Some(client_rate_limiter);
             }
         }
@@ -107,6 +133,7 @@ impl StandardRetryStrategy {
         match retry_reason {
             RetryAction::RetryIndicated(RetryReason::RetryableError { kind, retry_after }) => {
                 update_rate_limiter_if_exists(
+                    self,
                     runtime_components,
                     cfg,
                     *kind == ErrorKind::ThrottlingError,
@@ -117,7 +144,7 @@ impl StandardRetryStrategy {
                     debug!("explicit request from server to delay {delay:?} before retrying");
                     Ok(delay)
                 } else if let Some(delay) =
-                    check_rate_limiter_for_delay(runtime_components, cfg, *kind)
+                    check_rate_limiter_for_delay(self, runtime_components, cfg, *kind)
                 {
                     let delay = delay.min(retry_cfg.max_backoff());
                     debug!("rate limiter has requested a {delay:?} delay before retrying");
@@ -152,7 +179,7 @@ impl StandardRetryStrategy {
                 }
             }
             RetryAction::RetryForbidden | RetryAction::NoActionIndicated => {
-                update_rate_limiter_if_exists(runtime_components, cfg, false);
+                update_rate_limiter_if_exists(self, runtime_components, cfg, false);
                 debug!(
                     attempts = request_attempts,
                     max_attempts = retry_cfg.max_attempts(),
@@ -176,7 +203,7 @@ impl RetryStrategy for StandardRetryStrategy {
         runtime_components: &RuntimeComponents,
         cfg: &ConfigBag,
     ) -> Result<ShouldAttempt, BoxError> {
-        if let Some(crl) = Self::adaptive_retry_rate_limiter(runtime_components, cfg) {
+        if let Some(crl) = self.adaptive_retry_rate_limiter(runtime_components, cfg) {
             let seconds_since_unix_epoch = get_seconds_since_unix_epoch(runtime_components);
             if let Err(delay) = crl.acquire_permission_to_send_a_request(
                 seconds_since_unix_epoch,
@@ -205,7 +232,7 @@ impl RetryStrategy for StandardRetryStrategy {
             .expect("at least one request attempt is made before any retry is attempted")
             .attempts();
         if request_attempts >= retry_cfg.max_attempts() {
-            update_rate_limiter_if_exists(runtime_components, cfg, false);
+            update_rate_limiter_if_exists(self, runtime_components, cfg, false);
 
             debug!(
                 attempts = request_attempts,
@@ -249,7 +276,7 @@ impl RetryStrategy for StandardRetryStrategy {
                     tb.regenerate_a_token();
                 }
             }
-            update_rate_limiter_if_exists(runtime_components, cfg, false);
+            update_rate_limiter_if_exists(self, runtime_components, cfg, false);
 
             Ok(ShouldAttempt::No)
         }
@@ -257,22 +284,24 @@ impl RetryStrategy for StandardRetryStrategy {
 }
 
 fn update_rate_limiter_if_exists(
+    strategy: &StandardRetryStrategy,
     runtime_components: &RuntimeComponents,
     cfg: &ConfigBag,
     is_throttling_error: bool,
 ) {
-    if let Some(crl) = StandardRetryStrategy::adaptive_retry_rate_limiter(runtime_components, cfg) {
+    if let Some(crl) = strategy.adaptive_retry_rate_limiter(runtime_components, cfg) {
         let seconds_since_unix_epoch = get_seconds_since_unix_epoch(runtime_components);
         crl.update_rate_limiter(seconds_since_unix_epoch, is_throttling_error);
     }
 }
 
 fn check_rate_limiter_for_delay(
+    strategy: &StandardRetryStrategy,
     runtime_components: &RuntimeComponents,
     cfg: &ConfigBag,
     kind: ErrorKind,
 ) -> Option<Duration> {
-    if let Some(crl) = StandardRetryStrategy::adaptive_retry_rate_limiter(runtime_components, cfg) {
+    if let Some(crl) = strategy.adaptive_retry_rate_limiter(runtime_components, cfg) {
         let retry_reason = if kind == ErrorKind::ThrottlingError {
             RequestReason::RetryTimeout
         } else {
@@ -351,6 +380,9 @@ mod tests {
     use super::{calculate_exponential_backoff, StandardRetryStrategy};
     #[cfg(feature = "test-util")]
     use crate::client::retries::TokenBucket;
+    use crate::client::retries::{ClientRateLimiter, RetryPartition};
+    use aws_smithy_async::time::SystemTimeSource;
+    use aws_smithy_types::retry::{AdaptiveRetryConfig, RateLimiterScope};
 
     #[test]
     fn no_retry_necessary_for_ok_result() {
@@ -886,6 +918,110 @@ mod tests {
         assert_eq!(token_bucket.available_permits(), 480);
     }
 
+    fn adaptive_retry_cfg(scope: RateLimiterScope, partition: &'static str) -> ConfigBag {
+        adaptive_retry_cfg_with(
+            AdaptiveRetryConfig::builder().scope(scope).build().unwrap(),
+            partition,
+        )
+    }
+
+    fn adaptive_retry_cfg_with(
+        adaptive_retry_config: AdaptiveRetryConfig,
+        partition: &'static str,
+    ) -> ConfigBag {
+        let mut layer = Layer::new("test");
+        layer.store_put(RetryConfig::adaptive().with_adaptive_retry_config(adaptive_retry_config));
+        layer.store_put(RetryPartition::new(partition));
+        ConfigBag::of_layers(vec![layer])
+    }
+
+    fn runtime_components_with_time_source() -> RuntimeComponents {
+        RuntimeComponentsBuilder::for_tests()
+            .with_time_source(Some(SystemTimeSource::new()))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn client_scoped_rate_limiters_are_not_shared_between_strategies() {
+        let rc = runtime_components_with_time_source();
+        let cfg = adaptive_retry_cfg(RateLimiterScope::Client, "client_scoped_test");
+        let (a, b) = (StandardRetryStrategy::new(), StandardRetryStrategy::new());
+
+        let a_limiter = a.adaptive_retry_rate_limiter(&rc, &cfg).unwrap();
+        a_limiter.update_rate_limiter(0.0, true);
+
+        assert!(a
+            .adaptive_retry_rate_limiter(&rc, &cfg)
+            .unwrap()
+            .snapshot()
+            .is_enabled());
+        assert!(!b
+            .adaptive_retry_rate_limiter(&rc, &cfg)
+            .unwrap()
+            .snapshot()
+            .is_enabled());
+    }
+
+    #[test]
+    fn partition_scoped_rate_limiters_are_shared_between_strategies() {
+        let rc = runtime_components_with_time_source();
+        let cfg = adaptive_retry_cfg(RateLimiterScope::Partition, "partition_scoped_test");
+        let (a, b) = (StandardRetryStrategy::new(), StandardRetryStrategy::new());
+
+        let a_limiter = a.adaptive_retry_rate_limiter(&rc, &cfg).unwrap();
+        a_limiter.update_rate_limiter(0.0, true);
+
+        assert!(b
+            .adaptive_retry_rate_limiter(&rc, &cfg)
+            .unwrap()
+            .snapshot()
+            .is_enabled());
+    }
+
+    #[test]
+    fn partition_scoped_rate_limiters_are_not_shared_between_configs() {
+        let rc = runtime_components_with_time_source();
+        let cfg_a = adaptive_retry_cfg_with(
+            AdaptiveRetryConfig::builder().beta(0.5).build().unwrap(),
+            "partition_config_mismatch_test",
+        );
+        let cfg_b = adaptive_retry_cfg_with(
+            AdaptiveRetryConfig::builder().beta(0.9).build().unwrap(),
+            "partition_config_mismatch_test",
+        );
+        let (a, b) = (StandardRetryStrategy::new(), StandardRetryStrategy::new());
+
+        let a_limiter = a.adaptive_retry_rate_limiter(&rc, &cfg_a).unwrap();
+        a_limiter.update_rate_limiter(0.0, true);
+
+        // Same partition, different config: each client keeps its own tuning
+        let b_limiter = b.adaptive_retry_rate_limiter(&rc, &cfg_b).unwrap();
+        assert!(!b_limiter.snapshot().is_enabled());
+        // Same partition, same config: the rate limiter is shared
+        assert!(b
+            .adaptive_retry_rate_limiter(&rc, &cfg_a)
+            .unwrap()
+            .snapshot()
+            .is_enabled());
+    }
+
+    #[test]
+    fn rate_limiter_in_config_bag_takes_precedence() {
+        let rc = runtime_components_with_time_source();
+        let mut cfg = adaptive_retry_cfg(RateLimiterScope::Client, "config_bag_test");
+        let shared = ClientRateLimiter::new(0.0);
+        cfg.interceptor_state().store_put(shared.clone());
+
+        let strategy = StandardRetryStrategy::new();
+        strategy
+            .adaptive_retry_rate_limiter(&rc, &cfg)
+            .unwrap()
+            .update_rate_limiter(0.0, true);
+
+        assert!(shared.snapshot().is_enabled());
+    }
+
     const MAX_BACKOFF: Duration = Duration::from_secs(20);
 
     #[test]
@@ -5,9 +5,10 @@
 
 use aws_smithy_types::config_bag::{Storable, StoreReplace};
 use aws_smithy_types::retry::ErrorKind;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::{OwnedSemaphorePermit, Semaphore};
-use tracing::trace;
+use tracing::{debug, trace};
 
 const DEFAULT_CAPACITY: usize = 500;
 const RETRY_COST: u32 = 5;
@@ -15,12 +16,93 @@ const RETRY_TIMEOUT_COST: u32 = RETRY_COST * 2;
 const PERMIT_REGENERATION_AMOUNT: usize = 1;
 
 /// Token bucket used for standard and adaptive retry.
+///
+/// Every retry takes permits from the bucket, and no retry is attempted when it's empty. Use
+/// [`TokenBucket::stats`] to see how a bucket is being used. A `debug` event is emitted whenever
+/// a retry is denied because the bucket is empty.
 #[derive(Clone, Debug)]
 pub struct TokenBucket {
     semaphore: Arc<Semaphore>,
     max_permits: usize,
     timeout_retry_cost: u32,
     retry_cost: u32,
+    counters: Arc<Counters>,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    acquired: AtomicU64,
+    refunded: AtomicU64,
+    acquisition_failures: AtomicU64,
+}
+
+/// Usage statistics for a [`TokenBucket`].
+///
+/// Permit counts are totals since the bucket was created.
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TokenBucketStats {
+    available_permits: usize,
+    max_permits: usize,
+    permits_acquired: u64,
+    permits_refunded: u64,
+    acquisition_failures: u64,
+}
+
+impl TokenBucketStats {
+    /// Returns the number of permits currently in the bucket.
+    pub fn available_permits(&self) -> usize {
+        self.available_permits
+    }
+
+    /// Returns the maximum number of permits the bucket can hold.
+    pub fn max_permits(&self) -> usize {
+        self.max_permits
+    }
+
+    /// Returns the number of permits taken from the bucket by retries and hedged requests.
+    pub fn permits_acquired(&self) -> u64 {
+        self.permits_acquired
+    }
+
+    /// Returns the number of permits put back into the bucket, either because a retry succeeded
+    /// or because a request succeeded on its first attempt.
+    pub fn permits_refunded(&self) -> u64 {
+        self.permits_refunded
+    }
+
+    /// Returns the number of times a retry or hedged request wasn't sent because the bucket didn't
+    /// have enough permits.
+    pub fn acquisition_failures(&self) -> u64 {
+        self.acquisition_failures
+    }
+}
+
+/// Permits taken from a [`TokenBucket`]. They're put back into the bucket when this is dropped.
+#[derive(Debug)]
+pub(crate) struct TokenBucketPermit {
+    permit: Option<OwnedSemaphorePermit>,
+    cost: u32,
+    counters: Arc<Counters>,
+}
+
+impl TokenBucketPermit {
+    /// Permanently removes these permits from the bucket.
+    pub(crate) fn forget(mut self) {
+        if let Some(permit) = self.permit.take() {
+            permit.forget();
+        }
+    }
+}
+
+impl Drop for TokenBucketPermit {
+    fn drop(&mut self) {
+        if self.permit.take().is_some() {
+            self.counters
+                .refunded
+                .fetch_add(self.cost as u64, Ordering::Relaxed);
+        }
+    }
 }
 
 impl Storable for TokenBucket {
@@ -34,6 +116,7 @@ impl Default for TokenBucket {
             max_permits: DEFAULT_CAPACITY,
             timeout_retry_cost: RETRY_TIMEOUT_COST,
             retry_cost: RETRY_COST,
+            counters: Default::default(),
         }
     }
 }
@@ -46,26 +129,73 @@ impl TokenBucket {
             max_permits: initial_quota,
             retry_cost: RETRY_COST,
             timeout_retry_cost: RETRY_TIMEOUT_COST,
+            counters: Default::default(),
         }
     }
 
-    pub(crate) fn acquire(&self, err: &ErrorKind) -> Option<OwnedSemaphorePermit> {
+    pub(crate) fn acquire(&self, err: &ErrorKind) -> Option<TokenBucketPermit> {
         let retry_cost = if err == &ErrorKind::TransientError {
             self.timeout_retry_cost
         } else {
             self.retry_cost
         };
 
-        self.semaphore
-            .clone()
-            .try_acquire_many_owned(retry_cost)
-            .ok()
+        self.acquire_many(retry_cost)
+    }
+
+    /// Acquires the permits for a hedged request, which costs the same as a retry.
+    pub(crate) fn acquire_hedge(&self) -> Option<TokenBucketPermit> {
+        self.acquire_many(self.retry_cost)
+    }
+
+    fn acquire_many(&self, cost: u32) -> Option<TokenBucketPermit> {
+        match self.semaphore.clone().try_acquire_many_owned(cost) {
+            Ok(permit) => {
+                self.counters
+                    .acquired
+                    .fetch_add(cost as u64, Ordering::Relaxed);
+                Some(TokenBucketPermit {
+                    permit: Some(permit),
+                    cost,
+                    counters: self.counters.clone(),
+                })
+            }
+            Err(_) => {
+                let acquisition_failures = self
+                    .counters
+                    .acquisition_failures
+                    .fetch_add(1, Ordering::Relaxed)
+                    + 1;
+                debug!(
+                    cost,
+                    available_permits = self.semaphore.available_permits(),
+                    max_permits = self.max_permits,
+                    acquisition_failures,
+                    "retry token bucket doesn't have enough permits"
+                );
+                None
+            }
+        }
     }
 
     pub(crate) fn regenerate_a_token(&self) {
         if self.semaphore.available_permits() < (self.max_permits) {
             trace!("adding {PERMIT_REGENERATION_AMOUNT} back into the bucket");
-            self.semaphore.add_permits(PERMIT_REGENERATION_AMOUNT)
+            self.semaphore.add_permits(PERMIT_REGENERATION_AMOUNT);
+            self.counters
+                .refunded
+                .fetch_add(PERMIT_REGENERATION_AMOUNT as u64, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns usage statistics for this bucket. Clones of a bucket share their statistics.
+    pub fn stats(&self) -> TokenBucketStats {
+        TokenBucketStats {
+            available_permits: self.semaphore.available_permits(),
+            max_permits: self.max_permits,
+            permits_acquired: self.counters.acquired.load(Ordering::Relaxed),
+            permits_refunded: self.counters.refunded.load(Ordering::Relaxed),
+            acquisition_failures: self.counters.acquisition_failures.load(Ordering::Relaxed),
         }
     }
 
@@ -74,3 +204,28 @@ impl TokenBucket {
         self.semaphore.available_permits()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::TokenBucket;
+    use aws_smithy_types::retry::ErrorKind;
+
+    #[test]
+    fn stats_track_acquired_and_refunded_permits() {
+        let bucket = TokenBucket::new(12);
+        let refunded = bucket.acquire(&ErrorKind::ServerError).unwrap();
+        let forgotten = bucket.acquire(&ErrorKind::ServerError).unwrap();
+        assert!(bucket.acquire(&ErrorKind::TransientError).is_none());
+
+        drop(refunded);
+        forgotten.forget();
+        bucket.regenerate_a_token();
+
+        let stats = bucket.clone().stats();
+        assert_eq!(8, stats.available_permits());
+        assert_eq!(12, stats.max_permits());
+        assert_eq!(10, stats.permits_acquired());
+        assert_eq!(6, stats.permits_refunded());
+        assert_eq!(1, stats.acquisition_failures());
+    }
+}
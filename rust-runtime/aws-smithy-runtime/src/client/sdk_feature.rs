@@ -12,6 +12,8 @@ pub enum SmithySdkFeature {
     Paginator,
     GzipRequestCompression,
     ProtocolRpcV2Cbor,
+    ZstdRequestCompression,
+    BrotliRequestCompression,
 }
 
 impl Storable for SmithySdkFeature {
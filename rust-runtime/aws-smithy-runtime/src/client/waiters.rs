@@ -12,12 +12,20 @@ use aws_smithy_runtime_api::client::waiters::FinalPoll;
 use aws_smithy_runtime_api::client::{orchestrator::HttpResponse, result::SdkError};
 use aws_smithy_runtime_api::client::{
     result::CreateUnhandledError,
-    waiters::error::{ExceededMaxWait, FailureState, OperationFailed, WaiterError},
+    waiters::error::{Cancelled, ExceededMaxWait, FailureState, OperationFailed, WaiterError},
 };
-use std::future::Future;
+use std::collections::HashMap;
+use std::future::{poll_fn, Future};
+use std::pin::pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
 use std::time::Duration;
 
 mod backoff;
+mod custom;
+
+pub use custom::CustomWaiter;
 
 /// Waiter acceptor state
 ///
@@ -41,6 +49,198 @@ pub enum AcceptorState {
     Retry,
 }
 
+/// Jitter applied to the delay between waiter polls
+///
+/// The delay before each poll grows exponentially from the minimum delay up to the maximum delay.
+/// Jitter randomizes it so that many waiters don't poll at the same time.
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum Jitter {
+    /// Pick a random delay between the minimum delay and the exponential delay.
+    ///
+    /// This is the jitter described by the [Smithy spec](https://smithy.io/2.0/additional-specs/waiters.html#waiter-retries).
+    #[default]
+    Full,
+    /// Pick a random delay between half of the exponential delay and the full exponential delay.
+    Equal,
+    /// Always use the exponential delay.
+    Disabled,
+}
+
+/// Token for cooperatively cancelling waiters
+///
+/// Clones share their state, so cancelling one clone cancels every waiter that was given a clone of
+/// the token. A cancelled waiter stops at its next poll or delay, dropping any request in flight,
+/// and returns [`WaiterError::Cancelled`].
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    inner: Arc<CancellationState>,
+}
+
+#[derive(Debug, Default)]
+struct CancellationState {
+    cancelled: AtomicBool,
+    registrations: Mutex<Registrations>,
+}
+
+/// Wakers of the waiters currently using a token, keyed by registration
+#[derive(Debug, Default)]
+struct Registrations {
+    next_key: u64,
+    wakers: HashMap<u64, Waker>,
+}
+
+impl CancellationToken {
+    /// Creates a token that hasn't been cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels every waiter using this token.
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        let wakers: Vec<_> = {
+            let mut registrations = self.inner.registrations.lock().unwrap();
+            registrations
+                .wakers
+                .drain()
+                .map(|(_, waker)| waker)
+                .collect()
+        };
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    /// Returns true if [`cancel`](Self::cancel) was called.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    fn register(&self) -> Registration<'_> {
+        let mut registrations = self.inner.registrations.lock().unwrap();
+        let key = registrations.next_key;
+        registrations.next_key += 1;
+        Registration { token: self, key }
+    }
+}
+
+/// A waiter's slot for its waker in a [`CancellationToken`]
+///
+/// The slot holds at most one waker, which is replaced on every poll. It's removed when the
+/// registration is dropped, so finished waiters don't leave their wakers behind.
+struct Registration<'a> {
+    token: &'a CancellationToken,
+    key: u64,
+}
+
+impl Registration<'_> {
+    fn poll_cancelled(&self, cx: &mut Context<'_>) -> Poll<()> {
+        if self.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+        self.token
+            .inner
+            .registrations
+            .lock()
+            .unwrap()
+            .wakers
+            .insert(self.key, cx.waker().clone());
+        // check again in case the token was cancelled before the waker was registered
+        if self.token.is_cancelled() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut registrations) = self.token.inner.registrations.lock() {
+            registrations.wakers.remove(&self.key);
+        }
+    }
+}
+
+/// Runs `future` to completion, unless `token` is cancelled first.
+async fn unless_cancelled<F: Future>(
+    token: Option<&CancellationToken>,
+    future: F,
+) -> Option<F::Output> {
+    let registration = token.map(CancellationToken::register);
+    let mut future = pin!(future);
+    poll_fn(|cx| {
+        if let Some(registration) = &registration {
+            if registration.poll_cancelled(cx).is_ready() {
+                return Poll::Ready(None);
+            }
+        }
+        future.as_mut().poll(cx).map(Some)
+    })
+    .await
+}
+
+/// Progress of a waiter, reported by [`WaiterOrchestrator::orchestrate_with_progress`] after each poll
+#[non_exhaustive]
+#[derive(Debug)]
+pub struct WaiterProgress<'a, O, E> {
+    poll_count: u32,
+    elapsed: Duration,
+    acceptor_state: AcceptorState,
+    result: Result<&'a O, &'a SdkError<E, HttpResponse>>,
+    next_delay: Option<Duration>,
+}
+
+impl<'a, O, E> WaiterProgress<'a, O, E> {
+    /// Returns the number of polls made so far, including this one.
+    pub fn poll_count(&self) -> u32 {
+        self.poll_count
+    }
+
+    /// Returns how much time elapsed since the waiter started.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Returns the acceptor state that the result of this poll matched.
+    pub fn acceptor_state(&self) -> AcceptorState {
+        self.acceptor_state
+    }
+
+    /// Returns the result of this poll.
+    pub fn result(&self) -> Result<&'a O, &'a SdkError<E, HttpResponse>> {
+        self.result
+    }
+
+    /// Returns how long the waiter will wait before polling again, or `None` if it's done polling.
+    pub fn next_delay(&self) -> Option<Duration> {
+        self.next_delay
+    }
+}
+
+/// Waits for the first of several waiters to complete.
+///
+/// Returns the index of the waiter that completed first, along with its result. The other waiters
+/// are dropped, which stops them from polling.
+///
+/// # Panics
+///
+/// Panics if `waiters` is empty.
+pub async fn wait_for_any<F: Future>(waiters: impl IntoIterator<Item = F>) -> (usize, F::Output) {
+    let mut waiters: Vec<_> = waiters.into_iter().map(Box::pin).collect();
+    assert!(!waiters.is_empty(), "at least one waiter is required");
+    poll_fn(|cx| {
+        for (index, waiter) in waiters.iter_mut().enumerate() {
+            if let Poll::Ready(output) = waiter.as_mut().poll(cx) {
+                return Poll::Ready((index, output));
+            }
+        }
+        Poll::Pending
+    })
+    .await
+}
+
 /// Orchestrates waiting via polling with jittered exponential backoff.
 ///
 /// This is meant to be used internally by the generated code to provide
@@ -49,6 +249,7 @@ pub struct WaiterOrchestrator<AcceptorFn, OperationFn> {
     backoff: Backoff,
     time_source: SharedTimeSource,
     sleep_impl: SharedAsyncSleep,
+    cancellation_token: Option<CancellationToken>,
     acceptor_fn: AcceptorFn,
     operation_fn: OperationFn,
 }
@@ -65,6 +266,7 @@ impl<AcceptorFn, OperationFn> WaiterOrchestrator<AcceptorFn, OperationFn> {
         backoff: Backoff,
         time_source: SharedTimeSource,
         sleep_impl: SharedAsyncSleep,
+        cancellation_token: Option<CancellationToken>,
         acceptor_fn: AcceptorFn,
         operation_fn: OperationFn,
     ) -> Self {
@@ -72,6 +274,7 @@ impl<AcceptorFn, OperationFn> WaiterOrchestrator<AcceptorFn, OperationFn> {
             backoff,
             time_source,
             sleep_impl,
+            cancellation_token,
             acceptor_fn,
             operation_fn,
         }
@@ -88,21 +291,92 @@ where
     /// Orchestrates waiting via polling with jittered exponential backoff.
     pub async fn orchestrate(
         self,
+    ) -> Result<FinalPoll<O, SdkError<E, HttpResponse>>, WaiterError<O, E>> {
+        self.orchestrate_with_progress(|_| {}).await
+    }
+
+    /// Orchestrates waiting via polling with jittered exponential backoff, calling `progress`
+    /// after each poll.
+    ///
+    /// Polls that fail with an unmodeled error end waiting immediately, and aren't reported.
+    pub async fn orchestrate_with_progress(
+        self,
+        progress: impl FnMut(WaiterProgress<'_, O, E>),
+    ) -> Result<FinalPoll<O, SdkError<E, HttpResponse>>, WaiterError<O, E>> {
+        self.poll_until_done(
+            // "acceptable result" in this context means "an acceptor's matcher can match this result type"
+            |acceptor_fn, result| match result.map_err(|err| err.as_service_error()) {
+                Ok(output) => Some(acceptor_fn(Ok(output))),
+                Err(Some(err)) => Some(acceptor_fn(Err(err))),
+                Err(None) => None,
+            },
+            progress,
+        )
+        .await
+    }
+}
+
+impl<AcceptorFn, OperationFn, O, E, Fut> WaiterOrchestrator<AcceptorFn, OperationFn>
+where
+    AcceptorFn: Fn(Result<&O, &SdkError<E, HttpResponse>>) -> AcceptorState,
+    OperationFn: Fn() -> Fut,
+    Fut: Future<Output = Result<O, SdkError<E, HttpResponse>>>,
+    E: CreateUnhandledError + std::error::Error + Send + Sync + 'static,
+{
+    /// Like [`orchestrate_with_progress`](Self::orchestrate_with_progress), but the acceptor is
+    /// also called with unmodeled errors.
+    async fn orchestrate_sdk_errors_with_progress(
+        self,
+        progress: impl FnMut(WaiterProgress<'_, O, E>),
+    ) -> Result<FinalPoll<O, SdkError<E, HttpResponse>>, WaiterError<O, E>> {
+        self.poll_until_done(|acceptor_fn, result| Some(acceptor_fn(result)), progress)
+            .await
+    }
+}
+
+impl<AcceptorFn, OperationFn, O, E, Fut> WaiterOrchestrator<AcceptorFn, OperationFn>
+where
+    OperationFn: Fn() -> Fut,
+    Fut: Future<Output = Result<O, SdkError<E, HttpResponse>>>,
+    E: CreateUnhandledError + std::error::Error + Send + Sync + 'static,
+{
+    /// Polls until an acceptor decides that waiting is done.
+    ///
+    /// `accept` returns `None` for results that can't be matched by an acceptor, which end
+    /// waiting immediately.
+    async fn poll_until_done(
+        self,
+        accept: impl Fn(&AcceptorFn, Result<&O, &SdkError<E, HttpResponse>>) -> Option<AcceptorState>,
+        mut progress: impl FnMut(WaiterProgress<'_, O, E>),
     ) -> Result<FinalPoll<O, SdkError<E, HttpResponse>>, WaiterError<O, E>> {
         let start_time = self.time_source.now();
+        let total_elapsed = || {
+            self.time_source
+                .now()
+                .duration_since(start_time)
+                .unwrap_or_default()
+        };
+        let cancellation_token = self.cancellation_token.as_ref();
         let mut attempt = 0;
         let mut done_retrying = false;
         loop {
             tracing::debug!("executing waiter poll attempt #{}", attempt + 1);
-            let result = (self.operation_fn)().await;
+            let result = match unless_cancelled(cancellation_token, (self.operation_fn)()).await {
+                Some(result) => result,
+                None => {
+                    tracing::debug!("waiter was cancelled");
+                    return Err(WaiterError::Cancelled(Cancelled::new(
+                        total_elapsed(),
+                        attempt,
+                    )));
+                }
+            };
+            attempt += 1;
             let error = result.is_err();
 
-            // "acceptable result" in this context means "an acceptor's matcher can match this result type"
-            let acceptable_result = result.as_ref().map_err(|err| err.as_service_error());
-            let acceptor_state = match acceptable_result {
-                Ok(output) => (self.acceptor_fn)(Ok(output)),
-                Err(Some(err)) => (self.acceptor_fn)(Err(err)),
-                _ => {
+            let acceptor_state = match accept(&self.acceptor_fn, result.as_ref()) {
+                Some(acceptor_state) => acceptor_state,
+                None => {
                     // If we got an unmatchable failure (basically anything unmodeled), then just immediately exit
                     return Err(WaiterError::OperationFailed(OperationFailed::new(
                         result.err().expect("can only be an err in this branch"),
@@ -111,24 +385,35 @@ where
             };
 
             tracing::debug!("waiter acceptor state: {acceptor_state:?}");
+            let mut report = |elapsed: Duration, next_delay: Option<Duration>| {
+                progress(WaiterProgress {
+                    poll_count: attempt,
+                    elapsed,
+                    acceptor_state,
+                    result: result.as_ref(),
+                    next_delay,
+                })
+            };
             match acceptor_state {
-                AcceptorState::Success => return Ok(FinalPoll::new(result)),
+                AcceptorState::Success => {
+                    report(total_elapsed(), None);
+                    return Ok(FinalPoll::new(result));
+                }
                 AcceptorState::Failure => {
+                    report(total_elapsed(), None);
                     return Err(WaiterError::FailureState(FailureState::new(
                         FinalPoll::new(result.map_err(|err| err.into_service_error())),
-                    )))
+                    )));
                 }
-                // This occurs when there was a modeled error response, but none of the acceptors matched it
+                // This occurs when there was an error response, but none of the acceptors matched it
                 AcceptorState::NoAcceptorsMatched if error => {
+                    report(total_elapsed(), None);
                     return Err(WaiterError::OperationFailed(OperationFailed::new(
                         result.err().expect("checked above"),
-                    )))
+                    )));
                 }
                 AcceptorState::Retry | AcceptorState::NoAcceptorsMatched => {
-                    attempt += 1;
-
-                    let now = self.time_source.now();
-                    let elapsed = now.duration_since(start_time).unwrap_or_default();
+                    let elapsed = total_elapsed();
                     if !done_retrying && elapsed <= self.backoff.max_wait() {
                         let delay = self.backoff.delay(attempt, elapsed);
 
@@ -141,17 +426,29 @@ where
                                 "delay calculated for attempt #{attempt}; elapsed ({elapsed:?}); waiter is close to max time; will immediately poll one last time"
                             );
                             done_retrying = true;
+                            report(elapsed, Some(delay));
                         } else {
                             tracing::debug!(
                                 "delay calculated for attempt #{attempt}; elapsed ({elapsed:?}); waiter will poll again in {delay:?}"
                             );
-                            self.sleep_impl.sleep(delay).await;
+                            report(elapsed, Some(delay));
+                            if unless_cancelled(cancellation_token, self.sleep_impl.sleep(delay))
+                                .await
+                                .is_none()
+                            {
+                                tracing::debug!("waiter was cancelled");
+                                return Err(WaiterError::Cancelled(Cancelled::new(
+                                    total_elapsed(),
+                                    attempt,
+                                )));
+                            }
                         }
                     } else {
                         tracing::debug!(
                             "waiter exceeded max wait time of {:?}",
                             self.backoff.max_wait()
                         );
+                        report(elapsed, None);
                         return Err(WaiterError::ExceededMaxWait(ExceededMaxWait::new(
                             self.backoff.max_wait(),
                             elapsed,
@@ -172,6 +469,8 @@ pub struct WaiterOrchestratorBuilder<AcceptorFn = (), OperationFn = ()> {
     max_wait: Option<Duration>,
     time_source: Option<SharedTimeSource>,
     sleep_impl: Option<SharedAsyncSleep>,
+    jitter: Jitter,
+    cancellation_token: Option<CancellationToken>,
     random_fn: RandomImpl,
     acceptor_fn: Option<AcceptorFn>,
     operation_fn: Option<OperationFn>,
@@ -196,6 +495,18 @@ impl<AcceptorFn, OperationFn> WaiterOrchestratorBuilder<AcceptorFn, OperationFn>
         self
     }
 
+    /// Set the jitter applied to the delay between polls. Defaults to [`Jitter::Full`].
+    pub fn jitter(mut self, jitter: Jitter) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Set a token that cancels the waiter.
+    pub fn cancellation_token(mut self, cancellation_token: CancellationToken) -> Self {
+        self.cancellation_token = Some(cancellation_token);
+        self
+    }
+
     #[cfg(all(test, feature = "test-util"))]
     fn random(mut self, random_fn: impl Fn(u64, u64) -> u64 + Send + Sync + 'static) -> Self {
         self.random_fn = RandomImpl::Override(Box::new(random_fn));
@@ -221,10 +532,12 @@ impl<AcceptorFn, OperationFn> WaiterOrchestratorBuilder<AcceptorFn, OperationFn>
                 self.min_delay.expect("min delay is required"),
                 self.max_delay.expect("max delay is required"),
                 self.max_wait.expect("max wait is required"),
+                self.jitter,
                 self.random_fn,
             ),
             self.time_source.expect("time source required"),
             self.sleep_impl.expect("sleep impl required"),
+            self.cancellation_token,
             self.acceptor_fn.expect("acceptor fn required"),
             self.operation_fn.expect("operation fn required"),
         )
@@ -243,6 +556,8 @@ impl<OperationFn> WaiterOrchestratorBuilder<(), OperationFn> {
             max_wait: self.max_wait,
             time_source: self.time_source,
             sleep_impl: self.sleep_impl,
+            jitter: self.jitter,
+            cancellation_token: self.cancellation_token,
             random_fn: self.random_fn,
             acceptor_fn: Some(acceptor),
             operation_fn: self.operation_fn,
@@ -262,6 +577,8 @@ impl<AcceptorFn> WaiterOrchestratorBuilder<AcceptorFn, ()> {
             max_wait: self.max_wait,
             time_source: self.time_source,
             sleep_impl: self.sleep_impl,
+            jitter: self.jitter,
+            cancellation_token: self.cancellation_token,
             random_fn: self.random_fn,
             acceptor_fn: self.acceptor_fn,
             operation_fn: Some(operation),
@@ -526,4 +843,175 @@ mod tests {
         assert!(result.is_ok());
         assert!(result.unwrap().as_result().is_err());
     }
+
+    #[tokio::test]
+    async fn progress_is_reported_after_each_poll() {
+        let _logs = show_test_logs();
+        let (time_source, sleep_impl) = tick_advance_time_and_sleep();
+
+        let attempt = Arc::new(AtomicUsize::new(1));
+        let orchestrator = test_orchestrator(sleep_impl, time_source.clone())
+            .acceptor(|result: Result<&usize, &TestError>| match result {
+                Ok(3) => AcceptorState::Success,
+                _ => AcceptorState::Retry,
+            })
+            .operation(move || {
+                let attempt = attempt.clone();
+                async move {
+                    Result::<_, SdkError<TestError, HttpResponse>>::Ok(
+                        attempt.fetch_add(1, Ordering::SeqCst),
+                    )
+                }
+            })
+            .build();
+
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let task = tokio::spawn({
+            let reports = reports.clone();
+            orchestrator.orchestrate_with_progress(move |progress| {
+                reports.lock().unwrap().push((
+                    progress.poll_count(),
+                    progress.elapsed().as_secs(),
+                    progress.acceptor_state(),
+                    *progress.result().unwrap(),
+                    progress.next_delay().map(|delay| delay.as_secs()),
+                ))
+            })
+        });
+        tokio::task::yield_now().await;
+        time_source.tick(Duration::from_secs(500)).await;
+        let result = task.await.unwrap();
+
+        assert_eq!(3, *result.unwrap().as_result().unwrap());
+        assert_eq!(
+            vec![
+                (1, 0, AcceptorState::Retry, 1, Some(2)),
+                (2, 2, AcceptorState::Retry, 2, Some(3)),
+                (3, 5, AcceptorState::Success, 3, None),
+            ],
+            *reports.lock().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn cancelled_while_waiting() {
+        let _logs = show_test_logs();
+        let (time_source, sleep_impl) = tick_advance_time_and_sleep();
+        let token = CancellationToken::new();
+        let orchestrator = test_orchestrator(sleep_impl, time_source.clone())
+            .cancellation_token(token.clone())
+            .acceptor(|_result: Result<&usize, &TestError>| AcceptorState::Retry)
+            .operation(|| async { Result::<_, SdkError<TestError, HttpResponse>>::Ok(1) })
+            .build();
+
+        let task = tokio::spawn(orchestrator.orchestrate());
+        tokio::task::yield_now().await;
+        time_source.tick(Duration::from_secs(3)).await;
+        token.cancel();
+        let result = task.await.unwrap();
+
+        match result {
+            Err(WaiterError::Cancelled(context)) => {
+                assert_eq!(2, context.poll_count());
+                assert_eq!(3, context.elapsed().as_secs());
+            }
+            _ => panic!("expected Cancelled, got {result:?}"),
+        }
+        assert!(token.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn cancelled_before_polling() {
+        let _logs = show_test_logs();
+        let (time_source, sleep_impl) = tick_advance_time_and_sleep();
+        let token = CancellationToken::new();
+        token.cancel();
+        let orchestrator = test_orchestrator(sleep_impl, time_source)
+            .cancellation_token(token)
+            .acceptor(|_result: Result<&usize, &TestError>| unreachable!())
+            .operation(|| async {
+                unreachable!("a cancelled waiter shouldn't poll");
+                #[allow(unreachable_code)]
+                Result::<usize, SdkError<TestError, HttpResponse>>::Ok(1)
+            })
+            .build();
+
+        match orchestrator.orchestrate().await {
+            Err(WaiterError::Cancelled(context)) => assert_eq!(0, context.poll_count()),
+            result => panic!("expected Cancelled, got {result:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn finished_waiters_do_not_leave_wakers_behind() {
+        let _logs = show_test_logs();
+        let (time_source, sleep_impl) = tick_advance_time_and_sleep();
+        let token = CancellationToken::new();
+        let attempt = Arc::new(AtomicUsize::new(1));
+        let orchestrator = test_orchestrator(sleep_impl, time_source.clone())
+            .cancellation_token(token.clone())
+            .acceptor(|result: Result<&usize, &TestError>| {
+                if *result.unwrap() >= 10 {
+                    AcceptorState::Success
+                } else {
+                    AcceptorState::Retry
+                }
+            })
+            .operation(move || {
+                let attempt = attempt.clone();
+                async move {
+                    Result::<_, SdkError<TestError, HttpResponse>>::Ok(
+                        attempt.fetch_add(1, Ordering::SeqCst),
+                    )
+                }
+            })
+            .build();
+
+        let task = tokio::spawn(orchestrator.orchestrate());
+        tokio::task::yield_now().await;
+        for _ in 0..5 {
+            time_source.tick(Duration::from_secs(10)).await;
+            // a waiter that is waiting holds exactly one waker, however often it was polled
+            assert!(token.inner.registrations.lock().unwrap().wakers.len() <= 1);
+        }
+        time_source.tick(Duration::from_secs(500)).await;
+        assert!(task.await.unwrap().is_ok());
+        assert!(token.inner.registrations.lock().unwrap().wakers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn wait_for_any_returns_the_first_waiter_to_finish() {
+        let _logs = show_test_logs();
+        let (time_source, sleep_impl) = tick_advance_time_and_sleep();
+        let waiter = |succeed_after: usize| {
+            let attempt = Arc::new(AtomicUsize::new(1));
+            test_orchestrator(sleep_impl.clone(), time_source.clone())
+                .jitter(Jitter::Disabled)
+                .acceptor(move |result: Result<&usize, &TestError>| {
+                    if *result.unwrap() >= succeed_after {
+                        AcceptorState::Success
+                    } else {
+                        AcceptorState::Retry
+                    }
+                })
+                .operation(move || {
+                    let attempt = attempt.clone();
+                    async move {
+                        Result::<_, SdkError<TestError, HttpResponse>>::Ok(
+                            attempt.fetch_add(1, Ordering::SeqCst),
+                        )
+                    }
+                })
+                .build()
+                .orchestrate()
+        };
+
+        let task = tokio::spawn(wait_for_any([waiter(10), waiter(3), waiter(5)]));
+        tokio::task::yield_now().await;
+        time_source.tick(Duration::from_secs(500)).await;
+        let (index, result) = task.await.unwrap();
+
+        assert_eq!(1, index);
+        assert_eq!(3, *result.unwrap().as_result().unwrap());
+    }
 }
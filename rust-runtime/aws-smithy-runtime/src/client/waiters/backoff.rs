@@ -3,6 +3,7 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
+use super::Jitter;
 use std::{fmt, time::Duration};
 
 #[derive(Debug)]
@@ -11,6 +12,7 @@ pub(super) struct Backoff {
     max_delay: Duration,
     max_wait: Duration,
     attempt_ceiling: u32,
+    jitter: Jitter,
     random: RandomImpl,
 }
 
@@ -19,6 +21,7 @@ impl Backoff {
         min_delay: Duration,
         max_delay: Duration,
         max_wait: Duration,
+        jitter: Jitter,
         random: RandomImpl,
     ) -> Self {
         Self {
@@ -29,6 +32,7 @@ impl Backoff {
             attempt_ceiling: (((max_delay.as_secs_f64() / min_delay.as_secs_f64()).ln()
                 / 2f64.ln())
                 + 1.0) as u32,
+            jitter,
             random,
         }
     }
@@ -40,7 +44,13 @@ impl Backoff {
         } else {
             self.min_delay.as_secs() * 2u64.pow(attempt - 1)
         };
-        let mut delay = Duration::from_secs(self.random.random(self.min_delay.as_secs(), delay));
+        let min_delay = self.min_delay.as_secs();
+        let delay = match self.jitter {
+            Jitter::Full => self.random.random(min_delay, delay),
+            Jitter::Equal => self.random.random(min_delay.max(delay - delay / 2), delay),
+            Jitter::Disabled => delay,
+        };
+        let mut delay = Duration::from_secs(delay);
 
         let remaining_time = self.max_wait.saturating_sub(elapsed);
         if remaining_time.saturating_sub(delay) <= self.min_delay {
@@ -94,11 +104,28 @@ mod tests {
         max_delay: u64,
         test_random: impl Fn(u64, u64) -> u64 + Send + Sync + 'static,
         attempt_delays: &[(u64, u64)],
+    ) {
+        test_backoff_with_jitter(
+            min_delay,
+            max_delay,
+            Jitter::Full,
+            test_random,
+            attempt_delays,
+        )
+    }
+
+    fn test_backoff_with_jitter(
+        min_delay: u64,
+        max_delay: u64,
+        jitter: Jitter,
+        test_random: impl Fn(u64, u64) -> u64 + Send + Sync + 'static,
+        attempt_delays: &[(u64, u64)],
     ) {
         let backoff = dbg!(Backoff::new(
             Duration::from_secs(min_delay),
             Duration::from_secs(max_delay),
             Duration::from_secs(300),
+            jitter,
             RandomImpl::Override(Box::new(test_random)),
         ));
 
@@ -170,4 +197,43 @@ mod tests {
         ];
         test_backoff(15, 120, test_random, attempt_delays);
     }
+
+    #[test]
+    fn backoff_without_jitter() {
+        let test_random = |_min: u64, _max: u64| unreachable!("jitter is disabled");
+        let attempt_delays = &[
+            // delay, time
+            (2, 0),
+            (4, 2),
+            (8, 6),
+            (16, 14),
+            (32, 30),
+            (64, 62),
+            (120, 126),
+            (54, 246),
+            (0, 300),
+        ];
+        test_backoff_with_jitter(2, 120, Jitter::Disabled, test_random, attempt_delays);
+    }
+
+    #[test]
+    fn backoff_with_equal_jitter() {
+        // always picks the lowest delay allowed
+        let test_random = |min: u64, _max: u64| min;
+        let attempt_delays = &[
+            // delay, time
+            (2, 0),
+            (2, 2),
+            (4, 4),
+            (8, 8),
+            (16, 16),
+            (32, 32),
+            (60, 64),
+            (60, 124),
+            (60, 184),
+            (56, 244),
+            (0, 300),
+        ];
+        test_backoff_with_jitter(2, 120, Jitter::Equal, test_random, attempt_delays);
+    }
 }
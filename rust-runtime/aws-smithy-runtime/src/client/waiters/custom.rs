@@ -0,0 +1,475 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use super::{
+    attach_waiter_tracing_span, AcceptorState, CancellationToken, Jitter, WaiterOrchestrator,
+    WaiterProgress,
+};
+use aws_smithy_async::rt::sleep::{AsyncSleep, SharedAsyncSleep};
+use aws_smithy_async::time::{SharedTimeSource, TimeSource};
+use aws_smithy_runtime_api::client::orchestrator::HttpResponse;
+use aws_smithy_runtime_api::client::result::{CreateUnhandledError, SdkError};
+use aws_smithy_runtime_api::client::waiters::error::WaiterError;
+use aws_smithy_runtime_api::client::waiters::FinalPoll;
+use aws_smithy_runtime_api::shared::IntoShared;
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+/// Default minimum delay between polls, as defined by the Smithy spec
+const DEFAULT_MIN_DELAY: Duration = Duration::from_secs(2);
+/// Default maximum delay between polls, as defined by the Smithy spec
+const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(120);
+
+/// A waiter for resources that don't have a modeled waiter
+///
+/// A custom waiter polls an operation until an acceptor decides that waiting succeeded or failed,
+/// or until the max wait time is exceeded. It uses the same backoff and returns the same
+/// [`WaiterError`] as generated waiters:
+/// - The acceptor is called with the output or [`SdkError`] of each poll. Unlike generated
+///   waiters, which only match modeled errors, it's also called with unmodeled errors such as
+///   timeouts or dispatch failures, so that it can keep polling through transient failures.
+/// - An error that doesn't match an acceptor ends waiting with [`WaiterError::OperationFailed`],
+///   while an output that doesn't match any acceptor is polled again.
+///
+/// The waiter has to be given the time source and sleep implementation that the client uses,
+/// usually with [`set_time_source`](Self::set_time_source) and
+/// [`set_sleep_impl`](Self::set_sleep_impl) and the values from the client's config.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # mod aws_sdk_example {
+/// #     #[derive(Clone, Debug)]
+/// #     pub struct Client;
+/// #     pub struct DescribeThing;
+/// #     #[derive(Debug)]
+/// #     pub struct DescribeThingOutput { pub status: &'static str }
+/// #     #[derive(Debug)]
+/// #     pub struct DescribeThingError;
+/// #     impl std::fmt::Display for DescribeThingError {
+/// #         fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { f.write_str("DescribeThingError") }
+/// #     }
+/// #     impl std::error::Error for DescribeThingError {}
+/// #     impl aws_smithy_runtime_api::client::result::CreateUnhandledError for DescribeThingError {
+/// #         fn create_unhandled_error(
+/// #             _: Box<dyn std::error::Error + Send + Sync + 'static>,
+/// #             _: Option<aws_smithy_types::error::ErrorMetadata>,
+/// #         ) -> Self { DescribeThingError }
+/// #     }
+/// #     pub struct Config;
+/// #     impl Config {
+/// #         pub fn time_source(&self) -> Option<aws_smithy_async::time::SharedTimeSource> { None }
+/// #         pub fn sleep_impl(&self) -> Option<aws_smithy_async::rt::sleep::SharedAsyncSleep> { None }
+/// #     }
+/// #     impl Client {
+/// #         pub fn config(&self) -> &Config { &Config }
+/// #         pub fn describe_thing(&self) -> DescribeThing { DescribeThing }
+/// #     }
+/// #     impl DescribeThing {
+/// #         pub fn id(self, _: &str) -> Self { self }
+/// #         pub async fn send(self) -> Result<DescribeThingOutput, aws_smithy_runtime_api::client::result::SdkError<DescribeThingError, aws_smithy_runtime_api::client::orchestrator::HttpResponse>> { unimplemented!() }
+/// #     }
+/// # }
+/// # use aws_sdk_example::*;
+/// # let client = Client;
+/// use aws_smithy_runtime::client::waiters::{AcceptorState, CustomWaiter};
+/// use aws_smithy_runtime_api::client::orchestrator::HttpResponse;
+/// use aws_smithy_runtime_api::client::result::SdkError;
+/// use std::time::Duration;
+///
+/// let final_poll = CustomWaiter::new(
+///     || client.describe_thing().id("thing-1").send(),
+///     |result: Result<&DescribeThingOutput, &SdkError<DescribeThingError, HttpResponse>>| {
+///         match result {
+///             Ok(output) if output.status == "READY" => AcceptorState::Success,
+///             Ok(output) if output.status == "FAILED" => AcceptorState::Failure,
+///             Ok(_) => AcceptorState::Retry,
+///             // keep polling through timeouts
+///             Err(SdkError::TimeoutError(_)) => AcceptorState::Retry,
+///             Err(_) => AcceptorState::NoAcceptorsMatched,
+///         }
+///     },
+/// )
+/// .set_time_source(client.config().time_source())
+/// .set_sleep_impl(client.config().sleep_impl())
+/// .min_delay(Duration::from_secs(5))
+/// .wait(Duration::from_secs(600))
+/// .await?;
+/// # let _ = final_poll;
+/// # Ok(())
+/// # }
+/// ```
+pub struct CustomWaiter<OperationFn, AcceptorFn> {
+    operation_fn: OperationFn,
+    acceptor_fn: AcceptorFn,
+    min_delay: Duration,
+    max_delay: Duration,
+    jitter: Jitter,
+    cancellation_token: Option<CancellationToken>,
+    sleep_impl: Option<SharedAsyncSleep>,
+    time_source: Option<SharedTimeSource>,
+}
+
+impl<OperationFn, AcceptorFn> fmt::Debug for CustomWaiter<OperationFn, AcceptorFn> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CustomWaiter")
+            .field("min_delay", &self.min_delay)
+            .field("max_delay", &self.max_delay)
+            .field("jitter", &self.jitter)
+            .field("cancellation_token", &self.cancellation_token)
+            .finish()
+    }
+}
+
+impl<OperationFn, AcceptorFn> CustomWaiter<OperationFn, AcceptorFn> {
+    /// Creates a waiter that polls by calling `operation`, and uses `acceptor` to decide whether
+    /// waiting is done.
+    ///
+    /// The operation is usually a fluent builder's `send` method, for example
+    /// `|| client.describe_thing().id("thing-1").send()`.
+    pub fn new(operation: OperationFn, acceptor: AcceptorFn) -> Self {
+        Self {
+            operation_fn: operation,
+            acceptor_fn: acceptor,
+            min_delay: DEFAULT_MIN_DELAY,
+            max_delay: DEFAULT_MAX_DELAY,
+            jitter: Jitter::default(),
+            cancellation_token: None,
+            sleep_impl: None,
+            time_source: None,
+        }
+    }
+
+    /// Set the minimum delay between polls. Defaults to two seconds.
+    ///
+    /// Delays are rounded down to whole seconds, and must be at least one second.
+    pub fn min_delay(mut self, min_delay: Duration) -> Self {
+        self.min_delay = min_delay;
+        self
+    }
+
+    /// Set the maximum delay between polls. Defaults to 120 seconds.
+    ///
+    /// Delays are rounded down to whole seconds, and must be at least the minimum delay.
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Set the jitter applied to the delay between polls. Defaults to [`Jitter::Full`].
+    pub fn jitter(mut self, jitter: Jitter) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Set a token that cancels the waiter.
+    pub fn cancellation_token(mut self, cancellation_token: CancellationToken) -> Self {
+        self.cancellation_token = Some(cancellation_token);
+        self
+    }
+
+    /// Set the async sleep implementation used to delay between polls.
+    ///
+    /// This is required, and should usually be the client's sleep implementation.
+    pub fn sleep_impl(mut self, sleep_impl: impl AsyncSleep + 'static) -> Self {
+        self.sleep_impl = Some(sleep_impl.into_shared());
+        self
+    }
+
+    /// Set the async sleep implementation used to delay between polls.
+    ///
+    /// This is required, and should usually be the client's sleep implementation.
+    pub fn set_sleep_impl(mut self, sleep_impl: Option<SharedAsyncSleep>) -> Self {
+        self.sleep_impl = sleep_impl;
+        self
+    }
+
+    /// Set the time source used to measure how long the waiter has waited.
+    ///
+    /// This is required, and should usually be the client's time source.
+    pub fn time_source(mut self, time_source: impl TimeSource + 'static) -> Self {
+        self.time_source = Some(time_source.into_shared());
+        self
+    }
+
+    /// Set the time source used to measure how long the waiter has waited.
+    ///
+    /// This is required, and should usually be the client's time source.
+    pub fn set_time_source(mut self, time_source: Option<SharedTimeSource>) -> Self {
+        self.time_source = time_source;
+        self
+    }
+}
+
+impl<OperationFn, AcceptorFn, O, E, Fut> CustomWaiter<OperationFn, AcceptorFn>
+where
+    AcceptorFn: Fn(Result<&O, &SdkError<E, HttpResponse>>) -> AcceptorState,
+    OperationFn: Fn() -> Fut,
+    Fut: Future<Output = Result<O, SdkError<E, HttpResponse>>>,
+    E: CreateUnhandledError + std::error::Error + Send + Sync + 'static,
+{
+    /// Polls until waiting is done, or until `max_wait` has elapsed.
+    pub async fn wait(
+        self,
+        max_wait: Duration,
+    ) -> Result<FinalPoll<O, SdkError<E, HttpResponse>>, WaiterError<O, E>> {
+        self.wait_with_progress(max_wait, |_| {}).await
+    }
+
+    /// Polls until waiting is done, or until `max_wait` has elapsed, calling `progress` after
+    /// each poll.
+    ///
+    /// See [`WaiterOrchestrator::orchestrate_with_progress`].
+    pub async fn wait_with_progress(
+        self,
+        max_wait: Duration,
+        progress: impl FnMut(WaiterProgress<'_, O, E>),
+    ) -> Result<FinalPoll<O, SdkError<E, HttpResponse>>, WaiterError<O, E>> {
+        if self.min_delay.as_secs() < 1 {
+            return Err(WaiterError::construction_failure(
+                "the minimum delay of a waiter must be at least one second",
+            ));
+        }
+        if self.max_delay.as_secs() < self.min_delay.as_secs() {
+            return Err(WaiterError::construction_failure(format!(
+                "the maximum delay of a waiter ({:?}) must be at least its minimum delay ({:?})",
+                self.max_delay, self.min_delay
+            )));
+        }
+        let Some(sleep_impl) = self.sleep_impl else {
+            return Err(WaiterError::construction_failure(
+                "an async sleep implementation is required to wait. \
+                Set the client's with `CustomWaiter::set_sleep_impl`",
+            ));
+        };
+        let Some(time_source) = self.time_source else {
+            return Err(WaiterError::construction_failure(
+                "a time source is required to wait. \
+                Set the client's with `CustomWaiter::set_time_source`",
+            ));
+        };
+        let mut orchestrator = WaiterOrchestrator::builder()
+            .min_delay(self.min_delay)
+            .max_delay(self.max_delay)
+            .max_wait(max_wait)
+            .jitter(self.jitter)
+            .time_source(time_source)
+            .sleep_impl(sleep_impl);
+        if let Some(cancellation_token) = self.cancellation_token {
+            orchestrator = orchestrator.cancellation_token(cancellation_token);
+        }
+        let orchestrator = orchestrator
+            .acceptor(self.acceptor_fn)
+            .operation(self.operation_fn)
+            .build();
+        attach_waiter_tracing_span(orchestrator.orchestrate_sdk_errors_with_progress(progress))
+            .await
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use super::CustomWaiter;
+    use crate::client::waiters::{AcceptorState, Jitter};
+    use aws_smithy_async::test_util::tick_advance_sleep::tick_advance_time_and_sleep;
+    use aws_smithy_runtime_api::client::orchestrator::HttpResponse;
+    use aws_smithy_runtime_api::client::result::{CreateUnhandledError, SdkError};
+    use aws_smithy_runtime_api::client::waiters::error::WaiterError;
+    use aws_smithy_runtime_api::shared::IntoShared;
+    use std::fmt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[derive(Debug)]
+    struct TestError;
+    impl std::error::Error for TestError {}
+    impl fmt::Display for TestError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("TestError")
+        }
+    }
+    impl CreateUnhandledError for TestError {
+        fn create_unhandled_error(
+            _source: Box<dyn std::error::Error + Send + Sync + 'static>,
+            _meta: Option<aws_smithy_types::error::ErrorMetadata>,
+        ) -> Self {
+            TestError
+        }
+    }
+
+    fn counter() -> impl Fn() -> std::future::Ready<Result<usize, SdkError<TestError, HttpResponse>>>
+    {
+        let attempt = Arc::new(AtomicUsize::new(1));
+        move || std::future::ready(Ok(attempt.fetch_add(1, Ordering::SeqCst)))
+    }
+
+    #[tokio::test]
+    async fn waits_with_default_delays() {
+        let (time_source, sleep_impl) = tick_advance_time_and_sleep();
+        let waiter = CustomWaiter::new(
+            counter(),
+            |result: Result<&usize, &SdkError<TestError, HttpResponse>>| match result {
+                Ok(4) => AcceptorState::Success,
+                _ => AcceptorState::Retry,
+            },
+        )
+        .jitter(Jitter::Disabled)
+        .sleep_impl(sleep_impl)
+        .time_source(time_source.clone());
+
+        let delays = Arc::new(Mutex::new(Vec::new()));
+        let task = tokio::spawn(waiter.wait_with_progress(Duration::from_secs(300), {
+            let delays = delays.clone();
+            move |progress| delays.lock().unwrap().push(progress.next_delay())
+        }));
+        tokio::task::yield_now().await;
+        time_source.tick(Duration::from_secs(20)).await;
+        let result = task.await.unwrap();
+
+        assert_eq!(4, *result.unwrap().as_result().unwrap());
+        assert_eq!(
+            vec![
+                Some(Duration::from_secs(2)),
+                Some(Duration::from_secs(4)),
+                Some(Duration::from_secs(8)),
+                None
+            ],
+            *delays.lock().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn exceeds_max_wait() {
+        let (time_source, sleep_impl) = tick_advance_time_and_sleep();
+        let waiter = CustomWaiter::new(
+            counter(),
+            |_: Result<&usize, &SdkError<TestError, HttpResponse>>| AcceptorState::Retry,
+        )
+        .min_delay(Duration::from_secs(1))
+        .max_delay(Duration::from_secs(1))
+        .sleep_impl(sleep_impl)
+        .time_source(time_source.clone());
+
+        let task = tokio::spawn(waiter.wait(Duration::from_secs(5)));
+        tokio::task::yield_now().await;
+        time_source.tick(Duration::from_secs(10)).await;
+
+        match task.await.unwrap() {
+            Err(WaiterError::ExceededMaxWait(context)) => {
+                assert_eq!(Duration::from_secs(5), context.max_wait());
+            }
+            result => panic!("expected ExceededMaxWait, got {result:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn invalid_delays_fail_construction() {
+        let acceptor =
+            |_: Result<&usize, &SdkError<TestError, HttpResponse>>| AcceptorState::Success;
+        let result = CustomWaiter::new(counter(), acceptor)
+            .min_delay(Duration::from_millis(500))
+            .wait(Duration::from_secs(5))
+            .await;
+        assert!(
+            matches!(result, Err(WaiterError::ConstructionFailure(_))),
+            "{result:?}"
+        );
+
+        let result = CustomWaiter::new(counter(), acceptor)
+            .min_delay(Duration::from_secs(10))
+            .max_delay(Duration::from_secs(5))
+            .wait(Duration::from_secs(5))
+            .await;
+        assert!(
+            matches!(result, Err(WaiterError::ConstructionFailure(_))),
+            "{result:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn acceptor_sees_unmodeled_errors() {
+        let (time_source, sleep_impl) = tick_advance_time_and_sleep();
+        let attempt = Arc::new(AtomicUsize::new(1));
+        let operation = move || {
+            let attempt = attempt.fetch_add(1, Ordering::SeqCst);
+            std::future::ready(if attempt < 3 {
+                Err(SdkError::<TestError, HttpResponse>::timeout_error(
+                    "timed out",
+                ))
+            } else {
+                Ok(attempt)
+            })
+        };
+        let waiter = CustomWaiter::new(
+            operation,
+            |result: Result<&usize, &SdkError<TestError, HttpResponse>>| match result {
+                Ok(_) => AcceptorState::Success,
+                Err(SdkError::TimeoutError(_)) => AcceptorState::Retry,
+                Err(_) => AcceptorState::NoAcceptorsMatched,
+            },
+        )
+        .sleep_impl(sleep_impl)
+        .time_source(time_source.clone());
+
+        let task = tokio::spawn(waiter.wait(Duration::from_secs(300)));
+        tokio::task::yield_now().await;
+        time_source.tick(Duration::from_secs(20)).await;
+
+        assert_eq!(3, *task.await.unwrap().unwrap().as_result().unwrap());
+    }
+
+    #[tokio::test]
+    async fn unmatched_unmodeled_errors_fail_the_operation() {
+        let (time_source, sleep_impl) = tick_advance_time_and_sleep();
+        let waiter = CustomWaiter::new(
+            || {
+                std::future::ready(Result::<usize, _>::Err(
+                    SdkError::<TestError, HttpResponse>::timeout_error("timed out"),
+                ))
+            },
+            |_: Result<&usize, &SdkError<TestError, HttpResponse>>| {
+                AcceptorState::NoAcceptorsMatched
+            },
+        )
+        .sleep_impl(sleep_impl)
+        .time_source(time_source);
+
+        let result = waiter.wait(Duration::from_secs(300)).await;
+        match result {
+            Err(WaiterError::OperationFailed(err)) => {
+                assert!(matches!(err.error(), SdkError::TimeoutError(_)))
+            }
+            result => panic!("expected OperationFailed, got {result:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn time_source_and_sleep_impl_are_required() {
+        let (time_source, sleep_impl) = tick_advance_time_and_sleep();
+        let acceptor =
+            |_: Result<&usize, &SdkError<TestError, HttpResponse>>| AcceptorState::Success;
+        let result = CustomWaiter::new(counter(), acceptor)
+            .time_source(time_source)
+            .wait(Duration::from_secs(5))
+            .await;
+        assert!(
+            matches!(result, Err(WaiterError::ConstructionFailure(_))),
+            "{result:?}"
+        );
+
+        let result = CustomWaiter::new(counter(), acceptor)
+            .set_sleep_impl(Some(sleep_impl.into_shared()))
+            .set_time_source(None)
+            .wait(Duration::from_secs(5))
+            .await;
+        assert!(
+            matches!(result, Err(WaiterError::ConstructionFailure(_))),
+            "{result:?}"
+        );
+    }
+}
@@ -0,0 +1,180 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+#![cfg(all(
+    feature = "client",
+    feature = "wire-mock",
+    feature = "connector-hyper-0-14-x",
+))]
+
+use aws_smithy_async::rt::sleep::TokioSleep;
+use aws_smithy_async::time::SystemTimeSource;
+use aws_smithy_runtime::client::http::test_util::wire::{Route, RouteResponse, WireMockServer};
+use aws_smithy_runtime::{ev, match_events};
+use aws_smithy_runtime_api::client::http::{
+    HttpClient, HttpConnector, HttpConnectorSettings, SharedHttpConnector,
+};
+use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, HttpResponse};
+use aws_smithy_runtime_api::client::runtime_components::RuntimeComponentsBuilder;
+use aws_smithy_types::body::SdkBody;
+use aws_smithy_types::byte_stream::ByteStream;
+use std::time::Duration;
+
+fn connector(server: &WireMockServer) -> SharedHttpConnector {
+    let components = RuntimeComponentsBuilder::for_tests()
+        .with_sleep_impl(Some(TokioSleep::new()))
+        .with_time_source(Some(SystemTimeSource::new()))
+        .build()
+        .unwrap();
+    server
+        .http_client()
+        .http_connector(&HttpConnectorSettings::default(), &components)
+}
+
+async fn send(
+    connector: &SharedHttpConnector,
+    server: &WireMockServer,
+    method: &str,
+    path: &str,
+    headers: &[(&str, &str)],
+    body: &'static str,
+) -> HttpResponse {
+    let mut request = http_02x::Request::builder()
+        .method(method)
+        .uri(format!("{}{path}", server.endpoint_url()));
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let request = request.body(SdkBody::from(body)).unwrap();
+    connector
+        .call(HttpRequest::try_from(request).unwrap())
+        .await
+        .expect("request succeeds")
+}
+
+async fn body_of(response: HttpResponse) -> String {
+    let body = ByteStream::new(response.into_body())
+        .collect()
+        .await
+        .unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn routes_requests_and_records_them() {
+    let server = WireMockServer::builder()
+        .route(
+            Route::post("/items")
+                .header("content-type", "application/json")
+                .respond_with(
+                    RouteResponse::status(201)
+                        .header("x-item-id", "1")
+                        .body("created"),
+                ),
+        )
+        .route(Route::get("/items").respond_with(RouteResponse::ok().body("[]")))
+        .start()
+        .await;
+    let connector = connector(&server);
+
+    let response = send(
+        &connector,
+        &server,
+        "POST",
+        "/items",
+        &[("content-type", "application/json")],
+        r#"{ "name": "widget", "count": 1 }"#,
+    )
+    .await;
+    assert_eq!(201, response.status().as_u16());
+    assert_eq!(Some("1"), response.headers().get("x-item-id"));
+    assert_eq!("created", body_of(response).await);
+
+    let response = send(&connector, &server, "GET", "/items?page=2", &[], "").await;
+    assert_eq!("[]", body_of(response).await);
+
+    // the header doesn't match, so the first route doesn't handle this request
+    let response = send(&connector, &server, "POST", "/items", &[], "").await;
+    assert_eq!(404, response.status().as_u16());
+
+    let requests = server.requests();
+    assert_eq!(
+        vec![Some(0), Some(1), None],
+        requests.iter().map(|r| r.route()).collect::<Vec<_>>()
+    );
+    requests[0].assert_headers(&[("content-type", "application/json")]);
+    requests[0].assert_body(r#"{"count":1,"name":"widget"}"#, "application/json");
+    assert_eq!("/items?page=2", requests[1].request().uri());
+    match_events!(
+        ev!(dns),
+        ev!(connect),
+        ev!(http(201)),
+        ev!(http(200)),
+        ev!(http(404))
+    )(&server.events());
+}
+
+#[tokio::test]
+#[should_panic]
+async fn body_assertions_fail_for_different_bodies() {
+    let server = WireMockServer::builder().route(Route::new()).start().await;
+    let connector = connector(&server);
+    send(&connector, &server, "PUT", "/", &[], r#"{"a": 1}"#).await;
+    server.requests()[0].assert_body(r#"{"a": 2}"#, "application/json");
+}
+
+#[tokio::test]
+async fn streaming_bodies_and_delays() {
+    let server = WireMockServer::builder()
+        .route(
+            Route::new().respond_with(
+                RouteResponse::ok()
+                    .delay(Duration::from_millis(10))
+                    .streaming_body(["hello", " ", "world"], Duration::from_millis(10)),
+            ),
+        )
+        .start()
+        .await;
+    let connector = connector(&server);
+
+    let response = send(&connector, &server, "GET", "/", &[], "").await;
+    assert_eq!("hello world", body_of(response).await);
+}
+
+#[tokio::test]
+async fn tls_and_http2() {
+    let server = WireMockServer::builder()
+        .route(Route::get("/secure").respond_with(RouteResponse::ok().body("secret")))
+        .tls()
+        .http2_only()
+        .start()
+        .await;
+    let connector = connector(&server);
+    assert!(server.endpoint_url().starts_with("https://"));
+    assert!(server.certificate_der().is_some());
+
+    let response = send(&connector, &server, "GET", "/secure", &[], "").await;
+    assert_eq!("secret", body_of(response).await);
+    assert_eq!(
+        http_02x::Version::HTTP_2,
+        server.requests()[0].request().version()
+    );
+}
+
+#[tokio::test]
+async fn http2_with_prior_knowledge() {
+    let server = WireMockServer::builder()
+        .route(Route::new())
+        .http2_only()
+        .start()
+        .await;
+    let connector = connector(&server);
+
+    send(&connector, &server, "GET", "/", &[], "").await;
+    assert_eq!(
+        http_02x::Version::HTTP_2,
+        server.requests()[0].request().version()
+    );
+}
@@ -157,6 +157,7 @@ pub struct RetryConfigBuilder {
     initial_backoff: Option<Duration>,
     max_backoff: Option<Duration>,
     reconnect_mode: Option<ReconnectMode>,
+    adaptive_retry_config: Option<AdaptiveRetryConfig>,
 }
 
 impl RetryConfigBuilder {
@@ -237,6 +238,23 @@ impl RetryConfigBuilder {
         self
     }
 
+    /// Set the [`AdaptiveRetryConfig`] used to tune client-side rate limiting when the retry
+    /// mode is [`RetryMode::Adaptive`].
+    pub fn set_adaptive_retry_config(
+        &mut self,
+        adaptive_retry_config: Option<AdaptiveRetryConfig>,
+    ) -> &mut Self {
+        self.adaptive_retry_config = adaptive_retry_config;
+        self
+    }
+
+    /// Set the [`AdaptiveRetryConfig`] used to tune client-side rate limiting when the retry
+    /// mode is [`RetryMode::Adaptive`].
+    pub fn adaptive_retry_config(mut self, adaptive_retry_config: AdaptiveRetryConfig) -> Self {
+        self.set_adaptive_retry_config(Some(adaptive_retry_config));
+        self
+    }
+
     /// Merge two builders together. Values from `other` will only be used as a fallback for values
     /// from `self` Useful for merging configs from different sources together when you want to
     /// handle "precedence" per value instead of at the config level
@@ -260,6 +278,7 @@ impl RetryConfigBuilder {
             initial_backoff: self.initial_backoff.or(other.initial_backoff),
             max_backoff: self.max_backoff.or(other.max_backoff),
             reconnect_mode: self.reconnect_mode.or(other.reconnect_mode),
+            adaptive_retry_config: self.adaptive_retry_config.or(other.adaptive_retry_config),
         }
     }
 
@@ -276,6 +295,7 @@ impl RetryConfigBuilder {
                 .unwrap_or(ReconnectMode::ReconnectOnTransientError),
             max_backoff: self.max_backoff.unwrap_or_else(|| Duration::from_secs(20)),
             use_static_exponential_base: false,
+            adaptive_retry_config: self.adaptive_retry_config.unwrap_or_default(),
         }
     }
 }
@@ -290,6 +310,7 @@ pub struct RetryConfig {
     max_backoff: Duration,
     reconnect_mode: ReconnectMode,
     use_static_exponential_base: bool,
+    adaptive_retry_config: AdaptiveRetryConfig,
 }
 
 impl Storable for RetryConfig {
@@ -326,6 +347,7 @@ impl RetryConfig {
             reconnect_mode: ReconnectMode::ReconnectOnTransientError,
             max_backoff: Duration::from_secs(20),
             use_static_exponential_base: false,
+            adaptive_retry_config: AdaptiveRetryConfig::default(),
         }
     }
 
@@ -338,6 +360,7 @@ impl RetryConfig {
             reconnect_mode: ReconnectMode::ReconnectOnTransientError,
             max_backoff: Duration::from_secs(20),
             use_static_exponential_base: false,
+            adaptive_retry_config: AdaptiveRetryConfig::default(),
         }
     }
 
@@ -399,6 +422,16 @@ impl RetryConfig {
         self
     }
 
+    /// Set the [`AdaptiveRetryConfig`] used to tune client-side rate limiting when the retry mode
+    /// is [`RetryMode::Adaptive`].
+    pub fn with_adaptive_retry_config(
+        mut self,
+        adaptive_retry_config: AdaptiveRetryConfig,
+    ) -> Self {
+        self.adaptive_retry_config = adaptive_retry_config;
+        self
+    }
+
     /// Hint to the retry strategy whether to use a static exponential base.
     ///
     /// When a retry strategy uses exponential backoff, it calculates a random base. This causes the
@@ -450,11 +483,233 @@ impl RetryConfig {
     pub fn use_static_exponential_base(&self) -> bool {
         self.use_static_exponential_base
     }
+
+    /// Returns the [`AdaptiveRetryConfig`]. It only has an effect when the retry mode is
+    /// [`RetryMode::Adaptive`].
+    pub fn adaptive_retry_config(&self) -> &AdaptiveRetryConfig {
+        &self.adaptive_retry_config
+    }
+}
+
+const DEFAULT_BETA: f64 = 0.7;
+const DEFAULT_SCALE_CONSTANT: f64 = 0.4;
+const DEFAULT_SMOOTHING: f64 = 0.8;
+const DEFAULT_MIN_FILL_RATE: f64 = 0.5;
+
+/// Which clients share a client-side rate limiter in [`RetryMode::Adaptive`].
+#[non_exhaustive]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimiterScope {
+    /// All clients in the process that use the same retry partition share a rate limiter. When
+    /// one of them is throttled, all of them slow down.
+    #[default]
+    Partition,
+
+    /// Each client has a rate limiter of its own.
+    Client,
+}
+
+/// Tuning parameters for the client-side rate limiter used by [`RetryMode::Adaptive`].
+///
+/// The rate limiter uses the CUBIC congestion control algorithm: after a throttling response,
+/// the allowed send rate is multiplied by `beta`, and it then grows back along a cubic curve
+/// whose steepness is controlled by `scale_constant`.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdaptiveRetryConfig {
+    beta: f64,
+    scale_constant: f64,
+    smoothing: f64,
+    min_fill_rate: f64,
+    scope: RateLimiterScope,
+}
+
+impl Default for AdaptiveRetryConfig {
+    fn default() -> Self {
+        Self {
+            beta: DEFAULT_BETA,
+            scale_constant: DEFAULT_SCALE_CONSTANT,
+            smoothing: DEFAULT_SMOOTHING,
+            min_fill_rate: DEFAULT_MIN_FILL_RATE,
+            scope: RateLimiterScope::default(),
+        }
+    }
+}
+
+impl AdaptiveRetryConfig {
+    /// Creates a new builder.
+    pub fn builder() -> AdaptiveRetryConfigBuilder {
+        AdaptiveRetryConfigBuilder::default()
+    }
+
+    /// Returns the factor the send rate is multiplied by after a throttling response.
+    pub fn beta(&self) -> f64 {
+        self.beta
+    }
+
+    /// Returns the constant that controls how quickly the send rate grows back after a
+    /// throttling response.
+    pub fn scale_constant(&self) -> f64 {
+        self.scale_constant
+    }
+
+    /// Returns the weight given to the latest measurement when smoothing the measured send rate.
+    pub fn smoothing(&self) -> f64 {
+        self.smoothing
+    }
+
+    /// Returns the lowest rate, in requests per second, that the rate limiter will slow down to.
+    pub fn min_fill_rate(&self) -> f64 {
+        self.min_fill_rate
+    }
+
+    /// Returns which clients share a rate limiter.
+    pub fn scope(&self) -> RateLimiterScope {
+        self.scope
+    }
+}
+
+/// Builder for [`AdaptiveRetryConfig`].
+#[non_exhaustive]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AdaptiveRetryConfigBuilder {
+    beta: Option<f64>,
+    scale_constant: Option<f64>,
+    smoothing: Option<f64>,
+    min_fill_rate: Option<f64>,
+    scope: Option<RateLimiterScope>,
+}
+
+impl AdaptiveRetryConfigBuilder {
+    /// Sets the factor the send rate is multiplied by after a throttling response. Must be
+    /// greater than zero and less than one. Defaults to `0.7`.
+    pub fn set_beta(&mut self, beta: Option<f64>) -> &mut Self {
+        self.beta = beta;
+        self
+    }
+
+    /// Sets the factor the send rate is multiplied by after a throttling response. Must be
+    /// greater than zero and less than one. Defaults to `0.7`.
+    pub fn beta(mut self, beta: f64) -> Self {
+        self.set_beta(Some(beta));
+        self
+    }
+
+    /// Sets the constant that controls how quickly the send rate grows back after a throttling
+    /// response. Must be greater than zero. Defaults to `0.4`.
+    pub fn set_scale_constant(&mut self, scale_constant: Option<f64>) -> &mut Self {
+        self.scale_constant = scale_constant;
+        self
+    }
+
+    /// Sets the constant that controls how quickly the send rate grows back after a throttling
+    /// response. Must be greater than zero. Defaults to `0.4`.
+    pub fn scale_constant(mut self, scale_constant: f64) -> Self {
+        self.set_scale_constant(Some(scale_constant));
+        self
+    }
+
+    /// Sets the weight given to the latest measurement when smoothing the measured send rate.
+    /// Must be greater than zero and at most one. Defaults to `0.8`.
+    pub fn set_smoothing(&mut self, smoothing: Option<f64>) -> &mut Self {
+        self.smoothing = smoothing;
+        self
+    }
+
+    /// Sets the weight given to the latest measurement when smoothing the measured send rate.
+    /// Must be greater than zero and at most one. Defaults to `0.8`.
+    pub fn smoothing(mut self, smoothing: f64) -> Self {
+        self.set_smoothing(Some(smoothing));
+        self
+    }
+
+    /// Sets the lowest rate, in requests per second, that the rate limiter will slow down to.
+    /// Must be greater than zero. Defaults to `0.5`.
+    pub fn set_min_fill_rate(&mut self, min_fill_rate: Option<f64>) -> &mut Self {
+        self.min_fill_rate = min_fill_rate;
+        self
+    }
+
+    /// Sets the lowest rate, in requests per second, that the rate limiter will slow down to.
+    /// Must be greater than zero. Defaults to `0.5`.
+    pub fn min_fill_rate(mut self, min_fill_rate: f64) -> Self {
+        self.set_min_fill_rate(Some(min_fill_rate));
+        self
+    }
+
+    /// Sets which clients share a rate limiter. Defaults to [`RateLimiterScope::Partition`].
+    pub fn set_scope(&mut self, scope: Option<RateLimiterScope>) -> &mut Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Sets which clients share a rate limiter. Defaults to [`RateLimiterScope::Partition`].
+    pub fn scope(mut self, scope: RateLimiterScope) -> Self {
+        self.set_scope(Some(scope));
+        self
+    }
+
+    /// Builds an `AdaptiveRetryConfig`, or returns an error if a parameter is out of range.
+    pub fn build(self) -> Result<AdaptiveRetryConfig, InvalidAdaptiveRetryConfig> {
+        let beta = self.beta.unwrap_or(DEFAULT_BETA);
+        if !(beta > 0.0 && beta < 1.0) {
+            return Err(InvalidAdaptiveRetryConfig::new(format!(
+                "beta must be greater than zero and less than one, but was {beta}"
+            )));
+        }
+        let scale_constant = self.scale_constant.unwrap_or(DEFAULT_SCALE_CONSTANT);
+        if !(scale_constant > 0.0 && scale_constant.is_finite()) {
+            return Err(InvalidAdaptiveRetryConfig::new(format!(
+                "scale_constant must be greater than zero, but was {scale_constant}"
+            )));
+        }
+        let smoothing = self.smoothing.unwrap_or(DEFAULT_SMOOTHING);
+        if !(smoothing > 0.0 && smoothing <= 1.0) {
+            return Err(InvalidAdaptiveRetryConfig::new(format!(
+                "smoothing must be greater than zero and at most one, but was {smoothing}"
+            )));
+        }
+        let min_fill_rate = self.min_fill_rate.unwrap_or(DEFAULT_MIN_FILL_RATE);
+        if !(min_fill_rate > 0.0 && min_fill_rate.is_finite()) {
+            return Err(InvalidAdaptiveRetryConfig::new(format!(
+                "min_fill_rate must be greater than zero, but was {min_fill_rate}"
+            )));
+        }
+        Ok(AdaptiveRetryConfig {
+            beta,
+            scale_constant,
+            smoothing,
+            min_fill_rate,
+            scope: self.scope.unwrap_or_default(),
+        })
+    }
+}
+
+/// Failure to build an [`AdaptiveRetryConfig`] because a parameter was out of range.
+#[derive(Debug)]
+pub struct InvalidAdaptiveRetryConfig {
+    message: String,
 }
 
+impl InvalidAdaptiveRetryConfig {
+    fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for InvalidAdaptiveRetryConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid adaptive retry config: {}", self.message)
+    }
+}
+
+impl std::error::Error for InvalidAdaptiveRetryConfig {}
+
 #[cfg(test)]
 mod tests {
-    use crate::retry::{RetryConfigBuilder, RetryMode};
+    use crate::retry::{AdaptiveRetryConfig, RateLimiterScope, RetryConfigBuilder, RetryMode};
     use std::str::FromStr;
 
     #[test]
@@ -534,4 +789,55 @@ mod tests {
         assert_eq!(RetryMode::from_str("s t a n d a r d").ok(), None);
         assert_eq!(RetryMode::from_str("a d a p t i v e").ok(), None);
     }
+
+    #[test]
+    fn adaptive_retry_config_builder_uses_defaults_for_unset_values() {
+        let config = AdaptiveRetryConfig::builder()
+            .beta(0.5)
+            .scope(RateLimiterScope::Client)
+            .build()
+            .unwrap();
+        assert_eq!(0.5, config.beta());
+        assert_eq!(RateLimiterScope::Client, config.scope());
+        assert_eq!(
+            AdaptiveRetryConfig::default().scale_constant(),
+            config.scale_constant()
+        );
+        assert_eq!(
+            AdaptiveRetryConfig::default().smoothing(),
+            config.smoothing()
+        );
+        assert_eq!(
+            AdaptiveRetryConfig::default().min_fill_rate(),
+            config.min_fill_rate()
+        );
+    }
+
+    #[test]
+    fn adaptive_retry_config_builder_rejects_out_of_range_values() {
+        assert!(AdaptiveRetryConfig::builder().beta(1.0).build().is_err());
+        assert!(AdaptiveRetryConfig::builder()
+            .scale_constant(0.0)
+            .build()
+            .is_err());
+        assert!(AdaptiveRetryConfig::builder()
+            .smoothing(1.5)
+            .build()
+            .is_err());
+        assert!(AdaptiveRetryConfig::builder()
+            .min_fill_rate(f64::NAN)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn retry_config_builder_merges_adaptive_retry_config() {
+        let adaptive = AdaptiveRetryConfig::builder().beta(0.5).build().unwrap();
+        let a = RetryConfigBuilder::new().mode(RetryMode::Adaptive);
+        let b = RetryConfigBuilder::new().adaptive_retry_config(adaptive.clone());
+        assert_eq!(
+            &adaptive,
+            a.take_unset_from(b).build().adaptive_retry_config()
+        );
+    }
 }
@@ -90,7 +90,11 @@ impl Intercept for RequestCompressionInterceptor {
             .load::<RequestMinCompressionSizeBytes>()
             .cloned()
             .unwrap_or_default();
-        let options = CompressionOptions::default()
+        // The algorithm and level come from `CompressionOptions` in the config, if set
+        let options = cfg
+            .load::<CompressionOptions>()
+            .cloned()
+            .unwrap_or_default()
             .with_min_compression_size_bytes(request_min_compression_size_bytes.0)?
             .with_enabled(!disable_request_compression.0);
 
@@ -142,12 +146,15 @@ impl Intercept for RequestCompressionInterceptor {
             tracing::trace!("compressing unsized request body...");
         }
 
-        wrap_request_body_in_compressed_body(
-            request,
-            CompressionAlgorithm::Gzip.into_impl_http_body_0_4_x(&options),
-        )?;
+        let algorithm = options.algorithm();
+        wrap_request_body_in_compressed_body(request, algorithm, &options)?;
+        let feature = match algorithm {
+            CompressionAlgorithm::Zstd => SmithySdkFeature::ZstdRequestCompression,
+            CompressionAlgorithm::Brotli => SmithySdkFeature::BrotliRequestCompression,
+            _ => SmithySdkFeature::GzipRequestCompression,
+        };
         cfg.interceptor_state()
-            .store_append::<SmithySdkFeature>(SmithySdkFeature::GzipRequestCompression);
+            .store_append::<SmithySdkFeature>(feature);
 
         Ok(())
     }
@@ -155,8 +162,11 @@ impl Intercept for RequestCompressionInterceptor {
 
 fn wrap_request_body_in_compressed_body(
     request: &mut HttpRequest,
-    request_compress_impl: Box<dyn CompressRequest>,
+    compression_algorithm: CompressionAlgorithm,
+    compression_options: &CompressionOptions,
 ) -> Result<(), BuildError> {
+    let request_compress_impl =
+        compression_algorithm.into_impl_http_body_0_4_x(compression_options);
     request.headers_mut().append(
         request_compress_impl.header_name(),
         request_compress_impl.header_value(),
@@ -165,9 +175,16 @@ fn wrap_request_body_in_compressed_body(
         let body = mem::replace(request.body_mut(), SdkBody::taken());
 
         if body.is_streaming() {
+            // The length of a compressed stream can't be known ahead of time, so the body will be
+            // sent with chunked transfer encoding instead.
             request.headers_mut().remove(http::header::CONTENT_LENGTH);
+            let compression_options = compression_options.clone();
             body.map(move |body| {
-                let body = CompressedBody::new(body, request_compress_impl.clone());
+                // Streaming bodies are compressed into a single stream as they're read
+                let body = CompressedBody::new(
+                    body,
+                    compression_algorithm.into_streaming_compressor(&compression_options),
+                );
                 SdkBody::from_body_0_4(body)
             })
         } else {
@@ -225,7 +242,7 @@ mod tests {
     use crate::client_request_compression::{
         RequestCompressionInterceptor, RequestMinCompressionSizeBytes,
     };
-    use aws_smithy_compression::{CompressionAlgorithm, CompressionOptions};
+    use aws_smithy_compression::{CompressionAlgorithm, CompressionOptions, DecompressionOptions};
     use aws_smithy_runtime::client::sdk_feature::SmithySdkFeature;
     use aws_smithy_runtime_api::client::interceptors::context::{Input, InterceptorContext};
     use aws_smithy_runtime_api::client::interceptors::Intercept;
@@ -267,7 +284,8 @@ mod tests {
 
         wrap_request_body_in_compressed_body(
             &mut request,
-            compression_algorithm.into_impl_http_body_0_4_x(&compression_options),
+            compression_algorithm,
+            &compression_options,
         )
         .unwrap();
 
@@ -283,6 +301,40 @@ mod tests {
         assert_eq!(COMPRESSED_OUTPUT, body_data.as_slice());
     }
 
+    #[tokio::test]
+    async fn test_streaming_body_is_compressed() {
+        let body = http_body::Full::new(bytes::Bytes::from_static(UNCOMPRESSED_INPUT));
+        let mut request: HttpRequest = http::Request::builder()
+            .header(http::header::CONTENT_LENGTH, "11")
+            .body(SdkBody::from_body_0_4(body))
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        let compression_options = CompressionOptions::default()
+            .with_min_compression_size_bytes(0)
+            .unwrap();
+        wrap_request_body_in_compressed_body(
+            &mut request,
+            CompressionAlgorithm::Gzip,
+            &compression_options,
+        )
+        .unwrap();
+
+        assert_eq!(Some("gzip"), request.headers().get("content-encoding"));
+        assert_eq!(None, request.headers().get("content-length"));
+        let mut body_data = Vec::new();
+        let body = request.body_mut();
+        while let Some(data) = body.data().await {
+            body_data.extend_from_slice(&data.unwrap())
+        }
+        let mut decompressor =
+            CompressionAlgorithm::Gzip.into_decompressor(&DecompressionOptions::default());
+        let mut decompressed = decompressor.decompress_bytes(&body_data).unwrap().to_vec();
+        decompressed.extend_from_slice(&decompressor.finish().unwrap());
+        assert_eq!(UNCOMPRESSED_INPUT, decompressed);
+    }
+
     fn context() -> InterceptorContext {
         let mut context = InterceptorContext::new(Input::doesnt_matter());
         context.enter_serialization_phase();
@@ -320,4 +372,46 @@ mod tests {
             cfg.load::<SmithySdkFeature>().next().unwrap()
         );
     }
+
+    #[tokio::test]
+    async fn test_configured_algorithm_is_used() {
+        let mut cfg = ConfigBag::base();
+        let mut layer = Layer::new("test");
+        layer.store_put(RequestMinCompressionSizeBytes::from(0));
+        layer.store_put(
+            CompressionOptions::default()
+                .with_algorithm(CompressionAlgorithm::Zstd)
+                .unwrap()
+                .with_level(19)
+                .unwrap(),
+        );
+        cfg.push_layer(layer);
+        let mut context = context();
+        let ctx = Into::into(&context);
+
+        let sut = RequestCompressionInterceptor::new();
+        sut.read_before_execution(&ctx, &mut cfg).unwrap();
+
+        let rc = RuntimeComponentsBuilder::for_tests().build().unwrap();
+        let mut ctx = Into::into(&mut context);
+        sut.modify_before_retry_loop(&mut ctx, &rc, &mut cfg)
+            .unwrap();
+
+        assert_eq!(
+            &SmithySdkFeature::ZstdRequestCompression,
+            cfg.load::<SmithySdkFeature>().next().unwrap()
+        );
+        let request = context.request_mut().unwrap();
+        assert_eq!(Some("zstd"), request.headers().get("content-encoding"));
+        let mut body_data = Vec::new();
+        let body = request.body_mut();
+        while let Some(data) = body.data().await {
+            body_data.extend_from_slice(&data.unwrap())
+        }
+        let mut decompressor =
+            CompressionAlgorithm::Zstd.into_decompressor(&DecompressionOptions::default());
+        let mut decompressed = decompressor.decompress_bytes(&body_data).unwrap().to_vec();
+        decompressed.extend_from_slice(&decompressor.finish().unwrap());
+        assert_eq!(UNCOMPRESSED_INPUT, decompressed);
+    }
 }
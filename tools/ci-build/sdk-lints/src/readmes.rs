@@ -11,6 +11,7 @@ use std::path::{Path, PathBuf};
 const CRATES_TO_BE_USED_DIRECTLY: &[&str] = [
     "aws-config",
     "aws-smithy-types-convert",
+    "aws-smithy-mocks",
     "aws-smithy-mocks-experimental",
     "aws-smithy-experimental",
 ]